@@ -0,0 +1,102 @@
+//! Benchmarks of the phases of an analysis, on the facts directories of
+//! `inputs` of `CORPUS`, and on synthetic facts of a few sizes:
+//!
+//! - `load/<facts>`: loading the tab-delimited facts, with the interning.
+//! - `simplify/<facts>`: simplifying the CFG.
+//! - `<algorithm>/<facts>`: computing the output with each algorithm.
+//!
+//! Run them with `cargo bench`, or `cargo bench -- <filter>` for some of
+//! them, like `cargo bench -- DatafrogOpt`.
+
+#[macro_use]
+extern crate criterion;
+extern crate polonius;
+extern crate polonius_engine;
+
+use criterion::Criterion;
+use polonius::{Algorithm, AllFacts};
+use polonius_engine::generator::{self, GeneratorParams};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The numbers of blocks of the synthetic facts.
+const SYNTHETIC_BLOCKS: &[usize] = &[16, 128];
+
+/// The functions of `inputs` that the benchmarks run on, as the names of
+/// their dumps and of their facts directories. They are small enough that
+/// every algorithm runs in a fraction of a second: `polonius bench` is
+/// better suited to the larger dumps, like the `clap-rs` one.
+const CORPUS: &[(&str, &str)] = &[
+    ("issue-47680", "main"),
+    ("issue-47680", "{{impl}}-maybe_next"),
+];
+
+/// The facts directories of the corpus, by name.
+fn corpus() -> Vec<(String, PathBuf)> {
+    let inputs_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("inputs");
+    CORPUS
+        .iter()
+        .map(|&(dump, function)| {
+            let facts_dir = inputs_dir.join(dump).join("nll-facts").join(function);
+            (format!("{}/{}", dump, function), facts_dir)
+        })
+        .collect()
+}
+
+/// The facts of the corpus, and the synthetic facts, by name.
+fn facts() -> Vec<(String, AllFacts)> {
+    let mut facts: Vec<(String, AllFacts)> = corpus()
+        .into_iter()
+        .map(|(name, facts_dir)| {
+            let (all_facts, _) = polonius::load_facts(&facts_dir).expect("failed to load facts");
+            (name, all_facts)
+        })
+        .collect();
+    for &blocks in SYNTHETIC_BLOCKS {
+        let params = GeneratorParams {
+            blocks,
+            ..GeneratorParams::default()
+        };
+        facts.push((
+            format!("synthetic-{}", blocks),
+            generator::generate(&params),
+        ));
+    }
+    facts
+}
+
+fn load(c: &mut Criterion) {
+    for (name, facts_dir) in corpus() {
+        c.bench_function(&format!("load/{}", name), move |b| {
+            b.iter(|| polonius::load_facts(&facts_dir).expect("failed to load facts"))
+        });
+    }
+}
+
+fn simplify(c: &mut Criterion) {
+    for (name, all_facts) in facts() {
+        c.bench_function(&format!("simplify/{}", name), move |b| {
+            b.iter(|| polonius_engine::simplify_cfg(&all_facts))
+        });
+    }
+}
+
+fn algorithms(c: &mut Criterion) {
+    // `Compare` only runs two of the others.
+    let algorithms = Algorithm::variants()
+        .iter()
+        .filter(|&&name| name != "Compare")
+        .map(|name| Algorithm::from_str(name).unwrap())
+        .collect::<Vec<_>>();
+    for (name, all_facts) in facts() {
+        for &algorithm in &algorithms {
+            let all_facts = all_facts.clone();
+            c.bench_function(&format!("{}/{}", algorithm, name), move |b| {
+                b.iter(|| polonius::analyze(&all_facts, algorithm))
+            });
+        }
+    }
+}
+
+criterion_group!(benches, load, simplify, algorithms);
+criterion_main!(benches);
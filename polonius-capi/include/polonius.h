@@ -0,0 +1,112 @@
+#ifndef POLONIUS_H
+#define POLONIUS_H
+
+/* Generated by cbindgen from polonius-capi/src/lib.rs. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of the functions that can fail.
+ */
+typedef enum {
+  POLONIUS_STATUS_OK = 0,
+  /**
+   * A pointer argument was null.
+   */
+  POLONIUS_STATUS_NULL_POINTER = 1,
+  /**
+   * A string argument isn't UTF-8.
+   */
+  POLONIUS_STATUS_INVALID_UTF8 = 2,
+  /**
+   * The relation name isn't that of an input relation.
+   */
+  POLONIUS_STATUS_UNKNOWN_RELATION = 3,
+  /**
+   * The tuple doesn't have as many columns as the relation.
+   */
+  POLONIUS_STATUS_WRONG_ARITY = 4,
+  /**
+   * The algorithm name isn't that of an algorithm.
+   */
+  POLONIUS_STATUS_UNKNOWN_ALGORITHM = 5,
+  /**
+   * The analysis panicked, and its output was discarded.
+   */
+  POLONIUS_STATUS_PANICKED = 6,
+} PoloniusStatus;
+
+/**
+ * The facts to analyze, created by `polonius_facts_new`.
+ */
+typedef struct PoloniusFacts PoloniusFacts;
+
+/**
+ * The analysis of some facts, created by `polonius_compute`.
+ */
+typedef struct PoloniusOutput PoloniusOutput;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Analyzes `facts` with the algorithm named `algorithm`, ignoring case,
+ * and stores the output, to free with `polonius_output_free`, in
+ * `*output`. If the analysis panics, the panic doesn't unwind into the
+ * caller: `Panicked` is returned, and `*output` is left untouched.
+ */
+PoloniusStatus polonius_compute(const PoloniusFacts *facts,
+                                const char *algorithm,
+                                PoloniusOutput **output);
+
+/**
+ * Frees a fact set created by `polonius_facts_new`. Does nothing if
+ * `facts` is null.
+ */
+void polonius_facts_free(PoloniusFacts *facts);
+
+/**
+ * Creates an empty fact set, to free with `polonius_facts_free`.
+ */
+PoloniusFacts *polonius_facts_new(void);
+
+/**
+ * Adds the tuple of the `len` atom indices at `columns` to the input
+ * relation named `relation`, like `borrow_region` or `cfg_edge`, in the
+ * order of the columns of its `.facts` file.
+ */
+PoloniusStatus polonius_facts_push(PoloniusFacts *facts,
+                                   const char *relation,
+                                   const uint32_t *columns,
+                                   size_t len);
+
+/**
+ * Stores the point and the loan of the error at `index` of `output`, in
+ * the order of their points, in `*point` and `*loan`. Returns false, and
+ * stores nothing, if there is no such error.
+ */
+bool polonius_output_error(const PoloniusOutput *output,
+                           size_t index,
+                           uint32_t *point,
+                           uint32_t *loan);
+
+/**
+ * The number of errors of `output`, or 0 if it is null.
+ */
+size_t polonius_output_error_count(const PoloniusOutput *output);
+
+/**
+ * Frees an output created by `polonius_compute`. Does nothing if `output`
+ * is null.
+ */
+void polonius_output_free(PoloniusOutput *output);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* POLONIUS_H */
@@ -0,0 +1,186 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A C API to the analysis, declared by `include/polonius.h`. The caller
+//! interns its atoms itself: every region, loan, point, variable and move
+//! path is a `uint32_t` index, and the errors are reported with the same
+//! indices.
+//!
+//! A fact set is created with `polonius_facts_new`, filled one tuple at a
+//! time with `polonius_facts_push`, and analyzed by `polonius_compute`,
+//! whose output lists the errors through `polonius_output_error`.
+
+extern crate polonius_engine;
+
+use polonius_engine::{Algorithm, Loan, MovePath, Point, Region, RowError, Variable};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+mod test;
+
+type AllFacts = polonius_engine::AllFacts<Region, Loan, Point, Variable, MovePath>;
+type Output = polonius_engine::Output<Region, Loan, Point, Variable, MovePath>;
+
+/// The facts to analyze, created by `polonius_facts_new`.
+pub struct PoloniusFacts {
+    all_facts: AllFacts,
+}
+
+/// The analysis of some facts, created by `polonius_compute`.
+pub struct PoloniusOutput {
+    errors: Vec<(Point, Loan)>,
+}
+
+/// The result of the functions that can fail.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoloniusStatus {
+    Ok = 0,
+    /// A pointer argument was null.
+    NullPointer = 1,
+    /// A string argument isn't UTF-8.
+    InvalidUtf8 = 2,
+    /// The relation name isn't that of an input relation.
+    UnknownRelation = 3,
+    /// The tuple doesn't have as many columns as the relation.
+    WrongArity = 4,
+    /// The algorithm name isn't that of an algorithm.
+    UnknownAlgorithm = 5,
+    /// The analysis panicked, and its output was discarded.
+    Panicked = 6,
+}
+
+/// Creates an empty fact set, to free with `polonius_facts_free`.
+#[no_mangle]
+pub extern "C" fn polonius_facts_new() -> *mut PoloniusFacts {
+    Box::into_raw(Box::new(PoloniusFacts {
+        all_facts: AllFacts::default(),
+    }))
+}
+
+/// Frees a fact set created by `polonius_facts_new`. Does nothing if
+/// `facts` is null.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_facts_free(facts: *mut PoloniusFacts) {
+    if !facts.is_null() {
+        drop(Box::from_raw(facts));
+    }
+}
+
+/// Adds the tuple of the `len` atom indices at `columns` to the input
+/// relation named `relation`, like `borrow_region` or `cfg_edge`, in the
+/// order of the columns of its `.facts` file.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_facts_push(
+    facts: *mut PoloniusFacts,
+    relation: *const c_char,
+    columns: *const u32,
+    len: usize,
+) -> PoloniusStatus {
+    if facts.is_null() || relation.is_null() || (columns.is_null() && len > 0) {
+        return PoloniusStatus::NullPointer;
+    }
+    let relation = match CStr::from_ptr(relation).to_str() {
+        Ok(relation) => relation,
+        Err(_) => return PoloniusStatus::InvalidUtf8,
+    };
+    let columns: &[u32] = if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(columns, len)
+    };
+    let all_facts = &mut (*facts).all_facts;
+    match all_facts.push_row(relation, columns, |_, index| index as usize) {
+        Ok(()) => PoloniusStatus::Ok,
+        Err(RowError::UnknownRelation(_)) => PoloniusStatus::UnknownRelation,
+        Err(RowError::WrongArity { .. }) => PoloniusStatus::WrongArity,
+    }
+}
+
+/// Analyzes `facts` with the algorithm named `algorithm`, ignoring case,
+/// and stores the output, to free with `polonius_output_free`, in
+/// `*output`. If the analysis panics, the panic doesn't unwind into the
+/// caller: `Panicked` is returned, and `*output` is left untouched.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_compute(
+    facts: *const PoloniusFacts,
+    algorithm: *const c_char,
+    output: *mut *mut PoloniusOutput,
+) -> PoloniusStatus {
+    if facts.is_null() || algorithm.is_null() || output.is_null() {
+        return PoloniusStatus::NullPointer;
+    }
+    let algorithm: Algorithm = match CStr::from_ptr(algorithm).to_str() {
+        Ok(name) => match name.parse() {
+            Ok(algorithm) => algorithm,
+            Err(_) => return PoloniusStatus::UnknownAlgorithm,
+        },
+        Err(_) => return PoloniusStatus::InvalidUtf8,
+    };
+
+    let all_facts = &(*facts).all_facts;
+    let computed = match catch_panic(|| Output::compute(all_facts, algorithm, false)) {
+        Ok(computed) => computed,
+        Err(status) => return status,
+    };
+    *output = Box::into_raw(Box::new(PoloniusOutput {
+        errors: computed.iter_errors().collect(),
+    }));
+    PoloniusStatus::Ok
+}
+
+/// Runs `op`, returning `Panicked` instead of unwinding out of it, as
+/// unwinding into C is undefined behavior.
+fn catch_panic<T>(op: impl FnOnce() -> T) -> Result<T, PoloniusStatus> {
+    panic::catch_unwind(AssertUnwindSafe(op)).map_err(|_| PoloniusStatus::Panicked)
+}
+
+/// Frees an output created by `polonius_compute`. Does nothing if `output`
+/// is null.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_output_free(output: *mut PoloniusOutput) {
+    if !output.is_null() {
+        drop(Box::from_raw(output));
+    }
+}
+
+/// The number of errors of `output`, or 0 if it is null.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_output_error_count(output: *const PoloniusOutput) -> usize {
+    if output.is_null() {
+        return 0;
+    }
+    (*output).errors.len()
+}
+
+/// Stores the point and the loan of the error at `index` of `output`, in
+/// the order of their points, in `*point` and `*loan`. Returns false, and
+/// stores nothing, if there is no such error.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_output_error(
+    output: *const PoloniusOutput,
+    index: usize,
+    point: *mut u32,
+    loan: *mut u32,
+) -> bool {
+    if output.is_null() || point.is_null() || loan.is_null() {
+        return false;
+    }
+    match (*output).errors.get(index) {
+        Some(&(error_point, error_loan)) => {
+            *point = error_point.0;
+            *loan = error_loan.0;
+            true
+        }
+        None => false,
+    }
+}
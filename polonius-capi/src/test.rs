@@ -0,0 +1,81 @@
+#![cfg(test)]
+
+use super::*;
+use std::ffi::CString;
+use std::ptr;
+
+unsafe fn push(facts: *mut PoloniusFacts, relation: &str, columns: &[u32]) -> PoloniusStatus {
+    let relation = CString::new(relation).unwrap();
+    polonius_facts_push(facts, relation.as_ptr(), columns.as_ptr(), columns.len())
+}
+
+unsafe fn compute(
+    facts: *const PoloniusFacts,
+    algorithm: &str,
+) -> (PoloniusStatus, *mut PoloniusOutput) {
+    let algorithm = CString::new(algorithm).unwrap();
+    let mut output = ptr::null_mut();
+    let status = polonius_compute(facts, algorithm.as_ptr(), &mut output);
+    (status, output)
+}
+
+#[test]
+fn compute_reports_errors_by_index() {
+    unsafe {
+        // Region 0 borrows loan 0 at point 0 and is live at points 1 and
+        // 2, where the loan is invalidated.
+        let facts = polonius_facts_new();
+        assert_eq!(push(facts, "borrow_region", &[0, 0, 0]), PoloniusStatus::Ok);
+        assert_eq!(push(facts, "cfg_edge", &[0, 1]), PoloniusStatus::Ok);
+        assert_eq!(push(facts, "cfg_edge", &[1, 2]), PoloniusStatus::Ok);
+        assert_eq!(push(facts, "region_live_at", &[0, 1]), PoloniusStatus::Ok);
+        assert_eq!(push(facts, "region_live_at", &[0, 2]), PoloniusStatus::Ok);
+        assert_eq!(push(facts, "invalidates", &[2, 0]), PoloniusStatus::Ok);
+
+        let (status, output) = compute(facts, "naive");
+        assert_eq!(status, PoloniusStatus::Ok);
+        assert_eq!(polonius_output_error_count(output), 1);
+
+        let (mut point, mut loan) = (u32::max_value(), u32::max_value());
+        assert!(polonius_output_error(output, 0, &mut point, &mut loan));
+        assert_eq!((point, loan), (2, 0));
+        assert!(!polonius_output_error(output, 1, &mut point, &mut loan));
+
+        polonius_output_free(output);
+        polonius_facts_free(facts);
+    }
+}
+
+#[test]
+fn invalid_arguments_are_reported() {
+    unsafe {
+        let facts = polonius_facts_new();
+        assert_eq!(
+            push(facts, "borrow_regions", &[0, 0, 0]),
+            PoloniusStatus::UnknownRelation
+        );
+        assert_eq!(
+            push(facts, "borrow_region", &[0, 0]),
+            PoloniusStatus::WrongArity
+        );
+        assert_eq!(push(facts, "universal_region", &[0]), PoloniusStatus::Ok);
+        assert_eq!(
+            push(ptr::null_mut(), "cfg_edge", &[0, 1]),
+            PoloniusStatus::NullPointer
+        );
+
+        let (status, output) = compute(facts, "Quantum");
+        assert_eq!(status, PoloniusStatus::UnknownAlgorithm);
+        assert!(output.is_null());
+        assert_eq!(polonius_output_error_count(output), 0);
+
+        polonius_facts_free(facts);
+    }
+}
+
+#[test]
+fn panics_are_reported() {
+    assert_eq!(catch_panic(|| 1), Ok(1));
+    let panicked: Result<(), PoloniusStatus> = catch_panic(|| panic!("in the analysis"));
+    assert_eq!(panicked, Err(PoloniusStatus::Panicked));
+}
@@ -0,0 +1,57 @@
+//! Ready-made atoms, for the users of the engine that don't need atoms of
+//! their own. Each is a `u32` index, which keeps the tuples of the
+//! relations small, and can index a `Vec` of the atom's data.
+
+use super::Atom;
+
+macro_rules! atom {
+    ($(#[$attr:meta])* $t:ident) => {
+        $(#[$attr])*
+        #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Debug, Hash)]
+        #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+        pub struct $t(pub u32);
+
+        impl From<usize> for $t {
+            fn from(index: usize) -> $t {
+                $t(index as u32)
+            }
+        }
+
+        impl Into<usize> for $t {
+            fn into(self) -> usize {
+                self.0 as usize
+            }
+        }
+
+        impl Atom for $t {
+            fn index(self) -> usize {
+                self.into()
+            }
+        }
+    };
+}
+
+atom! {
+    /// A region, or origin: a set of loans.
+    Region
+}
+
+atom! {
+    /// A loan, issued by a borrow.
+    Loan
+}
+
+atom! {
+    /// A point of the CFG, like the start or the middle of a statement.
+    Point
+}
+
+atom! {
+    /// A local variable.
+    Variable
+}
+
+atom! {
+    /// A move path: a variable, or a place projected from one.
+    MovePath
+}
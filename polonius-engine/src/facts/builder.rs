@@ -0,0 +1,201 @@
+use facts::{AllFacts, Atom};
+use rustc_hash::FxHashMap;
+
+/// Builds an `AllFacts` one fact at a time, checking the facts against
+/// each other as they are added. Mostly useful for writing tests by hand:
+///
+/// ```ignore
+/// let mut builder = AllFactsBuilder::new();
+/// builder.edge(p0, p1).borrow(r0, l0, p0).live(r0, p1).invalidate(p1, l0);
+/// let all_facts = builder.build();
+/// ```
+///
+/// Loans must be issued with `borrow` before they are killed or
+/// invalidated, and each loan can only be issued once.
+pub struct AllFactsBuilder<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    facts: AllFacts<R, L, P, V, M>,
+
+    /// The region and point at which each loan was issued.
+    issued: FxHashMap<L, (R, P)>,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> AllFactsBuilder<R, L, P, V, M> {
+    pub fn new() -> Self {
+        AllFactsBuilder {
+            facts: AllFacts::default(),
+            issued: FxHashMap::default(),
+        }
+    }
+
+    /// `cfg_edge(p, q)`
+    pub fn edge(&mut self, p: P, q: P) -> &mut Self {
+        self.facts.cfg_edge.push((p, q));
+        self
+    }
+
+    /// `borrow_region(r, l, p)`, issuing the loan `l`.
+    pub fn borrow(&mut self, r: R, l: L, p: P) -> &mut Self {
+        match self.issued.get(&l) {
+            Some(&issued) if issued != (r, p) => panic!(
+                "loan {} is issued twice, at points {} and {}",
+                l.index(),
+                issued.1.index(),
+                p.index()
+            ),
+            _ => {}
+        }
+        self.issued.insert(l, (r, p));
+        self.facts.borrow_region.push((r, l, p));
+        self
+    }
+
+    /// `universal_region(r)`
+    pub fn universal(&mut self, r: R) -> &mut Self {
+        self.facts.universal_region.push(r);
+        self
+    }
+
+    /// `killed(l, p)`
+    pub fn kill(&mut self, l: L, p: P) -> &mut Self {
+        self.assert_issued(l);
+        self.facts.killed.push((l, p));
+        self
+    }
+
+    /// `outlives(r1, r2, p)`
+    pub fn outlives(&mut self, r1: R, r2: R, p: P) -> &mut Self {
+        self.facts.outlives.push((r1, r2, p));
+        self
+    }
+
+    /// `region_live_at(r, p)`
+    pub fn live(&mut self, r: R, p: P) -> &mut Self {
+        self.facts.region_live_at.push((r, p));
+        self
+    }
+
+    /// `invalidates(p, l)`
+    pub fn invalidate(&mut self, p: P, l: L) -> &mut Self {
+        self.assert_issued(l);
+        self.facts.invalidates.push((p, l));
+        self
+    }
+
+    /// `var_used_at(v, p)`
+    pub fn var_used(&mut self, v: V, p: P) -> &mut Self {
+        self.facts.var_used_at.push((v, p));
+        self
+    }
+
+    /// `var_defined_at(v, p)`
+    pub fn var_defined(&mut self, v: V, p: P) -> &mut Self {
+        self.facts.var_defined_at.push((v, p));
+        self
+    }
+
+    /// `var_drop_used_at(v, p)`
+    pub fn var_drop_used(&mut self, v: V, p: P) -> &mut Self {
+        self.facts.var_drop_used_at.push((v, p));
+        self
+    }
+
+    /// `use_of_var_derefs_origin(v, r)`
+    pub fn var_uses_region(&mut self, v: V, r: R) -> &mut Self {
+        self.facts.use_of_var_derefs_origin.push((v, r));
+        self
+    }
+
+    /// `drop_of_var_derefs_origin(v, r)`
+    pub fn var_drops_region(&mut self, v: V, r: R) -> &mut Self {
+        self.facts.drop_of_var_derefs_origin.push((v, r));
+        self
+    }
+
+    /// `child_path(child, parent)`
+    pub fn child_path(&mut self, child: M, parent: M) -> &mut Self {
+        self.facts.child_path.push((child, parent));
+        self
+    }
+
+    /// `path_is_var(m, v)`
+    pub fn path_is_var(&mut self, m: M, v: V) -> &mut Self {
+        self.facts.path_is_var.push((m, v));
+        self
+    }
+
+    /// `path_assigned_at(m, p)`
+    pub fn path_assigned(&mut self, m: M, p: P) -> &mut Self {
+        self.facts.path_assigned_at.push((m, p));
+        self
+    }
+
+    /// `path_moved_at(m, p)`
+    pub fn path_moved(&mut self, m: M, p: P) -> &mut Self {
+        self.facts.path_moved_at.push((m, p));
+        self
+    }
+
+    /// `path_accessed_at(m, p)`
+    pub fn path_accessed(&mut self, m: M, p: P) -> &mut Self {
+        self.facts.path_accessed_at.push((m, p));
+        self
+    }
+
+    /// `known_placeholder_subset(r1, r2)`
+    pub fn known_subset(&mut self, r1: R, r2: R) -> &mut Self {
+        self.facts.known_placeholder_subset.push((r1, r2));
+        self
+    }
+
+    /// `loan_borrows_path(l, m)`
+    pub fn borrow_path(&mut self, l: L, m: M) -> &mut Self {
+        self.assert_issued(l);
+        self.facts.loan_borrows_path.push((l, m));
+        self
+    }
+
+    /// The facts added so far, with every relation sorted and without
+    /// duplicate tuples.
+    pub fn build(&self) -> AllFacts<R, L, P, V, M> {
+        let mut facts = self.facts.clone();
+        sort_and_dedup(&mut facts.borrow_region);
+        sort_and_dedup(&mut facts.universal_region);
+        sort_and_dedup(&mut facts.cfg_edge);
+        sort_and_dedup(&mut facts.killed);
+        sort_and_dedup(&mut facts.outlives);
+        sort_and_dedup(&mut facts.region_live_at);
+        sort_and_dedup(&mut facts.invalidates);
+        sort_and_dedup(&mut facts.var_used_at);
+        sort_and_dedup(&mut facts.var_defined_at);
+        sort_and_dedup(&mut facts.var_drop_used_at);
+        sort_and_dedup(&mut facts.use_of_var_derefs_origin);
+        sort_and_dedup(&mut facts.drop_of_var_derefs_origin);
+        sort_and_dedup(&mut facts.child_path);
+        sort_and_dedup(&mut facts.path_is_var);
+        sort_and_dedup(&mut facts.path_assigned_at);
+        sort_and_dedup(&mut facts.path_moved_at);
+        sort_and_dedup(&mut facts.path_accessed_at);
+        sort_and_dedup(&mut facts.known_placeholder_subset);
+        sort_and_dedup(&mut facts.loan_borrows_path);
+        facts
+    }
+
+    fn assert_issued(&self, l: L) {
+        assert!(
+            self.issued.contains_key(&l),
+            "loan {} is used before it is issued by `borrow`",
+            l.index()
+        );
+    }
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> Default for AllFactsBuilder<R, L, P, V, M> {
+    fn default() -> Self {
+        AllFactsBuilder::new()
+    }
+}
+
+fn sort_and_dedup<T: Ord>(tuples: &mut Vec<T>) {
+    tuples.sort();
+    tuples.dedup();
+}
@@ -0,0 +1,211 @@
+//! Random, but well-formed, sets of facts, for fuzzing the analysis and for
+//! benchmarking it at sizes that no rustc dump reaches.
+//!
+//! The generated CFG mirrors the shape rustc emits: every statement has a
+//! "start" and a "mid" point, blocks are chained one after the other, and
+//! loops are back edges from the end of a block to the start of an
+//! earlier one. Every point is mentioned by `cfg_edge`, and every loan
+//! that is killed or invalidated was issued by `borrow_region`.
+
+use facts::{AllFacts, Atom};
+
+/// The knobs controlling the shape and density of the generated facts.
+/// The same parameters (including the seed) always generate the same
+/// facts.
+#[derive(Clone, Debug)]
+pub struct GeneratorParams {
+    pub seed: u64,
+
+    /// The number of basic blocks in the CFG, at least 1.
+    pub blocks: usize,
+
+    /// The number of statements in each block, at least 1.
+    pub statements_per_block: usize,
+
+    /// How many loops to nest around the middle of the CFG. Each loop adds
+    /// a back edge from the last statement of a block to the first
+    /// statement of an earlier one.
+    pub loop_nesting: usize,
+
+    /// The probability that a block also branches over its successor.
+    pub branch_density: f64,
+
+    /// The number of regions, of which the first `universal_regions` are
+    /// universal.
+    pub regions: usize,
+    pub universal_regions: usize,
+
+    /// The probability that a statement issues a new loan.
+    pub loan_density: f64,
+
+    /// The probability that a statement kills, or invalidates, one of the
+    /// loans issued so far.
+    pub kill_density: f64,
+    pub invalidation_density: f64,
+
+    /// The probability that a statement requires one region to outlive
+    /// another.
+    pub outlives_density: f64,
+
+    /// The probability that a given region is live at a given point.
+    pub liveness_density: f64,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        GeneratorParams {
+            seed: 0,
+            blocks: 16,
+            statements_per_block: 4,
+            loop_nesting: 1,
+            branch_density: 0.2,
+            regions: 16,
+            universal_regions: 2,
+            loan_density: 0.1,
+            kill_density: 0.05,
+            invalidation_density: 0.1,
+            outlives_density: 0.3,
+            liveness_density: 0.2,
+        }
+    }
+}
+
+/// Generates a set of facts according to `params`. Atoms are numbered
+/// densely from zero, so they can be interned as `"r{i}"`, `"l{i}"`, and
+/// so on, by whoever needs names for them.
+pub fn generate<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
+    params: &GeneratorParams,
+) -> AllFacts<Region, Loan, Point, Variable, MovePath> {
+    assert!(params.blocks > 0, "at least one block is required");
+    assert!(
+        params.statements_per_block > 0,
+        "at least one statement per block is required"
+    );
+    assert!(
+        params.universal_regions <= params.regions,
+        "more universal regions than regions"
+    );
+
+    let mut rng = Rng::new(params.seed);
+    let mut all_facts = AllFacts::default();
+
+    // Statement `s` of block `b` has start point `2 * (b * n + s)` and mid
+    // point `2 * (b * n + s) + 1`.
+    let statements = params.statements_per_block;
+    let start = |block: usize, statement: usize| {
+        Point::from(2 * (block * statements + statement))
+    };
+    let mid = |block: usize, statement: usize| {
+        Point::from(2 * (block * statements + statement) + 1)
+    };
+    let last = statements - 1;
+
+    for block in 0..params.blocks {
+        for statement in 0..statements {
+            all_facts
+                .cfg_edge
+                .push((start(block, statement), mid(block, statement)));
+            if statement < last {
+                all_facts
+                    .cfg_edge
+                    .push((mid(block, statement), start(block, statement + 1)));
+            }
+        }
+
+        if block + 1 < params.blocks {
+            all_facts.cfg_edge.push((mid(block, last), start(block + 1, 0)));
+        }
+        if block + 2 < params.blocks && rng.chance(params.branch_density) {
+            all_facts.cfg_edge.push((mid(block, last), start(block + 2, 0)));
+        }
+    }
+
+    // Loop `i` goes from the end of the `i`-th block from the end back to
+    // the start of the `i`-th block, so loops nest inside each other.
+    for depth in 0..params.loop_nesting {
+        let head = depth;
+        let tail = match (params.blocks - 1).checked_sub(depth) {
+            Some(tail) if head <= tail => tail,
+            _ => break,
+        };
+        all_facts.cfg_edge.push((mid(tail, last), start(head, 0)));
+    }
+
+    all_facts.universal_region = (0..params.universal_regions).map(Region::from).collect();
+
+    let mut loans = 0;
+    for block in 0..params.blocks {
+        for statement in 0..statements {
+            let point = mid(block, statement);
+
+            if params.regions > 0 && rng.chance(params.loan_density) {
+                let region = Region::from(rng.below(params.regions));
+                all_facts.borrow_region.push((region, Loan::from(loans), point));
+                loans += 1;
+            }
+
+            if loans > 0 && rng.chance(params.kill_density) {
+                let loan = Loan::from(rng.below(loans));
+                all_facts.killed.push((loan, start(block, statement)));
+            }
+
+            if loans > 0 && rng.chance(params.invalidation_density) {
+                let loan = Loan::from(rng.below(loans));
+                all_facts.invalidates.push((point, loan));
+            }
+
+            if params.regions > 1 && rng.chance(params.outlives_density) {
+                let r1 = Region::from(rng.below(params.regions));
+                let r2 = Region::from(rng.below(params.regions));
+                if r1 != r2 {
+                    all_facts.outlives.push((r1, r2, point));
+                }
+            }
+        }
+    }
+
+    let points = 2 * params.blocks * statements;
+    for point in 0..points {
+        for region in 0..params.regions {
+            if rng.chance(params.liveness_density) {
+                all_facts
+                    .region_live_at
+                    .push((Region::from(region), Point::from(point)));
+            }
+        }
+    }
+
+    all_facts
+}
+
+/// A small splitmix64 generator: the facts only need to be reproducible
+/// from a seed, not cryptographically random, and this keeps the engine
+/// free of a dependency on `rand`.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed number in `0..bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Returns true with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        let sample = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        sample < p
+    }
+}
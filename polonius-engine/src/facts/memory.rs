@@ -0,0 +1,58 @@
+//! How much memory the relations take, to weigh the memory that the
+//! optimized algorithms spend on their indices against their speed.
+
+use std::mem;
+
+use super::{AllFacts, Atom};
+
+/// The size of a relation: its number of tuples, and the bytes that they
+/// take, estimated from the size of a tuple.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelationMemory {
+    pub relation: &'static str,
+    pub tuples: usize,
+    pub bytes: usize,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> AllFacts<R, L, P, V, M> {
+    /// The size of each relation, in the order of the fields. The bytes
+    /// are those of the allocations of the relations, which can hold more
+    /// tuples than they do.
+    pub fn relation_memory(&self) -> Vec<RelationMemory> {
+        macro_rules! relation_memory {
+            ($($relation:ident,)*) => {
+                vec![$(RelationMemory {
+                    relation: stringify!($relation),
+                    tuples: self.$relation.len(),
+                    bytes: self.$relation.capacity() * tuple_bytes(&self.$relation),
+                },)*]
+            };
+        }
+
+        relation_memory! {
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        }
+    }
+}
+
+fn tuple_bytes<T>(_relation: &[T]) -> usize {
+    mem::size_of::<T>()
+}
@@ -0,0 +1,174 @@
+mod atoms;
+mod builder;
+pub mod generator;
+mod memory;
+mod named;
+mod shared;
+mod sorted;
+mod validate;
+
+pub use self::atoms::{Loan, MovePath, Point, Region, Variable};
+pub use self::builder::AllFactsBuilder;
+pub use self::memory::RelationMemory;
+pub use self::named::{unquoted, AtomKind, AtomNames, Column, Row, RowError, INPUT_RELATIONS};
+pub use self::shared::SharedVec;
+pub use self::sorted::{SortedIndex, SortedRelation};
+pub use self::validate::ValidationReport;
+
+use std::hash::Hash;
+
+/// The "facts" which are the basis of the NLL borrow analysis.
+///
+/// The relations are `SharedVec`s: cloning the facts shares all of them,
+/// and changing a relation of a clone only copies that relation.
+#[derive(Clone)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct AllFacts<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    /// `borrow_region(R, B, P)` -- the region R may refer to data
+    /// from borrow B starting at the point P (this is usually the
+    /// point *after* a borrow rvalue)
+    pub borrow_region: SharedVec<(R, L, P)>,
+
+    /// `universal_region(R)` -- this is a "free region" within fn body
+    pub universal_region: SharedVec<R>,
+
+    /// `cfg_edge(P,Q)` for each edge P -> Q in the control flow
+    pub cfg_edge: SharedVec<(P, P)>,
+
+    /// `killed(B,P)` when some prefix of the path borrowed at B is assigned at point P
+    pub killed: SharedVec<(L, P)>,
+
+    /// `outlives(R1, R2, P)` when we require `R1@P: R2@P`
+    pub outlives: SharedVec<(R, R, P)>,
+
+    /// `region_live_at(R, P)` when the region R appears in a live variable at P.
+    /// The engine extends this with the liveness it computes from the
+    /// `var_*` facts below, so producers can provide either.
+    pub region_live_at: SharedVec<(R, P)>,
+
+    ///  `invalidates(P, L)` when the loan L is invalidated at point P
+    pub invalidates: SharedVec<(P, L)>,
+
+    /// `var_used_at(V, P)` when the variable V is used at point P
+    pub var_used_at: SharedVec<(V, P)>,
+
+    /// `var_defined_at(V, P)` when the variable V is overwritten at point P
+    pub var_defined_at: SharedVec<(V, P)>,
+
+    /// `var_drop_used_at(V, P)` when the variable V is dropped at point P
+    pub var_drop_used_at: SharedVec<(V, P)>,
+
+    /// `use_of_var_derefs_origin(V, R)` when the type of V contains the region R
+    pub use_of_var_derefs_origin: SharedVec<(V, R)>,
+
+    /// `drop_of_var_derefs_origin(V, R)` when dropping V may access data in region R
+    pub drop_of_var_derefs_origin: SharedVec<(V, R)>,
+
+    /// `child_path(M1, M2)` when the move path M1 is a field (or other
+    /// projection) of the move path M2
+    pub child_path: SharedVec<(M, M)>,
+
+    /// `path_is_var(M, V)` when the move path M is the whole variable V
+    pub path_is_var: SharedVec<(M, V)>,
+
+    /// `path_assigned_at(M, P)` when the move path M is (re)initialized at point P
+    pub path_assigned_at: SharedVec<(M, P)>,
+
+    /// `path_moved_at(M, P)` when the move path M is moved out of at point P
+    pub path_moved_at: SharedVec<(M, P)>,
+
+    /// `path_accessed_at(M, P)` when the move path M is read or borrowed at point P
+    pub path_accessed_at: SharedVec<(M, P)>,
+
+    /// `known_placeholder_subset(R1, R2)` when the universal region R1 is
+    /// declared to outlive the universal region R2, e.g. by a where clause
+    pub known_placeholder_subset: SharedVec<(R, R)>,
+
+    /// `loan_borrows_path(L, M)` when the loan L borrows the move path M.
+    /// Assigning M, or any path that M is a projection of, kills L, in
+    /// addition to the kills listed in `killed`
+    pub loan_borrows_path: SharedVec<(L, M)>,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> Default for AllFacts<R, L, P, V, M> {
+    fn default() -> Self {
+        AllFacts {
+            borrow_region: SharedVec::default(),
+            universal_region: SharedVec::default(),
+            cfg_edge: SharedVec::default(),
+            killed: SharedVec::default(),
+            outlives: SharedVec::default(),
+            region_live_at: SharedVec::default(),
+            invalidates: SharedVec::default(),
+            var_used_at: SharedVec::default(),
+            var_defined_at: SharedVec::default(),
+            var_drop_used_at: SharedVec::default(),
+            use_of_var_derefs_origin: SharedVec::default(),
+            drop_of_var_derefs_origin: SharedVec::default(),
+            child_path: SharedVec::default(),
+            path_is_var: SharedVec::default(),
+            path_assigned_at: SharedVec::default(),
+            path_moved_at: SharedVec::default(),
+            path_accessed_at: SharedVec::default(),
+            known_placeholder_subset: SharedVec::default(),
+            loan_borrows_path: SharedVec::default(),
+        }
+    }
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> AllFacts<R, L, P, V, M> {
+    /// Appends all the facts of `other` to `self`. Both sets of facts
+    /// must already agree on what each atom means, e.g. because they were
+    /// interned into the same tables.
+    pub fn merge(&mut self, other: AllFacts<R, L, P, V, M>) {
+        self.borrow_region.extend(other.borrow_region);
+        self.universal_region.extend(other.universal_region);
+        self.cfg_edge.extend(other.cfg_edge);
+        self.killed.extend(other.killed);
+        self.outlives.extend(other.outlives);
+        self.region_live_at.extend(other.region_live_at);
+        self.invalidates.extend(other.invalidates);
+        self.var_used_at.extend(other.var_used_at);
+        self.var_defined_at.extend(other.var_defined_at);
+        self.var_drop_used_at.extend(other.var_drop_used_at);
+        self.use_of_var_derefs_origin
+            .extend(other.use_of_var_derefs_origin);
+        self.drop_of_var_derefs_origin
+            .extend(other.drop_of_var_derefs_origin);
+        self.child_path.extend(other.child_path);
+        self.path_is_var.extend(other.path_is_var);
+        self.path_assigned_at.extend(other.path_assigned_at);
+        self.path_moved_at.extend(other.path_moved_at);
+        self.path_accessed_at.extend(other.path_accessed_at);
+        self.known_placeholder_subset
+            .extend(other.known_placeholder_subset);
+        self.loan_borrows_path.extend(other.loan_borrows_path);
+    }
+
+    /// The number of tuples in all the relations.
+    pub fn tuple_count(&self) -> usize {
+        self.borrow_region.len()
+            + self.universal_region.len()
+            + self.cfg_edge.len()
+            + self.killed.len()
+            + self.outlives.len()
+            + self.region_live_at.len()
+            + self.invalidates.len()
+            + self.var_used_at.len()
+            + self.var_defined_at.len()
+            + self.var_drop_used_at.len()
+            + self.use_of_var_derefs_origin.len()
+            + self.drop_of_var_derefs_origin.len()
+            + self.child_path.len()
+            + self.path_is_var.len()
+            + self.path_assigned_at.len()
+            + self.path_moved_at.len()
+            + self.path_accessed_at.len()
+            + self.known_placeholder_subset.len()
+            + self.loan_borrows_path.len()
+    }
+}
+
+pub trait Atom: From<usize> + Into<usize> + Copy + Clone + Eq + Ord + Hash + 'static {
+    fn index(self) -> usize;
+}
@@ -0,0 +1,317 @@
+//! The input relations by name, for the users of the engine that get their
+//! facts from outside of Rust: the C API pushes rows of atom indices into
+//! the relations it names, and the WebAssembly and Python modules push rows
+//! of atom names, which `AtomNames` interns into the ready-made atoms.
+
+use std::fmt;
+
+use super::{AllFacts, Atom, Loan, MovePath, Point, Region, SharedVec, Variable};
+use rustc_hash::FxHashMap;
+
+/// The input relations, in the order of the fields of `AllFacts`.
+pub const INPUT_RELATIONS: [&str; 19] = [
+    "borrow_region",
+    "universal_region",
+    "cfg_edge",
+    "killed",
+    "outlives",
+    "region_live_at",
+    "invalidates",
+    "var_used_at",
+    "var_defined_at",
+    "var_drop_used_at",
+    "use_of_var_derefs_origin",
+    "drop_of_var_derefs_origin",
+    "child_path",
+    "path_is_var",
+    "path_assigned_at",
+    "path_moved_at",
+    "path_accessed_at",
+    "known_placeholder_subset",
+    "loan_borrows_path",
+];
+
+/// `name` without the quotes that the `.facts` files put around it.
+pub fn unquoted(name: &str) -> &str {
+    name.trim_matches('"')
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AtomKind {
+    Region,
+    Loan,
+    Point,
+    Variable,
+    MovePath,
+}
+
+/// A ready-made atom, as a column of the rows of the input relations.
+pub trait Column: Atom {
+    const KIND: AtomKind;
+}
+
+/// A row of an input relation: an atom, or a tuple of atoms.
+pub trait Row: Sized {
+    const ARITY: usize;
+
+    /// The row of the atoms that `column` gives, called with the kind of
+    /// each column in turn.
+    fn from_columns(column: &mut dyn FnMut(AtomKind) -> usize) -> Self;
+
+    /// The kind and the index of the atom of each column.
+    fn columns(&self) -> Vec<(AtomKind, usize)>;
+}
+
+macro_rules! column {
+    ($t:ident) => {
+        impl Column for $t {
+            const KIND: AtomKind = AtomKind::$t;
+        }
+
+        impl Row for $t {
+            const ARITY: usize = 1;
+
+            fn from_columns(column: &mut dyn FnMut(AtomKind) -> usize) -> Self {
+                $t::from(column(AtomKind::$t))
+            }
+
+            fn columns(&self) -> Vec<(AtomKind, usize)> {
+                vec![(AtomKind::$t, self.index())]
+            }
+        }
+    };
+}
+
+column!(Region);
+column!(Loan);
+column!(Point);
+column!(Variable);
+column!(MovePath);
+
+impl<A: Column, B: Column> Row for (A, B) {
+    const ARITY: usize = 2;
+
+    fn from_columns(column: &mut dyn FnMut(AtomKind) -> usize) -> Self {
+        let a = A::from(column(A::KIND));
+        let b = B::from(column(B::KIND));
+        (a, b)
+    }
+
+    fn columns(&self) -> Vec<(AtomKind, usize)> {
+        vec![(A::KIND, self.0.index()), (B::KIND, self.1.index())]
+    }
+}
+
+impl<A: Column, B: Column, C: Column> Row for (A, B, C) {
+    const ARITY: usize = 3;
+
+    fn from_columns(column: &mut dyn FnMut(AtomKind) -> usize) -> Self {
+        let a = A::from(column(A::KIND));
+        let b = B::from(column(B::KIND));
+        let c = C::from(column(C::KIND));
+        (a, b, c)
+    }
+
+    fn columns(&self) -> Vec<(AtomKind, usize)> {
+        vec![
+            (A::KIND, self.0.index()),
+            (B::KIND, self.1.index()),
+            (C::KIND, self.2.index()),
+        ]
+    }
+}
+
+/// Why a row can't be added to a relation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RowError {
+    UnknownRelation(String),
+    WrongArity {
+        relation: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RowError::UnknownRelation(ref relation) => write!(f, "unknown relation `{}`", relation),
+            RowError::WrongArity {
+                relation,
+                expected,
+                found,
+            } => write!(
+                f,
+                "a row of `{}` needs {} columns, not {}",
+                relation, expected, found
+            ),
+        }
+    }
+}
+
+/// Calls `$apply!(field)` on the field of the input relation named
+/// `$relation`, or evaluates to an `UnknownRelation` error.
+macro_rules! with_relation {
+    ($relation:expr, $apply:ident) => {
+        with_relation!(@fields $relation, $apply,
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        )
+    };
+    (@fields $relation:expr, $apply:ident, $($t:ident,)*) => {
+        match $relation {
+            $(
+                stringify!($t) => $apply!($t),
+            )*
+            relation => Err(RowError::UnknownRelation(relation.to_string())),
+        }
+    };
+}
+
+impl AllFacts<Region, Loan, Point, Variable, MovePath> {
+    /// Adds the row of `columns` to the input relation named `relation`.
+    /// `atom` gives the index of the atom of each column, from its kind
+    /// and the column, e.g. by interning its name.
+    pub fn push_row<C: Copy>(
+        &mut self,
+        relation: &str,
+        columns: &[C],
+        mut atom: impl FnMut(AtomKind, C) -> usize,
+    ) -> Result<(), RowError> {
+        macro_rules! push {
+            ($t:ident) => {
+                push(&mut self.$t, stringify!($t), columns, &mut atom)
+            };
+        }
+
+        with_relation!(relation, push)
+    }
+
+    /// The kind and the index of the atom of each column of each row of
+    /// the input relation named `relation`.
+    pub fn relation_rows(&self, relation: &str) -> Result<Vec<Vec<(AtomKind, usize)>>, RowError> {
+        macro_rules! columns {
+            ($t:ident) => {
+                Ok(self.$t.iter().map(|row| row.columns()).collect())
+            };
+        }
+
+        with_relation!(relation, columns)
+    }
+}
+
+fn push<R: Row + Clone, C: Copy>(
+    rows: &mut SharedVec<R>,
+    relation: &'static str,
+    columns: &[C],
+    atom: &mut dyn FnMut(AtomKind, C) -> usize,
+) -> Result<(), RowError> {
+    if columns.len() != R::ARITY {
+        return Err(RowError::WrongArity {
+            relation,
+            expected: R::ARITY,
+            found: columns.len(),
+        });
+    }
+    let mut columns = columns.iter();
+    rows.push(R::from_columns(&mut |kind| {
+        atom(kind, *columns.next().unwrap())
+    }));
+    Ok(())
+}
+
+/// Maps the names of the atoms of one kind to their indices.
+#[derive(Clone, Debug, Default)]
+struct Names {
+    indices: FxHashMap<String, usize>,
+    names: Vec<String>,
+}
+
+impl Names {
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.indices.get(name) {
+            return index;
+        }
+        self.names.push(name.to_string());
+        self.indices.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+}
+
+/// The names of the ready-made atoms, for the facts that name their
+/// atoms: the atoms of each kind are numbered in the order their names
+/// are first interned.
+#[derive(Clone, Debug, Default)]
+pub struct AtomNames {
+    regions: Names,
+    loans: Names,
+    points: Names,
+    variables: Names,
+    move_paths: Names,
+}
+
+impl AtomNames {
+    pub fn new() -> Self {
+        AtomNames::default()
+    }
+
+    /// The index of the atom of `kind` named `name`, which is numbered
+    /// next if no atom of `kind` has that name yet.
+    pub fn intern(&mut self, kind: AtomKind, name: &str) -> usize {
+        self.names_mut(kind).intern(name)
+    }
+
+    /// The atom named `name`, if one was interned.
+    pub fn lookup<A: Column>(&self, name: &str) -> Option<A> {
+        self.names(A::KIND)
+            .indices
+            .get(name)
+            .map(|&index| A::from(index))
+    }
+
+    pub fn name<A: Column>(&self, atom: A) -> &str {
+        self.name_of(A::KIND, atom.index())
+    }
+
+    /// The name of the atom of `kind` at `index`.
+    pub fn name_of(&self, kind: AtomKind, index: usize) -> &str {
+        &self.names(kind).names[index]
+    }
+
+    fn names(&self, kind: AtomKind) -> &Names {
+        match kind {
+            AtomKind::Region => &self.regions,
+            AtomKind::Loan => &self.loans,
+            AtomKind::Point => &self.points,
+            AtomKind::Variable => &self.variables,
+            AtomKind::MovePath => &self.move_paths,
+        }
+    }
+
+    fn names_mut(&mut self, kind: AtomKind) -> &mut Names {
+        match kind {
+            AtomKind::Region => &mut self.regions,
+            AtomKind::Loan => &mut self.loans,
+            AtomKind::Point => &mut self.points,
+            AtomKind::Variable => &mut self.variables,
+            AtomKind::MovePath => &mut self.move_paths,
+        }
+    }
+}
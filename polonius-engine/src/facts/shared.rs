@@ -0,0 +1,156 @@
+//! The storage of the relations of `AllFacts`: the tuples are shared
+//! between the clones of the facts, and only copied when one of the
+//! clones changes them, so that running several algorithms, or
+//! minimizing the facts, doesn't copy every relation each time.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::Arc;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A vector whose clones share their elements until one of them is
+/// mutated, which copies the elements if they are still shared.
+pub struct SharedVec<T> {
+    elements: Arc<Vec<T>>,
+}
+
+impl<T> SharedVec<T> {
+    pub fn new() -> Self {
+        SharedVec::from(Vec::new())
+    }
+
+    /// Whether `self` and `other` are clones that still share their
+    /// elements.
+    pub fn shares_storage_with(&self, other: &SharedVec<T>) -> bool {
+        Arc::ptr_eq(&self.elements, &other.elements)
+    }
+}
+
+impl<T: Clone> SharedVec<T> {
+    /// The elements, copied only if they are shared with another clone.
+    pub fn into_vec(self) -> Vec<T> {
+        match Arc::try_unwrap(self.elements) {
+            Ok(elements) => elements,
+            Err(elements) => (*elements).clone(),
+        }
+    }
+}
+
+impl<T> Deref for SharedVec<T> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.elements
+    }
+}
+
+impl<T: Clone> DerefMut for SharedVec<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        Arc::make_mut(&mut self.elements)
+    }
+}
+
+impl<T> Clone for SharedVec<T> {
+    fn clone(&self) -> Self {
+        SharedVec {
+            elements: self.elements.clone(),
+        }
+    }
+}
+
+impl<T> Default for SharedVec<T> {
+    fn default() -> Self {
+        SharedVec::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedVec<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.elements.fmt(formatter)
+    }
+}
+
+impl<T: PartialEq> PartialEq for SharedVec<T> {
+    fn eq(&self, other: &SharedVec<T>) -> bool {
+        self.elements == other.elements
+    }
+}
+
+impl<T: Eq> Eq for SharedVec<T> {}
+
+impl<T: PartialEq> PartialEq<Vec<T>> for SharedVec<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        *self.elements == *other
+    }
+}
+
+impl<T: PartialEq> PartialEq<SharedVec<T>> for Vec<T> {
+    fn eq(&self, other: &SharedVec<T>) -> bool {
+        *self == *other.elements
+    }
+}
+
+impl<T> From<Vec<T>> for SharedVec<T> {
+    fn from(elements: Vec<T>) -> Self {
+        SharedVec {
+            elements: Arc::new(elements),
+        }
+    }
+}
+
+impl<T: Clone> From<SharedVec<T>> for Vec<T> {
+    fn from(shared: SharedVec<T>) -> Self {
+        shared.into_vec()
+    }
+}
+
+impl<T> FromIterator<T> for SharedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iterator: I) -> Self {
+        SharedVec::from(iterator.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Clone> IntoIterator for SharedVec<T> {
+    type Item = T;
+    type IntoIter = ::std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SharedVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a mut SharedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Arc::make_mut(&mut self.elements).iter_mut()
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<T: Serialize> Serialize for SharedVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (*self.elements).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serialization")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SharedVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(SharedVec::from)
+    }
+}
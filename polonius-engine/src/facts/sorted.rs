@@ -0,0 +1,159 @@
+//! Relations stored sorted and without duplicates, like the relations of
+//! datafrog, but outside of an iteration: looking up the tuples of a key
+//! is a binary search, and joining two relations on their keys is a
+//! single merge of both, rather than building a hash map of one of them.
+
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::Deref;
+
+/// A set of tuples, sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortedRelation<T: Ord> {
+    elements: Vec<T>,
+}
+
+impl<T: Ord> SortedRelation<T> {
+    /// Sorts `elements`, and removes their duplicates.
+    pub fn from_vec(mut elements: Vec<T>) -> Self {
+        elements.sort_unstable();
+        elements.dedup();
+        SortedRelation { elements }
+    }
+
+    pub fn contains(&self, tuple: &T) -> bool {
+        self.elements.binary_search(tuple).is_ok()
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.elements
+    }
+}
+
+impl<K: Ord, V: Ord> SortedRelation<(K, V)> {
+    /// The tuples whose key, the first element, is `key`.
+    pub fn with_key(&self, key: &K) -> &[(K, V)] {
+        let start = partition_point(&self.elements, |&(ref k, _)| k < key);
+        let len = partition_point(&self.elements[start..], |&(ref k, _)| k == key);
+        &self.elements[start..start + len]
+    }
+
+    /// Calls `result` with the key and the values of every pair of tuples
+    /// of the two relations that have the same key, in the order of the
+    /// keys, in a single pass over both relations.
+    pub fn merge_join<V2: Ord>(
+        &self,
+        other: &SortedRelation<(K, V2)>,
+        mut result: impl FnMut(&K, &V, &V2),
+    ) {
+        let (left, right) = (&self.elements[..], &other.elements[..]);
+        let (mut i, mut j) = (0, 0);
+        while i < left.len() && j < right.len() {
+            match left[i].0.cmp(&right[j].0) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    let key = &left[i].0;
+                    let left_len = partition_point(&left[i..], |&(ref k, _)| k == key);
+                    let right_len = partition_point(&right[j..], |&(ref k, _)| k == key);
+                    for &(_, ref value) in &left[i..i + left_len] {
+                        for &(_, ref other_value) in &right[j..j + right_len] {
+                            result(key, value, other_value);
+                        }
+                    }
+                    i += left_len;
+                    j += right_len;
+                }
+            }
+        }
+    }
+}
+
+impl<T: Ord> Deref for SortedRelation<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.elements
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SortedRelation<T> {
+    fn from(elements: Vec<T>) -> Self {
+        SortedRelation::from_vec(elements)
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedRelation<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iterator: I) -> Self {
+        SortedRelation::from_vec(iterator.into_iter().collect())
+    }
+}
+
+/// The values of a relation of pairs, grouped by key: the values of a key
+/// are a sorted slice, found by a binary search of the keys.
+#[derive(Clone, Debug)]
+pub struct SortedIndex<K: Ord, V: Ord> {
+    keys: Vec<K>,
+
+    /// The start of the values of each key in `values`, followed by the
+    /// number of values.
+    starts: Vec<usize>,
+    values: Vec<V>,
+}
+
+impl<K: Ord, V: Ord> SortedIndex<K, V> {
+    /// The values of `key`, sorted.
+    pub fn get(&self, key: &K) -> &[V] {
+        match self.keys.binary_search(key) {
+            Ok(index) => &self.values[self.starts[index]..self.starts[index + 1]],
+            Err(_) => &[],
+        }
+    }
+
+    /// The keys that have values, sorted.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+}
+
+impl<K: Ord, V: Ord> From<SortedRelation<(K, V)>> for SortedIndex<K, V> {
+    fn from(relation: SortedRelation<(K, V)>) -> Self {
+        let mut keys: Vec<K> = Vec::new();
+        let mut starts = Vec::new();
+        let mut values = Vec::with_capacity(relation.len());
+        for (key, value) in relation.into_vec() {
+            if keys.last() != Some(&key) {
+                keys.push(key);
+                starts.push(values.len());
+            }
+            values.push(value);
+        }
+        starts.push(values.len());
+        SortedIndex {
+            keys,
+            starts,
+            values,
+        }
+    }
+}
+
+impl<K: Ord, V: Ord> FromIterator<(K, V)> for SortedIndex<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iterator: I) -> Self {
+        SortedIndex::from(iterator.into_iter().collect::<SortedRelation<_>>())
+    }
+}
+
+/// The number of leading elements of `slice` that satisfy `predicate`,
+/// which must hold of a prefix of the slice.
+fn partition_point<T>(slice: &[T], mut predicate: impl FnMut(&T) -> bool) -> usize {
+    let (mut low, mut high) = (0, slice.len());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if predicate(&slice[middle]) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
@@ -0,0 +1,132 @@
+use facts::{AllFacts, Atom};
+use std::collections::BTreeSet;
+
+/// Problems found by `AllFacts::validate`. None of them stop the analysis
+/// from running, but they usually mean the facts were produced (or edited)
+/// incorrectly, and that the results won't mean much.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationReport<L: Atom, P: Atom> {
+    /// Points that a relation refers to but that are not part of the CFG,
+    /// paired with the name of the relation.
+    pub points_not_in_cfg: Vec<(&'static str, P)>,
+
+    /// Loans that a relation refers to but that `borrow_region` never
+    /// issues, paired with the name of the relation.
+    pub unissued_loans: Vec<(&'static str, L)>,
+
+    /// The number of duplicate tuples in each relation that has any.
+    pub duplicate_tuples: Vec<(&'static str, usize)>,
+}
+
+impl<L: Atom, P: Atom> ValidationReport<L, P> {
+    pub fn is_empty(&self) -> bool {
+        self.points_not_in_cfg.is_empty()
+            && self.unissued_loans.is_empty()
+            && self.duplicate_tuples.is_empty()
+    }
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> AllFacts<R, L, P, V, M> {
+    /// Checks that the facts are consistent with each other: every point
+    /// is in the CFG, every loan is issued, and no tuple is repeated.
+    pub fn validate(&self) -> ValidationReport<L, P> {
+        let mut cfg_points = BTreeSet::new();
+        for &(p, q) in &self.cfg_edge {
+            cfg_points.insert(p);
+            cfg_points.insert(q);
+        }
+        let issued: BTreeSet<L> = self.borrow_region.iter().map(|&(_, l, _)| l).collect();
+
+        let mut points_not_in_cfg = Vec::new();
+        let borrow_region_points = self.borrow_region.iter().map(|&(_, _, p)| p);
+        push_unknown("borrow_region", borrow_region_points, &cfg_points, &mut points_not_in_cfg);
+        let killed_points = self.killed.iter().map(|&(_, p)| p);
+        push_unknown("killed", killed_points, &cfg_points, &mut points_not_in_cfg);
+        let outlives_points = self.outlives.iter().map(|&(_, _, p)| p);
+        push_unknown("outlives", outlives_points, &cfg_points, &mut points_not_in_cfg);
+        let region_live_at_points = self.region_live_at.iter().map(|&(_, p)| p);
+        push_unknown("region_live_at", region_live_at_points, &cfg_points, &mut points_not_in_cfg);
+        let invalidates_points = self.invalidates.iter().map(|&(p, _)| p);
+        push_unknown("invalidates", invalidates_points, &cfg_points, &mut points_not_in_cfg);
+        let var_used_at_points = self.var_used_at.iter().map(|&(_, p)| p);
+        push_unknown("var_used_at", var_used_at_points, &cfg_points, &mut points_not_in_cfg);
+        let var_defined_at_points = self.var_defined_at.iter().map(|&(_, p)| p);
+        push_unknown("var_defined_at", var_defined_at_points, &cfg_points, &mut points_not_in_cfg);
+        let var_drop_used_at_points = self.var_drop_used_at.iter().map(|&(_, p)| p);
+        push_unknown(
+            "var_drop_used_at",
+            var_drop_used_at_points,
+            &cfg_points,
+            &mut points_not_in_cfg,
+        );
+        for &(relation, path_points) in &[
+            ("path_assigned_at", &self.path_assigned_at),
+            ("path_moved_at", &self.path_moved_at),
+            ("path_accessed_at", &self.path_accessed_at),
+        ] {
+            let path_points = path_points.iter().map(|&(_, p)| p);
+            push_unknown(relation, path_points, &cfg_points, &mut points_not_in_cfg);
+        }
+
+        let mut unissued_loans = Vec::new();
+        let killed_loans = self.killed.iter().map(|&(l, _)| l);
+        push_unknown("killed", killed_loans, &issued, &mut unissued_loans);
+        let invalidates_loans = self.invalidates.iter().map(|&(_, l)| l);
+        push_unknown("invalidates", invalidates_loans, &issued, &mut unissued_loans);
+        let borrowed_path_loans = self.loan_borrows_path.iter().map(|&(l, _)| l);
+        push_unknown("loan_borrows_path", borrowed_path_loans, &issued, &mut unissued_loans);
+
+        macro_rules! duplicate_tuples {
+            ($($t:ident,)*) => {
+                vec![$((stringify!($t), count_duplicates(&self.$t)),)*]
+            }
+        }
+        let duplicate_tuples = duplicate_tuples! {
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        };
+
+        ValidationReport {
+            points_not_in_cfg,
+            unissued_loans,
+            duplicate_tuples: duplicate_tuples
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .collect(),
+        }
+    }
+}
+
+/// Pushes each distinct atom of `atoms` that isn't in `known` onto
+/// `unknown`, in order, tagged with `relation`.
+fn push_unknown<T: Ord + Copy>(
+    relation: &'static str,
+    atoms: impl Iterator<Item = T>,
+    known: &BTreeSet<T>,
+    unknown: &mut Vec<(&'static str, T)>,
+) {
+    let atoms: BTreeSet<T> = atoms.filter(|atom| !known.contains(atom)).collect();
+    unknown.extend(atoms.into_iter().map(|atom| (relation, atom)));
+}
+
+fn count_duplicates<T: Ord>(tuples: &[T]) -> usize {
+    let distinct: BTreeSet<&T> = tuples.iter().collect();
+    tuples.len() - distinct.len()
+}
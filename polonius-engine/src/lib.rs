@@ -5,11 +5,56 @@
 extern crate datafrog;
 extern crate rustc_hash;
 
+#[cfg(feature = "parallel")]
+extern crate rayon;
+
+#[cfg(feature = "serialization")]
+extern crate serde;
+#[cfg(feature = "serialization")]
+#[macro_use]
+extern crate serde_derive;
+
 mod facts;
+mod minimize;
 mod output;
+mod preprocess;
 
 // Reexports of facts
 pub use facts::AllFacts;
+pub use facts::AllFactsBuilder;
 pub use facts::Atom;
+pub use facts::generator;
+pub use facts::{SortedIndex, SortedRelation};
+pub use facts::{Loan, MovePath, Point, Region, Variable};
+pub use facts::RelationMemory;
+pub use facts::{unquoted, AtomKind, AtomNames, Column, Row, RowError, INPUT_RELATIONS};
+pub use facts::SharedVec;
+pub use facts::ValidationReport;
+pub use minimize::minimize;
 pub use output::Algorithm;
+pub use output::Budget;
+pub use output::BudgetExceeded;
+pub use output::Derivation;
+pub use output::ErrorObserver;
+pub use output::ErrorsDiff;
+pub use output::FactsDelta;
+pub use output::IncrementalAnalysis;
+pub use output::{AtomBitsets, LoanBitsets, RegionBitsets};
+pub use output::OriginStep;
 pub use output::Output;
+pub use output::OutputDiff;
+pub use output::QueryEngine;
+pub use output::RelationDiff;
+pub use output::SolverObserver;
+pub use output::Tuple;
+pub use preprocess::prune_dead_loans;
+pub use preprocess::prune_dead_regions;
+pub use preprocess::reduce_outlives;
+pub use preprocess::remove_unreachable_points;
+pub use preprocess::PointMapping;
+pub use preprocess::PreprocessLevel;
+pub use preprocess::Preprocessed;
+pub use preprocess::Preprocessor;
+pub use preprocess::PruningStats;
+pub use preprocess::simplify_cfg;
+pub use preprocess::verify_simplification;
@@ -0,0 +1,107 @@
+//! Delta-debugging style minimization of a set of facts, for reducing
+//! bug reports against the analysis to something a human can read.
+
+use facts::{AllFacts, Atom};
+
+/// Shrinks `all_facts` as far as possible while `property` keeps
+/// holding, by bisecting each relation and dropping chunks of tuples whose
+/// removal preserves the property. Passes over all relations are repeated
+/// until none of them can be shrunk further.
+///
+/// `property` must hold for `all_facts` itself.
+pub fn minimize<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+    mut property: impl FnMut(&AllFacts<Region, Loan, Point, Variable, MovePath>) -> bool,
+) -> AllFacts<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    assert!(
+        property(all_facts),
+        "the property to preserve does not hold for the initial facts"
+    );
+
+    let mut all_facts = all_facts.clone();
+
+    macro_rules! minimize_relations {
+        ($($t:ident,)*) => {
+            loop {
+                let mut changed = false;
+                $(
+                    let current = all_facts.clone();
+                    changed |= minimize_tuples(&mut all_facts.$t, |tuples| {
+                        let candidate = AllFacts {
+                            $t: tuples.to_vec().into(),
+                            ..current.clone()
+                        };
+                        property(&candidate)
+                    });
+                )*
+
+                if !changed {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Edges first: removing them tends to make many other facts irrelevant.
+    minimize_relations! {
+        cfg_edge,
+        region_live_at,
+        outlives,
+        borrow_region,
+        killed,
+        invalidates,
+        universal_region,
+        var_used_at,
+        var_defined_at,
+        var_drop_used_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        known_placeholder_subset,
+        loan_borrows_path,
+    }
+
+    all_facts
+}
+
+/// Removes chunks of `tuples`, from the whole vector down to single
+/// tuples, for as long as `still_holds` accepts the remaining tuples.
+/// Returns whether anything was removed.
+fn minimize_tuples<T: Clone>(
+    tuples: &mut Vec<T>,
+    mut still_holds: impl FnMut(&[T]) -> bool,
+) -> bool {
+    let mut changed = false;
+    let mut chunk_size = tuples.len();
+    while chunk_size > 0 {
+        let mut start = 0;
+        while start < tuples.len() {
+            let end = (start + chunk_size).min(tuples.len());
+            let candidate: Vec<T> = tuples[..start]
+                .iter()
+                .chain(&tuples[end..])
+                .cloned()
+                .collect();
+
+            if still_holds(&candidate) {
+                *tuples = candidate;
+                changed = true;
+            } else {
+                start += chunk_size;
+            }
+        }
+        chunk_size /= 2;
+    }
+    changed
+}
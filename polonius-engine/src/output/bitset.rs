@@ -0,0 +1,217 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dense representations of the relations that map points to sets of
+//! atoms, like `borrow_live_at` or `region_live_at`, for the functions
+//! where many atoms are live at many points: a bit per atom at each point
+//! takes much less memory than a vector of them in a map, and the unions
+//! and intersections of the sets are computed a word of 64 atoms at a
+//! time.
+
+use rustc_hash::FxHashMap;
+use std::marker::PhantomData;
+use std::mem;
+
+use facts::Atom;
+
+const BITS: usize = 64;
+
+/// A set of atoms `A` for each key `K`, usually a point, as a bitset
+/// indexed by the atoms. The bitsets of the keys are laid out one after
+/// the other, by key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AtomBitsets<A: Atom, K: Atom> {
+    words_per_key: usize,
+    words: Vec<u64>,
+    atoms: PhantomData<(A, K)>,
+}
+
+/// The loans live at each point.
+pub type LoanBitsets<Loan, Point> = AtomBitsets<Loan, Point>;
+
+/// The regions live at each point.
+pub type RegionBitsets<Region, Point> = AtomBitsets<Region, Point>;
+
+impl<A: Atom, K: Atom> AtomBitsets<A, K> {
+    /// Empty sets for the keys below `keys`, which can hold the atoms below
+    /// `atoms`.
+    pub fn new(keys: usize, atoms: usize) -> Self {
+        let words_per_key = (atoms + BITS - 1) / BITS;
+        AtomBitsets {
+            words_per_key,
+            words: vec![0; keys * words_per_key],
+            atoms: PhantomData,
+        }
+    }
+
+    /// The bitsets of the atoms of each key of `relation`, which are sized
+    /// for the largest key and atom it mentions.
+    pub fn from_map(relation: &FxHashMap<K, Vec<A>>) -> Self {
+        let keys = relation.keys().map(|&key| key.index() + 1).max();
+        let atoms = relation
+            .values()
+            .flat_map(|atoms| atoms.iter().map(|&atom| atom.index() + 1))
+            .max();
+
+        let mut bitsets = AtomBitsets::new(keys.unwrap_or(0), atoms.unwrap_or(0));
+        for (&key, atoms) in relation {
+            for &atom in atoms {
+                bitsets.insert(key, atom);
+            }
+        }
+        bitsets
+    }
+
+    /// Adds `atom` to the set of `key`, which must fit in the bitsets, and
+    /// returns whether it was missing.
+    pub fn insert(&mut self, key: K, atom: A) -> bool {
+        let (word, bit) = self.position(key, atom);
+        let missing = self.words[word] & (1 << bit) == 0;
+        self.words[word] |= 1 << bit;
+        missing
+    }
+
+    pub fn contains(&self, key: K, atom: A) -> bool {
+        if key.index() >= self.keys() || atom.index() >= self.words_per_key * BITS {
+            return false;
+        }
+        let (word, bit) = self.position(key, atom);
+        self.words[word] & (1 << bit) != 0
+    }
+
+    /// The atoms of `key`, sorted.
+    pub fn atoms_at(&self, key: K) -> Vec<A> {
+        let mut atoms = Vec::new();
+        for (index, &word) in self.words_at(key).iter().enumerate() {
+            for bit in 0..BITS {
+                if word & (1 << bit) != 0 {
+                    atoms.push(A::from(index * BITS + bit));
+                }
+            }
+        }
+        atoms
+    }
+
+    /// Adds the atoms of `source` in `other` to the set of `key`, and
+    /// returns whether that changed it. Both bitsets must hold the same
+    /// atoms.
+    pub fn union_from<K2: Atom>(&mut self, key: K, other: &AtomBitsets<A, K2>, source: K2) -> bool {
+        assert_eq!(self.words_per_key, other.words_per_key);
+        let start = key.index() * self.words_per_key;
+        let mut changed = false;
+        for (index, &word) in other.words_at(source).iter().enumerate() {
+            let target = &mut self.words[start + index];
+            changed |= word & !*target != 0;
+            *target |= word;
+        }
+        changed
+    }
+
+    /// Adds the atoms of `source` that aren't in the set of `target` in
+    /// `excluded` to the set of `target`, and returns whether that changed
+    /// it: the union along a CFG edge `target -> source` of a backward
+    /// analysis, where `excluded` are the atoms the edge kills.
+    pub fn propagate(&mut self, source: K, target: K, excluded: &Self) -> bool {
+        assert_eq!(self.words_per_key, excluded.words_per_key);
+        let (source, target) = (source.index(), target.index());
+        if source >= self.keys() || target >= self.keys() {
+            return false;
+        }
+        let excluded = excluded.words_at(K::from(target));
+        let mut changed = false;
+        for index in 0..self.words_per_key {
+            let mut word = self.words[source * self.words_per_key + index];
+            if let Some(&excluded) = excluded.get(index) {
+                word &= !excluded;
+            }
+            let position = target * self.words_per_key + index;
+            changed |= word & !self.words[position] != 0;
+            self.words[position] |= word;
+        }
+        changed
+    }
+
+    /// The atoms of `key` that are also in its set in `other`, sorted.
+    pub fn intersection_at(&self, key: K, other: &Self) -> Vec<A> {
+        let theirs = other.words_at(key);
+        let mut atoms = Vec::new();
+        for (index, &word) in self.words_at(key).iter().enumerate() {
+            let word = word & theirs.get(index).cloned().unwrap_or(0);
+            for bit in 0..BITS {
+                if word & (1 << bit) != 0 {
+                    atoms.push(A::from(index * BITS + bit));
+                }
+            }
+        }
+        atoms
+    }
+
+    /// The number of `(key, atom)` pairs in the sets.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The bytes that the words of the bitsets take.
+    pub fn bytes(&self) -> usize {
+        self.words.len() * mem::size_of::<u64>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// The sets, in the representation of `Output::borrow_live_at`: the
+    /// keys without atoms are missing, and the atoms are sorted.
+    pub fn to_map(&self) -> FxHashMap<K, Vec<A>> {
+        (0..self.keys())
+            .map(|index| {
+                let key = K::from(index);
+                (key, self.atoms_at(key))
+            })
+            .filter(|(_, atoms)| !atoms.is_empty())
+            .collect()
+    }
+
+    /// The `(atom, key)` pairs of the sets, sorted like the facts of
+    /// `region_live_at`.
+    pub fn to_pairs(&self) -> Vec<(A, K)> {
+        let mut pairs: Vec<(A, K)> = (0..self.keys())
+            .flat_map(|index| {
+                let key = K::from(index);
+                self.atoms_at(key).into_iter().map(move |atom| (atom, key))
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn keys(&self) -> usize {
+        if self.words_per_key == 0 {
+            0
+        } else {
+            self.words.len() / self.words_per_key
+        }
+    }
+
+    /// The words of the set of `key`, which are empty if the bitsets don't
+    /// have it.
+    fn words_at(&self, key: K) -> &[u64] {
+        if key.index() >= self.keys() {
+            return &[];
+        }
+        let start = key.index() * self.words_per_key;
+        &self.words[start..start + self.words_per_key]
+    }
+
+    fn position(&self, key: K, atom: A) -> (usize, usize) {
+        let word = key.index() * self.words_per_key + atom.index() / BITS;
+        (word, atom.index() % BITS)
+    }
+}
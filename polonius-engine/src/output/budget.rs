@@ -0,0 +1,285 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bounds how long the loan and subset analyses may run. The budget is a
+//! solver observer like any other, checked once per round of the fixpoint
+//! iterations: once it runs out, the remaining rounds derive nothing, so
+//! the iterations drain and stop with the tuples derived so far.
+
+use std::cmp;
+use std::fmt;
+use std::mem;
+use std::time::Duration;
+
+use crate::output::clock::Instant;
+use crate::output::observer::{ErrorObserver, SolverObserver};
+use datafrog::Variable;
+use facts::Atom;
+
+/// Limits on the work `Output::compute_with_budget` may do. The default
+/// budget is unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// How long the analyses may run.
+    pub max_duration: Option<Duration>,
+
+    /// How many tuples the analyses may derive, counting each tuple of
+    /// the main relations, e.g. `subset` and `requires`, once.
+    pub max_tuples: Option<usize>,
+}
+
+impl Budget {
+    pub fn unlimited() -> Self {
+        Budget::default()
+    }
+
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    pub fn with_max_tuples(mut self, max_tuples: usize) -> Self {
+        self.max_tuples = Some(max_tuples);
+        self
+    }
+}
+
+/// The limit of a `Budget` that stopped an analysis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetExceeded {
+    Duration,
+    Tuples,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BudgetExceeded::Duration => write!(f, "the analysis ran out of time"),
+            BudgetExceeded::Tuples => write!(f, "the analysis derived too many tuples"),
+        }
+    }
+}
+
+/// What is left of a `Budget` while the analyses run: the observer that
+/// stops them once they exceed it.
+struct Limits {
+    budget: Budget,
+    start: Instant,
+    exceeded: Option<BudgetExceeded>,
+}
+
+impl SolverObserver for Limits {
+    fn iteration(&mut self, _delta: usize, total: usize) {
+        if self.exceeded.is_some() {
+            return;
+        }
+        if self.budget.max_tuples.map_or(false, |max| total > max) {
+            self.exceeded = Some(BudgetExceeded::Tuples);
+        } else if self
+            .budget
+            .max_duration
+            .map_or(false, |max| self.start.elapsed() > max)
+        {
+            self.exceeded = Some(BudgetExceeded::Duration);
+        }
+    }
+
+    fn exceeded(&self) -> Option<BudgetExceeded> {
+        self.exceeded
+    }
+}
+
+/// Tells `progress` the tuples derived so far, once per round.
+pub(super) struct Progress<'a>(pub(super) &'a mut dyn FnMut(usize));
+
+impl<'a> SolverObserver for Progress<'a> {
+    fn iteration(&mut self, _delta: usize, total: usize) {
+        (self.0)(total)
+    }
+}
+
+/// The state of the analyses as they run: the budget, the observer to
+/// tell about the errors they derive, and the observer to tell how the
+/// solving goes, which may stop them too.
+pub(super) struct Tracker<'a, Loan: Atom, Point: Atom> {
+    limits: Limits,
+    tuples: usize,
+    relation_sizes: Vec<(&'static str, usize)>,
+    relation_bytes: Vec<(&'static str, usize)>,
+    exceeded: Option<BudgetExceeded>,
+    observer: Option<&'a mut dyn ErrorObserver<Loan, Point>>,
+    solver: Option<&'a mut dyn SolverObserver>,
+}
+
+impl<'a, Loan: Atom, Point: Atom> Tracker<'a, Loan, Point> {
+    pub(super) fn new(budget: Budget) -> Self {
+        Tracker {
+            limits: Limits {
+                budget,
+                start: Instant::now(),
+                exceeded: None,
+            },
+            tuples: 0,
+            relation_sizes: Vec::new(),
+            relation_bytes: Vec::new(),
+            exceeded: None,
+            observer: None,
+            solver: None,
+        }
+    }
+
+    pub(super) fn unlimited() -> Self {
+        Tracker::new(Budget::unlimited())
+    }
+
+    pub(super) fn with_observer(
+        budget: Budget,
+        observer: &'a mut dyn ErrorObserver<Loan, Point>,
+    ) -> Self {
+        let mut tracker = Tracker::new(budget);
+        tracker.observer = Some(observer);
+        tracker
+    }
+
+    pub(super) fn with_solver_observer(
+        budget: Budget,
+        solver: &'a mut dyn SolverObserver,
+    ) -> Self {
+        let mut tracker = Tracker::new(budget);
+        tracker.solver = Some(solver);
+        tracker
+    }
+
+    /// Tells the budget, and then the solver observer, if any.
+    fn notify(&mut self, mut notify: impl FnMut(&mut dyn SolverObserver)) {
+        notify(&mut self.limits);
+        if let Some(ref mut solver) = self.solver {
+            notify(&mut **solver);
+        }
+    }
+
+    /// Runs `op` as the phase `phase`, recording how long it took in
+    /// `phase_timings`, and telling the solver observer when it starts and
+    /// ends.
+    pub(super) fn phase<T>(
+        &mut self,
+        phase_timings: &mut Vec<(&'static str, Duration)>,
+        phase: &'static str,
+        op: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        self.notify(|solver| solver.phase_start(phase));
+        let start = Instant::now();
+        let result = op(self);
+        let duration = start.elapsed();
+        phase_timings.push((phase, duration));
+        self.notify(|solver| solver.phase_end(phase, duration));
+        result
+    }
+
+    /// Counts `tuples` more derived tuples, and returns whether the
+    /// observers allow another round. Once they don't, they never do
+    /// again.
+    pub(super) fn charge(&mut self, tuples: usize) -> bool {
+        if self.exceeded.is_some() {
+            return false;
+        }
+
+        self.tuples += tuples;
+        let total = self.tuples;
+        self.notify(|solver| solver.iteration(tuples, total));
+        let solver_exceeded = self.solver.as_ref().and_then(|solver| solver.exceeded());
+        self.exceeded = self.limits.exceeded().or(solver_exceeded);
+        self.exceeded.is_none()
+    }
+
+    /// The tuples counted so far.
+    pub(super) fn tuples(&self) -> usize {
+        self.tuples
+    }
+
+    /// Records how many tuples `variable`, the relation `relation`, holds
+    /// once its iteration is done, and about how many bytes they take. Of
+    /// the analyses that derive a relation of the same name, the largest
+    /// one is kept.
+    pub(super) fn record_size<Tuple: Ord>(
+        &mut self,
+        relation: &'static str,
+        variable: &Variable<Tuple>,
+    ) {
+        let size = variable
+            .stable
+            .borrow()
+            .iter()
+            .map(|batch| batch.elements.len())
+            .sum::<usize>()
+            + recent_len(variable);
+        self.notify(|solver| solver.relation_finalized(relation, size));
+        record_largest(&mut self.relation_sizes, relation, size);
+        let bytes = size * mem::size_of::<Tuple>();
+        record_largest(&mut self.relation_bytes, relation, bytes);
+    }
+
+    /// The sizes recorded by `record_size`, largest first.
+    pub(super) fn relation_sizes(&self) -> Vec<(&'static str, usize)> {
+        largest_first(&self.relation_sizes)
+    }
+
+    /// The bytes of the tuples of the relations recorded by `record_size`,
+    /// largest first.
+    pub(super) fn relation_bytes(&self) -> Vec<(&'static str, usize)> {
+        largest_first(&self.relation_bytes)
+    }
+
+    pub(super) fn exceeded(&self) -> Option<BudgetExceeded> {
+        self.exceeded
+    }
+
+    /// Tells the observer about the errors derived in the last round.
+    pub(super) fn report_errors(&mut self, errors: &Variable<(Loan, Point)>) {
+        if let Some(ref mut observer) = self.observer {
+            for &(loan, point) in &errors.recent.borrow().elements {
+                observer.error(point, loan);
+            }
+        }
+    }
+
+    /// Runs `op` with the observer detached, for analyses whose errors
+    /// another analysis reports too.
+    pub(super) fn unobserved<T>(&mut self, op: impl FnOnce(&mut Self) -> T) -> T {
+        let observer = self.observer.take();
+        let result = op(self);
+        self.observer = observer;
+        result
+    }
+}
+
+/// The tuples that `variable` derived in the last round.
+pub(super) fn recent_len<Tuple: Ord>(variable: &Variable<Tuple>) -> usize {
+    variable.recent.borrow().elements.len()
+}
+
+/// Records `size` as the size of `relation` in `sizes`, unless it already
+/// holds a larger one.
+fn record_largest(sizes: &mut Vec<(&'static str, usize)>, relation: &'static str, size: usize) {
+    let position = sizes.iter().position(|&(name, _)| name == relation);
+    match position {
+        Some(index) => {
+            let largest = &mut sizes[index].1;
+            *largest = cmp::max(*largest, size);
+        }
+        None => sizes.push((relation, size)),
+    }
+}
+
+fn largest_first(sizes: &[(&'static str, usize)]) -> Vec<(&'static str, usize)> {
+    let mut sizes = sizes.to_vec();
+    sizes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    sizes
+}
@@ -0,0 +1,37 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The clock that times the analyses. `wasm32-unknown-unknown` has none,
+//! and `std::time::Instant::now` panics there, so on that target every
+//! phase takes no time, and `Budget::max_duration` never runs out.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(super) use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub(super) use self::no_clock::Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod no_clock {
+    use std::time::Duration;
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Instant;
+
+    impl Instant {
+        pub fn now() -> Self {
+            Instant
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Duration::from_secs(0)
+        }
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Cross-checks the optimized analysis against the naive one, which is
+//! the reference for what the analysis should compute.
+
+use rustc_hash::FxHashMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::output::budget::Tracker;
+use crate::output::datafrog_opt;
+use crate::output::naive;
+use crate::output::Output;
+use facts::{AllFacts, Atom};
+
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
+    dump_enabled: bool,
+    all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath> {
+    // Both analyses derive the same errors, which only `opt` reports.
+    let naive = budget.unobserved(|budget| {
+        naive::compute(dump_enabled, all_facts.clone(), budget)
+    });
+    let mut opt = datafrog_opt::compute(dump_enabled, all_facts, budget);
+
+    // Stopped early, the two analyses have derived different subsets of
+    // their errors.
+    if budget.exceeded().is_none() {
+        opt.errors_diff = Some(ErrorsDiff::between(&naive, &opt));
+    }
+
+    opt
+}
+
+/// The `(point, loan)` errors that differ between two outputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorsDiff<Loan: Atom, Point: Atom> {
+    /// Errors of the expected output that the actual output lacks.
+    pub missing: Vec<(Point, Loan)>,
+
+    /// Errors of the actual output that the expected output lacks.
+    pub unexpected: Vec<(Point, Loan)>,
+}
+
+impl<Loan: Atom, Point: Atom> ErrorsDiff<Loan, Point> {
+    pub fn between<Region: Atom, Variable: Atom, MovePath: Atom>(
+        expected: &Output<Region, Loan, Point, Variable, MovePath>,
+        actual: &Output<Region, Loan, Point, Variable, MovePath>,
+    ) -> Self {
+        let expected = error_tuples(expected);
+        let actual = error_tuples(actual);
+        ErrorsDiff {
+            missing: expected.difference(&actual).cloned().collect(),
+            unexpected: actual.difference(&expected).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+
+    /// Adds `other`, the differences of another part of the facts, to
+    /// `diff`.
+    pub(super) fn merge_into(diff: &mut Option<Self>, other: Option<Self>) {
+        if let Some(other) = other {
+            if let Some(ref mut diff) = *diff {
+                diff.missing.extend(other.missing);
+                diff.unexpected.extend(other.unexpected);
+                return;
+            }
+            *diff = Some(other);
+        }
+    }
+}
+
+/// Lists the differences one per line, as `- point loan` for missing
+/// errors and `+ point loan` for unexpected ones, using atom indices.
+impl<Loan: Atom, Point: Atom> fmt::Display for ErrorsDiff<Loan, Point> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(point, loan) in &self.missing {
+            writeln!(f, "- {}\t{}", point.index(), loan.index())?;
+        }
+        for &(point, loan) in &self.unexpected {
+            writeln!(f, "+ {}\t{}", point.index(), loan.index())?;
+        }
+        Ok(())
+    }
+}
+
+fn error_tuples<Region, Loan, Point, Variable, MovePath>(
+    output: &Output<Region, Loan, Point, Variable, MovePath>,
+) -> BTreeSet<(Point, Loan)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    output
+        .errors
+        .iter()
+        .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+        .collect()
+}
+
+/// The tuples of one relation that differ between two outputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelationDiff<T> {
+    /// The tuples that only the first output has.
+    pub removed: Vec<T>,
+
+    /// The tuples that only the second output has.
+    pub added: Vec<T>,
+}
+
+impl<T: Ord + Clone> RelationDiff<T> {
+    fn between(before: BTreeSet<T>, after: BTreeSet<T>) -> Self {
+        RelationDiff {
+            removed: before.difference(&after).cloned().collect(),
+            added: after.difference(&before).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+/// How the relations that every algorithm computes differ between two
+/// outputs, as computed by `Output::diff`. The tuples of each relation are
+/// sorted, with the point first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputDiff<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom> {
+    pub errors: RelationDiff<(Point, Loan)>,
+    pub borrow_live_at: RelationDiff<(Point, Loan)>,
+    pub move_errors: RelationDiff<(Point, MovePath)>,
+    pub var_maybe_uninitialized_on_entry: RelationDiff<(Point, Variable)>,
+    pub subset_errors: RelationDiff<(Point, Region, Region)>,
+}
+
+impl<Region, Loan, Point, Variable, MovePath> OutputDiff<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    pub(super) fn between(
+        before: &Output<Region, Loan, Point, Variable, MovePath>,
+        after: &Output<Region, Loan, Point, Variable, MovePath>,
+    ) -> Self {
+        OutputDiff {
+            errors: RelationDiff::between(error_tuples(before), error_tuples(after)),
+            borrow_live_at: RelationDiff::between(
+                tuples(&before.borrow_live_at()),
+                tuples(&after.borrow_live_at()),
+            ),
+            move_errors: RelationDiff::between(
+                tuples(&before.move_errors),
+                tuples(&after.move_errors),
+            ),
+            var_maybe_uninitialized_on_entry: RelationDiff::between(
+                tuples(&before.var_maybe_uninitialized_on_entry),
+                tuples(&after.var_maybe_uninitialized_on_entry),
+            ),
+            subset_errors: RelationDiff::between(
+                subset_error_tuples(before),
+                subset_error_tuples(after),
+            ),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+            && self.borrow_live_at.is_empty()
+            && self.move_errors.is_empty()
+            && self.var_maybe_uninitialized_on_entry.is_empty()
+            && self.subset_errors.is_empty()
+    }
+}
+
+/// Lists the differences one per line, as `- relation atoms...` for the
+/// tuples that only the first output has and `+ relation atoms...` for
+/// those that only the second one has, using atom indices.
+impl<Region, Loan, Point, Variable, MovePath> fmt::Display
+    for OutputDiff<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_relation(f, "errors", &self.errors, |&(p, l)| {
+            vec![p.index(), l.index()]
+        })?;
+        write_relation(f, "borrow_live_at", &self.borrow_live_at, |&(p, l)| {
+            vec![p.index(), l.index()]
+        })?;
+        write_relation(f, "move_errors", &self.move_errors, |&(p, m)| {
+            vec![p.index(), m.index()]
+        })?;
+        write_relation(
+            f,
+            "var_maybe_uninitialized_on_entry",
+            &self.var_maybe_uninitialized_on_entry,
+            |&(p, v)| vec![p.index(), v.index()],
+        )?;
+        write_relation(f, "subset_errors", &self.subset_errors, |&(p, r1, r2)| {
+            vec![p.index(), r1.index(), r2.index()]
+        })
+    }
+}
+
+fn write_relation<T>(
+    f: &mut fmt::Formatter,
+    relation: &str,
+    diff: &RelationDiff<T>,
+    indices: impl Fn(&T) -> Vec<usize>,
+) -> fmt::Result {
+    let changes = diff
+        .removed
+        .iter()
+        .map(|tuple| ('-', tuple))
+        .chain(diff.added.iter().map(|tuple| ('+', tuple)));
+    for (sign, tuple) in changes {
+        write!(f, "{} {}", sign, relation)?;
+        for index in indices(tuple) {
+            write!(f, "\t{}", index)?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+fn tuples<K: Atom, V: Atom>(relation: &FxHashMap<K, Vec<V>>) -> BTreeSet<(K, V)> {
+    relation
+        .iter()
+        .flat_map(|(&key, values)| values.iter().map(move |&value| (key, value)))
+        .collect()
+}
+
+fn subset_error_tuples<Region, Loan, Point, Variable, MovePath>(
+    output: &Output<Region, Loan, Point, Variable, MovePath>,
+) -> BTreeSet<(Point, Region, Region)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    output
+        .subset_errors
+        .iter()
+        .flat_map(|(&point, pairs)| pairs.iter().map(move |&(r1, r2)| (point, r1, r2)))
+        .collect()
+}
@@ -0,0 +1,249 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Solves the connected components of the CFG one at a time. The rules
+//! only relate the tuples of a point to those of the points next to it,
+//! so the analyses of two components never see each other's tuples:
+//! solving each component by itself, and merging their outputs, finds the
+//! same tuples as solving them together, in smaller fixpoints. The facts
+//! that rustc dumps often have such components, like the cleanup blocks
+//! that nothing reaches.
+//!
+//! The location-insensitive algorithms only approximate the rules, and
+//! let the regions of a component flow into those of another: split into
+//! components, they can only report fewer of their false errors.
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use crate::output::{Algorithm, ErrorsDiff, Output};
+use facts::{AllFacts, Atom};
+use rustc_hash::FxHashMap;
+
+impl<Region, Loan, Point, Variable, MovePath> Output<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    /// Like `compute`, but solves each connected component of the CFG on
+    /// its own, and merges their outputs.
+    pub fn compute_by_component(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+    ) -> Self {
+        let components = split_by_component(all_facts);
+        if components.len() < 2 {
+            return Output::compute(all_facts, algorithm, dump_enabled);
+        }
+
+        let mut output = Output::new(dump_enabled);
+        for component in &components {
+            output.merge_component(Output::compute(component, algorithm, dump_enabled));
+        }
+        output.sort_tuples();
+        output
+    }
+
+    /// Adds the output of a component to this one. No two components have
+    /// a point in common, so the relations of the points are merged by
+    /// adding the points of the component.
+    pub(super) fn merge_component(&mut self, component: Self) {
+        self.errors.extend(component.errors);
+        self.borrow_live_at.extend(component.borrow_live_at);
+        self.move_errors.extend(component.move_errors);
+        self.var_maybe_uninitialized_on_entry
+            .extend(component.var_maybe_uninitialized_on_entry);
+        self.subset_errors.extend(component.subset_errors);
+        self.restricts.extend(component.restricts);
+        self.region_live_at.extend(component.region_live_at);
+        self.invalidates.extend(component.invalidates);
+        self.subset.extend(component.subset);
+        for (region, loans) in component.restricts_anywhere {
+            self.restricts_anywhere
+                .entry(region)
+                .or_insert_with(BTreeSet::new)
+                .extend(loans);
+        }
+        for (region, regions) in component.subset_anywhere {
+            self.subset_anywhere
+                .entry(region)
+                .or_insert_with(BTreeSet::new)
+                .extend(regions);
+        }
+
+        if self.budget_exceeded.is_none() {
+            self.budget_exceeded = component.budget_exceeded;
+        }
+        self.derived_tuples += component.derived_tuples;
+        ErrorsDiff::merge_into(&mut self.errors_diff, component.errors_diff);
+        self.full_borrow_live_at &= component.full_borrow_live_at;
+        add_timings(&mut self.phase_timings, component.phase_timings);
+        add_timings(
+            &mut self.algorithm_phase_timings,
+            component.algorithm_phase_timings,
+        );
+        add_sizes(
+            &mut self.derived_relation_sizes,
+            component.derived_relation_sizes,
+        );
+        add_sizes(
+            &mut self.derived_relation_bytes,
+            component.derived_relation_bytes,
+        );
+    }
+}
+
+/// Adds the relation sizes of a partition to those of the whole analysis,
+/// keeping them largest first: the partitions are solved at the same time,
+/// so their relations take up memory together.
+pub(super) fn add_sizes(
+    sizes: &mut Vec<(&'static str, usize)>,
+    partition_sizes: Vec<(&'static str, usize)>,
+) {
+    for (relation, size) in partition_sizes {
+        let position = sizes.iter().position(|&(name, _)| name == relation);
+        match position {
+            Some(index) => sizes[index].1 += size,
+            None => sizes.push((relation, size)),
+        }
+    }
+    sizes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+}
+
+/// Adds the time a component spent in each phase to `timings`, in the
+/// order the phases first ran.
+fn add_timings(
+    timings: &mut Vec<(&'static str, Duration)>,
+    component_timings: Vec<(&'static str, Duration)>,
+) {
+    for (phase, duration) in component_timings {
+        let position = timings.iter().position(|&(name, _)| name == phase);
+        match position {
+            Some(index) => timings[index].1 += duration,
+            None => timings.push((phase, duration)),
+        }
+    }
+}
+
+/// The facts of each connected component of the CFG of `all_facts`, in
+/// the order of their first edge. The facts of the points without any CFG
+/// edge make up one last component, and the facts that aren't about
+/// points are part of every component.
+pub(super) fn split_by_component<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+) -> Vec<AllFacts<Region, Loan, Point, Variable, MovePath>>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let component_of = components(&all_facts.cfg_edge);
+    let isolated = component_of
+        .values()
+        .max()
+        .map_or(0, |&component| component + 1);
+    let component = |point: Point| *component_of.get(&point).unwrap_or(&isolated);
+
+    let mut components: Vec<AllFacts<Region, Loan, Point, Variable, MovePath>> = (0..isolated + 1)
+        .map(|_| AllFacts {
+            universal_region: all_facts.universal_region.clone(),
+            use_of_var_derefs_origin: all_facts.use_of_var_derefs_origin.clone(),
+            drop_of_var_derefs_origin: all_facts.drop_of_var_derefs_origin.clone(),
+            child_path: all_facts.child_path.clone(),
+            path_is_var: all_facts.path_is_var.clone(),
+            known_placeholder_subset: all_facts.known_placeholder_subset.clone(),
+            loan_borrows_path: all_facts.loan_borrows_path.clone(),
+            ..AllFacts::default()
+        })
+        .collect();
+    let mut isolated_facts = 0;
+
+    macro_rules! split {
+        ($($relation:ident: |$tuple:pat| $point:expr,)*) => {
+            $(
+                for &tuple in &all_facts.$relation {
+                    let $tuple = tuple;
+                    let index = component($point);
+                    if index == isolated {
+                        isolated_facts += 1;
+                    }
+                    components[index].$relation.push(tuple);
+                }
+            )*
+        };
+    }
+
+    split! {
+        borrow_region: |(_, _, p)| p,
+        cfg_edge: |(p, _)| p,
+        killed: |(_, p)| p,
+        outlives: |(_, _, p)| p,
+        region_live_at: |(_, p)| p,
+        invalidates: |(p, _)| p,
+        var_used_at: |(_, p)| p,
+        var_defined_at: |(_, p)| p,
+        var_drop_used_at: |(_, p)| p,
+        path_assigned_at: |(_, p)| p,
+        path_moved_at: |(_, p)| p,
+        path_accessed_at: |(_, p)| p,
+    }
+
+    if isolated_facts == 0 {
+        components.pop();
+    }
+    components
+}
+
+/// The number of the connected component of each point of `cfg_edge`,
+/// numbered in the order of their first edge.
+fn components<Point: Atom>(cfg_edge: &[(Point, Point)]) -> FxHashMap<Point, usize> {
+    let mut parents = FxHashMap::default();
+    for &(p, q) in cfg_edge {
+        let (p, q) = (find(&mut parents, p), find(&mut parents, q));
+        if p != q {
+            parents.insert(p.max(q), p.min(q));
+        }
+    }
+
+    let mut numbers = FxHashMap::default();
+    let mut component_of = FxHashMap::default();
+    for &(p, q) in cfg_edge {
+        for &point in &[p, q] {
+            let root = find(&mut parents, point);
+            let next = numbers.len();
+            let number = *numbers.entry(root).or_insert(next);
+            component_of.insert(point, number);
+        }
+    }
+    component_of
+}
+
+/// The root of the tree of `point` in the union-find forest `parents`,
+/// where the roots have no parent. The points on the way to the root are
+/// made its children.
+fn find<Point: Atom>(parents: &mut FxHashMap<Point, Point>, point: Point) -> Point {
+    let mut root = point;
+    while let Some(&parent) = parents.get(&root) {
+        root = parent;
+    }
+    let mut current = point;
+    while current != root {
+        let parent = parents[&current];
+        parents.insert(current, root);
+        current = parent;
+    }
+    root
+}
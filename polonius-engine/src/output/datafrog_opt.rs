@@ -9,17 +9,21 @@
 // except according to those terms.
 
 use std::collections::{BTreeMap, BTreeSet};
-use std::time::Instant;
 
-use crate::output::Output;
+use crate::output::budget::{recent_len, Tracker};
+use crate::output::clock::Instant;
+use crate::output::{Output, PhaseClock};
 
 use datafrog::{Iteration, Relation};
 use facts::{AllFacts, Atom};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath> {
+    let mut clock = PhaseClock::start();
+
     // Declare that each universal region is live at every point.
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
@@ -107,8 +111,18 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         subset.insert(all_facts.outlives.into());
         requires.insert(all_facts.borrow_region.into());
 
+        clock.end_phase(&mut result.algorithm_phase_timings, "setup");
+
         // .. and then start iterating rules!
         while iteration.changed() {
+            budget.report_errors(&errors);
+
+            // once the budget runs out, the rounds derive nothing and the
+            // iteration drains.
+            if !budget.charge(recent_len(&subset) + recent_len(&requires)) {
+                continue;
+            }
+
             // remap fields to re-index by the different keys
             subset_r1p.from_map(&subset, |&(r1, r2, p)| ((r1, p), r2));
             subset_p.from_map(&subset, |&(r1, r2, p)| (p, (r1, r2)));
@@ -308,6 +322,17 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // .decl errors(B, P) :- invalidates(B, P), borrow_live_at(B, P).
             errors.from_join(&invalidates, &borrow_live_at, |&(b, p), &(), &()| (b, p));
         }
+        clock.end_phase(&mut result.algorithm_phase_timings, "fixpoint");
+
+        budget.record_size("subset", &subset);
+        budget.record_size("requires", &requires);
+        budget.record_size("borrow_live_at", &borrow_live_at);
+        budget.record_size("live_to_dead_regions", &live_to_dead_regions);
+        budget.record_size("dead_region_requires", &dead_region_requires);
+        budget.record_size("dead_can_reach_origins", &dead_can_reach_origins);
+        budget.record_size("dead_can_reach", &dead_can_reach);
+        budget.record_size("dead_can_reach_live", &dead_can_reach_live);
+        budget.record_size("errors", &errors);
 
         if dump_enabled {
             for (region, location) in &region_live_at_rel.elements {
@@ -339,22 +364,22 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
                     .or_insert(BTreeSet::new())
                     .insert(*borrow);
             }
+        }
 
-            let borrow_live_at = borrow_live_at.complete();
-            for ((borrow, location), ()) in &borrow_live_at.elements {
-                result
-                    .borrow_live_at
-                    .entry(*location)
-                    .or_insert(Vec::new())
-                    .push(*borrow);
-            }
+        let borrow_live_at = borrow_live_at.complete();
+        for ((borrow, location), ()) in &borrow_live_at.elements {
+            result
+                .borrow_live_at
+                .entry(*location)
+                .or_insert(Vec::new())
+                .push(*borrow);
         }
 
         errors.complete()
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "errors is complete: {} tuples, {:?}",
             errors.len(),
             timer.elapsed()
@@ -368,6 +393,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             .or_insert(Vec::new())
             .push(*borrow);
     }
+    clock.end_phase(&mut result.algorithm_phase_timings, "results");
 
     result
 }
@@ -0,0 +1,86 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Runs the location-insensitive analysis first, and then the precise
+//! analysis only on the loans it flags. The location-insensitive errors
+//! are a superset of the precise ones, so the loans it doesn't flag can't
+//! cause errors, and a function without any potential errors is done
+//! after the fast pass.
+
+use crate::output::budget::Tracker;
+use crate::output::clock::Instant;
+use crate::output::datafrog_opt;
+use crate::output::location_insensitive;
+use crate::output::Output;
+use facts::{AllFacts, Atom};
+use rustc_hash::FxHashSet;
+
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
+    dump_enabled: bool,
+    all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath> {
+    let timer = Instant::now();
+
+    // Only the errors that `datafrog_opt` confirms are reported.
+    let potential_errors = budget.unobserved(|budget| {
+        location_insensitive::compute(false, all_facts.clone(), budget).errors
+    });
+
+    let potential_error_loans: FxHashSet<Loan> = potential_errors
+        .values()
+        .flat_map(|loans| loans.iter().cloned())
+        .collect();
+    let location_insensitive_time = timer.elapsed();
+
+    if dump_enabled {
+        eprintln!(
+            "location-insensitive pass is complete: {} potential error loans, {:?}",
+            potential_error_loans.len(),
+            location_insensitive_time
+        );
+    }
+
+    // The loans that can't cause errors are missing from `borrow_live_at`.
+    let mut output = if potential_error_loans.is_empty() {
+        Output::new(dump_enabled)
+    } else {
+        datafrog_opt::compute(
+            dump_enabled,
+            restrict_to_loans(all_facts, &potential_error_loans),
+            budget,
+        )
+    };
+    output
+        .algorithm_phase_timings
+        .insert(0, ("location-insensitive pass", location_insensitive_time));
+    output.full_borrow_live_at = false;
+    output
+}
+
+/// Drops the facts about every loan not in `loans`. Loans don't interact
+/// with each other, so this doesn't change what the analysis computes for
+/// the loans that are kept.
+pub(super) fn restrict_to_loans<Region, Loan, Point, Variable, MovePath>(
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    loans: &FxHashSet<Loan>,
+) -> AllFacts<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    all_facts.borrow_region.retain(|&(_, l, _)| loans.contains(&l));
+    all_facts.killed.retain(|&(l, _)| loans.contains(&l));
+    all_facts.invalidates.retain(|&(_, l)| loans.contains(&l));
+    all_facts
+}
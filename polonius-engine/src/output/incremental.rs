@@ -0,0 +1,227 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Keeps the results of an analysis up to date as its facts change.
+//!
+//! Loans don't interact with each other, so a change that only touches
+//! the facts about some loans -- `borrow_region`, `killed` and
+//! `invalidates` -- only needs the analysis to be redone for those loans.
+//! Any other change can affect every loan, and recomputes everything.
+
+use std::hash::Hash;
+
+use crate::output::{compute_borrows, with_derived_facts, Algorithm, Output};
+use crate::output::{hybrid, kills};
+use facts::{AllFacts, Atom, SharedVec};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A change to a set of facts: the tuples in `removed` are taken out of
+/// the facts, and then the tuples in `added` are put in.
+#[derive(Clone)]
+pub struct FactsDelta<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    pub added: AllFacts<R, L, P, V, M>,
+    pub removed: AllFacts<R, L, P, V, M>,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> FactsDelta<R, L, P, V, M> {
+    pub fn new() -> Self {
+        FactsDelta {
+            added: AllFacts::default(),
+            removed: AllFacts::default(),
+        }
+    }
+
+    /// The loans whose facts this delta changes, or `None` if it also
+    /// changes facts that aren't about a single loan.
+    fn affected_loans(&self) -> Option<FxHashSet<L>> {
+        let mut loans = FxHashSet::default();
+        for facts in &[&self.added, &self.removed] {
+            macro_rules! loan_independent {
+                ($($t:ident,)*) => {
+                    $(!facts.$t.is_empty())||*
+                }
+            }
+            let changes_other_facts = loan_independent! {
+                universal_region,
+                cfg_edge,
+                outlives,
+                region_live_at,
+                var_used_at,
+                var_defined_at,
+                var_drop_used_at,
+                use_of_var_derefs_origin,
+                drop_of_var_derefs_origin,
+                child_path,
+                path_is_var,
+                path_assigned_at,
+                path_moved_at,
+                path_accessed_at,
+                known_placeholder_subset,
+                loan_borrows_path,
+            };
+            if changes_other_facts {
+                return None;
+            }
+
+            loans.extend(facts.borrow_region.iter().map(|&(_, l, _)| l));
+            loans.extend(facts.killed.iter().map(|&(l, _)| l));
+            loans.extend(facts.invalidates.iter().map(|&(_, l)| l));
+        }
+        Some(loans)
+    }
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> Default for FactsDelta<R, L, P, V, M> {
+    fn default() -> Self {
+        FactsDelta::new()
+    }
+}
+
+/// The solved state of an analysis, which `apply` updates in place as the
+/// facts change. The outputs are computed without `dump_enabled`, so only
+/// the outputs that are always computed are available.
+pub struct IncrementalAnalysis<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    algorithm: Algorithm,
+    all_facts: AllFacts<R, L, P, V, M>,
+
+    /// `all_facts`, with the `region_live_at` computed from the variable
+    /// facts and the kills derived from the path assignments added in.
+    derived_facts: AllFacts<R, L, P, V, M>,
+
+    output: Output<R, L, P, V, M>,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> IncrementalAnalysis<R, L, P, V, M> {
+    pub fn new(all_facts: AllFacts<R, L, P, V, M>, algorithm: Algorithm) -> Self {
+        let derived_facts = with_derived_facts(&all_facts, false);
+        let output = Output::compute(&all_facts, algorithm, false);
+        IncrementalAnalysis {
+            algorithm,
+            all_facts,
+            derived_facts,
+            output,
+        }
+    }
+
+    /// The current facts, with every delta applied so far.
+    pub fn facts(&self) -> &AllFacts<R, L, P, V, M> {
+        &self.all_facts
+    }
+
+    /// The output for the current facts.
+    pub fn output(&self) -> &Output<R, L, P, V, M> {
+        &self.output
+    }
+
+    /// Updates the facts with `delta`, and the output to match them.
+    pub fn apply(&mut self, delta: &FactsDelta<R, L, P, V, M>) {
+        apply_delta(&mut self.all_facts, delta);
+
+        let loans = match delta.affected_loans() {
+            Some(loans) => loans,
+            None => {
+                self.derived_facts = with_derived_facts(&self.all_facts, false);
+                self.output = Output::compute(&self.all_facts, self.algorithm, false);
+                return;
+            }
+        };
+
+        if loans.is_empty() {
+            return;
+        }
+
+        // The delta only touches loan facts, which `region_live_at` doesn't
+        // depend on, so the derived facts get the same update. `killed` is
+        // rebuilt from the kills of the facts and the path kills though:
+        // removing a `killed` tuple mustn't remove a path kill of the same
+        // loan, and the path kills mustn't pile up with each update.
+        apply_delta(&mut self.derived_facts, delta);
+        let mut killed = self.all_facts.killed.clone();
+        killed.extend(kills::compute_path_kills(false, &self.all_facts));
+        self.derived_facts.killed = killed;
+
+        let restricted = hybrid::restrict_to_loans(self.derived_facts.clone(), &loans);
+        let recomputed = compute_borrows(restricted, self.algorithm, false);
+
+        replace_loans(&mut self.output.errors, recomputed.errors, &loans);
+        replace_loans(
+            &mut self.output.borrow_live_at,
+            recomputed.borrow_live_at,
+            &loans,
+        );
+    }
+}
+
+fn apply_delta<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom>(
+    all_facts: &mut AllFacts<R, L, P, V, M>,
+    delta: &FactsDelta<R, L, P, V, M>,
+) {
+    macro_rules! apply_delta {
+        ($($t:ident,)*) => {
+            $(
+                update_relation(&mut all_facts.$t, &delta.added.$t, &delta.removed.$t);
+            )*
+        }
+    }
+
+    apply_delta! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_used_at,
+        var_defined_at,
+        var_drop_used_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        known_placeholder_subset,
+        loan_borrows_path,
+    }
+}
+
+/// Applies the change to one relation. The relations that the delta
+/// doesn't change stay shared with the clones of the facts.
+fn update_relation<T: Copy + Eq + Hash>(tuples: &mut SharedVec<T>, added: &[T], removed: &[T]) {
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+    if !removed.is_empty() {
+        let removed: FxHashSet<T> = removed.iter().cloned().collect();
+        tuples.retain(|tuple| !removed.contains(tuple));
+    }
+    tuples.extend(added.iter().cloned());
+}
+
+/// Replaces the entries for `loans` in `previous` with the ones in
+/// `recomputed`, keeping the loans at each point sorted.
+fn replace_loans<Loan: Atom, Point: Atom>(
+    previous: &mut FxHashMap<Point, Vec<Loan>>,
+    recomputed: FxHashMap<Point, Vec<Loan>>,
+    loans: &FxHashSet<Loan>,
+) {
+    for point_loans in previous.values_mut() {
+        point_loans.retain(|loan| !loans.contains(loan));
+    }
+    previous.retain(|_, point_loans| !point_loans.is_empty());
+
+    for (point, recomputed_loans) in recomputed {
+        let point_loans = previous.entry(point).or_insert(Vec::new());
+        point_loans.extend(recomputed_loans);
+        point_loans.sort();
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The move and initialization analysis: which move paths may have been
+//! moved out of (and not reassigned since) at each point, and the move
+//! errors caused by accessing them.
+
+use crate::output::clock::Instant;
+use datafrog::{Iteration, Relation};
+use facts::{AllFacts, Atom};
+use rustc_hash::FxHashMap;
+
+pub(super) struct Initialization<Point: Atom, Variable: Atom, MovePath: Atom> {
+    pub(super) move_errors: FxHashMap<Point, Vec<MovePath>>,
+    pub(super) var_maybe_uninitialized_on_entry: FxHashMap<Point, Vec<Variable>>,
+}
+
+pub(super) fn compute<Region, Loan, Point, Variable, MovePath>(
+    dump_enabled: bool,
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+) -> Initialization<Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let timer = Instant::now();
+
+    // Moves, assignments and accesses of a path also apply to all of its
+    // children, so first propagate the path facts down the path tree.
+    let (path_moved_at, path_assigned_at, path_accessed_at, path_begins_with_var) = {
+        let mut iteration = Iteration::new();
+
+        // static inputs
+        let child_path = iteration.variable::<(MovePath, MovePath)>("child_path");
+        let path_moved_at_base = iteration.variable::<(MovePath, Point)>("path_moved_at_base");
+        let path_assigned_at_base =
+            iteration.variable::<(MovePath, Point)>("path_assigned_at_base");
+        let path_accessed_at_base =
+            iteration.variable::<(MovePath, Point)>("path_accessed_at_base");
+        let path_is_var = iteration.variable::<(MovePath, Variable)>("path_is_var");
+
+        // `ancestor_path(Parent, Child)`
+        let ancestor_path = iteration.variable::<(MovePath, MovePath)>("ancestor_path");
+
+        let path_moved_at = iteration.variable::<(MovePath, Point)>("path_moved_at");
+        let path_assigned_at = iteration.variable::<(MovePath, Point)>("path_assigned_at");
+        let path_accessed_at = iteration.variable::<(MovePath, Point)>("path_accessed_at");
+        let path_begins_with_var =
+            iteration.variable::<(MovePath, Variable)>("path_begins_with_var");
+
+        // load initial facts.
+        child_path.insert(all_facts.child_path.clone().into());
+        path_moved_at_base.insert(all_facts.path_moved_at.clone().into());
+        path_assigned_at_base.insert(all_facts.path_assigned_at.clone().into());
+        path_accessed_at_base.insert(all_facts.path_accessed_at.clone().into());
+        path_is_var.insert(all_facts.path_is_var.clone().into());
+
+        // ancestor_path(Parent, Child) :- child_path(Child, Parent).
+        ancestor_path.insert(Relation::from(
+            all_facts.child_path.iter().map(|&(child, parent)| (parent, child)),
+        ));
+
+        // path_moved_at(Path, P) :- path_moved_at_base(Path, P).
+        path_moved_at.insert(all_facts.path_moved_at.clone().into());
+        path_assigned_at.insert(all_facts.path_assigned_at.clone().into());
+        path_accessed_at.insert(all_facts.path_accessed_at.clone().into());
+
+        // path_begins_with_var(Path, V) :- path_is_var(Path, V).
+        path_begins_with_var.insert(all_facts.path_is_var.clone().into());
+
+        while iteration.changed() {
+            // ancestor_path(Grandparent, Child) :-
+            //   ancestor_path(Parent, Child),
+            //   child_path(Parent, Grandparent).
+            ancestor_path.from_join(&ancestor_path, &child_path, |&_parent, &child, &gp| {
+                (gp, child)
+            });
+
+            // path_moved_at(Child, P) :-
+            //   path_moved_at_base(Parent, P),
+            //   ancestor_path(Parent, Child).
+            path_moved_at.from_join(&path_moved_at_base, &ancestor_path, |&_m, &p, &child| {
+                (child, p)
+            });
+
+            // path_assigned_at(Child, P) :-
+            //   path_assigned_at_base(Parent, P),
+            //   ancestor_path(Parent, Child).
+            path_assigned_at.from_join(&path_assigned_at_base, &ancestor_path, |&_m, &p, &c| {
+                (c, p)
+            });
+
+            // path_accessed_at(Child, P) :-
+            //   path_accessed_at_base(Parent, P),
+            //   ancestor_path(Parent, Child).
+            path_accessed_at.from_join(&path_accessed_at_base, &ancestor_path, |&_m, &p, &c| {
+                (c, p)
+            });
+
+            // path_begins_with_var(Child, V) :-
+            //   path_is_var(Parent, V),
+            //   ancestor_path(Parent, Child).
+            path_begins_with_var.from_join(&path_is_var, &ancestor_path, |&_m, &v, &child| {
+                (child, v)
+            });
+        }
+
+        (
+            path_moved_at.complete(),
+            path_assigned_at.complete(),
+            path_accessed_at.complete(),
+            path_begins_with_var.complete(),
+        )
+    };
+
+    let (move_errors, var_maybe_uninitialized_on_entry) = {
+        let mut iteration = Iteration::new();
+
+        // static inputs
+        let cfg_edge = iteration.variable::<(Point, Point)>("cfg_edge");
+        let path_accessed_at_var =
+            iteration.variable::<((MovePath, Point), ())>("path_accessed_at");
+        let path_begins_with_var_var =
+            iteration.variable::<(MovePath, Variable)>("path_begins_with_var");
+
+        let path_maybe_uninitialized_on_exit =
+            iteration.variable::<(MovePath, Point)>("path_maybe_uninitialized_on_exit");
+        let path_maybe_uninitialized_on_exit_p =
+            iteration.variable_indistinct("path_maybe_uninitialized_on_exit_p");
+
+        // `path_maybe_uninitialized_on_entry((Path, Q), ())`
+        let path_maybe_uninitialized_on_entry =
+            iteration.variable_indistinct("path_maybe_uninitialized_on_entry");
+        let path_maybe_uninitialized_on_entry_m =
+            iteration.variable_indistinct("path_maybe_uninitialized_on_entry_m");
+
+        let move_errors = iteration.variable::<(MovePath, Point)>("move_errors");
+        let var_maybe_uninitialized_on_entry =
+            iteration.variable::<(Variable, Point)>("var_maybe_uninitialized_on_entry");
+
+        // load initial facts.
+        cfg_edge.insert(all_facts.cfg_edge.clone().into());
+        path_accessed_at_var.insert(Relation::from(
+            path_accessed_at.elements.iter().map(|&(m, p)| ((m, p), ())),
+        ));
+        path_begins_with_var_var.insert(path_begins_with_var);
+
+        // path_maybe_uninitialized_on_exit(Path, P) :- path_moved_at(Path, P).
+        path_maybe_uninitialized_on_exit.insert(Relation::from(
+            path_moved_at.elements.iter().cloned(),
+        ));
+
+        while iteration.changed() {
+            // remap fields to re-index by keys.
+            path_maybe_uninitialized_on_exit_p
+                .from_map(&path_maybe_uninitialized_on_exit, |&(m, p)| (p, m));
+            path_maybe_uninitialized_on_entry_m
+                .from_map(&path_maybe_uninitialized_on_entry, |&((m, q), ())| (m, q));
+
+            // path_maybe_uninitialized_on_entry(Path, Q) :-
+            //   path_maybe_uninitialized_on_exit(Path, P),
+            //   cfg_edge(P, Q).
+            path_maybe_uninitialized_on_entry.from_join(
+                &path_maybe_uninitialized_on_exit_p,
+                &cfg_edge,
+                |&_p, &m, &q| ((m, q), ()),
+            );
+
+            // path_maybe_uninitialized_on_exit(Path, Q) :-
+            //   path_maybe_uninitialized_on_entry(Path, Q),
+            //   !path_assigned_at(Path, Q).
+            path_maybe_uninitialized_on_exit.from_antijoin(
+                &path_maybe_uninitialized_on_entry,
+                &path_assigned_at,
+                |&(m, q), &()| (m, q),
+            );
+
+            // move_errors(Path, Q) :-
+            //   path_maybe_uninitialized_on_entry(Path, Q),
+            //   path_accessed_at(Path, Q).
+            move_errors.from_join(
+                &path_maybe_uninitialized_on_entry,
+                &path_accessed_at_var,
+                |&(m, q), &(), &()| (m, q),
+            );
+
+            // var_maybe_uninitialized_on_entry(V, Q) :-
+            //   path_maybe_uninitialized_on_entry(Path, Q),
+            //   path_begins_with_var(Path, V).
+            var_maybe_uninitialized_on_entry.from_join(
+                &path_maybe_uninitialized_on_entry_m,
+                &path_begins_with_var_var,
+                |&_m, &q, &v| (v, q),
+            );
+        }
+
+        (
+            move_errors.complete(),
+            var_maybe_uninitialized_on_entry.complete(),
+        )
+    };
+
+    if dump_enabled {
+        eprintln!(
+            "move_errors is complete: {} tuples, {:?}",
+            move_errors.len(),
+            timer.elapsed()
+        );
+    }
+
+    let mut result = Initialization {
+        move_errors: FxHashMap::default(),
+        var_maybe_uninitialized_on_entry: FxHashMap::default(),
+    };
+
+    for &(path, location) in &move_errors.elements {
+        result
+            .move_errors
+            .entry(location)
+            .or_insert(Vec::new())
+            .push(path);
+    }
+
+    for &(variable, location) in &var_maybe_uninitialized_on_entry.elements {
+        result
+            .var_maybe_uninitialized_on_entry
+            .entry(location)
+            .or_insert(Vec::new())
+            .push(variable);
+    }
+
+    result
+}
@@ -0,0 +1,97 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Derives `killed` from the paths that loans borrow: overwriting a path
+//! ends the loans of that path and of its projections. For example,
+//! assigning `p` kills a reborrow `&mut *p`, and assigning `*p` kills a
+//! loan of `(*p).f`.
+
+use crate::output::clock::Instant;
+use datafrog::{Iteration, Relation};
+use facts::{AllFacts, Atom};
+
+pub(super) fn compute_path_kills<Region, Loan, Point, Variable, MovePath>(
+    dump_enabled: bool,
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+) -> Vec<(Loan, Point)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    if all_facts.loan_borrows_path.is_empty() {
+        return Vec::new();
+    }
+
+    let timer = Instant::now();
+
+    let killed = {
+        let mut iteration = Iteration::new();
+
+        // static inputs
+        let child_path = iteration.variable::<(MovePath, MovePath)>("child_path");
+        let loan_borrows_path = iteration.variable::<(MovePath, Loan)>("loan_borrows_path");
+
+        // `path_overwritten_at(Path, P)`: an ancestor of `Path`, or `Path`
+        // itself, is assigned at `P`.
+        let path_overwritten_at = iteration.variable::<(MovePath, Point)>("path_overwritten_at");
+        let path_overwritten_at_parent =
+            iteration.variable_indistinct("path_overwritten_at_parent");
+        let killed = iteration.variable::<(Loan, Point)>("killed");
+
+        // load initial facts.
+        child_path.insert(Relation::from(
+            all_facts
+                .child_path
+                .iter()
+                .map(|&(child, parent)| (parent, child)),
+        ));
+        loan_borrows_path.insert(Relation::from(
+            all_facts.loan_borrows_path.iter().map(|&(l, m)| (m, l)),
+        ));
+
+        // path_overwritten_at(Path, P) :- path_assigned_at(Path, P).
+        path_overwritten_at.insert(all_facts.path_assigned_at.clone().into());
+
+        while iteration.changed() {
+            path_overwritten_at_parent.from_map(&path_overwritten_at, |&(m, p)| (m, p));
+
+            // path_overwritten_at(Child, P) :-
+            //   path_overwritten_at(Parent, P),
+            //   child_path(Child, Parent).
+            path_overwritten_at.from_join(
+                &path_overwritten_at_parent,
+                &child_path,
+                |&_parent, &p, &child| (child, p),
+            );
+
+            // killed(L, P) :-
+            //   loan_borrows_path(L, Path),
+            //   path_overwritten_at(Path, P).
+            killed.from_join(&path_overwritten_at, &loan_borrows_path, |&_m, &p, &l| {
+                (l, p)
+            });
+        }
+
+        killed.complete()
+    };
+
+    if dump_enabled {
+        eprintln!(
+            "path kills are complete: {} tuples, {:?}",
+            killed.len(),
+            timer.elapsed()
+        );
+    }
+
+    killed.elements
+}
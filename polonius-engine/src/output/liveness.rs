@@ -0,0 +1,156 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Computes `region_live_at` from the variable liveness facts: a region is
+//! live wherever a variable whose type contains it may later be used, or
+//! dropped, before being overwritten.
+//!
+//! Instead of datafrog joins, the live variables of each point are a
+//! bitset, which a backward dataflow along the CFG edges fills a word of
+//! variables at a time, and the regions of the live variables are unioned
+//! into a bitset of regions per point.
+
+use crate::output::bitset::{AtomBitsets, RegionBitsets};
+use crate::output::clock::Instant;
+use facts::{AllFacts, Atom, SortedIndex};
+
+pub(super) fn compute_region_live_at<Region, Loan, Point, Variable, MovePath>(
+    dump_enabled: bool,
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+) -> Vec<(Region, Point)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let timer = Instant::now();
+
+    let var_facts = || {
+        all_facts
+            .var_used_at
+            .iter()
+            .chain(&all_facts.var_defined_at)
+            .chain(&all_facts.var_drop_used_at)
+    };
+    let origin_facts = || {
+        all_facts
+            .use_of_var_derefs_origin
+            .iter()
+            .chain(&all_facts.drop_of_var_derefs_origin)
+    };
+    let points = all_facts
+        .cfg_edge
+        .iter()
+        .map(|&(p, _)| p)
+        .chain(all_facts.cfg_edge.iter().map(|&(_, q)| q))
+        .chain(var_facts().map(|&(_, p)| p))
+        .map(|p| p.index() + 1)
+        .max()
+        .unwrap_or(0);
+    let variables = var_facts()
+        .map(|&(v, _)| v)
+        .chain(origin_facts().map(|&(v, _)| v))
+        .map(|v| v.index() + 1)
+        .max()
+        .unwrap_or(0);
+    let regions = origin_facts()
+        .map(|&(_, r)| r.index() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let variables_at = |facts: &[(Variable, Point)]| {
+        let mut bitsets = AtomBitsets::new(points, variables);
+        for &(v, p) in facts {
+            bitsets.insert(p, v);
+        }
+        bitsets
+    };
+    let var_defined = variables_at(&all_facts.var_defined_at);
+
+    // var_live(V, P) :- var_used_at(V, P).
+    // var_live(V, P) :-
+    //   var_live(V, Q),
+    //   cfg_edge(P, Q),
+    //   !var_defined_at(V, P).
+    let mut var_live = variables_at(&all_facts.var_used_at);
+
+    // var_drop_live(V, P) :- var_drop_used_at(V, P).
+    // var_drop_live(V, P) :-
+    //   var_drop_live(V, Q),
+    //   cfg_edge(P, Q),
+    //   !var_defined_at(V, P).
+    let mut var_drop_live = variables_at(&all_facts.var_drop_used_at);
+
+    let predecessors: SortedIndex<Point, Point> =
+        all_facts.cfg_edge.iter().map(|&(p, q)| (q, p)).collect();
+    propagate_backward(&mut var_live, &var_defined, &predecessors, points);
+    propagate_backward(&mut var_drop_live, &var_defined, &predecessors, points);
+
+    let regions_of = |facts: &[(Variable, Region)]| {
+        let mut bitsets = AtomBitsets::new(variables, regions);
+        for &(v, r) in facts {
+            bitsets.insert(v, r);
+        }
+        bitsets
+    };
+    let use_of_var_derefs_origin = regions_of(&all_facts.use_of_var_derefs_origin);
+    let drop_of_var_derefs_origin = regions_of(&all_facts.drop_of_var_derefs_origin);
+
+    // region_live_at(R, P) :-
+    //   var_live(V, P),
+    //   use_of_var_derefs_origin(V, R).
+    // region_live_at(R, P) :-
+    //   var_drop_live(V, P),
+    //   drop_of_var_derefs_origin(V, R).
+    let mut region_live: RegionBitsets<Region, Point> = AtomBitsets::new(points, regions);
+    for index in 0..points {
+        let p = Point::from(index);
+        for v in var_live.atoms_at(p) {
+            region_live.union_from(p, &use_of_var_derefs_origin, v);
+        }
+        for v in var_drop_live.atoms_at(p) {
+            region_live.union_from(p, &drop_of_var_derefs_origin, v);
+        }
+    }
+    let region_live_at = region_live.to_pairs();
+
+    if dump_enabled {
+        eprintln!(
+            "region_live_at is complete: {} tuples, {:?}",
+            region_live_at.len(),
+            timer.elapsed()
+        );
+    }
+
+    region_live_at
+}
+
+/// Propagates the live variables of each point to its predecessors, except
+/// the variables the predecessor defines, until they don't change.
+fn propagate_backward<Variable: Atom, Point: Atom>(
+    live: &mut AtomBitsets<Variable, Point>,
+    defined: &AtomBitsets<Variable, Point>,
+    predecessors: &SortedIndex<Point, Point>,
+    points: usize,
+) {
+    let mut worklist: Vec<Point> = (0..points).map(Point::from).collect();
+    let mut queued = vec![true; points];
+    while let Some(q) = worklist.pop() {
+        queued[q.index()] = false;
+        for &p in predecessors.get(&q) {
+            if live.propagate(q, p, defined) && !queued[p.index()] {
+                queued[p.index()] = true;
+                worklist.push(p);
+            }
+        }
+    }
+}
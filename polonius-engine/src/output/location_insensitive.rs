@@ -8,18 +8,36 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! A location-insensitive variant of the analysis: `subset` and `requires`
+//! ignore points entirely, as if the whole CFG were a single node. Only
+//! liveness and invalidations are still tracked per point, to report where
+//! the errors would be. Every error of the location-sensitive analyses is
+//! also an error here, but not the other way around, which makes this a
+//! fast pre-check: a function without errors here has none at all.
+//!
+//! Since `subset` ignores points, regions in a cycle of `outlives`
+//! constraints all require the same loans. Each such cycle is collapsed
+//! into a single region before solving, and the debugging outputs are
+//! expanded back to the original regions afterwards: `subset_anywhere` is
+//! the `outlives` constraints themselves, which are kept for it, and each
+//! region requires the loans of the region of its cycle.
+
 use std::collections::BTreeSet;
-use std::time::Instant;
 
-use crate::output::Output;
+use crate::output::budget::{recent_len, Tracker};
+use crate::output::clock::Instant;
+use crate::output::scc::RegionSccs;
+use crate::output::{Output, PhaseClock};
 
 use datafrog::{Iteration, Relation};
 use facts::{AllFacts, Atom};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath> {
+    let mut clock = PhaseClock::start();
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
         .iter()
@@ -34,9 +52,38 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
     }
 
     let mut result = Output::new(dump_enabled);
+    result.full_borrow_live_at = false;
 
     let potential_errors_start = Instant::now();
 
+    if dump_enabled {
+        let region_live_at: Relation<_> = all_facts.region_live_at.clone().into();
+        for (region, location) in &region_live_at.elements {
+            result
+                .region_live_at
+                .entry(*location)
+                .or_insert(vec![])
+                .push(*region);
+        }
+    }
+
+    // subset(R1, R2) :- outlives(R1, R2, _P), before the cycles are
+    // collapsed away.
+    let subset_anywhere: Vec<(Region, Region)> = if dump_enabled {
+        all_facts
+            .outlives
+            .iter()
+            .map(|&(r1, r2, _p)| (r1, r2))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let sccs = RegionSccs::compute(all_facts.outlives.iter().map(|&(r1, r2, _p)| (r1, r2)));
+    if !sccs.is_trivial() {
+        collapse_cycles(&mut all_facts, &sccs);
+    }
+
     let potential_errors = {
         // Create a new iteration context, ...
         let mut iteration = Iteration::new();
@@ -69,8 +116,18 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             all_facts.invalidates.iter().map(|&(p, b)| ((b, p), ())),
         ));
 
+        clock.end_phase(&mut result.algorithm_phase_timings, "setup");
+
         // .. and then start iterating rules!
         while iteration.changed() {
+            budget.report_errors(&potential_errors);
+
+            // once the budget runs out, the rounds derive nothing and the
+            // iteration drains.
+            if !budget.charge(recent_len(&subset) + recent_len(&requires)) {
+                continue;
+            }
+
             // remap fields to re-index by keys.
             borrow_live_at_lp.from_map(&borrow_live_at, |&(b, p)| ((b, p), ()));
 
@@ -84,42 +141,30 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             potential_errors
                 .from_join(&invalidates, &borrow_live_at_lp, |&(b, p), &(), &()| (b, p));
         }
+        clock.end_phase(&mut result.algorithm_phase_timings, "fixpoint");
+
+        budget.record_size("subset_anywhere", &subset);
+        budget.record_size("requires_anywhere", &requires);
+        budget.record_size("potential_errors", &potential_errors);
 
         if dump_enabled {
-            let subset = subset.complete();
-            for (r1, r2) in &subset.elements {
+            for &(r1, r2) in &subset_anywhere {
                 result
                     .subset_anywhere
-                    .entry(*r1)
+                    .entry(r1)
                     .or_insert(BTreeSet::new())
-                    .insert(*r2);
+                    .insert(r2);
             }
 
             let requires = requires.complete();
             for (region, borrow) in &requires.elements {
-                result
-                    .restricts_anywhere
-                    .entry(*region)
-                    .or_insert(BTreeSet::new())
-                    .insert(*borrow);
-            }
-
-            let borrow_live_at = borrow_live_at.complete();
-            for (borrow, location) in &borrow_live_at.elements {
-                result
-                    .borrow_live_at
-                    .entry(*location)
-                    .or_insert(vec![])
-                    .push(*borrow);
-            }
-
-            let region_live_at = region_live_at.complete();
-            for (region, location) in &region_live_at.elements {
-                result
-                    .region_live_at
-                    .entry(*location)
-                    .or_insert(vec![])
-                    .push(*region);
+                for member in sccs.members(*region) {
+                    result
+                        .restricts_anywhere
+                        .entry(member)
+                        .or_insert(BTreeSet::new())
+                        .insert(*borrow);
+                }
             }
 
             let invalidates = invalidates.complete();
@@ -132,11 +177,20 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             }
         }
 
+        let borrow_live_at = borrow_live_at.complete();
+        for (borrow, location) in &borrow_live_at.elements {
+            result
+                .borrow_live_at
+                .entry(*location)
+                .or_insert(vec![])
+                .push(*borrow);
+        }
+
         potential_errors.complete()
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "potential_errors is complete: {} tuples, {:?}",
             potential_errors.len(),
             potential_errors_start.elapsed()
@@ -150,6 +204,33 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             .or_insert(Vec::new())
             .push(*borrow);
     }
+    clock.end_phase(&mut result.algorithm_phase_timings, "results");
 
     result
 }
+
+/// Replaces every region with the representative of its cycle, dropping
+/// the `outlives` constraints that become trivial.
+fn collapse_cycles<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &mut AllFacts<Region, Loan, Point, Variable, MovePath>,
+    sccs: &RegionSccs<Region>,
+) where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    for outlives in &mut all_facts.outlives {
+        outlives.0 = sccs.representative(outlives.0);
+        outlives.1 = sccs.representative(outlives.1);
+    }
+    all_facts.outlives.retain(|&(r1, r2, _p)| r1 != r2);
+
+    for borrow_region in &mut all_facts.borrow_region {
+        borrow_region.0 = sccs.representative(borrow_region.0);
+    }
+    for region_live_at in &mut all_facts.region_live_at {
+        region_live_at.0 = sccs.representative(region_live_at.0);
+    }
+}
@@ -11,53 +11,317 @@
 use rustc_hash::FxHashMap;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+use std::time::Duration;
 
+mod bitset;
+mod budget;
+mod clock;
+mod compare;
+mod components;
 mod datafrog_opt;
+mod hybrid;
+mod incremental;
+mod initialization;
+mod kills;
+mod liveness;
 mod location_insensitive;
 mod naive;
-use facts::{AllFacts, Atom};
+mod observer;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod provenance;
+mod query;
+mod scc;
+mod subset_errors;
+use facts::{AllFacts, Atom, RelationMemory};
 
-#[derive(Debug, Clone, Copy)]
+use self::budget::{Progress, Tracker};
+use self::clock::Instant;
+
+pub use self::bitset::{AtomBitsets, LoanBitsets, RegionBitsets};
+pub use self::budget::{Budget, BudgetExceeded};
+pub use self::compare::{ErrorsDiff, OutputDiff, RelationDiff};
+pub use self::incremental::{FactsDelta, IncrementalAnalysis};
+pub use self::observer::{ErrorObserver, SolverObserver};
+pub use self::provenance::{Derivation, OriginStep, Tuple};
+pub use self::query::QueryEngine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Algorithm {
     Naive,
     DatafrogOpt,
+
+    /// A fast over-approximation of the errors, ignoring where subset
+    /// relations and loan restrictions hold.
     LocationInsensitive,
+
+    /// `LocationInsensitive` to find the loans that may cause errors, and
+    /// then `DatafrogOpt` on just those loans. The other loans are missing
+    /// from the outputs, e.g. from `borrow_live_at`.
+    Hybrid,
+
+    /// Runs both `Naive` and `DatafrogOpt`, and reports the output of
+    /// `DatafrogOpt`, with the differences between their errors in
+    /// `Output::errors_diff`.
+    Compare,
+}
+
+impl Algorithm {
+    /// The names of every algorithm, as accepted by `from_str`.
+    pub fn variants() -> [&'static str; 5] {
+        [
+            "Naive",
+            "DatafrogOpt",
+            "LocationInsensitive",
+            "Hybrid",
+            "Compare",
+        ]
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
 }
 
+impl FromStr for Algorithm {
+    type Err = String;
+
+    /// Parses an algorithm name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "naive" => Ok(Algorithm::Naive),
+            "datafrogopt" => Ok(Algorithm::DatafrogOpt),
+            "locationinsensitive" => Ok(Algorithm::LocationInsensitive),
+            "hybrid" => Ok(Algorithm::Hybrid),
+            "compare" => Ok(Algorithm::Compare),
+            _ => Err(format!(
+                "unknown algorithm `{}`, expected one of: {}",
+                s,
+                Algorithm::variants().join(", ")
+            )),
+        }
+    }
+}
+
+/// The results of an analysis. The relations are maps from each point to
+/// its tuples, which are sorted, so that iterating over the tuples of a
+/// point, or looking them up with the accessors, always finds them in the
+/// same order. The maps themselves aren't ordered.
 #[derive(Clone, Debug)]
-pub struct Output<Region: Atom, Loan: Atom, Point: Atom> {
-    pub borrow_live_at: FxHashMap<Point, Vec<Loan>>,
+pub struct Output<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom> {
+    /// The loans that are invalidated while they are live, by point.
+    /// Every algorithm computes these.
+    pub errors: FxHashMap<Point, Vec<Loan>>,
+
+    /// The loans whose restrictions must be enforced at each point. Every
+    /// algorithm computes these (for `Hybrid`, only for the loans it
+    /// analyzes precisely). Empty after `compact_borrow_live_at`, so it is
+    /// only read through the accessors.
+    borrow_live_at: FxHashMap<Point, Vec<Loan>>,
+
+    /// The move paths that are accessed at a point while they may have
+    /// been moved out of on some path leading to it.
+    pub move_errors: FxHashMap<Point, Vec<MovePath>>,
+
+    /// The variables that some part of may be uninitialized on entry to
+    /// each point.
+    pub var_maybe_uninitialized_on_entry: FxHashMap<Point, Vec<Variable>>,
+
+    /// The pairs of universal regions `(R1, R2)` for which the analysis
+    /// requires `R1: R2` at a point, without `known_placeholder_subset`
+    /// declaring it. These are reported separately from `errors`.
+    pub subset_errors: FxHashMap<Point, BTreeSet<(Region, Region)>>,
+
+    /// Set by `compute_with_budget` if the budget ran out before the
+    /// analyses were done. The outputs then only hold the tuples derived
+    /// so far, so a missing error doesn't mean there is none.
+    pub budget_exceeded: Option<BudgetExceeded>,
+
+    /// How many tuples the analyses derived, counted as `Budget::max_tuples`
+    /// counts them.
+    pub derived_tuples: usize,
+
+    /// Set by `Algorithm::Compare`, unless the budget ran out: the errors
+    /// that `DatafrogOpt` is missing, or reports unexpectedly, compared to
+    /// `Naive`. It is up to the caller to decide what a difference means.
+    pub errors_diff: Option<ErrorsDiff<Loan, Point>>,
+
+    /// How long each phase of the analysis took, in the order they ran.
+    pub phase_timings: Vec<(&'static str, Duration)>,
+
+    /// How long each phase of the algorithm took, within the `loans` phase:
+    /// the `setup` of its relations, their `fixpoint`, and collecting the
+    /// `results`. `Hybrid` first has its `location-insensitive pass`. The
+    /// partitions of `compute_parallel` run at the same time, so it only
+    /// has the phases of `phase_timings`.
+    pub algorithm_phase_timings: Vec<(&'static str, Duration)>,
+
+    /// The number of tuples of the main relations that the analyses
+    /// derived, largest first.
+    pub derived_relation_sizes: Vec<(&'static str, usize)>,
+
+    /// About how many bytes the tuples of the main relations that the
+    /// analyses derived take, largest first.
+    pub derived_relation_bytes: Vec<(&'static str, usize)>,
 
     pub dump_enabled: bool,
 
-    // these are just for debugging
+    // these are just for debugging, and are only computed when
+    // `dump_enabled` is set
     pub restricts: FxHashMap<Point, BTreeMap<Region, BTreeSet<Loan>>>,
     pub restricts_anywhere: FxHashMap<Region, BTreeSet<Loan>>,
     pub region_live_at: FxHashMap<Point, Vec<Region>>,
     pub invalidates: FxHashMap<Point, Vec<Loan>>,
-    pub errors: FxHashMap<Point, Vec<Loan>>,
     pub subset: FxHashMap<Point, BTreeMap<Region, BTreeSet<Region>>>,
     pub subset_anywhere: FxHashMap<Region, BTreeSet<Region>>,
+
+    /// Why each tuple holds, only recorded by `compute_with_provenance`.
+    provenance: Option<provenance::Provenance<Region, Loan, Point>>,
+
+    /// Whether `borrow_live_at` holds every loan in scope at each point,
+    /// and only those: the location-insensitive algorithms only
+    /// approximate it, and it misses the loans that `prune_dead_loans`
+    /// removed from the facts.
+    pub(crate) full_borrow_live_at: bool,
+
+    /// `borrow_live_at`, once `compact_borrow_live_at` has moved it here.
+    compact_borrow_live_at: Option<LoanBitsets<Loan, Point>>,
 }
 
-impl<Region, Loan, Point> Output<Region, Loan, Point>
+impl<Region, Loan, Point, Variable, MovePath> Output<Region, Loan, Point, Variable, MovePath>
 where
     Region: Atom,
     Loan: Atom,
     Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
 {
     pub fn compute(
-        all_facts: &AllFacts<Region, Loan, Point>,
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
         algorithm: Algorithm,
         dump_enabled: bool,
     ) -> Self {
-        match algorithm {
-            Algorithm::Naive => naive::compute(dump_enabled, all_facts.clone()),
-            Algorithm::DatafrogOpt => datafrog_opt::compute(dump_enabled, all_facts.clone()),
-            Algorithm::LocationInsensitive => {
-                location_insensitive::compute(dump_enabled, all_facts.clone())
-            }
-        }
+        Output::compute_with_budget(all_facts, algorithm, dump_enabled, Budget::unlimited())
+    }
+
+    /// Like `compute`, but stops the loan and subset analyses when they
+    /// exceed `budget`, instead of running for as long as they take. The
+    /// output then has `budget_exceeded` set.
+    pub fn compute_with_budget(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        budget: Budget,
+    ) -> Self {
+        Output::compute_tracked(all_facts, algorithm, dump_enabled, Tracker::new(budget))
+    }
+
+    /// Like `compute_with_budget`, but also tells `observer` about each
+    /// error as soon as the analysis derives it, instead of only once the
+    /// whole fixpoint is reached.
+    pub fn compute_with_observer(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        budget: Budget,
+        observer: &mut dyn ErrorObserver<Loan, Point>,
+    ) -> Self {
+        let budget = Tracker::with_observer(budget, observer);
+        Output::compute_tracked(all_facts, algorithm, dump_enabled, budget)
+    }
+
+    /// Like `compute_with_budget`, but also calls `progress` once per round
+    /// of the fixpoint iterations, with the number of tuples derived so far.
+    pub fn compute_with_progress(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        budget: Budget,
+        progress: &mut dyn FnMut(usize),
+    ) -> Self {
+        let mut progress = Progress(progress);
+        let budget = Tracker::with_solver_observer(budget, &mut progress);
+        Output::compute_tracked(all_facts, algorithm, dump_enabled, budget)
+    }
+
+    /// Like `compute_with_budget`, but also tells `observer` how the
+    /// solving goes: when each phase starts and ends, how many tuples each
+    /// round of the fixpoint iterations derives, and the size of each
+    /// relation once it is derived.
+    pub fn compute_with_solver_observer(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        budget: Budget,
+        observer: &mut dyn SolverObserver,
+    ) -> Self {
+        let budget = Tracker::with_solver_observer(budget, observer);
+        Output::compute_tracked(all_facts, algorithm, dump_enabled, budget)
+    }
+
+    fn compute_tracked(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        mut budget: Tracker<Loan, Point>,
+    ) -> Self {
+        let mut phase_timings = Vec::new();
+        let all_facts = budget.phase(&mut phase_timings, "liveness", |_| {
+            with_derived_facts(all_facts, dump_enabled)
+        });
+
+        // Neither the move analysis nor the subset error check depend on
+        // the loans, so they are the same whichever algorithm was picked.
+        let initialization = budget.phase(&mut phase_timings, "initialization", |_| {
+            initialization::compute(dump_enabled, &all_facts)
+        });
+        let subset_errors = budget.phase(&mut phase_timings, "subset errors", |budget| {
+            subset_errors::compute(dump_enabled, &all_facts, budget)
+        });
+
+        let mut output = budget.phase(&mut phase_timings, "loans", |budget| {
+            compute_borrows_within(all_facts, algorithm, dump_enabled, budget)
+        });
+        output.phase_timings = phase_timings;
+        output.move_errors = initialization.move_errors;
+        output.var_maybe_uninitialized_on_entry = initialization.var_maybe_uninitialized_on_entry;
+        output.subset_errors = subset_errors;
+        output.budget_exceeded = budget.exceeded();
+        output.sort_tuples();
+
+        output
+    }
+
+    /// Like `compute`, but also records why each tuple of the naive
+    /// analysis holds, for `why` to explain the errors. This is much
+    /// slower than any of the algorithms.
+    pub fn compute_with_provenance(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+    ) -> Self {
+        let mut output = Output::compute(all_facts, algorithm, dump_enabled);
+        let all_facts = with_derived_facts(all_facts, false);
+        let provenance = timed_phase(&mut output.phase_timings, "provenance", || {
+            provenance::compute(&all_facts)
+        });
+        output.provenance = Some(provenance);
+        output
+    }
+
+    /// The facts that the loan analyses run on: `all_facts`, with the
+    /// `region_live_at` implied by the liveness of the variables, and the
+    /// `killed` of the assignments to move paths. Other engines can run
+    /// the rules of the algorithms on these.
+    pub fn derived_facts(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+    ) -> AllFacts<Region, Loan, Point, Variable, MovePath> {
+        with_derived_facts(all_facts, false)
     }
 
     fn new(dump_enabled: bool) -> Self {
@@ -68,19 +332,122 @@ where
             region_live_at: FxHashMap::default(),
             invalidates: FxHashMap::default(),
             errors: FxHashMap::default(),
+            move_errors: FxHashMap::default(),
+            var_maybe_uninitialized_on_entry: FxHashMap::default(),
+            subset_errors: FxHashMap::default(),
+            budget_exceeded: None,
+            derived_tuples: 0,
+            errors_diff: None,
+            phase_timings: Vec::new(),
+            algorithm_phase_timings: Vec::new(),
+            derived_relation_sizes: Vec::new(),
+            derived_relation_bytes: Vec::new(),
             subset: FxHashMap::default(),
             subset_anywhere: FxHashMap::default(),
+            provenance: None,
+            full_borrow_live_at: true,
+            compact_borrow_live_at: None,
             dump_enabled,
         }
     }
 
-    pub fn borrows_in_scope_at(&self, location: Point) -> &[Loan] {
+    /// Sorts the tuples of each point of the relations, so that they are
+    /// in the same order whatever the algorithm, and from run to run.
+    fn sort_tuples(&mut self) {
+        sort_values(&mut self.errors);
+        sort_values(&mut self.borrow_live_at);
+        sort_values(&mut self.move_errors);
+        sort_values(&mut self.var_maybe_uninitialized_on_entry);
+        sort_values(&mut self.region_live_at);
+        sort_values(&mut self.invalidates);
+    }
+
+    /// The size of each main relation that the analyses derived, from
+    /// `derived_relation_sizes` and `derived_relation_bytes`, largest
+    /// first.
+    pub fn derived_relation_memory(&self) -> Vec<RelationMemory> {
+        let mut memory: Vec<RelationMemory> = self
+            .derived_relation_sizes
+            .iter()
+            .map(|&(relation, tuples)| RelationMemory {
+                relation,
+                tuples,
+                bytes: self
+                    .derived_relation_bytes
+                    .iter()
+                    .find(|&&(name, _)| name == relation)
+                    .map_or(0, |&(_, bytes)| bytes),
+            })
+            .collect();
+        memory.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.relation.cmp(b.relation)));
+        memory
+    }
+
+    /// The loans whose restrictions must be enforced at each point, whether
+    /// `compact_borrow_live_at` moved them to bitsets or not.
+    pub fn borrow_live_at(&self) -> Cow<'_, FxHashMap<Point, Vec<Loan>>> {
+        match self.compact_borrow_live_at {
+            Some(ref bitsets) => Cow::Owned(bitsets.to_map()),
+            None => Cow::Borrowed(&self.borrow_live_at),
+        }
+    }
+
+    /// `borrow_live_at`, to change it, moved back from the bitsets of
+    /// `compact_borrow_live_at` first.
+    pub fn borrow_live_at_mut(&mut self) -> &mut FxHashMap<Point, Vec<Loan>> {
+        if let Some(bitsets) = self.compact_borrow_live_at.take() {
+            self.borrow_live_at = bitsets.to_map();
+        }
+        &mut self.borrow_live_at
+    }
+
+    /// The loans in scope at `location`, sorted, from `borrow_live_at`.
+    /// With an algorithm that doesn't compute it fully, see
+    /// `has_full_borrow_live_at`, `QueryEngine::borrows_in_scope_at`
+    /// recomputes them.
+    pub fn borrows_in_scope_at(&self, location: Point) -> Cow<'_, [Loan]> {
+        if let Some(ref bitsets) = self.compact_borrow_live_at {
+            return Cow::Owned(bitsets.atoms_at(location));
+        }
         match self.borrow_live_at.get(&location) {
-            Some(p) => p,
-            None => &[],
+            Some(p) => Cow::Borrowed(p),
+            None => Cow::Borrowed(&[]),
         }
     }
 
+    /// Whether `loan` is in scope at `location`, from `borrow_live_at`.
+    pub fn is_borrow_in_scope_at(&self, location: Point, loan: Loan) -> bool {
+        match self.compact_borrow_live_at {
+            Some(ref bitsets) => bitsets.contains(location, loan),
+            None => self.borrows_in_scope_at(location).contains(&loan),
+        }
+    }
+
+    /// Stores `borrow_live_at` as a bitset of the loans of each point, if
+    /// that takes less memory, as it does when many loans are live at many
+    /// points, leaving the map empty. The bitsets have a bit for every
+    /// loan at every point, so a sparse relation stays a map. The
+    /// accessors answer from the bitsets from then on.
+    ///
+    /// Returns whether `borrow_live_at` is stored as bitsets.
+    pub fn compact_borrow_live_at(&mut self) -> bool {
+        if self.compact_borrow_live_at.is_none() {
+            let bitsets = LoanBitsets::from_map(&self.borrow_live_at);
+            if bitsets.bytes() < map_bytes(&self.borrow_live_at) {
+                self.borrow_live_at = FxHashMap::default();
+                self.compact_borrow_live_at = Some(bitsets);
+            }
+        }
+        self.compact_borrow_live_at.is_some()
+    }
+
+    /// Whether `borrow_live_at` is the full relation, and not just the
+    /// approximation that the `LocationInsensitive` and `Hybrid`
+    /// algorithms compute, or the loans left by `prune_dead_loans`.
+    pub fn has_full_borrow_live_at(&self) -> bool {
+        self.full_borrow_live_at
+    }
+
     pub fn restricts_at(&self, location: Point) -> Cow<'_, BTreeMap<Region, BTreeSet<Loan>>> {
         assert!(self.dump_enabled);
         match self.restricts.get(&location) {
@@ -89,6 +456,7 @@ where
         }
     }
 
+    /// The regions live at `location`, sorted.
     pub fn regions_live_at(&self, location: Point) -> &[Region] {
         assert!(self.dump_enabled);
         match self.region_live_at.get(&location) {
@@ -97,19 +465,199 @@ where
         }
     }
 
-    pub fn errors_at(&self, location: Point) -> Cow<'_, Vec<Loan>> {
-        assert!(self.dump_enabled);
+    /// The loans invalidated at `location` while they are live, sorted.
+    pub fn errors_at(&self, location: Point) -> &[Loan] {
         match self.errors.get(&location) {
-            Some(v) => Cow::Borrowed(v),
-            None => Cow::Owned(Vec::default()),
+            Some(v) => v,
+            None => &[],
         }
     }
 
-    pub fn subsets_at(&self, location: Point) -> Cow<'_, BTreeMap<Region, BTreeSet<Region>>> {
+    /// Each error, as the point and the loan invalidated there, sorted by
+    /// point and then by loan.
+    pub fn iter_errors(&self) -> impl Iterator<Item = (Point, Loan)> {
+        let mut errors: Vec<(Point, Loan)> = self
+            .errors
+            .iter()
+            .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+            .collect();
+        errors.sort();
+        errors.into_iter()
+    }
+
+    /// The number of errors, over every point.
+    pub fn error_count(&self) -> usize {
+        self.errors.values().map(|loans| loans.len()).sum()
+    }
+
+    /// The tuples of the relations that every algorithm computes which
+    /// only this output or only `other` has. The debugging relations of
+    /// `dump_enabled` aren't compared.
+    pub fn diff(&self, other: &Self) -> OutputDiff<Region, Loan, Point, Variable, MovePath> {
+        OutputDiff::between(self, other)
+    }
+
+    /// The derivation of the error for `loan` at `location`, from the
+    /// input facts, or `None` if the naive analysis doesn't report it, or
+    /// if this output wasn't computed with provenance.
+    pub fn why(&self, location: Point, loan: Loan) -> Option<Derivation<Region, Loan, Point>> {
+        let provenance = self.provenance.as_ref()?;
+        let error = Tuple::Error(loan, location);
+        if provenance.holds(error) {
+            Some(provenance.derivation(error))
+        } else {
+            None
+        }
+    }
+
+    /// How `region` comes to contain `loan` at `location`: the chain of
+    /// `borrow_region`, subset and CFG steps, from the loan being issued
+    /// on, or `None` if the naive analysis doesn't derive
+    /// `requires(region, loan, location)`, or if this output wasn't
+    /// computed with provenance.
+    pub fn origin_chain(
+        &self,
+        region: Region,
+        loan: Loan,
+        location: Point,
+    ) -> Option<Vec<OriginStep<Region, Point>>> {
+        let provenance = self.provenance.as_ref()?;
+        provenance.origin_chain(Tuple::Requires(region, loan, location))
+    }
+
+    /// The pairs of regions `(R1, R2)` such that `R1` is a subset of `R2`
+    /// at `location`, sorted. Only the location-sensitive algorithms
+    /// compute them, and only if the output is computed with
+    /// `dump_enabled`.
+    pub fn subsets_at(&self, location: Point) -> impl Iterator<Item = (Region, Region)> + '_ {
         assert!(self.dump_enabled);
-        match self.subset.get(&location) {
-            Some(v) => Cow::Borrowed(v),
-            None => Cow::Owned(BTreeMap::default()),
+        self.subset
+            .get(&location)
+            .into_iter()
+            .flat_map(|subsets| subsets.iter())
+            .flat_map(|(&r1, regions)| regions.iter().map(move |&r2| (r1, r2)))
+    }
+}
+
+/// Runs `op`, recording how long it took as the timing of `phase`.
+fn timed_phase<T>(
+    phase_timings: &mut Vec<(&'static str, Duration)>,
+    phase: &'static str,
+    op: impl FnOnce() -> T,
+) -> T {
+    let start = Instant::now();
+    let result = op();
+    phase_timings.push((phase, start.elapsed()));
+    result
+}
+
+/// Times the phases of an algorithm, one after the other, for
+/// `Output::algorithm_phase_timings`.
+struct PhaseClock {
+    phase_start: Instant,
+}
+
+impl PhaseClock {
+    fn start() -> Self {
+        PhaseClock {
+            phase_start: Instant::now(),
         }
     }
+
+    /// Records the time since the end of the previous phase, or since the
+    /// clock started, as the timing of `phase`.
+    fn end_phase(
+        &mut self,
+        phase_timings: &mut Vec<(&'static str, Duration)>,
+        phase: &'static str,
+    ) {
+        phase_timings.push((phase, self.phase_start.elapsed()));
+        self.phase_start = Instant::now();
+    }
+}
+
+/// About how many bytes the tuples of `relation` take, without the
+/// overhead of the map.
+fn map_bytes<K, V>(relation: &FxHashMap<K, Vec<V>>) -> usize {
+    let values: usize = relation.values().map(|values| values.capacity()).sum();
+    relation.len() * (mem::size_of::<K>() + mem::size_of::<Vec<V>>()) + values * mem::size_of::<V>()
+}
+
+fn sort_values<K, V: Ord>(relation: &mut FxHashMap<K, Vec<V>>) {
+    for values in relation.values_mut() {
+        values.sort();
+    }
+}
+
+/// A copy of `all_facts` whose `region_live_at` also holds the liveness
+/// computed from the variable facts, and whose `killed` also holds the
+/// kills derived from the path assignments.
+fn with_derived_facts<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+    dump_enabled: bool,
+) -> AllFacts<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let mut all_facts = all_facts.clone();
+    let region_live_at = liveness::compute_region_live_at(dump_enabled, &all_facts);
+    all_facts.region_live_at.extend(region_live_at);
+    let killed = kills::compute_path_kills(dump_enabled, &all_facts);
+    all_facts.killed.extend(killed);
+    all_facts
+}
+
+/// Runs just the loan analysis of `algorithm`, on facts that already
+/// went through `with_derived_facts`.
+fn compute_borrows<Region, Loan, Point, Variable, MovePath>(
+    all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    algorithm: Algorithm,
+    dump_enabled: bool,
+) -> Output<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    compute_borrows_within(
+        all_facts,
+        algorithm,
+        dump_enabled,
+        &mut Tracker::unlimited(),
+    )
+}
+
+/// Like `compute_borrows`, but within what is left of `budget`.
+fn compute_borrows_within<Region, Loan, Point, Variable, MovePath>(
+    all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    algorithm: Algorithm,
+    dump_enabled: bool,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let mut output = match algorithm {
+        Algorithm::Naive => naive::compute(dump_enabled, all_facts, budget),
+        Algorithm::DatafrogOpt => datafrog_opt::compute(dump_enabled, all_facts, budget),
+        Algorithm::LocationInsensitive => {
+            location_insensitive::compute(dump_enabled, all_facts, budget)
+        }
+        Algorithm::Hybrid => hybrid::compute(dump_enabled, all_facts, budget),
+        Algorithm::Compare => compare::compute(dump_enabled, all_facts, budget),
+    };
+    output.derived_tuples = budget.tuples();
+    output.derived_relation_sizes = budget.relation_sizes();
+    output.derived_relation_bytes = budget.relation_bytes();
+    output
 }
@@ -11,17 +11,20 @@
 //! A version of the Naive datalog analysis using Datafrog.
 
 use std::collections::{BTreeMap, BTreeSet};
-use std::time::Instant;
 
-use crate::output::Output;
+use crate::output::budget::{recent_len, Tracker};
+use crate::output::clock::Instant;
+use crate::output::{Output, PhaseClock};
 use facts::{AllFacts, Atom};
 
 use datafrog::{Iteration, Relation};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, MovePath: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> Output<Region, Loan, Point, Variable, MovePath> {
+    let mut clock = PhaseClock::start();
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
         .iter()
@@ -39,7 +42,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
 
     let borrow_live_at_start = Instant::now();
 
-    let borrow_live_at = {
+    let (borrow_live_at, errors) = {
         // Create a new iteration context, ...
         let mut iteration = Iteration::new();
 
@@ -47,6 +50,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         let subset = iteration.variable::<(Region, Region, Point)>("subset");
         let requires = iteration.variable::<(Region, Loan, Point)>("requires");
         let borrow_live_at = iteration.variable::<(Loan, Point)>("borrow_live_at");
+        let errors = iteration.variable::<(Loan, Point)>("errors");
 
         // different indices for `subset`.
         let subset_r1p = iteration.variable_indistinct("subset_r1p");
@@ -57,6 +61,9 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         let requires_rp = iteration.variable_indistinct("requires_rp");
         let requires_bp = iteration.variable_indistinct("requires_bp");
 
+        // `borrow_live_at`, indexed by both of its fields.
+        let borrow_live_at_bp = iteration.variable_indistinct("borrow_live_at_bp");
+
         // temporaries as we perform a multi-way join.
         let subset_1 = iteration.variable_indistinct("subset_1");
         let subset_2 = iteration.variable_indistinct("subset_2");
@@ -66,6 +73,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         let killed = all_facts.killed.into();
         let region_live_at = iteration.variable::<((Region, Point), ())>("region_live_at");
         let cfg_edge_p = iteration.variable::<(Point, Point)>("cfg_edge_p");
+        let invalidates = iteration.variable::<((Loan, Point), ())>("invalidates");
 
         // load initial facts.
         subset.insert(all_facts.outlives.into());
@@ -74,9 +82,22 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             all_facts.region_live_at.iter().map(|&(r, p)| ((r, p), ())),
         ));
         cfg_edge_p.insert(all_facts.cfg_edge.clone().into());
+        invalidates.insert(Relation::from(
+            all_facts.invalidates.iter().map(|&(p, b)| ((b, p), ())),
+        ));
+
+        clock.end_phase(&mut result.algorithm_phase_timings, "setup");
 
         // .. and then start iterating rules!
         while iteration.changed() {
+            budget.report_errors(&errors);
+
+            // once the budget runs out, the rounds derive nothing and the
+            // iteration drains.
+            if !budget.charge(recent_len(&subset) + recent_len(&requires)) {
+                continue;
+            }
+
             // remap fields to re-index by keys.
             subset_r1p.from_map(&subset, |&(r1, r2, p)| ((r1, p), r2));
             subset_r2p.from_map(&subset, |&(r1, r2, p)| ((r2, p), r1));
@@ -84,6 +105,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
 
             requires_rp.from_map(&requires, |&(r, b, p)| ((r, p), b));
             requires_bp.from_map(&requires, |&(r, b, p)| ((b, p), r));
+            borrow_live_at_bp.from_map(&borrow_live_at, |&(b, p)| ((b, p), ()));
 
             // subset(R1, R2, P) :- outlives(R1, R2, P).
             // Already loaded; outlives is static.
@@ -121,7 +143,16 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
 
             // borrow_live_at(B, P) :- requires(R, B, P), region_live_at(R, P)
             borrow_live_at.from_join(&requires_rp, &region_live_at, |&(_r, p), &b, &()| (b, p));
+
+            // errors(B, P) :- invalidates(P, B), borrow_live_at(B, P).
+            errors.from_join(&invalidates, &borrow_live_at_bp, |&(b, p), &(), &()| (b, p));
         }
+        clock.end_phase(&mut result.algorithm_phase_timings, "fixpoint");
+
+        budget.record_size("subset", &subset);
+        budget.record_size("requires", &requires);
+        budget.record_size("borrow_live_at", &borrow_live_at);
+        budget.record_size("errors", &errors);
 
         if dump_enabled {
             let subset = subset.complete();
@@ -156,11 +187,11 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             }
         }
 
-        borrow_live_at.complete()
+        (borrow_live_at.complete(), errors.complete())
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "borrow_live_at is complete: {} tuples, {:?}",
             borrow_live_at.len(),
             borrow_live_at_start.elapsed()
@@ -175,5 +206,14 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             .push(*borrow);
     }
 
+    for (borrow, location) in &errors.elements {
+        result
+            .errors
+            .entry(*location)
+            .or_insert(Vec::new())
+            .push(*borrow);
+    }
+    clock.end_phase(&mut result.algorithm_phase_timings, "results");
+
     result
 }
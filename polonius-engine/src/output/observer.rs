@@ -0,0 +1,58 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reports the errors of an analysis while it is still solving, e.g. for
+//! an editor to show the diagnostics of the early blocks of a function
+//! before the later ones are done, and how the solving goes, e.g. for a
+//! profiler or a progress bar.
+
+use std::time::Duration;
+
+use crate::output::budget::BudgetExceeded;
+use facts::Atom;
+
+/// Told about each error as soon as an analysis derives it, once per
+/// error. Closures taking the point and the loan are observers.
+pub trait ErrorObserver<Loan: Atom, Point: Atom> {
+    fn error(&mut self, point: Point, loan: Loan);
+}
+
+impl<Loan: Atom, Point: Atom, F: FnMut(Point, Loan)> ErrorObserver<Loan, Point> for F {
+    fn error(&mut self, point: Point, loan: Loan) {
+        self(point, loan)
+    }
+}
+
+/// Told how the solving of `Output::compute_with_solver_observer` goes.
+/// Every method does nothing by default, so an observer only implements
+/// the ones it needs. The `Budget` of the analyses and the progress of
+/// `Output::compute_with_progress` are solver observers too.
+pub trait SolverObserver {
+    /// `phase`, one of the phases of `Output::phase_timings`, starts.
+    fn phase_start(&mut self, _phase: &'static str) {}
+
+    /// `phase` is done, after running for `duration`.
+    fn phase_end(&mut self, _phase: &'static str, _duration: Duration) {}
+
+    /// A round of a fixpoint iteration derived `delta` tuples of the main
+    /// relations, for `total` tuples since the analyses started.
+    fn iteration(&mut self, _delta: usize, _total: usize) {}
+
+    /// The iteration deriving `relation` is done, with `size` tuples in
+    /// it. A relation that several analyses derive is reported by each.
+    fn relation_finalized(&mut self, _relation: &'static str, _size: usize) {}
+
+    /// Whether the analyses should stop after this round, and which limit
+    /// they exceeded: they then drain like when their budget runs out, and
+    /// the output has `budget_exceeded` set.
+    fn exceeded(&self) -> Option<BudgetExceeded> {
+        None
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Solves the loan analysis on several threads. Loans don't interact with
+//! each other, so the loans are split into one partition per thread, and
+//! each thread runs the analysis on the facts of its partition alone.
+
+use std::collections::BTreeSet;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::output::budget::Tracker;
+use crate::output::clock::Instant;
+use crate::output::components::{add_sizes, split_by_component};
+use crate::output::hybrid;
+use crate::output::initialization;
+use crate::output::subset_errors;
+use crate::output::{
+    compute_borrows, timed_phase, with_derived_facts, Algorithm, ErrorsDiff, Output,
+};
+use facts::{AllFacts, Atom};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+impl<Region, Loan, Point, Variable, MovePath> Output<Region, Loan, Point, Variable, MovePath>
+where
+    Region: Atom + Send + Sync,
+    Loan: Atom + Send + Sync,
+    Point: Atom + Send + Sync,
+    Variable: Atom + Send + Sync,
+    MovePath: Atom + Send + Sync,
+{
+    /// Like `compute`, but partitions the loans across `threads` threads,
+    /// or as many as rayon picks by default if `threads` is 0.
+    ///
+    /// The debugging outputs don't survive the partitioning, so when
+    /// `dump_enabled` is set this computes everything on one thread.
+    pub fn compute_parallel(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        threads: usize,
+    ) -> Self {
+        if dump_enabled {
+            return Output::compute(all_facts, algorithm, dump_enabled);
+        }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to start the analysis threads");
+        let partition_count = pool.current_num_threads();
+
+        pool.install(|| {
+            let mut phase_timings = Vec::new();
+            let all_facts = timed_phase(&mut phase_timings, "liveness", || {
+                with_derived_facts(all_facts, false)
+            });
+
+            // The other phases run at the same time, so they are timed as one.
+            let start = Instant::now();
+            let ((initialization, (subset_errors, subset_budget)), partial_outputs) = rayon::join(
+                || {
+                    rayon::join(
+                        || initialization::compute(false, &all_facts),
+                        || {
+                            let mut budget = Tracker::unlimited();
+                            let subset_errors =
+                                subset_errors::compute(false, &all_facts, &mut budget);
+                            let sizes = (budget.relation_sizes(), budget.relation_bytes());
+                            (subset_errors, (budget.tuples(), sizes))
+                        },
+                    )
+                },
+                || {
+                    partition_loans(&all_facts, partition_count)
+                        .into_par_iter()
+                        .map(|loans| {
+                            let partition = hybrid::restrict_to_loans(all_facts.clone(), &loans);
+                            compute_borrows(partition, algorithm, false)
+                        })
+                        .collect::<Vec<_>>()
+                },
+            );
+
+            phase_timings.push(("loans, initialization and subset errors", start.elapsed()));
+
+            let (subset_tuples, (subset_sizes, subset_bytes)) = subset_budget;
+            let mut output = Output::new(false);
+            output.derived_tuples = subset_tuples;
+            output.phase_timings = phase_timings;
+            output.derived_relation_sizes = subset_sizes;
+            output.derived_relation_bytes = subset_bytes;
+            for partial_output in partial_outputs {
+                output.derived_tuples += partial_output.derived_tuples;
+                add_sizes(
+                    &mut output.derived_relation_sizes,
+                    partial_output.derived_relation_sizes,
+                );
+                add_sizes(
+                    &mut output.derived_relation_bytes,
+                    partial_output.derived_relation_bytes,
+                );
+                merge_loans(&mut output.errors, partial_output.errors);
+                merge_loans(&mut output.borrow_live_at, partial_output.borrow_live_at);
+                ErrorsDiff::merge_into(&mut output.errors_diff, partial_output.errors_diff);
+                output.full_borrow_live_at &= partial_output.full_borrow_live_at;
+            }
+            output.move_errors = initialization.move_errors;
+            output.var_maybe_uninitialized_on_entry =
+                initialization.var_maybe_uninitialized_on_entry;
+            output.subset_errors = subset_errors;
+            output.sort_tuples();
+
+            output
+        })
+    }
+
+    /// Like `compute_by_component`, but solves the components on `threads`
+    /// threads, or as many as rayon picks by default if `threads` is 0. A
+    /// CFG of a single component has its loans partitioned across the
+    /// threads instead, like `compute_parallel` does.
+    pub fn compute_by_component_parallel(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+        threads: usize,
+    ) -> Self {
+        let components = split_by_component(all_facts);
+        if components.len() < 2 {
+            return Output::compute_parallel(all_facts, algorithm, dump_enabled, threads);
+        }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to start the analysis threads");
+        let outputs: Vec<Self> = pool.install(|| {
+            components
+                .par_iter()
+                .map(|component| Output::compute(component, algorithm, dump_enabled))
+                .collect()
+        });
+
+        let mut output = Output::new(dump_enabled);
+        for component in outputs {
+            output.merge_component(component);
+        }
+        output.sort_tuples();
+        output
+    }
+}
+
+/// Splits the loans issued in `all_facts` into at most `partition_count`
+/// partitions of about the same size.
+fn partition_loans<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+    partition_count: usize,
+) -> Vec<FxHashSet<Loan>>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let loans: BTreeSet<Loan> = all_facts.borrow_region.iter().map(|&(_, l, _)| l).collect();
+    let partition_count = partition_count.max(1).min(loans.len());
+
+    let mut partitions = vec![FxHashSet::default(); partition_count];
+    for (index, loan) in loans.into_iter().enumerate() {
+        partitions[index % partition_count].insert(loan);
+    }
+    partitions
+}
+
+fn merge_loans<Loan: Atom, Point: Atom>(
+    merged: &mut FxHashMap<Point, Vec<Loan>>,
+    partial: FxHashMap<Point, Vec<Loan>>,
+) {
+    for (point, loans) in partial {
+        let point_loans = merged.entry(point).or_insert(Vec::new());
+        point_loans.extend(loans);
+        point_loans.sort();
+    }
+}
@@ -0,0 +1,502 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Records why each tuple of the naive analysis holds. The rules are the
+//! same as in `naive`, but solved one tuple at a time with a worklist, so
+//! that the rule and premises that first derived each tuple can be kept.
+//! Following the first derivations always ends at input facts, which
+//! makes them a derivation DAG: the tuples that several derivations rest
+//! on are derived once, and their derivation is shared.
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use facts::{AllFacts, Atom};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A tuple of one of the relations of the analysis, input or derived.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Tuple<Region: Atom, Loan: Atom, Point: Atom> {
+    // inputs
+    BorrowRegion(Region, Loan, Point),
+    CfgEdge(Point, Point),
+    Outlives(Region, Region, Point),
+    RegionLiveAt(Region, Point),
+    Invalidates(Point, Loan),
+    /// The absence of `killed(Loan, Point)`.
+    NotKilled(Loan, Point),
+
+    // derived
+    Subset(Region, Region, Point),
+    Requires(Region, Loan, Point),
+    BorrowLiveAt(Loan, Point),
+    Error(Loan, Point),
+}
+
+/// How a tuple holds: either it is an input fact, and `rule` is `None`,
+/// or it was derived by `rule` from the derivations of its premises. The
+/// premises that are the same tuple share their derivation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Derivation<Region: Atom, Loan: Atom, Point: Atom> {
+    pub tuple: Tuple<Region, Loan, Point>,
+    pub rule: Option<&'static str>,
+    pub premises: Vec<Rc<Derivation<Region, Loan, Point>>>,
+}
+
+/// One of the steps that bring a loan into a region, as told by
+/// `Derivation::origin_chain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OriginStep<Region: Atom, Point: Atom> {
+    /// The `borrow_region` fact that issues the loan into `region` at
+    /// `point`.
+    Issued { region: Region, point: Point },
+
+    /// At `point`, `from` is a subset of `to`, so the loan flows from the
+    /// one into the other, because of the `outlives` facts `because`.
+    Subset {
+        from: Region,
+        to: Region,
+        point: Point,
+        because: Vec<(Region, Region, Point)>,
+    },
+
+    /// The loan stays in `region` along the `steps` CFG edges from `from`
+    /// to `to`, where `region` is live and the loan isn't killed.
+    Flow {
+        region: Region,
+        from: Point,
+        to: Point,
+        steps: usize,
+    },
+}
+
+impl<Region: Atom, Loan: Atom, Point: Atom> Derivation<Region, Loan, Point> {
+    /// If this is the derivation of `requires(R, L, P)`, the steps that
+    /// bring `L` into `R` at `P`, from the loan being issued on. The
+    /// consecutive steps along the CFG in one region are merged.
+    pub fn origin_chain(&self) -> Option<Vec<OriginStep<Region, Point>>> {
+        origin_chain(self)
+    }
+}
+
+/// A tuple, with the rule and the premises that derived it: either a node
+/// of a `Derivation`, or a tuple looked up in the justifications of a
+/// `Provenance`, so that the origin chains can be followed without
+/// building the derivation first.
+trait Justified<Region: Atom, Loan: Atom, Point: Atom>: Copy {
+    fn tuple(self) -> Tuple<Region, Loan, Point>;
+    fn rule(self) -> Option<&'static str>;
+    fn premises(self) -> Vec<Self>;
+}
+
+impl<'a, Region: Atom, Loan: Atom, Point: Atom> Justified<Region, Loan, Point>
+    for &'a Derivation<Region, Loan, Point>
+{
+    fn tuple(self) -> Tuple<Region, Loan, Point> {
+        self.tuple
+    }
+
+    fn rule(self) -> Option<&'static str> {
+        self.rule
+    }
+
+    fn premises(self) -> Vec<Self> {
+        self.premises.iter().map(|premise| &**premise).collect()
+    }
+}
+
+impl<'a, Region: Atom, Loan: Atom, Point: Atom> Justified<Region, Loan, Point>
+    for (
+        &'a Provenance<Region, Loan, Point>,
+        Tuple<Region, Loan, Point>,
+    )
+{
+    fn tuple(self) -> Tuple<Region, Loan, Point> {
+        self.1
+    }
+
+    fn rule(self) -> Option<&'static str> {
+        self.0.justifications.get(&self.1).map(|&(rule, _)| rule)
+    }
+
+    fn premises(self) -> Vec<Self> {
+        match self.0.justifications.get(&self.1) {
+            Some(&(_, ref premises)) => premises.iter().map(|&premise| (self.0, premise)).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The steps that bring the loan of `requires` into its region, as told by
+/// `Derivation::origin_chain`.
+fn origin_chain<Region, Loan, Point, J>(requires: J) -> Option<Vec<OriginStep<Region, Point>>>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    J: Justified<Region, Loan, Point>,
+{
+    let mut steps = Vec::new();
+    let mut requires = requires;
+    loop {
+        let premises = requires.premises();
+        let from = premises.first().map(|premise| premise.tuple());
+        match (requires.tuple(), requires.rule(), from) {
+            (Tuple::Requires(region, _, point), Some("requires_borrow_region"), _) => {
+                steps.push(OriginStep::Issued { region, point });
+                break;
+            }
+            (
+                Tuple::Requires(to, _, point),
+                Some("requires_subset"),
+                Some(Tuple::Requires(from, _, _)),
+            ) => {
+                steps.push(OriginStep::Subset {
+                    from,
+                    to,
+                    point,
+                    because: outlives_facts(premises[1]),
+                });
+            }
+            (
+                Tuple::Requires(region, _, to),
+                Some("requires_cfg"),
+                Some(Tuple::Requires(_, _, from)),
+            ) => {
+                steps.push(OriginStep::Flow {
+                    region,
+                    from,
+                    to,
+                    steps: 1,
+                });
+            }
+            _ => return None,
+        }
+        requires = premises[0];
+    }
+    steps.reverse();
+
+    let mut merged: Vec<OriginStep<Region, Point>> = Vec::new();
+    for step in steps {
+        if let OriginStep::Flow {
+            region: next_region,
+            to: next_to,
+            ..
+        } = step
+        {
+            if let Some(&mut OriginStep::Flow {
+                region,
+                ref mut to,
+                steps: ref mut count,
+                ..
+            }) = merged.last_mut()
+            {
+                if region == next_region {
+                    *to = next_to;
+                    *count += 1;
+                    continue;
+                }
+            }
+        }
+        merged.push(step);
+    }
+    Some(merged)
+}
+
+/// The `outlives` facts that the derivation of `justified` rests on, in
+/// the order they are first found. The shared premises are only walked
+/// once.
+fn outlives_facts<Region, Loan, Point, J>(justified: J) -> Vec<(Region, Region, Point)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    J: Justified<Region, Loan, Point>,
+{
+    let mut facts = Vec::new();
+    let mut visited = FxHashSet::default();
+    let mut stack = vec![justified];
+    while let Some(justified) = stack.pop() {
+        if !visited.insert(justified.tuple()) {
+            continue;
+        }
+        if let Tuple::Outlives(r1, r2, point) = justified.tuple() {
+            facts.push((r1, r2, point));
+        }
+        stack.extend(justified.premises().into_iter().rev());
+    }
+    facts
+}
+
+/// The rule that derived a tuple, and the premises it used.
+type Justification<Region, Loan, Point> = (&'static str, Vec<Tuple<Region, Loan, Point>>);
+
+#[derive(Clone, Debug)]
+pub(super) struct Provenance<Region: Atom, Loan: Atom, Point: Atom> {
+    /// The rule and premises of the first derivation of each derived tuple.
+    justifications: FxHashMap<Tuple<Region, Loan, Point>, Justification<Region, Loan, Point>>,
+}
+
+impl<Region: Atom, Loan: Atom, Point: Atom> Provenance<Region, Loan, Point> {
+    /// The derivation of `tuple`, in which the derivation of each tuple is
+    /// built once, however many derivations rest on it: as a tree, the
+    /// derivations of the transitive subsets can grow exponentially.
+    pub(super) fn derivation(
+        &self,
+        tuple: Tuple<Region, Loan, Point>,
+    ) -> Derivation<Region, Loan, Point> {
+        let derivation = self.shared_derivation(tuple, &mut FxHashMap::default());
+        Rc::try_unwrap(derivation).unwrap_or_else(|derivation| (*derivation).clone())
+    }
+
+    fn shared_derivation(
+        &self,
+        tuple: Tuple<Region, Loan, Point>,
+        built: &mut FxHashMap<Tuple<Region, Loan, Point>, Rc<Derivation<Region, Loan, Point>>>,
+    ) -> Rc<Derivation<Region, Loan, Point>> {
+        if let Some(derivation) = built.get(&tuple) {
+            return derivation.clone();
+        }
+        let derivation = match self.justifications.get(&tuple) {
+            Some(&(rule, ref premises)) => Derivation {
+                tuple,
+                rule: Some(rule),
+                premises: premises
+                    .iter()
+                    .map(|&premise| self.shared_derivation(premise, built))
+                    .collect(),
+            },
+            None => Derivation {
+                tuple,
+                rule: None,
+                premises: Vec::new(),
+            },
+        };
+        let derivation = Rc::new(derivation);
+        built.insert(tuple, derivation.clone());
+        derivation
+    }
+
+    pub(super) fn holds(&self, tuple: Tuple<Region, Loan, Point>) -> bool {
+        self.justifications.contains_key(&tuple)
+    }
+
+    /// The origin chain of the derivation of `requires`, followed through
+    /// the justifications.
+    pub(super) fn origin_chain(
+        &self,
+        requires: Tuple<Region, Loan, Point>,
+    ) -> Option<Vec<OriginStep<Region, Point>>> {
+        origin_chain((self, requires))
+    }
+}
+
+pub(super) fn compute<Region, Loan, Point, Variable, MovePath>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+) -> Provenance<Region, Loan, Point>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let mut solver = Solver {
+        justifications: FxHashMap::default(),
+        worklist: VecDeque::new(),
+    };
+
+    let mut successors: FxHashMap<Point, Vec<Point>> = FxHashMap::default();
+    let mut all_points = FxHashSet::default();
+    for &(p, q) in &all_facts.cfg_edge {
+        successors.entry(p).or_insert(Vec::new()).push(q);
+        all_points.insert(p);
+        all_points.insert(q);
+    }
+
+    let mut region_live_at: FxHashSet<(Region, Point)> =
+        all_facts.region_live_at.iter().cloned().collect();
+    for &r in &all_facts.universal_region {
+        for &p in &all_points {
+            region_live_at.insert((r, p));
+        }
+    }
+
+    let killed: FxHashSet<(Loan, Point)> = all_facts.killed.iter().cloned().collect();
+    let invalidates: FxHashSet<(Point, Loan)> = all_facts.invalidates.iter().cloned().collect();
+
+    // The derived tuples so far, indexed for the joins below.
+    let mut subset_r1p: FxHashMap<(Region, Point), Vec<Region>> = FxHashMap::default();
+    let mut subset_r2p: FxHashMap<(Region, Point), Vec<Region>> = FxHashMap::default();
+    let mut requires_rp: FxHashMap<(Region, Point), Vec<Loan>> = FxHashMap::default();
+
+    // subset(R1, R2, P) :- outlives(R1, R2, P).
+    for &(r1, r2, p) in &all_facts.outlives {
+        solver.derive(
+            Tuple::Subset(r1, r2, p),
+            "subset_outlives",
+            vec![Tuple::Outlives(r1, r2, p)],
+        );
+    }
+
+    // requires(R, B, P) :- borrow_region(R, B, P).
+    for &(r, b, p) in &all_facts.borrow_region {
+        solver.derive(
+            Tuple::Requires(r, b, p),
+            "requires_borrow_region",
+            vec![Tuple::BorrowRegion(r, b, p)],
+        );
+    }
+
+    while let Some(tuple) = solver.worklist.pop_front() {
+        match tuple {
+            Tuple::Subset(r1, r2, p) => {
+                subset_r1p.entry((r1, p)).or_insert(Vec::new()).push(r2);
+                subset_r2p.entry((r2, p)).or_insert(Vec::new()).push(r1);
+
+                // subset(R1, R3, P) :- subset(R1, R2, P), subset(R2, R3, P).
+                for &r3 in lookup(&subset_r1p, &(r2, p)) {
+                    solver.derive(
+                        Tuple::Subset(r1, r3, p),
+                        "subset_transitive",
+                        vec![Tuple::Subset(r1, r2, p), Tuple::Subset(r2, r3, p)],
+                    );
+                }
+                for &r0 in lookup(&subset_r2p, &(r1, p)) {
+                    solver.derive(
+                        Tuple::Subset(r0, r2, p),
+                        "subset_transitive",
+                        vec![Tuple::Subset(r0, r1, p), Tuple::Subset(r1, r2, p)],
+                    );
+                }
+
+                // subset(R1, R2, Q) :-
+                //   subset(R1, R2, P),
+                //   cfg_edge(P, Q),
+                //   region_live_at(R1, Q),
+                //   region_live_at(R2, Q).
+                for &q in lookup(&successors, &p) {
+                    if region_live_at.contains(&(r1, q)) && region_live_at.contains(&(r2, q)) {
+                        solver.derive(
+                            Tuple::Subset(r1, r2, q),
+                            "subset_cfg",
+                            vec![
+                                Tuple::Subset(r1, r2, p),
+                                Tuple::CfgEdge(p, q),
+                                Tuple::RegionLiveAt(r1, q),
+                                Tuple::RegionLiveAt(r2, q),
+                            ],
+                        );
+                    }
+                }
+
+                // requires(R2, B, P) :- requires(R1, B, P), subset(R1, R2, P).
+                for &b in lookup(&requires_rp, &(r1, p)) {
+                    solver.derive(
+                        Tuple::Requires(r2, b, p),
+                        "requires_subset",
+                        vec![Tuple::Requires(r1, b, p), Tuple::Subset(r1, r2, p)],
+                    );
+                }
+            }
+
+            Tuple::Requires(r, b, p) => {
+                requires_rp.entry((r, p)).or_insert(Vec::new()).push(b);
+
+                // requires(R2, B, P) :- requires(R1, B, P), subset(R1, R2, P).
+                for &r2 in lookup(&subset_r1p, &(r, p)) {
+                    solver.derive(
+                        Tuple::Requires(r2, b, p),
+                        "requires_subset",
+                        vec![Tuple::Requires(r, b, p), Tuple::Subset(r, r2, p)],
+                    );
+                }
+
+                // requires(R, B, Q) :-
+                //   requires(R, B, P),
+                //   !killed(B, P),
+                //   cfg_edge(P, Q),
+                //   region_live_at(R, Q).
+                if !killed.contains(&(b, p)) {
+                    for &q in lookup(&successors, &p) {
+                        if region_live_at.contains(&(r, q)) {
+                            solver.derive(
+                                Tuple::Requires(r, b, q),
+                                "requires_cfg",
+                                vec![
+                                    Tuple::Requires(r, b, p),
+                                    Tuple::NotKilled(b, p),
+                                    Tuple::CfgEdge(p, q),
+                                    Tuple::RegionLiveAt(r, q),
+                                ],
+                            );
+                        }
+                    }
+                }
+
+                // borrow_live_at(B, P) :- requires(R, B, P), region_live_at(R, P).
+                if region_live_at.contains(&(r, p)) {
+                    solver.derive(
+                        Tuple::BorrowLiveAt(b, p),
+                        "borrow_live_at",
+                        vec![Tuple::Requires(r, b, p), Tuple::RegionLiveAt(r, p)],
+                    );
+                }
+            }
+
+            Tuple::BorrowLiveAt(b, p) => {
+                // errors(B, P) :- invalidates(P, B), borrow_live_at(B, P).
+                if invalidates.contains(&(p, b)) {
+                    solver.derive(
+                        Tuple::Error(b, p),
+                        "errors",
+                        vec![Tuple::Invalidates(p, b), Tuple::BorrowLiveAt(b, p)],
+                    );
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    Provenance {
+        justifications: solver.justifications,
+    }
+}
+
+struct Solver<Region: Atom, Loan: Atom, Point: Atom> {
+    justifications: FxHashMap<Tuple<Region, Loan, Point>, Justification<Region, Loan, Point>>,
+    worklist: VecDeque<Tuple<Region, Loan, Point>>,
+}
+
+impl<Region: Atom, Loan: Atom, Point: Atom> Solver<Region, Loan, Point> {
+    /// Records `tuple` as derived by `rule` from `premises`, unless it was
+    /// already derived, and queues it to derive more tuples.
+    fn derive(
+        &mut self,
+        tuple: Tuple<Region, Loan, Point>,
+        rule: &'static str,
+        premises: Vec<Tuple<Region, Loan, Point>>,
+    ) {
+        if self.justifications.contains_key(&tuple) {
+            return;
+        }
+        self.justifications.insert(tuple, (rule, premises));
+        self.worklist.push_back(tuple);
+    }
+}
+
+fn lookup<'a, K: Eq + Hash, V>(map: &'a FxHashMap<K, Vec<V>>, key: &K) -> &'a [V] {
+    match map.get(key) {
+        Some(values) => values,
+        None => &[],
+    }
+}
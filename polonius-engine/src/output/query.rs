@@ -0,0 +1,244 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Answers questions about a few points or loans, by running the analysis
+//! on just the facts the answer depends on. This is the restriction that
+//! the magic sets transformation would derive for these queries:
+//!
+//! - the tuples of a loan only depend on the facts about that loan, as
+//!   loans don't interact with each other;
+//! - the tuples at a point only depend on the facts at the points that
+//!   can reach it, as `subset` and `requires` flow forward along the CFG.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::Hash;
+
+use crate::output::hybrid;
+use crate::output::{compute_borrows, with_derived_facts, Algorithm};
+use datafrog::{Iteration, Relation};
+use facts::{AllFacts, Atom};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+pub struct QueryEngine<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    algorithm: Algorithm,
+
+    /// The facts, with the `region_live_at` computed from the variable
+    /// facts and the path kills added in. Liveness flows backward, from the uses of the
+    /// variables, so it is computed once for the whole CFG.
+    all_facts: AllFacts<R, L, P, V, M>,
+
+    predecessors: FxHashMap<P, Vec<P>>,
+}
+
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> QueryEngine<R, L, P, V, M> {
+    pub fn new(all_facts: &AllFacts<R, L, P, V, M>, algorithm: Algorithm) -> Self {
+        let mut predecessors = FxHashMap::default();
+        for &(p, q) in &all_facts.cfg_edge {
+            predecessors.entry(q).or_insert(Vec::new()).push(p);
+        }
+
+        QueryEngine {
+            algorithm,
+            all_facts: with_derived_facts(all_facts, false),
+            predecessors,
+        }
+    }
+
+    /// Whether `loan` is in scope at `point`, i.e. in `borrow_live_at`.
+    pub fn loan_in_scope(&self, loan: L, point: P) -> bool {
+        let mut loans = FxHashSet::default();
+        loans.insert(loan);
+
+        let output = compute_borrows(
+            self.restrict(&loans, &[point]),
+            self.precise_algorithm(),
+            false,
+        );
+        output.is_borrow_in_scope_at(point, loan)
+    }
+
+    /// The loans in scope at `point`, i.e. in `borrow_live_at`, sorted.
+    /// Unlike the `borrow_live_at` of `Output`, they are exact whatever the
+    /// algorithm of the engine.
+    pub fn borrows_in_scope_at(&self, point: P) -> Vec<L> {
+        let loans: FxHashSet<L> = self
+            .all_facts
+            .borrow_region
+            .iter()
+            .map(|&(_, l, _)| l)
+            .collect();
+
+        let output = compute_borrows(
+            self.restrict(&loans, &[point]),
+            self.precise_algorithm(),
+            false,
+        );
+        let mut in_scope = output.borrows_in_scope_at(point).to_vec();
+        in_scope.sort();
+        in_scope
+    }
+
+    /// The errors at each of `points`, e.g. the points of a basic block.
+    /// Points without errors are missing from the result.
+    pub fn errors_at(&self, points: &[P]) -> FxHashMap<P, Vec<L>> {
+        // An error at a point needs the loan to be invalidated there.
+        let points_set: FxHashSet<P> = points.iter().cloned().collect();
+        let loans: FxHashSet<L> = self
+            .all_facts
+            .invalidates
+            .iter()
+            .filter(|&&(p, _)| points_set.contains(&p))
+            .map(|&(_, l)| l)
+            .collect();
+        if loans.is_empty() {
+            return FxHashMap::default();
+        }
+
+        let output = compute_borrows(self.restrict(&loans, points), self.algorithm, false);
+        let mut errors = output.errors;
+        errors.retain(|p, _| points_set.contains(p));
+        errors
+    }
+
+    /// The regions live at `point`: those of `region_live_at`, with the
+    /// liveness computed from the variable facts, and the universal regions.
+    pub fn regions_live_at(&self, point: P) -> BTreeSet<R> {
+        let mut regions: BTreeSet<R> = self
+            .all_facts
+            .region_live_at
+            .iter()
+            .filter(|&&(_, p)| p == point)
+            .map(|&(r, _)| r)
+            .collect();
+        if self
+            .all_facts
+            .cfg_edge
+            .iter()
+            .any(|&(p, q)| p == point || q == point)
+        {
+            regions.extend(self.all_facts.universal_region.iter().cloned());
+        }
+        regions
+    }
+
+    /// The regions that each region is a subset of at `point`, as the
+    /// naive analysis derives them, whatever the algorithm of the engine.
+    pub fn subsets_at(&self, point: P) -> BTreeMap<R, BTreeSet<R>> {
+        let all_facts = self.restrict(&FxHashSet::default(), &[point]);
+
+        // The universal regions are live at every point.
+        let mut live = all_facts.region_live_at.clone();
+        let points: FxHashSet<P> = all_facts
+            .cfg_edge
+            .iter()
+            .flat_map(|&(p, q)| vec![p, q])
+            .collect();
+        for &r in &all_facts.universal_region {
+            live.extend(points.iter().map(|&p| (r, p)));
+        }
+
+        let subset = {
+            let mut iteration = Iteration::new();
+
+            let subset = iteration.variable::<(R, R, P)>("subset");
+            let subset_r1p = iteration.variable_indistinct("subset_r1p");
+            let subset_r2p = iteration.variable_indistinct("subset_r2p");
+            let subset_p = iteration.variable_indistinct("subset_p");
+            let subset_1 = iteration.variable_indistinct("subset_1");
+            let subset_2 = iteration.variable_indistinct("subset_2");
+
+            let region_live_at = iteration.variable::<((R, P), ())>("region_live_at");
+            let cfg_edge_p = iteration.variable::<(P, P)>("cfg_edge_p");
+
+            // subset(R1, R2, P) :- outlives(R1, R2, P).
+            subset.insert(all_facts.outlives.clone().into());
+            region_live_at.insert(Relation::from(live.iter().map(|&(r, p)| ((r, p), ()))));
+            cfg_edge_p.insert(all_facts.cfg_edge.clone().into());
+
+            while iteration.changed() {
+                subset_r1p.from_map(&subset, |&(r1, r2, p)| ((r1, p), r2));
+                subset_r2p.from_map(&subset, |&(r1, r2, p)| ((r2, p), r1));
+                subset_p.from_map(&subset, |&(r1, r2, p)| (p, (r1, r2)));
+
+                // subset(R1, R3, P) :-
+                //   subset(R1, R2, P),
+                //   subset(R2, R3, P).
+                subset.from_join(&subset_r2p, &subset_r1p, |&(_r2, p), &r1, &r3| (r1, r3, p));
+
+                // subset(R1, R2, Q) :-
+                //   subset(R1, R2, P),
+                //   cfg_edge(P, Q),
+                //   region_live_at(R1, Q),
+                //   region_live_at(R2, Q).
+                subset_1.from_join(&subset_p, &cfg_edge_p, |&_p, &(r1, r2), &q| ((r1, q), r2));
+                subset_2.from_join(&subset_1, &region_live_at, |&(r1, q), &r2, &()| {
+                    ((r2, q), r1)
+                });
+                subset.from_join(&subset_2, &region_live_at, |&(r2, q), &r1, &()| (r1, r2, q));
+            }
+
+            subset.complete()
+        };
+
+        let mut subsets = BTreeMap::new();
+        for &(r1, r2, p) in &subset.elements {
+            if p == point {
+                subsets.entry(r1).or_insert(BTreeSet::new()).insert(r2);
+            }
+        }
+        subsets
+    }
+
+    /// The algorithm of the engine, unless it only approximates
+    /// `borrow_live_at`, in which case `DatafrogOpt` computes it exactly.
+    fn precise_algorithm(&self) -> Algorithm {
+        match self.algorithm {
+            Algorithm::LocationInsensitive | Algorithm::Hybrid => Algorithm::DatafrogOpt,
+            algorithm => algorithm,
+        }
+    }
+
+    /// The facts about `loans` at the points that can reach `points`.
+    fn restrict(&self, loans: &FxHashSet<L>, points: &[P]) -> AllFacts<R, L, P, V, M> {
+        let cone = self.backward_cone(points);
+        let mut all_facts = hybrid::restrict_to_loans(self.all_facts.clone(), loans);
+
+        // The edges out of the cone are kept, so that the points of the
+        // cone stay in the CFG even if they have no predecessors in it.
+        all_facts.cfg_edge.retain(|&(p, _)| cone.contains(&p));
+        all_facts.borrow_region.retain(|&(_, _, p)| cone.contains(&p));
+        all_facts.killed.retain(|&(_, p)| cone.contains(&p));
+        all_facts.outlives.retain(|&(_, _, p)| cone.contains(&p));
+        all_facts.region_live_at.retain(|&(_, p)| cone.contains(&p));
+        all_facts.invalidates.retain(|&(p, _)| cone.contains(&p));
+        all_facts
+    }
+
+    /// The points from which some point of `points` can be reached.
+    fn backward_cone(&self, points: &[P]) -> FxHashSet<P> {
+        let mut cone: FxHashSet<P> = points.iter().cloned().collect();
+        let mut stack: Vec<P> = points.to_vec();
+        while let Some(point) = stack.pop() {
+            for &predecessor in lookup(&self.predecessors, &point) {
+                if cone.insert(predecessor) {
+                    stack.push(predecessor);
+                }
+            }
+        }
+        cone
+    }
+}
+
+fn lookup<'a, K: Eq + Hash, V>(map: &'a FxHashMap<K, Vec<V>>, key: &K) -> &'a [V] {
+    match map.get(key) {
+        Some(values) => values,
+        None => &[],
+    }
+}
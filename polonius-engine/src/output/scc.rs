@@ -0,0 +1,149 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The strongly connected components of a graph of regions, as found by
+//! Tarjan's algorithm. Regions in a cycle of subset relations all contain
+//! the same loans, so an analysis can replace them with a single
+//! representative region and expand its results back afterwards.
+
+use std::cmp;
+
+use facts::Atom;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+pub(super) struct RegionSccs<Region: Atom> {
+    /// The representative of each region in a component of more than one
+    /// region: its smallest region. Other regions represent themselves.
+    representatives: FxHashMap<Region, Region>,
+
+    /// The regions of each component of more than one region, sorted, by
+    /// representative.
+    members: FxHashMap<Region, Vec<Region>>,
+}
+
+impl<Region: Atom> RegionSccs<Region> {
+    pub(super) fn compute(edges: impl Iterator<Item = (Region, Region)>) -> Self {
+        let mut successors: FxHashMap<Region, Vec<Region>> = FxHashMap::default();
+        for (r1, r2) in edges {
+            successors.entry(r1).or_insert(Vec::new()).push(r2);
+            successors.entry(r2).or_insert(Vec::new());
+        }
+
+        let mut sccs = RegionSccs {
+            representatives: FxHashMap::default(),
+            members: FxHashMap::default(),
+        };
+
+        let mut indices: FxHashMap<Region, usize> = FxHashMap::default();
+        let mut lowlinks: FxHashMap<Region, usize> = FxHashMap::default();
+        let mut stack: Vec<Region> = Vec::new();
+        let mut on_stack: FxHashSet<Region> = FxHashSet::default();
+
+        let mut roots: Vec<Region> = successors.keys().cloned().collect();
+        roots.sort();
+
+        for root in roots {
+            if indices.contains_key(&root) {
+                continue;
+            }
+
+            // The depth-first search is done with an explicit stack of
+            // `(region, index of the next successor to visit)`, as the
+            // graphs can be deep enough to overflow the real one.
+            let mut visiting = vec![(root, 0)];
+            let index = indices.len();
+            indices.insert(root, index);
+            lowlinks.insert(root, index);
+            stack.push(root);
+            on_stack.insert(root);
+
+            loop {
+                let (region, next) = match visiting.last() {
+                    Some(&(region, next)) => (region, next),
+                    None => break,
+                };
+
+                let region_successors = &successors[&region];
+                if next < region_successors.len() {
+                    visiting.last_mut().unwrap().1 += 1;
+
+                    let successor = region_successors[next];
+                    if !indices.contains_key(&successor) {
+                        let index = indices.len();
+                        indices.insert(successor, index);
+                        lowlinks.insert(successor, index);
+                        stack.push(successor);
+                        on_stack.insert(successor);
+                        visiting.push((successor, 0));
+                    } else if on_stack.contains(&successor) {
+                        let lowlink = cmp::min(lowlinks[&region], indices[&successor]);
+                        lowlinks.insert(region, lowlink);
+                    }
+                    continue;
+                }
+
+                visiting.pop();
+                if let Some(&(parent, _)) = visiting.last() {
+                    let lowlink = cmp::min(lowlinks[&parent], lowlinks[&region]);
+                    lowlinks.insert(parent, lowlink);
+                }
+
+                if lowlinks[&region] == indices[&region] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack.remove(&member);
+                        component.push(member);
+                        if member == region {
+                            break;
+                        }
+                    }
+                    sccs.add_component(component);
+                }
+            }
+        }
+
+        sccs
+    }
+
+    fn add_component(&mut self, mut component: Vec<Region>) {
+        if component.len() < 2 {
+            return;
+        }
+
+        component.sort();
+        let representative = component[0];
+        for &member in &component {
+            self.representatives.insert(member, representative);
+        }
+        self.members.insert(representative, component);
+    }
+
+    /// Whether every region is alone in its component, so that there is
+    /// nothing to collapse.
+    pub(super) fn is_trivial(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub(super) fn representative(&self, region: Region) -> Region {
+        match self.representatives.get(&region) {
+            Some(&representative) => representative,
+            None => region,
+        }
+    }
+
+    /// The regions that `representative` stands for, including itself.
+    pub(super) fn members(&self, representative: Region) -> Vec<Region> {
+        match self.members.get(&representative) {
+            Some(members) => members.clone(),
+            None => vec![representative],
+        }
+    }
+}
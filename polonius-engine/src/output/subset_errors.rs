@@ -0,0 +1,176 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the subset relations the analysis requires between universal
+//! regions against the ones the function declares: a function body can't
+//! require `'a: 'b` unless its signature says so.
+
+use std::collections::{BTreeSet, HashSet};
+
+use crate::output::budget::{recent_len, Tracker};
+use crate::output::clock::Instant;
+use datafrog::{Iteration, Relation};
+use facts::{AllFacts, Atom};
+use rustc_hash::FxHashMap;
+
+pub(super) fn compute<Region, Loan, Point, Variable, MovePath>(
+    dump_enabled: bool,
+    all_facts: &AllFacts<Region, Loan, Point, Variable, MovePath>,
+    budget: &mut Tracker<Loan, Point>,
+) -> FxHashMap<Point, BTreeSet<(Region, Region)>>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    MovePath: Atom,
+{
+    let mut result = FxHashMap::default();
+
+    // An error needs two distinct universal regions.
+    let universal_regions: HashSet<Region> = all_facts.universal_region.iter().cloned().collect();
+    if universal_regions.len() < 2 {
+        return result;
+    }
+
+    let timer = Instant::now();
+
+    let all_points: BTreeSet<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .map(|&(p, _)| p)
+        .chain(all_facts.cfg_edge.iter().map(|&(_, q)| q))
+        .collect();
+
+    let subset = {
+        let mut iteration = Iteration::new();
+
+        let subset = iteration.variable::<(Region, Region, Point)>("subset");
+
+        // different indices for `subset`.
+        let subset_r1p = iteration.variable_indistinct("subset_r1p");
+        let subset_r2p = iteration.variable_indistinct("subset_r2p");
+        let subset_p = iteration.variable_indistinct("subset_p");
+
+        // temporaries as we perform a multi-way join.
+        let subset_1 = iteration.variable_indistinct("subset_1");
+        let subset_2 = iteration.variable_indistinct("subset_2");
+
+        let region_live_at = iteration.variable::<((Region, Point), ())>("region_live_at");
+        let cfg_edge_p = iteration.variable::<(Point, Point)>("cfg_edge_p");
+
+        // load initial facts.
+        subset.insert(all_facts.outlives.clone().into());
+        region_live_at.insert(Relation::from(
+            all_facts
+                .region_live_at
+                .iter()
+                .map(|&(r, p)| ((r, p), ()))
+                .chain(
+                    // universal regions are live at every point.
+                    universal_regions
+                        .iter()
+                        .flat_map(|&r| all_points.iter().map(move |&p| ((r, p), ()))),
+                ),
+        ));
+        cfg_edge_p.insert(all_facts.cfg_edge.clone().into());
+
+        while iteration.changed() {
+            // once the budget runs out, the rounds derive nothing and the
+            // iteration drains.
+            if !budget.charge(recent_len(&subset)) {
+                continue;
+            }
+
+            // remap fields to re-index by keys.
+            subset_r1p.from_map(&subset, |&(r1, r2, p)| ((r1, p), r2));
+            subset_r2p.from_map(&subset, |&(r1, r2, p)| ((r2, p), r1));
+            subset_p.from_map(&subset, |&(r1, r2, p)| (p, (r1, r2)));
+
+            // subset(R1, R3, P) :-
+            //   subset(R1, R2, P),
+            //   subset(R2, R3, P).
+            subset.from_join(&subset_r2p, &subset_r1p, |&(_r2, p), &r1, &r3| (r1, r3, p));
+
+            // subset(R1, R2, Q) :-
+            //   subset(R1, R2, P),
+            //   cfg_edge(P, Q),
+            //   region_live_at(R1, Q),
+            //   region_live_at(R2, Q).
+            subset_1.from_join(&subset_p, &cfg_edge_p, |&_p, &(r1, r2), &q| ((r1, q), r2));
+            subset_2.from_join(&subset_1, &region_live_at, |&(r1, q), &r2, &()| {
+                ((r2, q), r1)
+            });
+            subset.from_join(&subset_2, &region_live_at, |&(r2, q), &r1, &()| (r1, r2, q));
+        }
+
+        budget.record_size("subset, for the subset errors", &subset);
+        subset.complete()
+    };
+
+    let known_contains = {
+        let mut iteration = Iteration::new();
+
+        let known_placeholder_subset =
+            iteration.variable::<(Region, Region)>("known_placeholder_subset");
+        let known_contains = iteration.variable::<(Region, Region)>("known_contains");
+        let known_contains_r2 = iteration.variable_indistinct("known_contains_r2");
+
+        known_placeholder_subset.insert(all_facts.known_placeholder_subset.clone().into());
+
+        // known_contains(R1, R2) :- known_placeholder_subset(R1, R2).
+        known_contains.insert(all_facts.known_placeholder_subset.clone().into());
+
+        while iteration.changed() {
+            known_contains_r2.from_map(&known_contains, |&(r1, r2)| (r2, r1));
+
+            // known_contains(R1, R3) :-
+            //   known_contains(R1, R2),
+            //   known_placeholder_subset(R2, R3).
+            known_contains.from_join(
+                &known_contains_r2,
+                &known_placeholder_subset,
+                |&_r2, &r1, &r3| (r1, r3),
+            );
+        }
+
+        known_contains.complete()
+    };
+
+    // subset_errors(R1, R2, P) :-
+    //   subset(R1, R2, P),
+    //   universal_region(R1),
+    //   universal_region(R2),
+    //   R1 != R2,
+    //   !known_contains(R1, R2).
+    let known_contains: HashSet<(Region, Region)> = known_contains.elements.into_iter().collect();
+    for &(r1, r2, location) in &subset.elements {
+        if r1 != r2
+            && universal_regions.contains(&r1)
+            && universal_regions.contains(&r2)
+            && !known_contains.contains(&(r1, r2))
+        {
+            result
+                .entry(location)
+                .or_insert(BTreeSet::new())
+                .insert((r1, r2));
+        }
+    }
+
+    if dump_enabled {
+        eprintln!(
+            "subset_errors is complete: {} points, {:?}",
+            result.len(),
+            timer.elapsed()
+        );
+    }
+
+    result
+}
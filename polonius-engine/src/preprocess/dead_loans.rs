@@ -0,0 +1,46 @@
+//! Removes the loans that can't be the cause of an error.
+
+use facts::{AllFacts, Atom};
+use preprocess::PruningStats;
+use rustc_hash::FxHashSet;
+
+/// Drops the `borrow_region`, `killed` and `loan_borrows_path` facts of the
+/// loans that no point invalidates. Such loans never cause an error, but
+/// they are still live where their regions are, so `borrow_live_at` no
+/// longer lists them afterwards.
+pub fn prune_dead_loans<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+) -> (AllFacts<R, L, P, V, M>, PruningStats)
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let invalidated: FxHashSet<L> = all_facts.invalidates.iter().map(|&(_, l)| l).collect();
+    let dead: FxHashSet<L> = all_facts
+        .borrow_region
+        .iter()
+        .map(|&(_, l, _)| l)
+        .chain(all_facts.killed.iter().map(|&(l, _)| l))
+        .chain(all_facts.loan_borrows_path.iter().map(|&(l, _)| l))
+        .filter(|l| !invalidated.contains(l))
+        .collect();
+    if dead.is_empty() {
+        return (all_facts.clone(), PruningStats::default());
+    }
+
+    let mut pruned = all_facts.clone();
+    pruned.borrow_region.retain(|&(_, l, _)| !dead.contains(&l));
+    pruned.killed.retain(|&(l, _)| !dead.contains(&l));
+    pruned
+        .loan_borrows_path
+        .retain(|&(l, _)| !dead.contains(&l));
+
+    let stats = PruningStats {
+        atoms: dead.len(),
+        tuples: all_facts.tuple_count() - pruned.tuple_count(),
+    };
+    (pruned, stats)
+}
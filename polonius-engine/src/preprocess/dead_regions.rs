@@ -0,0 +1,159 @@
+//! Removes the regions that can't affect the errors.
+
+use facts::{AllFacts, Atom};
+use preprocess::PruningStats;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Removes two kinds of regions from `all_facts`:
+///
+/// - the regions that are never live, and so never carry loans or subset
+///   relations from one point to the next. Their `outlives` and
+///   `borrow_region` facts are replaced by the ones they imply at the same
+///   point: `outlives(R1, D, P)` and `outlives(D, R2, P)` become
+///   `outlives(R1, R2, P)`, and `borrow_region(D, B, P)` becomes
+///   `borrow_region(R2, B, P)`;
+/// - the regions that no `borrow_region` or `outlives` fact mentions, and
+///   so never hold a loan. Their liveness facts are dropped.
+///
+/// Universal regions are live everywhere, and are never removed.
+pub fn prune_dead_regions<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+) -> (AllFacts<R, L, P, V, M>, PruningStats)
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let universal: FxHashSet<R> = all_facts.universal_region.iter().cloned().collect();
+
+    let mut maybe_live = universal.clone();
+    maybe_live.extend(all_facts.region_live_at.iter().map(|&(r, _)| r));
+    maybe_live.extend(all_facts.use_of_var_derefs_origin.iter().map(|&(_, r)| r));
+    maybe_live.extend(all_facts.drop_of_var_derefs_origin.iter().map(|&(_, r)| r));
+
+    let mut constrained = universal.clone();
+    constrained.extend(all_facts.borrow_region.iter().map(|&(r, _, _)| r));
+    constrained.extend(
+        all_facts
+            .outlives
+            .iter()
+            .flat_map(|&(r1, r2, _)| vec![r1, r2]),
+    );
+
+    let never_live: FxHashSet<R> = constrained
+        .iter()
+        .cloned()
+        .filter(|r| !maybe_live.contains(r))
+        .collect();
+    let unconstrained: FxHashSet<R> = maybe_live
+        .iter()
+        .cloned()
+        .filter(|r| !constrained.contains(r))
+        .collect();
+
+    let mut pruned = all_facts.clone();
+    if !never_live.is_empty() {
+        bypass_never_live(&mut pruned, &never_live);
+    }
+    if !unconstrained.is_empty() {
+        pruned
+            .region_live_at
+            .retain(|&(r, _)| !unconstrained.contains(&r));
+        pruned
+            .use_of_var_derefs_origin
+            .retain(|&(_, r)| !unconstrained.contains(&r));
+        pruned
+            .drop_of_var_derefs_origin
+            .retain(|&(_, r)| !unconstrained.contains(&r));
+    }
+
+    let stats = PruningStats {
+        atoms: never_live.len() + unconstrained.len(),
+        tuples: all_facts.tuple_count().saturating_sub(pruned.tuple_count()),
+    };
+    (pruned, stats)
+}
+
+/// Replaces the facts mentioning the regions of `never_live` with the
+/// facts about the other regions they imply.
+fn bypass_never_live<R, L, P, V, M>(
+    all_facts: &mut AllFacts<R, L, P, V, M>,
+    never_live: &FxHashSet<R>,
+) where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    // The `outlives` edges out of each region that is never live.
+    let mut successors: FxHashMap<(R, P), Vec<R>> = FxHashMap::default();
+    for &(r1, r2, p) in &all_facts.outlives {
+        if never_live.contains(&r1) {
+            successors.entry((r1, p)).or_insert(Vec::new()).push(r2);
+        }
+    }
+
+    // The other regions that a region that is never live outlives at a
+    // point, directly or through other regions that are never live.
+    let mut implied: FxHashMap<(R, P), Vec<R>> = FxHashMap::default();
+    let mut implied_by = |r: R, p: P| -> Vec<R> {
+        if let Some(regions) = implied.get(&(r, p)) {
+            return regions.clone();
+        }
+
+        let mut regions = Vec::new();
+        let mut visited = FxHashSet::default();
+        let mut stack = vec![r];
+        while let Some(region) = stack.pop() {
+            if !visited.insert(region) {
+                continue;
+            }
+            for &successor in successors.get(&(region, p)).map_or(&[][..], |s| &s[..]) {
+                if never_live.contains(&successor) {
+                    stack.push(successor);
+                } else {
+                    regions.push(successor);
+                }
+            }
+        }
+        regions.sort();
+        regions.dedup();
+        implied.insert((r, p), regions.clone());
+        regions
+    };
+
+    let mut outlives = Vec::new();
+    for &(r1, r2, p) in &all_facts.outlives {
+        if never_live.contains(&r1) {
+            continue;
+        }
+        if never_live.contains(&r2) {
+            for r3 in implied_by(r2, p) {
+                if r3 != r1 {
+                    outlives.push((r1, r3, p));
+                }
+            }
+        } else {
+            outlives.push((r1, r2, p));
+        }
+    }
+
+    let mut borrow_region = Vec::new();
+    for &(r, l, p) in &all_facts.borrow_region {
+        if never_live.contains(&r) {
+            borrow_region.extend(implied_by(r, p).into_iter().map(|r2| (r2, l, p)));
+        } else {
+            borrow_region.push((r, l, p));
+        }
+    }
+
+    outlives.sort();
+    outlives.dedup();
+    borrow_region.sort();
+    borrow_region.dedup();
+    all_facts.outlives = outlives.into();
+    all_facts.borrow_region = borrow_region.into();
+}
@@ -0,0 +1,98 @@
+//! Relates the points of simplified facts to the points they came from.
+
+use facts::{AllFacts, Atom};
+use output::Output;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// The point that stands in for each point that `simplify_cfg` removed.
+/// The points it kept represent themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PointMapping<P: Atom> {
+    representatives: FxHashMap<P, P>,
+}
+
+impl<P: Atom> PointMapping<P> {
+    pub fn new() -> Self {
+        PointMapping {
+            representatives: FxHashMap::default(),
+        }
+    }
+
+    /// The point of the simplified facts that `point` was merged into.
+    pub fn representative(&self, point: P) -> P {
+        *self.representatives.get(&point).unwrap_or(&point)
+    }
+
+    /// The number of points that were removed.
+    pub fn removed_points(&self) -> usize {
+        self.representatives.len()
+    }
+
+    /// Records that the points in `removed` were merged into the points
+    /// they map to, which `removed` itself doesn't remove.
+    pub(super) fn record(&mut self, removed: &FxHashMap<P, P>) {
+        for representative in self.representatives.values_mut() {
+            if let Some(&merged_into) = removed.get(representative) {
+                *representative = merged_into;
+            }
+        }
+        self.representatives
+            .extend(removed.iter().map(|(&p, &q)| (p, q)));
+    }
+
+    /// Translates `output`, computed on the simplified facts, back to the
+    /// points of `original`, the facts before simplification.
+    ///
+    /// The errors are exact: a point has the errors of its representative
+    /// for the loans it invalidates in `original`. The other outputs of a
+    /// removed point are copied from its representative, which may hold
+    /// more loans or subsets than the point itself would have. The
+    /// debugging outputs are left as they are.
+    pub fn translate_output<R, L, V, M>(
+        &self,
+        output: &Output<R, L, P, V, M>,
+        original: &AllFacts<R, L, P, V, M>,
+    ) -> Output<R, L, P, V, M>
+    where
+        R: Atom,
+        L: Atom,
+        V: Atom,
+        M: Atom,
+    {
+        let mut translated = output.clone();
+
+        let error_loans: FxHashSet<(P, L)> = output
+            .errors
+            .iter()
+            .flat_map(|(&p, loans)| loans.iter().map(move |&l| (p, l)))
+            .collect();
+        translated.errors = FxHashMap::default();
+        for &(p, l) in &original.invalidates {
+            if error_loans.contains(&(self.representative(p), l)) {
+                translated.errors.entry(p).or_insert(Vec::new()).push(l);
+            }
+        }
+        for loans in translated.errors.values_mut() {
+            loans.sort();
+            loans.dedup();
+        }
+
+        for (&p, &representative) in &self.representatives {
+            copy_entry(translated.borrow_live_at_mut(), representative, p);
+            copy_entry(
+                &mut translated.var_maybe_uninitialized_on_entry,
+                representative,
+                p,
+            );
+            copy_entry(&mut translated.subset_errors, representative, p);
+        }
+
+        translated
+    }
+}
+
+fn copy_entry<P: Atom, T: Clone>(map: &mut FxHashMap<P, T>, from: P, to: P) {
+    if let Some(value) = map.get(&from).cloned() {
+        map.insert(to, value);
+    }
+}
@@ -0,0 +1,30 @@
+//! Passes that shrink a set of facts before it is analyzed, without
+//! changing the errors the analysis finds.
+
+mod dead_loans;
+mod dead_regions;
+mod mapping;
+mod outlives;
+mod pipeline;
+mod simplify_cfg;
+mod unreachable;
+mod verify;
+
+pub use self::dead_loans::prune_dead_loans;
+pub use self::dead_regions::prune_dead_regions;
+pub use self::mapping::PointMapping;
+pub use self::outlives::reduce_outlives;
+pub use self::pipeline::{PreprocessLevel, Preprocessed, Preprocessor};
+pub use self::simplify_cfg::simplify_cfg;
+pub use self::unreachable::remove_unreachable_points;
+pub use self::verify::verify_simplification;
+
+/// How much a pruning pass removed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PruningStats {
+    /// The atoms that no fact mentions anymore, e.g. regions or loans.
+    pub atoms: usize,
+
+    /// The tuples removed, less the ones added to stand in for them.
+    pub tuples: usize,
+}
@@ -0,0 +1,92 @@
+//! Removes the `outlives` facts that others imply by transitivity.
+
+use facts::{AllFacts, Atom};
+use preprocess::PruningStats;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Replaces the `outlives` facts of each point with the transitive
+/// reduction of their graph: `outlives(R1, R3, P)` is dropped when
+/// `outlives(R1, R2, P)` and `outlives(R2, R3, P)` imply it. The analyses
+/// close `subset` under transitivity at each point, so they derive the
+/// same relations from the remaining facts.
+///
+/// The edges are removed one at a time, each one only if the others left
+/// still imply it, so the regions of a cycle stay connected. Facts like
+/// `outlives(R, R, P)` are kept.
+pub fn reduce_outlives<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+) -> (AllFacts<R, L, P, V, M>, PruningStats)
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let mut outlives_at: FxHashMap<P, Vec<(R, R)>> = FxHashMap::default();
+    for &(r1, r2, p) in &all_facts.outlives {
+        outlives_at.entry(p).or_insert(Vec::new()).push((r1, r2));
+    }
+
+    let mut outlives = Vec::new();
+    for (p, mut edges) in outlives_at {
+        edges.sort();
+        edges.dedup();
+        outlives.extend(reduce(edges).into_iter().map(|(r1, r2)| (r1, r2, p)));
+    }
+    outlives.sort();
+
+    let mut reduced = all_facts.clone();
+    reduced.outlives = outlives.into();
+
+    let stats = PruningStats {
+        atoms: 0,
+        tuples: all_facts.outlives.len() - reduced.outlives.len(),
+    };
+    (reduced, stats)
+}
+
+/// The transitive reduction of the graph of the sorted and deduplicated
+/// `edges`.
+fn reduce<R: Atom>(edges: Vec<(R, R)>) -> Vec<(R, R)> {
+    let mut successors: FxHashMap<R, Vec<R>> = FxHashMap::default();
+    for &(r1, r2) in &edges {
+        successors.entry(r1).or_insert(Vec::new()).push(r2);
+    }
+
+    for &(r1, r2) in &edges {
+        if r1 == r2 {
+            continue;
+        }
+
+        // Whether `r2` is reachable from `r1` without the edge `r1 -> r2`.
+        let mut visited = FxHashSet::default();
+        let mut stack: Vec<R> = successors[&r1]
+            .iter()
+            .cloned()
+            .filter(|&r| r != r2)
+            .collect();
+        let mut implied = false;
+        while let Some(r) = stack.pop() {
+            if r == r2 {
+                implied = true;
+                break;
+            }
+            if !visited.insert(r) {
+                continue;
+            }
+            if let Some(next) = successors.get(&r) {
+                stack.extend(next.iter().filter(|r| !visited.contains(r)));
+            }
+        }
+
+        if implied {
+            successors.get_mut(&r1).unwrap().retain(|&r| r != r2);
+        }
+    }
+
+    edges
+        .into_iter()
+        .filter(|&(r1, r2)| successors[&r1].contains(&r2))
+        .collect()
+}
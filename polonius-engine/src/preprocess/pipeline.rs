@@ -0,0 +1,225 @@
+//! Runs a configurable selection of the preprocessing passes.
+
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+use facts::{AllFacts, Atom};
+use output::Output;
+use preprocess::{
+    prune_dead_loans, prune_dead_regions, reduce_outlives, remove_unreachable_points, simplify_cfg,
+    PointMapping, PruningStats,
+};
+
+/// The presets of `Preprocessor`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreprocessLevel {
+    /// Runs no pass.
+    None,
+
+    /// Runs the passes that keep the outputs exact, except at the points
+    /// that can't be reached: removing those points, reducing `outlives`,
+    /// and pruning dead regions.
+    Basic,
+
+    /// Also prunes the loans that nothing invalidates, and simplifies the
+    /// CFG. The errors stay exact, but `borrow_live_at` misses the pruned
+    /// loans, and the outputs of the removed points are approximated.
+    Aggressive,
+}
+
+impl PreprocessLevel {
+    /// The names of every level, as accepted by `from_str`.
+    pub fn variants() -> [&'static str; 3] {
+        ["None", "Basic", "Aggressive"]
+    }
+}
+
+impl fmt::Display for PreprocessLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for PreprocessLevel {
+    type Err = String;
+
+    /// Parses a level name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "none" => Ok(PreprocessLevel::None),
+            "basic" => Ok(PreprocessLevel::Basic),
+            "aggressive" => Ok(PreprocessLevel::Aggressive),
+            _ => Err(format!(
+                "unknown preprocessing level `{}`, expected one of: {}",
+                s,
+                PreprocessLevel::variants().join(", ")
+            )),
+        }
+    }
+}
+
+/// Which passes to run on a set of facts before analyzing it. The passes
+/// run in the order of the fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Preprocessor {
+    pub remove_unreachable: bool,
+    pub reduce_outlives: bool,
+    pub prune_dead_regions: bool,
+    pub prune_dead_loans: bool,
+    pub simplify_cfg: bool,
+}
+
+impl Preprocessor {
+    /// The passes of `level`, which the other methods then toggle.
+    pub fn new(level: PreprocessLevel) -> Self {
+        let basic = level != PreprocessLevel::None;
+        let aggressive = level == PreprocessLevel::Aggressive;
+        Preprocessor {
+            remove_unreachable: basic,
+            reduce_outlives: basic,
+            prune_dead_regions: basic,
+            prune_dead_loans: aggressive,
+            simplify_cfg: aggressive,
+        }
+    }
+
+    pub fn remove_unreachable(mut self, enabled: bool) -> Self {
+        self.remove_unreachable = enabled;
+        self
+    }
+
+    pub fn reduce_outlives(mut self, enabled: bool) -> Self {
+        self.reduce_outlives = enabled;
+        self
+    }
+
+    pub fn prune_dead_regions(mut self, enabled: bool) -> Self {
+        self.prune_dead_regions = enabled;
+        self
+    }
+
+    pub fn prune_dead_loans(mut self, enabled: bool) -> Self {
+        self.prune_dead_loans = enabled;
+        self
+    }
+
+    pub fn simplify_cfg(mut self, enabled: bool) -> Self {
+        self.simplify_cfg = enabled;
+        self
+    }
+
+    /// Runs the enabled passes on `all_facts`.
+    pub fn run<R, L, P, V, M>(
+        &self,
+        all_facts: &AllFacts<R, L, P, V, M>,
+    ) -> Preprocessed<R, L, P, V, M>
+    where
+        R: Atom,
+        L: Atom,
+        P: Atom,
+        V: Atom,
+        M: Atom,
+    {
+        let mut atoms = 0;
+        let mut pruned_loans = false;
+        let mut facts = all_facts.clone();
+        if self.remove_unreachable {
+            facts = remove_unreachable_points(&facts);
+        }
+
+        if self.reduce_outlives {
+            let (reduced, stats) = reduce_outlives(&facts);
+            facts = reduced;
+            atoms += stats.atoms;
+        }
+        if self.prune_dead_regions {
+            let (pruned, stats) = prune_dead_regions(&facts);
+            facts = pruned;
+            atoms += stats.atoms;
+        }
+        if self.prune_dead_loans {
+            let (pruned, stats) = prune_dead_loans(&facts);
+            facts = pruned;
+            pruned_loans = stats.atoms > 0;
+            atoms += stats.atoms;
+        }
+
+        let simplified = if self.simplify_cfg {
+            let (simplified_facts, mapping) = simplify_cfg(&facts);
+            atoms += mapping.removed_points();
+            Some((mem::replace(&mut facts, simplified_facts), mapping))
+        } else {
+            None
+        };
+
+        let stats = PruningStats {
+            atoms,
+            tuples: all_facts.tuple_count().saturating_sub(facts.tuple_count()),
+        };
+        Preprocessed {
+            facts,
+            stats,
+            pruned_loans,
+            simplified,
+        }
+    }
+}
+
+/// The facts that a `Preprocessor` produced.
+#[derive(Clone, Debug)]
+pub struct Preprocessed<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
+    /// The facts to analyze.
+    pub facts: AllFacts<R, L, P, V, M>,
+
+    /// How much the passes removed together. The removed points of
+    /// `simplify_cfg` count as atoms.
+    pub stats: PruningStats,
+
+    /// Whether `prune_dead_loans` removed loans, which `borrow_live_at`
+    /// then doesn't list.
+    pruned_loans: bool,
+
+    /// The facts before `simplify_cfg`, and the mapping from their points,
+    /// if it ran.
+    simplified: Option<(AllFacts<R, L, P, V, M>, PointMapping<P>)>,
+}
+
+impl<R, L, P, V, M> Preprocessed<R, L, P, V, M>
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    /// The facts as they were before `simplify_cfg` removed points, which
+    /// are the analyzed facts if it didn't run.
+    pub fn unsimplified_facts(&self) -> &AllFacts<R, L, P, V, M> {
+        match self.simplified {
+            Some((ref facts, _)) => facts,
+            None => &self.facts,
+        }
+    }
+
+    /// The mapping of the points that `simplify_cfg` removed, if it ran.
+    pub fn point_mapping(&self) -> Option<&PointMapping<P>> {
+        self.simplified.as_ref().map(|&(_, ref mapping)| mapping)
+    }
+
+    /// Translates `output`, computed on the analyzed facts, back to the
+    /// points of `unsimplified_facts`, as `PointMapping::translate_output`
+    /// does. If `prune_dead_loans` removed loans, the translated output
+    /// doesn't have the full `borrow_live_at`, see
+    /// `Output::has_full_borrow_live_at`.
+    pub fn translate_output(&self, output: Output<R, L, P, V, M>) -> Output<R, L, P, V, M> {
+        let mut translated = match self.simplified {
+            Some((ref facts, ref mapping)) => mapping.translate_output(&output, facts),
+            None => output,
+        };
+        if self.pruned_loans {
+            translated.full_borrow_live_at = false;
+        }
+        translated
+    }
+}
@@ -0,0 +1,344 @@
+//! Removes the CFG points whose facts can be moved onto other points, or
+//! that carry no facts at all, without changing the errors.
+
+use facts::{AllFacts, Atom, SortedIndex, SortedRelation};
+use preprocess::PointMapping;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Removes the points of `all_facts` that add nothing to the analysis, by
+/// collapsing chains and branches until neither shrinks the CFG further.
+///
+/// A point `q` of a chain is merged into its predecessor `p` when the edge
+/// `p -> q` is the only edge out of `p` and the only edge into `q`, the
+/// same regions are live at both, and their loan facts can share a point
+/// without changing the errors. The facts and the edges out of `q` are
+/// moved onto `p`. Sharing a point moves the kills and the borrows of `p`
+/// next to those of `q`, so the points may not be merged when
+///
+/// - `p` kills loans and `q` issues or invalidates loans: a loan killed at
+///   `p` would still reach the facts of `q`;
+/// - `p` invalidates loans and `q` issues loans: the new loans would reach
+///   the invalidations of `p`.
+///
+/// A branch arm is a point without loan or `outlives` facts whose only
+/// predecessor is the branch point `p` and whose only successor is the
+/// join point `q`. Arms
+/// of the same branch with the same live regions are merged into one, and
+/// an arm is removed altogether when `p -> q` is also an edge and every
+/// region live at `q` is live in the arm, as the arm then restricts
+/// nothing that the edge doesn't.
+///
+/// Points with variable or move path facts, or whose `borrow_region` and
+/// `outlives` facts mention regions that aren't live there, are never
+/// removed.
+///
+/// Each round builds the indexes of the facts by point once, and collapses
+/// every chain in a single walk from its first point.
+///
+/// Returns the simplified facts, and the point each removed point was
+/// merged into, to translate the results back to the original points.
+pub fn simplify_cfg<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+) -> (AllFacts<R, L, P, V, M>, PointMapping<P>)
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let mut simplified = all_facts.clone();
+    let mut mapping = PointMapping::new();
+    loop {
+        let index = PointIndex::new(&simplified);
+        let mut removed = collapse_chains(&index);
+        if removed.is_empty() {
+            removed = collapse_branches(&index);
+        }
+        if removed.is_empty() {
+            return (simplified, mapping);
+        }
+
+        remove_points(&mut simplified, &removed);
+        mapping.record(&removed);
+    }
+}
+
+/// The points merged into the heads of the chains of `index`, mapped to
+/// their heads.
+fn collapse_chains<R: Atom, P: Atom>(index: &PointIndex<R, P>) -> FxHashMap<P, P> {
+    let mut removed = FxHashMap::default();
+    for &start in &index.points {
+        if index.is_linked_to_predecessor(start) {
+            continue;
+        }
+
+        // A chain only ends up back at its start if the start is linked to
+        // its predecessor, so this walk terminates.
+        let mut head = start;
+        let mut merged = index.facts_at(start);
+        let mut last = start;
+        while let Some(next) = index.linked_successor(last) {
+            let next_facts = index.facts_at(next);
+            if merged.can_absorb(next_facts) {
+                removed.insert(next, head);
+                merged = merged.absorb(next_facts);
+            } else {
+                head = next;
+                merged = next_facts;
+            }
+            last = next;
+        }
+    }
+    removed
+}
+
+/// The redundant arms of the branches of `index`, mapped to the arm they
+/// were merged into, or to the branch point if they were removed.
+fn collapse_branches<R: Atom, P: Atom>(index: &PointIndex<R, P>) -> FxHashMap<P, P> {
+    let mut removed = FxHashMap::default();
+    for &p in &index.points {
+        let successors = index.successors.get(&p);
+        if successors.len() < 2 {
+            continue;
+        }
+
+        // The arms out of `p`, by join point.
+        let mut arms: FxHashMap<P, Vec<P>> = FxHashMap::default();
+        for &a in successors {
+            if let Some(q) = index.arm_join(p, a) {
+                arms.entry(q).or_insert(Vec::new()).push(a);
+            }
+        }
+
+        for (q, arms) in arms {
+            let mut kept: Vec<P> = Vec::new();
+            for a in arms {
+                let live_at_q = index.live_regions_at(q);
+                let live_in_arm = index.live_regions_at(a);
+                if successors.contains(&q) && live_at_q.iter().all(|r| live_in_arm.contains(r)) {
+                    removed.insert(a, p);
+                    continue;
+                }
+
+                let twin = kept
+                    .iter()
+                    .cloned()
+                    .find(|&b| index.live_regions_at(b) == live_in_arm);
+                match twin {
+                    Some(b) => {
+                        removed.insert(a, b);
+                    }
+                    None => kept.push(a),
+                }
+            }
+        }
+    }
+    removed
+}
+
+/// Takes the points in `removed` out of `all_facts`, moving their facts and
+/// the edges out of them to the points they map to. The edges into the
+/// removed points are dropped: they are the edges that were collapsed.
+fn remove_points<R, L, P, V, M>(all_facts: &mut AllFacts<R, L, P, V, M>, removed: &FxHashMap<P, P>)
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let representative = |p: P| *removed.get(&p).unwrap_or(&p);
+
+    let mut cfg_edge: Vec<(P, P)> = all_facts
+        .cfg_edge
+        .iter()
+        .filter(|&&(_, q)| !removed.contains_key(&q))
+        .map(|&(p, q)| (representative(p), q))
+        .collect();
+    cfg_edge.sort();
+    cfg_edge.dedup();
+    all_facts.cfg_edge = cfg_edge.into();
+
+    all_facts
+        .region_live_at
+        .retain(|&(_, p)| !removed.contains_key(&p));
+
+    for fact in &mut all_facts.borrow_region {
+        fact.2 = representative(fact.2);
+    }
+    for fact in &mut all_facts.killed {
+        fact.1 = representative(fact.1);
+    }
+    for fact in &mut all_facts.outlives {
+        fact.2 = representative(fact.2);
+    }
+    for fact in &mut all_facts.invalidates {
+        fact.0 = representative(fact.0);
+    }
+}
+
+/// The loan facts of a point, or of the points merged into it.
+#[derive(Copy, Clone, Default)]
+struct PointFacts {
+    borrows: bool,
+    kills: bool,
+    invalidates: bool,
+}
+
+impl PointFacts {
+    /// Whether the facts of the next point can join these.
+    fn can_absorb(self, next: PointFacts) -> bool {
+        !(self.kills && (next.borrows || next.invalidates)) && !(self.invalidates && next.borrows)
+    }
+
+    fn absorb(self, next: PointFacts) -> PointFacts {
+        PointFacts {
+            borrows: self.borrows || next.borrows,
+            kills: self.kills || next.kills,
+            invalidates: self.invalidates || next.invalidates,
+        }
+    }
+}
+
+/// The facts of each point that `simplify_cfg` looks at.
+struct PointIndex<R: Atom, P: Atom> {
+    /// Every point of the CFG, sorted.
+    points: Vec<P>,
+    successors: SortedIndex<P, P>,
+    predecessors: SortedIndex<P, P>,
+
+    /// The live regions of each point.
+    live_regions: SortedIndex<P, R>,
+
+    loan_facts: FxHashMap<P, PointFacts>,
+
+    /// The points with `outlives` facts, which are never branch arms.
+    outlives_points: FxHashSet<P>,
+
+    /// The points that are never merged: the ones with variable or move
+    /// path facts, or with facts about regions that aren't live there.
+    pinned: FxHashSet<P>,
+}
+
+impl<R: Atom, P: Atom> PointIndex<R, P> {
+    fn new<L: Atom, V: Atom, M: Atom>(all_facts: &AllFacts<R, L, P, V, M>) -> Self {
+        let edges = &all_facts.cfg_edge;
+        let points: SortedRelation<P> = edges
+            .iter()
+            .map(|&(p, _)| p)
+            .chain(edges.iter().map(|&(_, q)| q))
+            .collect();
+        let successors: SortedIndex<P, P> = edges.iter().cloned().collect();
+        let predecessors: SortedIndex<P, P> = edges.iter().map(|&(p, q)| (q, p)).collect();
+        let live_regions: SortedIndex<P, R> = all_facts
+            .region_live_at
+            .iter()
+            .map(|&(r, p)| (p, r))
+            .collect();
+
+        let mut loan_facts: FxHashMap<P, PointFacts> = FxHashMap::default();
+        for &(_, _, p) in &all_facts.borrow_region {
+            loan_facts.entry(p).or_insert(PointFacts::default()).borrows = true;
+        }
+        for &(_, p) in &all_facts.killed {
+            loan_facts.entry(p).or_insert(PointFacts::default()).kills = true;
+        }
+        for &(p, _) in &all_facts.invalidates {
+            loan_facts
+                .entry(p)
+                .or_insert(PointFacts::default())
+                .invalidates = true;
+        }
+
+        let outlives_points = all_facts.outlives.iter().map(|&(_, _, p)| p).collect();
+
+        // Universal regions are live everywhere.
+        let universal_regions: FxHashSet<R> = all_facts.universal_region.iter().cloned().collect();
+        let live_at: SortedRelation<(R, P)> = all_facts.region_live_at.iter().cloned().collect();
+        let is_live = |r: R, p: P| universal_regions.contains(&r) || live_at.contains(&(r, p));
+
+        let mut pinned = FxHashSet::default();
+        pinned.extend(all_facts.var_used_at.iter().map(|&(_, p)| p));
+        pinned.extend(all_facts.var_defined_at.iter().map(|&(_, p)| p));
+        pinned.extend(all_facts.var_drop_used_at.iter().map(|&(_, p)| p));
+        pinned.extend(all_facts.path_assigned_at.iter().map(|&(_, p)| p));
+        pinned.extend(all_facts.path_moved_at.iter().map(|&(_, p)| p));
+        pinned.extend(all_facts.path_accessed_at.iter().map(|&(_, p)| p));
+        pinned.extend(
+            all_facts
+                .borrow_region
+                .iter()
+                .filter(|&&(r, _, p)| !is_live(r, p))
+                .map(|&(_, _, p)| p),
+        );
+        pinned.extend(
+            all_facts
+                .outlives
+                .iter()
+                .filter(|&&(r1, r2, p)| !is_live(r1, p) || !is_live(r2, p))
+                .map(|&(_, _, p)| p),
+        );
+
+        PointIndex {
+            points: points.into_vec(),
+            successors,
+            predecessors,
+            live_regions,
+            loan_facts,
+            outlives_points,
+            pinned,
+        }
+    }
+
+    fn live_regions_at(&self, p: P) -> &[R] {
+        self.live_regions.get(&p)
+    }
+
+    fn facts_at(&self, p: P) -> PointFacts {
+        self.loan_facts.get(&p).cloned().unwrap_or_default()
+    }
+
+    /// Whether `q` could be merged into `p`, before looking at the loan
+    /// facts of the points merged with `p`.
+    fn is_edge_collapsible(&self, p: P, q: P) -> bool {
+        p != q
+            && self.successors.get(&p).len() == 1
+            && self.predecessors.get(&q).len() == 1
+            && !self.pinned.contains(&p)
+            && !self.pinned.contains(&q)
+            && self.live_regions_at(p) == self.live_regions_at(q)
+    }
+
+    fn linked_successor(&self, p: P) -> Option<P> {
+        let successors = self.successors.get(&p);
+        if successors.len() == 1 && self.is_edge_collapsible(p, successors[0]) {
+            Some(successors[0])
+        } else {
+            None
+        }
+    }
+
+    /// The join point of `a`, if it is an arm of a branch out of `p`.
+    fn arm_join(&self, p: P, a: P) -> Option<P> {
+        if a == p
+            || self.pinned.contains(&a)
+            || self.loan_facts.contains_key(&a)
+            || self.outlives_points.contains(&a)
+            || self.predecessors.get(&a).len() != 1
+        {
+            return None;
+        }
+        let successors = self.successors.get(&a);
+        if successors.len() == 1 && successors[0] != a && successors[0] != p {
+            Some(successors[0])
+        } else {
+            None
+        }
+    }
+
+    fn is_linked_to_predecessor(&self, q: P) -> bool {
+        let predecessors = self.predecessors.get(&q);
+        predecessors.len() == 1 && self.is_edge_collapsible(predecessors[0], q)
+    }
+}
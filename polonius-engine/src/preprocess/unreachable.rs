@@ -0,0 +1,79 @@
+//! Removes the CFG points that can't be reached from an entry point.
+
+use facts::{AllFacts, Atom, SortedIndex};
+use rustc_hash::FxHashSet;
+
+/// Drops every fact mentioning a point of the CFG that no path from an
+/// entry point, i.e. a point without predecessors, reaches. When every
+/// point has a predecessor, as when the function starts with a loop, the
+/// entry point is the source of the first edge, where rustc's edges
+/// start. Points that only other relations mention are not part of the
+/// CFG, and are kept.
+pub fn remove_unreachable_points<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+) -> AllFacts<R, L, P, V, M>
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let unreachable = unreachable_points(all_facts);
+    if unreachable.is_empty() {
+        return all_facts.clone();
+    }
+
+    let reachable = |p: P| !unreachable.contains(&p);
+    let mut all_facts = all_facts.clone();
+    all_facts.borrow_region.retain(|&(_, _, p)| reachable(p));
+    all_facts
+        .cfg_edge
+        .retain(|&(p, q)| reachable(p) && reachable(q));
+    all_facts.killed.retain(|&(_, p)| reachable(p));
+    all_facts.outlives.retain(|&(_, _, p)| reachable(p));
+    all_facts.region_live_at.retain(|&(_, p)| reachable(p));
+    all_facts.invalidates.retain(|&(p, _)| reachable(p));
+    all_facts.var_used_at.retain(|&(_, p)| reachable(p));
+    all_facts.var_defined_at.retain(|&(_, p)| reachable(p));
+    all_facts.var_drop_used_at.retain(|&(_, p)| reachable(p));
+    all_facts.path_assigned_at.retain(|&(_, p)| reachable(p));
+    all_facts.path_moved_at.retain(|&(_, p)| reachable(p));
+    all_facts.path_accessed_at.retain(|&(_, p)| reachable(p));
+    all_facts
+}
+
+/// The points of the CFG that no entry point reaches.
+fn unreachable_points<R, L, P, V, M>(all_facts: &AllFacts<R, L, P, V, M>) -> FxHashSet<P>
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let successors: SortedIndex<P, P> = all_facts.cfg_edge.iter().cloned().collect();
+    let targets: FxHashSet<P> = all_facts.cfg_edge.iter().map(|&(_, q)| q).collect();
+
+    let mut reached: FxHashSet<P> = FxHashSet::default();
+    let mut stack: Vec<P> = all_facts
+        .cfg_edge
+        .iter()
+        .map(|&(p, _)| p)
+        .filter(|p| !targets.contains(p))
+        .collect();
+    if stack.is_empty() {
+        stack.extend(all_facts.cfg_edge.first().map(|&(p, _)| p));
+    }
+    while let Some(p) = stack.pop() {
+        if !reached.insert(p) {
+            continue;
+        }
+        stack.extend(successors.get(&p).iter().filter(|&q| !reached.contains(q)));
+    }
+
+    targets
+        .into_iter()
+        .filter(|q| !reached.contains(q))
+        .collect()
+}
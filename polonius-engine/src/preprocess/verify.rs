@@ -0,0 +1,26 @@
+//! Checks that simplifying a set of facts doesn't change its errors.
+
+use facts::{AllFacts, Atom};
+use output::{Algorithm, ErrorsDiff, Output};
+use preprocess::simplify_cfg;
+
+/// Runs `algorithm` on `all_facts` and on the result of `simplify_cfg`,
+/// and returns how the errors of the latter, translated back to the
+/// original points, differ from the errors of the former. The difference
+/// is empty unless `simplify_cfg` is wrong.
+pub fn verify_simplification<R, L, P, V, M>(
+    all_facts: &AllFacts<R, L, P, V, M>,
+    algorithm: Algorithm,
+) -> ErrorsDiff<L, P>
+where
+    R: Atom,
+    L: Atom,
+    P: Atom,
+    V: Atom,
+    M: Atom,
+{
+    let (simplified, mapping) = simplify_cfg(all_facts);
+    let expected = Output::compute(all_facts, algorithm, false);
+    let actual = Output::compute(&simplified, algorithm, false);
+    ErrorsDiff::between(&expected, &mapping.translate_output(&actual, all_facts))
+}
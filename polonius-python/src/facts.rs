@@ -0,0 +1,102 @@
+//! The facts of the Python module: atoms interned by name, read from a
+//! facts directory, a JSON facts document, or rows of names pushed one at
+//! a time. Unlike in the `.facts` files, the names are unquoted.
+
+use polonius_engine::{self, unquoted, AtomNames, RowError, INPUT_RELATIONS};
+use polonius_engine::{Loan, MovePath, Point, Region, Variable};
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+crate type AllFacts = polonius_engine::AllFacts<Region, Loan, Point, Variable, MovePath>;
+crate type Output = polonius_engine::Output<Region, Loan, Point, Variable, MovePath>;
+
+/// A row of a relation of a JSON facts document: an array of names or,
+/// for unary relations like `universal_region`, a bare name.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRow {
+    Single(String),
+    Tuple(Vec<String>),
+}
+
+/// Adds the row of the names `columns` to `relation`.
+crate fn push_row(
+    all_facts: &mut AllFacts,
+    names: &mut AtomNames,
+    relation: &str,
+    columns: &[&str],
+) -> Result<(), String> {
+    all_facts
+        .push_row(relation, columns, |kind, name| names.intern(kind, name))
+        .map_err(|error| error.to_string())
+}
+
+/// The rows of `relation`, as names.
+crate fn rows(
+    all_facts: &AllFacts,
+    names: &AtomNames,
+    relation: &str,
+) -> Result<Vec<Vec<String>>, String> {
+    let rows = all_facts
+        .relation_rows(relation)
+        .map_err(|error| error.to_string())?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(kind, index)| names.name_of(kind, index).to_string())
+                .collect()
+        })
+        .collect())
+}
+
+/// Loads the `.facts` files of the directory `dir`, a missing file being
+/// an empty relation.
+crate fn load_facts_dir(names: &mut AtomNames, dir: &Path) -> io::Result<AllFacts> {
+    let mut all_facts = AllFacts::default();
+    for relation in &INPUT_RELATIONS {
+        let path = dir.join(format!("{}.facts", relation));
+        if !path.exists() {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        for (index, line) in contents.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').map(unquoted).collect();
+            push_row(&mut all_facts, names, relation, &columns).map_err(|error| {
+                let error = format!("{}, in line {} of `{}`", error, index + 1, path.display());
+                io::Error::new(io::ErrorKind::InvalidData, error)
+            })?;
+        }
+    }
+    Ok(all_facts)
+}
+
+/// Loads the facts of a JSON document, in the format of the JSON inputs of
+/// `polonius`.
+crate fn read_json_facts(names: &mut AtomNames, json: &str) -> Result<AllFacts, String> {
+    let relations: BTreeMap<String, Vec<JsonRow>> =
+        serde_json::from_str(json).map_err(|error| format!("invalid facts: {}", error))?;
+
+    let mut all_facts = AllFacts::default();
+    for (relation, rows) in &relations {
+        if !INPUT_RELATIONS.contains(&relation.as_str()) {
+            return Err(RowError::UnknownRelation(relation.clone()).to_string());
+        }
+        for (index, row) in rows.iter().enumerate() {
+            let columns: Vec<&str> = match *row {
+                JsonRow::Single(ref column) => vec![column.as_str()],
+                JsonRow::Tuple(ref columns) => columns.iter().map(|s| s.as_str()).collect(),
+            };
+            push_row(&mut all_facts, names, relation, &columns)
+                .map_err(|error| format!("{}, in row {}", error, index))?;
+        }
+    }
+    Ok(all_facts)
+}
@@ -0,0 +1,224 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `polonius` Python module:
+//!
+//! ```python
+//! import polonius
+//!
+//! facts = polonius.Facts.load("inputs/issue-47680/nll-facts/main")
+//! output = facts.compute("DatafrogOpt")
+//! for point, loan in output.errors():
+//!     print(loan, "is invalidated at", point)
+//! ```
+//!
+//! Atoms are named by strings, without the quotes of the `.facts` files.
+
+#![feature(crate_visibility_modifier)]
+#![feature(proc_macro, specialization)]
+
+extern crate polonius_engine;
+#[macro_use]
+extern crate pyo3;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+mod facts;
+mod test;
+
+use polonius_engine::{Algorithm, AtomNames, Column};
+use pyo3::exc;
+use pyo3::prelude::*;
+use std::path::Path;
+
+/// The facts of a function, loaded from a facts directory or a JSON facts
+/// document, or pushed row by row to an empty `Facts()`.
+#[pyclass]
+struct Facts {
+    all_facts: facts::AllFacts,
+    names: AtomNames,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Facts {
+    #[new]
+    fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|token| Facts {
+            all_facts: facts::AllFacts::default(),
+            names: AtomNames::new(),
+            token,
+        })
+    }
+
+    /// Loads a facts directory, or a JSON facts document if `path` ends
+    /// in `.json`.
+    #[staticmethod]
+    fn load(py: Python, path: &str) -> PyResult<Py<Facts>> {
+        let mut names = AtomNames::new();
+        let all_facts = if path.ends_with(".json") {
+            let json = std::fs::read_to_string(path)?;
+            facts::read_json_facts(&mut names, &json).map_err(exc::ValueError::py_err)?
+        } else {
+            facts::load_facts_dir(&mut names, Path::new(path))?
+        };
+        py.init(|token| Facts {
+            all_facts,
+            names,
+            token,
+        })
+    }
+
+    /// Loads the facts of a JSON facts document.
+    #[staticmethod]
+    fn from_json(py: Python, json: &str) -> PyResult<Py<Facts>> {
+        let mut names = AtomNames::new();
+        let all_facts =
+            facts::read_json_facts(&mut names, json).map_err(exc::ValueError::py_err)?;
+        py.init(|token| Facts {
+            all_facts,
+            names,
+            token,
+        })
+    }
+
+    /// Adds the row of the names `row` to the input relation `relation`.
+    fn push(&mut self, relation: &str, row: Vec<String>) -> PyResult<()> {
+        let columns: Vec<&str> = row.iter().map(|column| column.as_str()).collect();
+        facts::push_row(&mut self.all_facts, &mut self.names, relation, &columns)
+            .map_err(exc::ValueError::py_err)
+    }
+
+    /// The rows of the input relation `relation`, as lists of names.
+    fn relation(&self, relation: &str) -> PyResult<Vec<Vec<String>>> {
+        facts::rows(&self.all_facts, &self.names, relation).map_err(exc::ValueError::py_err)
+    }
+
+    /// Analyzes the facts with `algorithm`, one of `polonius.algorithms()`.
+    #[args(algorithm = "\"Naive\"")]
+    fn compute(&self, algorithm: &str) -> PyResult<Py<Output>> {
+        let algorithm: Algorithm = algorithm.parse().map_err(exc::ValueError::py_err)?;
+        let output = facts::Output::compute(&self.all_facts, algorithm, false);
+        let names = self.names.clone();
+        self.py().init(|token| Output {
+            output,
+            names,
+            token,
+        })
+    }
+}
+
+/// The results of `Facts.compute`.
+#[pyclass]
+struct Output {
+    output: facts::Output,
+    names: AtomNames,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Output {
+    /// The `(point, loan)` pairs of the loans that are invalidated while
+    /// they are live.
+    fn errors(&self) -> PyResult<Vec<(String, String)>> {
+        Ok(self
+            .output
+            .iter_errors()
+            .map(|(point, loan)| (self.name(point), self.name(loan)))
+            .collect())
+    }
+
+    fn error_count(&self) -> PyResult<usize> {
+        Ok(self.output.error_count())
+    }
+
+    /// The loans that are invalidated at `point` while they are live.
+    fn errors_at(&self, point: &str) -> PyResult<Vec<String>> {
+        Ok(match self.names.lookup(point) {
+            Some(point) => self.names(self.output.errors_at(point)),
+            None => Vec::new(),
+        })
+    }
+
+    /// The loans in scope at `point`, as `borrow_live_at` has them.
+    fn borrows_in_scope_at(&self, point: &str) -> PyResult<Vec<String>> {
+        Ok(match self.names.lookup(point) {
+            Some(point) => self.names(&self.output.borrows_in_scope_at(point)),
+            None => Vec::new(),
+        })
+    }
+
+    /// The `(point, move path)` pairs of the move paths accessed while they
+    /// may have been moved out of.
+    fn move_errors(&self) -> PyResult<Vec<(String, String)>> {
+        let mut errors: Vec<(String, String)> = self
+            .output
+            .move_errors
+            .iter()
+            .flat_map(|(&point, paths)| paths.iter().map(move |&path| (point, path)))
+            .map(|(point, path)| (self.name(point), self.name(path)))
+            .collect();
+        errors.sort();
+        Ok(errors)
+    }
+
+    /// The `(point, region, region)` triples of the subsets between
+    /// universal regions that the facts don't declare.
+    fn subset_errors(&self) -> PyResult<Vec<(String, String, String)>> {
+        let mut errors: Vec<(String, String, String)> = self
+            .output
+            .subset_errors
+            .iter()
+            .flat_map(|(&point, subsets)| subsets.iter().map(move |&(r1, r2)| (point, r1, r2)))
+            .map(|(point, r1, r2)| (self.name(point), self.name(r1), self.name(r2)))
+            .collect();
+        errors.sort();
+        Ok(errors)
+    }
+
+    /// How long each phase of the analysis took, in seconds.
+    fn phase_timings(&self) -> PyResult<Vec<(String, f64)>> {
+        Ok(self
+            .output
+            .phase_timings
+            .iter()
+            .map(|&(phase, duration)| {
+                let seconds = duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9;
+                (phase.to_string(), seconds)
+            })
+            .collect())
+    }
+}
+
+impl Output {
+    fn name(&self, atom: impl Column) -> String {
+        self.names.name(atom).to_string()
+    }
+
+    fn names<A: Column + Copy>(&self, atoms: &[A]) -> Vec<String> {
+        atoms.iter().map(|&atom| self.name(atom)).collect()
+    }
+}
+
+/// The names of the algorithms that `Facts.compute` accepts.
+#[pyfunction]
+fn algorithms() -> PyResult<Vec<&'static str>> {
+    Ok(Algorithm::variants().to_vec())
+}
+
+#[pymodinit]
+fn polonius(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Facts>()?;
+    m.add_class::<Output>()?;
+    m.add_function(wrap_function!(algorithms))?;
+    Ok(())
+}
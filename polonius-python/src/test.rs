@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use facts;
+use polonius_engine::{Algorithm, AtomNames};
+use std::path::Path;
+
+#[test]
+fn load_facts_dir_unquotes_names() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../inputs/issue-47680/nll-facts/main");
+    let names = &mut AtomNames::new();
+    let all_facts = facts::load_facts_dir(names, &dir).unwrap();
+
+    let rows = facts::rows(&all_facts, names, "borrow_region").unwrap();
+    assert_eq!(rows[0], vec!["\\'_#2r", "bw0", "Mid(bb0[3])"]);
+
+    let naive = facts::Output::compute(&all_facts, Algorithm::Naive, false);
+    let opt = facts::Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+    assert_eq!(
+        naive.iter_errors().collect::<Vec<_>>(),
+        opt.iter_errors().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn read_json_facts_and_push_rows() {
+    let json = r#"{
+        "universal_region": ["'static"],
+        "cfg_edge": [["Start(bb0[0])", "Mid(bb0[0])"]]
+    }"#;
+    let names = &mut AtomNames::new();
+    let mut all_facts = facts::read_json_facts(names, json).unwrap();
+    facts::push_row(
+        &mut all_facts,
+        names,
+        "cfg_edge",
+        &["Mid(bb0[0])", "Start(bb0[1])"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        facts::rows(&all_facts, names, "universal_region").unwrap(),
+        vec![vec!["'static"]]
+    );
+    assert_eq!(
+        facts::rows(&all_facts, names, "cfg_edge").unwrap(),
+        vec![
+            vec!["Start(bb0[0])", "Mid(bb0[0])"],
+            vec!["Mid(bb0[0])", "Start(bb0[1])"],
+        ]
+    );
+
+    assert_eq!(
+        facts::push_row(&mut all_facts, names, "cfg_edges", &["a", "b"]),
+        Err("unknown relation `cfg_edges`".to_string())
+    );
+    assert_eq!(
+        facts::push_row(&mut all_facts, names, "cfg_edge", &["a"]),
+        Err("a row of `cfg_edge` needs 2 columns, not 1".to_string())
+    );
+    assert!(facts::read_json_facts(names, r#"{ "killed": [["bw0"]] }"#).is_err());
+}
@@ -0,0 +1,55 @@
+// The JavaScript API of `polonius-wasm`, for a playground or a visualizer
+// to run the analysis in the browser:
+//
+//     const polonius = await Polonius.load("polonius_wasm.wasm");
+//     const errors = polonius.analyze(facts, "DatafrogOpt");
+//
+// where `facts` is an object in the format of the JSON inputs of
+// `polonius`, and `errors` an array of `{ point, loan }` objects.
+
+export class Polonius {
+  constructor(instance) {
+    this.exports = instance.exports;
+  }
+
+  /// Fetches and instantiates the module at `url`.
+  static async load(url) {
+    const response = await fetch(url);
+    const bytes = await response.arrayBuffer();
+    const { instance } = await WebAssembly.instantiate(bytes, {});
+    return new Polonius(instance);
+  }
+
+  /// The errors of `facts`, analyzed by `algorithm`. Throws if the facts
+  /// are invalid or the algorithm is unknown.
+  analyze(facts, algorithm = "Naive") {
+    const json = typeof facts === "string" ? facts : JSON.stringify(facts);
+    const [factsPtr, factsLen] = this.copyIn(json);
+    const [algorithmPtr, algorithmLen] = this.copyIn(algorithm);
+
+    let report;
+    try {
+      const reportPtr = this.exports.polonius_analyze(factsPtr, factsLen, algorithmPtr, algorithmLen);
+      const len = new DataView(this.exports.memory.buffer).getUint32(reportPtr, true);
+      const bytes = new Uint8Array(this.exports.memory.buffer, reportPtr + 4, len);
+      report = JSON.parse(new TextDecoder().decode(bytes));
+      this.exports.polonius_free(reportPtr, 4 + len);
+    } finally {
+      this.exports.polonius_free(factsPtr, factsLen);
+      this.exports.polonius_free(algorithmPtr, algorithmLen);
+    }
+
+    if (report.failure !== null) {
+      throw new Error(report.failure);
+    }
+    return report.errors;
+  }
+
+  /// Copies `text` into the memory of the module, as UTF-8.
+  copyIn(text) {
+    const bytes = new TextEncoder().encode(text);
+    const ptr = this.exports.polonius_alloc(bytes.length);
+    new Uint8Array(this.exports.memory.buffer, ptr, bytes.length).set(bytes);
+    return [ptr, bytes.length];
+  }
+}
@@ -0,0 +1,150 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The analysis compiled to WebAssembly, for a playground or a visualizer
+//! to run it in the browser. `polonius.js` wraps the functions that the
+//! module exports: it copies the facts into the memory of the module, as
+//! a JSON document in the format of the JSON inputs of `polonius`, and
+//! reads back the report of `polonius_analyze`, a JSON document too.
+
+extern crate polonius_engine;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use polonius_engine::{Algorithm, AtomNames, RowError, INPUT_RELATIONS};
+use polonius_engine::{Loan, MovePath, Point, Region, Variable};
+use std::collections::BTreeMap;
+use std::slice;
+use std::str;
+
+mod test;
+
+type AllFacts = polonius_engine::AllFacts<Region, Loan, Point, Variable, MovePath>;
+type Output = polonius_engine::Output<Region, Loan, Point, Variable, MovePath>;
+
+/// A row of a relation of the facts document: an array of names or, for
+/// unary relations like `universal_region`, a bare name.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRow {
+    Single(String),
+    Tuple(Vec<String>),
+}
+
+/// What `polonius_analyze` returns: the errors, or why there are none.
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Report {
+    errors: Vec<ReportError>,
+    failure: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ReportError {
+    point: String,
+    loan: String,
+}
+
+/// Allocates `len` bytes of the memory of the module, e.g. to copy the
+/// facts passed to `polonius_analyze` into, to free with `polonius_free`.
+#[no_mangle]
+pub extern "C" fn polonius_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// Frees the `len` bytes at `ptr`, allocated by `polonius_alloc` or
+/// returned by `polonius_analyze`.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_free(ptr: *mut u8, len: usize) {
+    drop(Box::from_raw(slice::from_raw_parts_mut(ptr, len)));
+}
+
+/// Analyzes the JSON facts document of `facts_len` bytes at `facts` with
+/// the algorithm named by the `algorithm_len` bytes at `algorithm`, and
+/// returns the JSON report, after its length as 4 little-endian bytes.
+/// The report has the `errors` as `{ point, loan }` objects, or else the
+/// `failure` that prevented the analysis.
+#[no_mangle]
+pub unsafe extern "C" fn polonius_analyze(
+    facts: *const u8,
+    facts_len: usize,
+    algorithm: *const u8,
+    algorithm_len: usize,
+) -> *mut u8 {
+    let facts = slice::from_raw_parts(facts, facts_len);
+    let algorithm = slice::from_raw_parts(algorithm, algorithm_len);
+    let report = match analyze(facts, algorithm) {
+        Ok(errors) => Report {
+            errors,
+            failure: None,
+        },
+        Err(failure) => Report {
+            errors: Vec::new(),
+            failure: Some(failure),
+        },
+    };
+
+    let json = serde_json::to_vec(&report).expect("failed to serialize the report");
+    let mut result = Vec::with_capacity(4 + json.len());
+    let len = json.len() as u32;
+    result.extend_from_slice(&[
+        len as u8,
+        (len >> 8) as u8,
+        (len >> 16) as u8,
+        (len >> 24) as u8,
+    ]);
+    result.extend(json);
+    Box::into_raw(result.into_boxed_slice()) as *mut u8
+}
+
+fn analyze(facts: &[u8], algorithm: &[u8]) -> Result<Vec<ReportError>, String> {
+    let facts = str::from_utf8(facts).map_err(|_| "the facts aren't UTF-8".to_string())?;
+    let algorithm: Algorithm = str::from_utf8(algorithm)
+        .map_err(|_| "the algorithm name isn't UTF-8".to_string())?
+        .parse()?;
+    let names = &mut AtomNames::new();
+    let all_facts = read_facts(names, facts)?;
+
+    let output = Output::compute(&all_facts, algorithm, false);
+    Ok(errors(&output, names))
+}
+
+fn read_facts(names: &mut AtomNames, json: &str) -> Result<AllFacts, String> {
+    let relations: BTreeMap<String, Vec<JsonRow>> =
+        serde_json::from_str(json).map_err(|error| format!("invalid facts: {}", error))?;
+
+    let mut all_facts = AllFacts::default();
+    for (relation, rows) in &relations {
+        if !INPUT_RELATIONS.contains(&relation.as_str()) {
+            return Err(RowError::UnknownRelation(relation.clone()).to_string());
+        }
+        for (index, row) in rows.iter().enumerate() {
+            let columns: Vec<&str> = match *row {
+                JsonRow::Single(ref column) => vec![column.as_str()],
+                JsonRow::Tuple(ref columns) => columns.iter().map(|s| s.as_str()).collect(),
+            };
+            all_facts
+                .push_row(relation, &columns, |kind, name| names.intern(kind, name))
+                .map_err(|error| format!("{}, in row {}", error, index))?;
+        }
+    }
+    Ok(all_facts)
+}
+
+fn errors(output: &Output, names: &AtomNames) -> Vec<ReportError> {
+    output
+        .iter_errors()
+        .map(|(point, loan)| ReportError {
+            point: names.name(point).to_string(),
+            loan: names.name(loan).to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+#![cfg(test)]
+
+use super::{analyze, polonius_analyze, polonius_free, ReportError};
+
+const FACTS: &str = r#"{
+    "borrow_region": [["'a", "L0", "Mid(bb0[0])"]],
+    "cfg_edge": [["Mid(bb0[0])", "Mid(bb0[1])"], ["Mid(bb0[1])", "Mid(bb0[2])"]],
+    "region_live_at": [["'a", "Mid(bb0[1])"], ["'a", "Mid(bb0[2])"]],
+    "invalidates": [["Mid(bb0[2])", "L0"]],
+    "universal_region": []
+}"#;
+
+#[test]
+fn analyze_reports_errors_by_name() {
+    let errors = analyze(FACTS.as_bytes(), b"Naive").unwrap();
+    assert_eq!(
+        errors,
+        vec![ReportError {
+            point: "Mid(bb0[2])".to_string(),
+            loan: "L0".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn analyze_rejects_invalid_input() {
+    let unknown = r#"{ "borrow_regions": [] }"#;
+    assert_eq!(
+        analyze(unknown.as_bytes(), b"Naive"),
+        Err("unknown relation `borrow_regions`".to_string())
+    );
+
+    let short = r#"{ "cfg_edge": [["Mid(bb0[0])"]] }"#;
+    assert_eq!(
+        analyze(short.as_bytes(), b"Naive"),
+        Err("a row of `cfg_edge` needs 2 columns, not 1, in row 0".to_string())
+    );
+
+    assert!(analyze(FACTS.as_bytes(), b"Quantum").is_err());
+}
+
+#[test]
+fn polonius_analyze_returns_length_prefixed_json() {
+    let algorithm = "DatafrogOpt";
+    let json = unsafe {
+        let report = polonius_analyze(
+            FACTS.as_ptr(),
+            FACTS.len(),
+            algorithm.as_ptr(),
+            algorithm.len(),
+        );
+        let prefix = ::std::slice::from_raw_parts(report, 4);
+        let len = prefix
+            .iter()
+            .rev()
+            .fold(0, |len, &byte| len << 8 | byte as usize);
+        let json = ::std::slice::from_raw_parts(report.offset(4), len).to_vec();
+        polonius_free(report, 4 + len);
+        String::from_utf8(json).unwrap()
+    };
+    assert_eq!(
+        json,
+        r#"{"errors":[{"point":"Mid(bb0[2])","loan":"L0"}],"failure":null}"#
+    );
+}
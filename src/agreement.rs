@@ -0,0 +1,117 @@
+#![cfg(test)]
+
+//! Property tests of the agreement of the algorithms: on any well-formed
+//! facts, built by the generator of the engine from random parameters,
+//! the naive, optimized and hybrid analyses must find the same errors.
+//! When they don't, quickcheck shrinks the parameters to the smallest
+//! facts it finds that still disagree, and prints them.
+
+use crate::facts::{AllFacts, Output};
+use polonius_engine::generator::{self, GeneratorParams};
+use polonius_engine::Algorithm;
+use quickcheck::{Arbitrary, Gen, QuickCheck};
+
+/// The number of random sets of facts each property is checked on.
+const TESTS: usize = 200;
+
+/// Parameters of the generator, within bounds that keep each analysis in
+/// the milliseconds.
+#[derive(Clone, Debug)]
+struct Params(GeneratorParams);
+
+impl Arbitrary for Params {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let regions = g.gen_range(0, 12);
+        Params(GeneratorParams {
+            seed: g.gen(),
+            blocks: g.gen_range(1, 12),
+            statements_per_block: g.gen_range(1, 5),
+            loop_nesting: g.gen_range(0, 3),
+            branch_density: g.gen_range(0.0, 0.5),
+            regions,
+            universal_regions: g.gen_range(0, regions + 1),
+            loan_density: g.gen_range(0.0, 0.5),
+            kill_density: g.gen_range(0.0, 0.5),
+            invalidation_density: g.gen_range(0.0, 0.5),
+            outlives_density: g.gen_range(0.0, 0.5),
+            liveness_density: g.gen_range(0.0, 0.5),
+        })
+    }
+
+    /// Smaller CFGs, fewer regions, and sparser facts, one parameter at a
+    /// time.
+    fn shrink(&self) -> Box<Iterator<Item = Params>> {
+        let params = &self.0;
+        let mut candidates = Vec::new();
+
+        macro_rules! shrink_counts {
+            ($($field:ident >= $min:expr,)*) => {
+                $(
+                    for value in smaller(params.$field, $min) {
+                        candidates.push(GeneratorParams {
+                            $field: value,
+                            ..params.clone()
+                        });
+                    }
+                )*
+            };
+        }
+
+        macro_rules! shrink_densities {
+            ($($field:ident,)*) => {
+                $(
+                    if params.$field > 0.0 {
+                        candidates.push(GeneratorParams {
+                            $field: 0.0,
+                            ..params.clone()
+                        });
+                    }
+                )*
+            };
+        }
+
+        shrink_counts! {
+            blocks >= 1,
+            statements_per_block >= 1,
+            loop_nesting >= 0,
+            regions >= params.universal_regions,
+            universal_regions >= 0,
+        }
+        shrink_densities! {
+            branch_density,
+            loan_density,
+            kill_density,
+            invalidation_density,
+            outlives_density,
+            liveness_density,
+        }
+
+        Box::new(candidates.into_iter().map(Params))
+    }
+}
+
+/// The values between `min` and `value` to try instead of `value`, the
+/// smallest first.
+fn smaller(value: usize, min: usize) -> Vec<usize> {
+    let mut values: Vec<usize> = vec![min, value / 2, value.saturating_sub(1)]
+        .into_iter()
+        .filter(|&smaller| smaller >= min && smaller < value)
+        .collect();
+    values.dedup();
+    values
+}
+
+fn errors_agree(params: Params) -> bool {
+    let all_facts: AllFacts = generator::generate(&params.0);
+    let naive = Output::compute(&all_facts, Algorithm::Naive, false);
+    [Algorithm::DatafrogOpt, Algorithm::Hybrid]
+        .iter()
+        .all(|&algorithm| Output::compute(&all_facts, algorithm, false).errors == naive.errors)
+}
+
+#[test]
+fn test_algorithms_agree_on_errors() {
+    QuickCheck::new()
+        .tests(TESTS)
+        .quickcheck(errors_agree as fn(Params) -> bool);
+}
@@ -0,0 +1,25 @@
+use crate::intern::InternerTables;
+
+/// Builds interner tables that give every atom of `tables` an opaque name
+/// derived only from its index. Since the indices themselves are
+/// unchanged, writing facts out with these tables yields a dump with
+/// exactly the same structure, but none of the original names.
+crate fn anonymized_tables(tables: &InternerTables) -> InternerTables {
+    let mut anonymized = InternerTables::new();
+    for index in 0..tables.regions.len() {
+        anonymized.regions.intern(&format!("\"r{}\"", index));
+    }
+    for index in 0..tables.loans.len() {
+        anonymized.loans.intern(&format!("\"l{}\"", index));
+    }
+    for index in 0..tables.points.len() {
+        anonymized.points.intern(&format!("\"p{}\"", index));
+    }
+    for index in 0..tables.variables.len() {
+        anonymized.variables.intern(&format!("\"v{}\"", index));
+    }
+    for index in 0..tables.move_paths.len() {
+        anonymized.move_paths.intern(&format!("\"m{}\"", index));
+    }
+    anonymized
+}
@@ -0,0 +1,137 @@
+//! The baselines of `analyze --save-baseline` and `--compare-baseline`:
+//! the errors of every analyzed function, recorded so that a later run,
+//! say after changing a rule or a preprocessing pass, can report the
+//! errors that appeared and disappeared since.
+
+use crate::dump::WithTables;
+use crate::facts::Output;
+use crate::intern::InternerTables;
+use serde_json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+crate struct Baseline {
+    /// The errors of each function, by the facts directories it was
+    /// analyzed from: see `function_name`.
+    functions: BTreeMap<String, BTreeSet<BaselineError>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+crate struct BaselineError {
+    crate point: String,
+    crate loan: String,
+}
+
+/// How the errors of a run differ from those of a baseline.
+#[derive(Debug, Default, PartialEq)]
+crate struct BaselineChanges {
+    /// The errors that the baseline doesn't have, by function.
+    crate appeared: Vec<(String, BaselineError)>,
+
+    /// The errors of the baseline that are gone, by function.
+    crate disappeared: Vec<(String, BaselineError)>,
+
+    /// The functions of the baseline that the run didn't analyze, and
+    /// whose errors are therefore neither compared nor gone.
+    crate missing: Vec<String>,
+}
+
+/// The name in baselines of the function of `facts_dirs`, several of them
+/// being those of `--merge`. The directories are named by their canonical
+/// paths, so that a baseline saved from one directory, or naming them with
+/// other relative paths, is compared with the same functions.
+crate fn function_name(facts_dirs: &[String]) -> String {
+    let paths: Vec<String> = facts_dirs
+        .iter()
+        .map(|facts_dir| match fs::canonicalize(facts_dir) {
+            Ok(path) => path.display().to_string(),
+            Err(_) => facts_dir.clone(),
+        })
+        .collect();
+    paths.join(" + ")
+}
+
+impl Baseline {
+    crate fn new() -> Self {
+        Baseline::default()
+    }
+
+    /// Records the errors of `output`, the analysis of `function`.
+    crate fn record(&mut self, function: &str, output: &Output, tables: &InternerTables) {
+        let errors = output
+            .with_tables(tables)
+            .errors()
+            .map(|(point, loan)| BaselineError {
+                point: point.to_string(),
+                loan: loan.to_string(),
+            })
+            .collect();
+        self.functions.insert(function.to_string(), errors);
+    }
+
+    crate fn load(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    crate fn save(&self, path: &Path) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// The changes from this baseline to the errors of `current`. The
+    /// functions that are new in `current` only have errors that appeared.
+    crate fn compare(&self, current: &Baseline) -> BaselineChanges {
+        let mut changes = BaselineChanges::default();
+        let none = BTreeSet::new();
+        for (function, errors) in &current.functions {
+            let baseline_errors = self.functions.get(function).unwrap_or(&none);
+            for error in errors.difference(baseline_errors) {
+                changes.appeared.push((function.clone(), error.clone()));
+            }
+            for error in baseline_errors.difference(errors) {
+                changes.disappeared.push((function.clone(), error.clone()));
+            }
+        }
+        for function in self.functions.keys() {
+            if !current.functions.contains_key(function) {
+                changes.missing.push(function.clone());
+            }
+        }
+        changes
+    }
+}
+
+impl BaselineChanges {
+    /// Whether every function that was analyzed has the errors of the
+    /// baseline.
+    crate fn is_unchanged(&self) -> bool {
+        self.appeared.is_empty() && self.disappeared.is_empty()
+    }
+}
+
+impl fmt::Display for BaselineChanges {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Compared to the baseline: {} new errors, {} errors gone",
+            self.appeared.len(),
+            self.disappeared.len()
+        )?;
+        for (function, error) in &self.appeared {
+            writeln!(f, "+ {}: `{}` at `{}`", function, error.loan, error.point)?;
+        }
+        for (function, error) in &self.disappeared {
+            writeln!(f, "- {}: `{}` at `{}`", function, error.loan, error.point)?;
+        }
+        for function in &self.missing {
+            writeln!(f, "? {}: not analyzed", function)?;
+        }
+        Ok(())
+    }
+}
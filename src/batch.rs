@@ -0,0 +1,206 @@
+use crate::cli::seconds;
+use regex::Regex;
+use std::cmp;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// The functions that `--function` or `--function-regex` keep, by the
+/// names of their directories.
+#[derive(Clone, Debug)]
+crate enum FunctionFilter {
+    /// The names that contain this.
+    Containing(String),
+
+    /// The names that this matches some part of.
+    Matching(Regex),
+}
+
+impl FunctionFilter {
+    /// The filter of `--function`, or of `--function-regex`, if either is
+    /// given.
+    crate fn from_options(
+        function: Option<&str>,
+        function_regex: Option<&str>,
+    ) -> Result<Option<Self>, regex::Error> {
+        Ok(match (function, function_regex) {
+            (Some(function), _) => Some(FunctionFilter::Containing(function.to_string())),
+            (None, Some(regex)) => Some(FunctionFilter::Matching(Regex::new(regex)?)),
+            (None, None) => None,
+        })
+    }
+
+    crate fn matches(&self, name: &str) -> bool {
+        match *self {
+            FunctionFilter::Containing(ref function) => name.contains(function.as_str()),
+            FunctionFilter::Matching(ref regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// The facts directories under `root`: `root` itself if it holds facts,
+/// like a function's directory or a JSON document, and otherwise its
+/// subdirectories that do, like the functions that `-Znll-facts` writes
+/// to an `nll-facts` directory. With `function`, only the directories
+/// whose name it matches are kept.
+crate fn facts_dirs(root: &Path, function: Option<&FunctionFilter>) -> io::Result<Vec<PathBuf>> {
+    let mut facts_dirs = Vec::new();
+    if root.is_file() || holds_facts(root)? {
+        facts_dirs.push(root.to_owned());
+    } else {
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if path.is_dir() && holds_facts(&path)? {
+                facts_dirs.push(path);
+            }
+        }
+        facts_dirs.sort();
+    }
+
+    if let Some(function) = function {
+        facts_dirs.retain(|facts_dir| {
+            facts_dir
+                .file_name()
+                .map_or(false, |name| function.matches(&name.to_string_lossy()))
+        });
+    }
+    Ok(facts_dirs)
+}
+
+/// The facts directories under each of `roots`, in order. The roots whose
+/// directories can't be listed, e.g. because they don't exist, are passed
+/// to `on_error` with the error, and skipped.
+crate fn facts_dirs_under(
+    roots: &[String],
+    function: Option<&FunctionFilter>,
+    mut on_error: impl FnMut(&str, io::Error),
+) -> Vec<PathBuf> {
+    let mut all_facts_dirs = Vec::new();
+    for root in roots {
+        match facts_dirs(Path::new(root), function) {
+            Ok(facts_dirs) => all_facts_dirs.extend(facts_dirs),
+            Err(error) => on_error(root, error),
+        }
+    }
+    all_facts_dirs
+}
+
+/// Whether `dir` has any `.facts` or `.facts.gz` files.
+fn holds_facts(dir: &Path) -> io::Result<bool> {
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if name.ends_with(".facts") || name.ends_with(".facts.gz") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The roots listed in the file at `path`, one per line. Blank lines,
+/// and lines starting with `#`, are skipped.
+crate fn read_roots(path: &Path) -> io::Result<Vec<String>> {
+    let mut roots = Vec::new();
+    for line in io::BufReader::new(fs::File::open(path)?).lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            roots.push(line.to_string());
+        }
+    }
+    Ok(roots)
+}
+
+/// The results of `work` on each of `items`, computed on `jobs` threads
+/// and returned in the order of `items`, whichever finish first. Each
+/// result is passed to `on_done` on the calling thread as soon as it is
+/// computed, e.g. to show the progress.
+crate fn run_in_parallel<T, R>(
+    items: Vec<T>,
+    jobs: usize,
+    work: impl Fn(T) -> R + Send + Sync + 'static,
+    mut on_done: impl FnMut(&R),
+) -> Vec<R>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+{
+    let count = items.len();
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+    let work = Arc::new(work);
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> = (0..cmp::max(jobs, 1))
+        .map(|_| {
+            let queue = queue.clone();
+            let work = work.clone();
+            let sender = sender.clone();
+            thread::spawn(move || loop {
+                let next = queue.lock().expect("batch queue poisoned").next();
+                match next {
+                    Some((index, item)) => {
+                        let _ = sender.send((index, work(item)));
+                    }
+                    None => return,
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut results: Vec<Option<R>> = (0..count).map(|_| None).collect();
+    for (index, result) in receiver {
+        on_done(&result);
+        results[index] = Some(result);
+    }
+    for worker in workers {
+        worker.join().expect("batch thread panicked");
+    }
+    results
+        .into_iter()
+        .map(|result| result.expect("every item has a result"))
+        .collect()
+}
+
+/// The totals of a batch of analyses.
+#[derive(Default)]
+crate struct BatchSummary {
+    crate analyzed: usize,
+    crate failed: usize,
+    crate errors: usize,
+    crate time: Duration,
+
+    /// The directory that took the longest to analyze.
+    crate slowest: Option<(String, Duration)>,
+}
+
+impl BatchSummary {
+    crate fn record_analysis(&mut self, facts_dir: &str, errors: usize, time: Duration) {
+        self.analyzed += 1;
+        self.errors += errors;
+        self.time += time;
+        if self.slowest.as_ref().map_or(true, |&(_, slowest)| time > slowest) {
+            self.slowest = Some((facts_dir.to_string(), time));
+        }
+    }
+
+    crate fn record_failure(&mut self) {
+        self.failed += 1;
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<20} {}", "analyzed", self.analyzed)?;
+        writeln!(f, "{:<20} {}", "failed", self.failed)?;
+        writeln!(f, "{:<20} {}", "errors", self.errors)?;
+        writeln!(f, "{:<20} {:.3}s", "time", seconds(self.time))?;
+        if let Some((ref facts_dir, time)) = self.slowest {
+            writeln!(f, "{:<20} {} ({:.3}s)", "slowest", facts_dir, seconds(time))?;
+        }
+        Ok(())
+    }
+}
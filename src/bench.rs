@@ -0,0 +1,86 @@
+use crate::cli::seconds;
+use polonius_engine::Algorithm;
+use std::fmt;
+use std::time::Duration;
+
+/// The timings of the runs of an algorithm on a facts directory.
+crate struct BenchResult {
+    crate runs: usize,
+    crate min: Duration,
+    crate median: Duration,
+    crate max: Duration,
+
+    /// The standard deviation of the run times, in seconds.
+    crate stddev: f64,
+
+    /// The most tuples that a run derived. Every run derives the same
+    /// ones, unless an algorithm is nondeterministic.
+    crate derived_tuples: usize,
+}
+
+impl BenchResult {
+    /// Summarizes `durations`, or `None` if there are none.
+    crate fn new(durations: &[Duration], derived_tuples: usize) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+
+        let runs = sorted.len();
+        let mean = sorted.iter().map(|&d| seconds(d)).sum::<f64>() / runs as f64;
+        let variance = sorted
+            .iter()
+            .map(|&d| (seconds(d) - mean).powi(2))
+            .sum::<f64>()
+            / runs as f64;
+
+        Some(BenchResult {
+            runs,
+            min: sorted[0],
+            median: sorted[runs / 2],
+            max: sorted[runs - 1],
+            stddev: variance.sqrt(),
+            derived_tuples,
+        })
+    }
+}
+
+impl fmt::Display for BenchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "min {:0.3}s, median {:0.3}s, max {:0.3}s, stddev {:0.3}s over {} runs, {} tuples",
+            seconds(self.min),
+            seconds(self.median),
+            seconds(self.max),
+            self.stddev,
+            self.runs,
+            self.derived_tuples
+        )
+    }
+}
+
+crate const CSV_HEADER: &str = "directory,algorithm,runs,min_seconds,median_seconds,\
+                                max_seconds,stddev_seconds,derived_tuples";
+
+/// `result` as a row under `CSV_HEADER`. Directories with commas or
+/// quotes in their name are quoted.
+crate fn csv_row(facts_dir: &str, algorithm: Algorithm, result: &BenchResult) -> String {
+    let facts_dir = if facts_dir.contains(',') || facts_dir.contains('"') {
+        format!("\"{}\"", facts_dir.replace('"', "\"\""))
+    } else {
+        facts_dir.to_string()
+    };
+    format!(
+        "{},{},{},{:.6},{:.6},{:.6},{:.6},{}",
+        facts_dir,
+        algorithm,
+        result.runs,
+        seconds(result.min),
+        seconds(result.median),
+        seconds(result.max),
+        result.stddev,
+        result.derived_tuples
+    )
+}
@@ -0,0 +1,19 @@
+extern crate failure;
+extern crate polonius;
+extern crate structopt;
+
+use std::env;
+use std::process;
+use structopt::StructOpt;
+
+pub fn main() -> Result<(), failure::Error> {
+    // `cargo polonius` runs `cargo check` with this binary as the rustc
+    // wrapper, which cargo then runs as `cargo-polonius rustc <args>`.
+    if polonius::cargo::is_rustc_wrapper() {
+        let code = polonius::cargo::run_rustc(env::args().skip(1))?;
+        process::exit(code);
+    }
+
+    let cargo = polonius::cargo::Cargo::from_args();
+    polonius::cargo::main(cargo)
+}
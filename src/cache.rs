@@ -0,0 +1,201 @@
+//! A binary cache of a parsed facts directory, stored as a `.factsbin`
+//! file next to the directory. Reloading it skips both the text parsing
+//! and the string interning, which dominate load time on large dumps.
+
+use crate::facts::AllFacts;
+use crate::intern::InternerTables;
+use crate::tab_delim::{self, LoadError, LoadOptions};
+use bincode;
+use std::fs::{self, File};
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the layout of `CachedFacts` changes, so that stale
+/// caches are ignored rather than misread.
+const CACHE_FORMAT_VERSION: u32 = 7;
+
+/// What the cached facts depend on: the cache is only used if it was built
+/// with the same key.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct CacheKey {
+    options: LoadOptions,
+
+    /// The names of the `.facts` files the cache was built from, sorted,
+    /// so that removing one of them invalidates the cache too.
+    source_files: Vec<String>,
+
+    /// The newest modification time (seconds and nanoseconds since the
+    /// epoch) of those files.
+    source_mtime: (u64, u32),
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedFacts {
+    format_version: u32,
+    key: CacheKey,
+
+    regions: Vec<String>,
+    loans: Vec<String>,
+    points: Vec<String>,
+    variables: Vec<String>,
+    move_paths: Vec<String>,
+
+    facts: AllFacts,
+}
+
+crate fn cache_path(facts_dir: &Path) -> PathBuf {
+    let mut file_name = facts_dir
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    file_name.push(".factsbin");
+    facts_dir.with_file_name(file_name)
+}
+
+/// Loads the facts in `facts_dir`, going through the `.factsbin` cache
+/// next to it. The cache is rebuilt whenever it is missing, unreadable,
+/// older than any of the `.facts` files, built from other files, or built
+/// with other `options`.
+///
+/// The atoms in the cache are stored as raw indices, so `tables` must be
+/// empty.
+crate fn load_cached_facts(
+    tables: &mut InternerTables,
+    facts_dir: &Path,
+    options: LoadOptions,
+) -> Result<AllFacts, LoadError> {
+    assert!(
+        tables.regions.len() == 0
+            && tables.loans.len() == 0
+            && tables.points.len() == 0
+            && tables.variables.len() == 0
+            && tables.move_paths.len() == 0,
+        "cached facts can only be loaded into empty interner tables"
+    );
+
+    let cache_file = cache_path(facts_dir);
+    let key = cache_key(facts_dir, options).map_err(|error| LoadError::io(facts_dir, error))?;
+
+    if let Some(all_facts) = read_cache(tables, &cache_file, &key) {
+        return Ok(all_facts);
+    }
+
+    let all_facts = tab_delim::load_tab_delimited_facts_with(tables, facts_dir, options)?;
+    if let Err(error) = write_cache(all_facts.clone(), tables, &cache_file, key) {
+        eprintln!(
+            "warning: failed to write fact cache `{}`: {}",
+            cache_file.display(),
+            error
+        );
+    }
+    Ok(all_facts)
+}
+
+fn read_cache(
+    tables: &mut InternerTables,
+    cache_file: &Path,
+    key: &CacheKey,
+) -> Option<AllFacts> {
+    let file = File::open(cache_file).ok()?;
+    let cached: CachedFacts = bincode::deserialize_from(io::BufReader::new(file)).ok()?;
+    if cached.format_version != CACHE_FORMAT_VERSION || cached.key != *key {
+        return None;
+    }
+
+    // Interning the strings in index order reproduces the original indices.
+    for region in &cached.regions {
+        tables.regions.intern(region);
+    }
+    for loan in &cached.loans {
+        tables.loans.intern(loan);
+    }
+    for point in &cached.points {
+        tables.points.intern(point);
+    }
+    for variable in &cached.variables {
+        tables.variables.intern(variable);
+    }
+    for move_path in &cached.move_paths {
+        tables.move_paths.intern(move_path);
+    }
+
+    Some(cached.facts)
+}
+
+fn write_cache(
+    all_facts: AllFacts,
+    tables: &InternerTables,
+    cache_file: &Path,
+    key: CacheKey,
+) -> io::Result<()> {
+    let cached = CachedFacts {
+        format_version: CACHE_FORMAT_VERSION,
+        key,
+        regions: owned_strings(tables.regions.strings()),
+        loans: owned_strings(tables.loans.strings()),
+        points: owned_strings(tables.points.strings()),
+        variables: owned_strings(tables.variables.strings()),
+        move_paths: owned_strings(tables.move_paths.strings()),
+        facts: all_facts,
+    };
+
+    let mut writer = io::BufWriter::new(File::create(cache_file)?);
+    bincode::serialize_into(&mut writer, &cached)
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    writer.flush()
+}
+
+fn owned_strings(strings: &[&str]) -> Vec<String> {
+    strings.iter().map(|string| string.to_string()).collect()
+}
+
+fn cache_key(facts_dir: &Path, options: LoadOptions) -> io::Result<CacheKey> {
+    let mut source_files = Vec::new();
+    for path in facts_files(facts_dir)? {
+        if let Some(name) = path.file_name() {
+            source_files.push(name.to_string_lossy().into_owned());
+        }
+    }
+    source_files.sort();
+    Ok(CacheKey {
+        options,
+        source_files,
+        source_mtime: newest_mtime(facts_dir)?,
+    })
+}
+
+/// The newest modification time among the `.facts` (or `.facts.gz`)
+/// files in `facts_dir`.
+crate fn newest_mtime(facts_dir: &Path) -> io::Result<(u64, u32)> {
+    let mut newest = (0, 0);
+    for path in facts_files(facts_dir)? {
+        newest = newest.max(mtime(&path)?);
+    }
+    Ok(newest)
+}
+
+/// The `.facts` (or `.facts.gz`) files in `facts_dir`.
+fn facts_files(facts_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(facts_dir)? {
+        let path = entry?.path();
+        let is_facts_file = path.to_str().map_or(false, |path| {
+            path.ends_with(".facts") || path.ends_with(".facts.gz")
+        });
+        if is_facts_file {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// The modification time of the file at `path`, in seconds and
+/// nanoseconds since the epoch.
+crate fn mtime(path: &Path) -> io::Result<(u64, u32)> {
+    let modified = fs::metadata(path)?.modified()?;
+    let since_epoch = modified
+        .duration_since(UNIX_EPOCH)
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    Ok((since_epoch.as_secs(), since_epoch.subsec_nanos()))
+}
@@ -0,0 +1,263 @@
+//! `cargo polonius`: dumps the facts of every crate of a workspace, and
+//! analyzes them all.
+//!
+//! The facts are dumped by `cargo check`, with `cargo-polonius` itself as
+//! the `RUSTC_WRAPPER`. Cargo then runs it in place of rustc, and it adds
+//! the flags that dump the facts to the rustc of the workspace members,
+//! but not to that of their dependencies. Each crate gets its own facts
+//! directory, since the functions of different crates can have the same
+//! name.
+//!
+//! With `--compare-rustc`, the errors of rustc's NLL borrow checker are
+//! collected from the JSON messages of `cargo check`, and the functions
+//! whose errors disagree with them are reported instead, see
+//! `differential`.
+
+use crate::batch::{self, BatchSummary};
+use crate::cli::{self, LoadConfig};
+use crate::differential::{self, DifferentialReport, Function, RustcError};
+use crate::progress::Progress;
+use failure::{self, Error};
+use polonius_engine::Algorithm;
+use serde_json::{self, Value};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use structopt::StructOpt;
+
+/// The directory that the wrapper dumps the facts of each crate into, and
+/// whose presence makes `cargo-polonius` a wrapper.
+const FACTS_DIR_VAR: &str = "POLONIUS_FACTS_DIR";
+
+/// The manifest directories of the workspace members, separated like the
+/// paths of `PATH`.
+const MEMBERS_VAR: &str = "POLONIUS_WORKSPACE_MEMBERS";
+
+/// The rustc flags that dump the facts into a directory, to which the
+/// directory's path is appended.
+const FACTS_FLAGS: &[&str] = &["-Zborrowck=mir", "-Znll-facts"];
+const FACTS_DIR_FLAG: &str = "-Znll-facts-dir=";
+
+#[derive(StructOpt, Debug)]
+#[structopt(bin_name = "cargo")]
+pub enum Cargo {
+    /// Dump the facts of every crate of the workspace, and analyze every function
+    #[structopt(name = "polonius")]
+    Polonius(CargoOpt),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct CargoOpt {
+    #[structopt(
+        short = "a",
+        long = "algorithm",
+        default_value = "naive",
+        raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+    )]
+    algorithm: Algorithm,
+
+    /// Where to dump the facts, `polonius` in the target directory if not given
+    #[structopt(long = "facts-dir")]
+    facts_dir: Option<String>,
+
+    /// Don't keep a status line of the progress through the functions on stderr
+    #[structopt(long = "no-progress")]
+    no_progress: bool,
+
+    /// Report the functions whose errors differ from those of rustc's NLL borrow checker
+    #[structopt(long = "compare-rustc")]
+    compare_rustc: bool,
+
+    /// The arguments of `cargo check`, such as `--features`, after a `--`
+    #[structopt(raw(last = "true"))]
+    cargo_args: Vec<String>,
+}
+
+/// Whether `cargo-polonius` was run by cargo in place of rustc.
+pub fn is_rustc_wrapper() -> bool {
+    env::var_os(FACTS_DIR_VAR).is_some()
+}
+
+/// Runs the rustc of the arguments of the wrapper, `rustc` followed by its
+/// arguments, adding the flags that dump the facts if it compiles one of
+/// the workspace members. Returns the exit code of rustc.
+pub fn run_rustc(mut args: impl Iterator<Item = String>) -> Result<i32, Error> {
+    let rustc = args
+        .next()
+        .ok_or_else(|| failure::err_msg("no rustc to wrap"))?;
+    let mut args: Vec<String> = args.collect();
+
+    let facts_root = PathBuf::from(env::var_os(FACTS_DIR_VAR).unwrap_or_default());
+    let members = env::var_os(MEMBERS_VAR).unwrap_or_default();
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    let is_member = manifest_dir.map_or(false, |dir| env::split_paths(&members).any(|m| m == dir));
+    if is_member {
+        if let Some(flags) = facts_flags(&args, &facts_root) {
+            args.extend(flags);
+        }
+    }
+
+    let status = Command::new(rustc).args(&args).status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// The flags that dump the facts of the crate that rustc compiles with
+/// `args` into its directory of `facts_root`, named after the crate and
+/// its type, e.g. `polonius.bin`. `None` if `args` don't compile a crate,
+/// like those that cargo uses to query rustc.
+crate fn facts_flags(args: &[String], facts_root: &Path) -> Option<Vec<String>> {
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
+    let crate_name = value_of("--crate-name")?;
+    let crate_type = value_of("--crate-type").map_or("lib", |crate_type| crate_type.as_str());
+
+    let facts_dir = facts_root.join(format!("{}.{}", crate_name, crate_type));
+    let mut flags: Vec<String> = FACTS_FLAGS.iter().map(|flag| flag.to_string()).collect();
+    flags.push(format!("{}{}", FACTS_DIR_FLAG, facts_dir.display()));
+    Some(flags)
+}
+
+/// The manifest directories of the members of the workspace, and its
+/// target directory, from the output of `cargo metadata`.
+crate fn workspace_layout(metadata: &str) -> Result<(Vec<PathBuf>, PathBuf), Error> {
+    let metadata: Value = serde_json::from_str(metadata)?;
+    let invalid = || failure::err_msg("unexpected output of `cargo metadata`");
+
+    let mut members = Vec::new();
+    for package in metadata["packages"].as_array().ok_or_else(invalid)? {
+        let manifest_path = package["manifest_path"].as_str().ok_or_else(invalid)?;
+        let manifest_dir = Path::new(manifest_path).parent().ok_or_else(invalid)?;
+        members.push(manifest_dir.to_owned());
+    }
+    let target_dir = metadata["target_directory"].as_str().ok_or_else(invalid)?;
+    Ok((members, PathBuf::from(target_dir)))
+}
+
+pub fn main(cargo: Cargo) -> Result<(), Error> {
+    let Cargo::Polonius(opt) = cargo;
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+
+    let metadata = Command::new(&cargo)
+        .args(&["metadata", "--no-deps", "--format-version", "1"])
+        .output()?;
+    if !metadata.status.success() {
+        return Err(failure::err_msg("`cargo metadata` failed"));
+    }
+    let (members, target_dir) = workspace_layout(&String::from_utf8_lossy(&metadata.stdout))?;
+    let facts_root = match opt.facts_dir {
+        Some(ref facts_dir) => PathBuf::from(facts_dir),
+        None => target_dir.join("polonius"),
+    };
+    fs::create_dir_all(&facts_root)?;
+    let facts_root = facts_root.canonicalize()?;
+
+    // The facts are only dumped when the members are rebuilt, so the facts
+    // of the members that didn't change since the last run are reused. The
+    // workspace is checked in a target directory of its own, so that the
+    // builds of the usual one, without the facts, stay up to date.
+    let mut check = Command::new(&cargo);
+    check.arg("check");
+    if opt.compare_rustc {
+        check.arg("--message-format=json");
+    }
+    check
+        .args(&opt.cargo_args)
+        .env("RUSTC_WRAPPER", env::current_exe()?)
+        .env(FACTS_DIR_VAR, &facts_root)
+        .env(MEMBERS_VAR, env::join_paths(&members)?)
+        .env("CARGO_TARGET_DIR", facts_root.join("target"));
+    let rustc_errors = if opt.compare_rustc {
+        // The borrow errors of rustc make `cargo check` fail, and are what
+        // the run is after, so its failure isn't one here.
+        let output = check.stderr(Stdio::inherit()).output()?;
+        Some(differential::parse_rustc_errors(&String::from_utf8_lossy(&output.stdout)))
+    } else {
+        if !check.status()?.success() {
+            return Err(failure::err_msg("`cargo check` failed"));
+        }
+        None
+    };
+
+    let mut crate_dirs = Vec::new();
+    for entry in fs::read_dir(&facts_root)? {
+        let path = entry?.path();
+        if path.is_dir() && path.file_name().map_or(false, |name| name != "target") {
+            crate_dirs.push(path);
+        }
+    }
+    crate_dirs.sort();
+
+    if let Some(rustc_errors) = rustc_errors {
+        return compare_with_rustc(&opt, &crate_dirs, &rustc_errors);
+    }
+
+    let load_config = LoadConfig::default();
+    let mut summary = BatchSummary::default();
+    for crate_dir in crate_dirs {
+        let crate_name = crate_dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let facts_dirs = batch::facts_dirs(&crate_dir, None)?;
+        let mut progress = Progress::new(facts_dirs.len(), !opt.no_progress);
+        for facts_dir in facts_dirs {
+            let function = facts_dir.file_name().unwrap_or_default().to_string_lossy();
+            let name = format!("{}::{}", crate_name, function);
+            progress.start(&name);
+            let result =
+                cli::analyze_in_batch(&facts_dir, opt.algorithm, load_config, Some(&mut progress));
+            progress.clear();
+            cli::report_in_batch(&mut summary, &name, result);
+        }
+    }
+    println!("--------------------------------------------------");
+    print!("{}", summary);
+    Ok(())
+}
+
+/// Analyzes every function of `crate_dirs`, and prints those whose errors
+/// disagree with `rustc_errors`.
+fn compare_with_rustc(
+    opt: &CargoOpt,
+    crate_dirs: &[PathBuf],
+    rustc_errors: &[RustcError],
+) -> Result<(), Error> {
+    let mut report = DifferentialReport::default();
+    for crate_dir in crate_dirs {
+        let crate_name = crate_dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let facts_dirs = batch::facts_dirs(crate_dir, None)?;
+        let mut progress = Progress::new(facts_dirs.len(), !opt.no_progress);
+        let mut functions = Vec::new();
+        for facts_dir in facts_dirs {
+            let function = facts_dir.file_name().unwrap_or_default().to_string_lossy();
+            let name = format!("{}::{}", crate_name, function);
+            progress.start(&name);
+            let result = Function::analyze(&name, &facts_dir, opt.algorithm);
+            progress.clear();
+            match result {
+                Ok(function) => functions.push(function),
+                Err(error) => eprintln!("`{}`: {}", name, error),
+            }
+        }
+
+        let crate_errors: Vec<RustcError> = rustc_errors
+            .iter()
+            .filter(|error| error.crate_dir == crate_name)
+            .cloned()
+            .collect();
+        report.record(&functions, &crate_errors);
+    }
+
+    // The errors of the crates that dumped no facts are outside of every
+    // function.
+    report.unattributed.extend(
+        rustc_errors
+            .iter()
+            .filter(|error| !crate_dirs.iter().any(|dir| dir.ends_with(&error.crate_dir)))
+            .cloned(),
+    );
+    print!("{}", report);
+    Ok(())
+}
@@ -1,82 +1,813 @@
-#![allow(deprecated)] // arg_enum! uses deprecated stuff
-
+use crate::anonymize;
+use crate::baseline::{self, Baseline};
+use crate::batch::{self, BatchSummary, FunctionFilter};
+use crate::bench::{self, BenchResult};
+use crate::cache;
+use crate::diff;
 use crate::dump;
-use crate::facts::{Loan, Point, Region};
-use crate::intern;
-use crate::tab_delim;
-use failure::Error;
-use polonius_engine::{Algorithm, Output};
-use std::path::Path;
+use crate::explain;
+use crate::facts::{AllFacts, Output, Point};
+use crate::graph;
+use crate::html;
+use crate::intern::{self, InternerTables};
+use crate::json::{self, JsonReport};
+use crate::lsp;
+use crate::memory;
+use crate::progress::Progress;
+use crate::renumber;
+use crate::sarif::{self, SarifLog};
+use crate::serve;
+use crate::souffle;
+use crate::sqlite;
+use crate::repl::Repl;
+use crate::stats;
+use crate::tab_delim::{self, LoadOptions};
+use crate::watch;
+use clap::AppSettings;
+use failure::{self, Error};
+use polonius_engine::{
+    Algorithm, Budget, PreprocessLevel, Preprocessor, QueryEngine, RelationMemory,
+};
+use std::cmp;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 use structopt::StructOpt;
 
-arg_enum! {
-    #[derive(Debug, Clone, Copy)]
-    pub enum AlgorithmOpts {
-        Naive,
-        DatafrogOpt,
-        LocationInsensitive,
-    }
-}
-
-impl Into<Algorithm> for AlgorithmOpts {
-    fn into(self) -> Algorithm {
-        match self {
-            AlgorithmOpts::Naive => Algorithm::Naive,
-            AlgorithmOpts::DatafrogOpt => Algorithm::DatafrogOpt,
-            AlgorithmOpts::LocationInsensitive => Algorithm::LocationInsensitive,
-        }
-    }
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "polonius",
+    raw(setting = "AppSettings::SubcommandRequiredElseHelp")
+)]
+pub struct Opt {
+    // The options for loading facts, shared by every command.
+    /// Reuse (or create) a binary `.factsbin` cache next to each facts directory
+    #[structopt(long = "cache")]
+    cache: bool,
+    /// Fail on missing relation files, instead of treating them as empty
+    #[structopt(long = "strict")]
+    strict: bool,
+    /// Parse the relation files of each facts directory concurrently
+    #[structopt(long = "parallel-load")]
+    parallel_load: bool,
+    /// Memory-map uncompressed relation files instead of reading them line by line
+    #[structopt(long = "mmap")]
+    mmap: bool,
+    #[structopt(subcommand)]
+    command: Command,
 }
 
+/// The options of `analyze`.
 #[derive(StructOpt, Debug)]
-#[structopt(name = "borrow-check")]
-pub struct Opt {
+pub struct AnalyzeOpt {
     #[structopt(
         short = "a",
+        long = "algorithm",
         default_value = "naive",
-        raw(possible_values = "&AlgorithmOpts::variants()", case_insensitive = "true")
+        raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+    )]
+    algorithm: Algorithm,
+    /// Run both the naive and optimized algorithms, and report any errors they disagree on
+    #[structopt(
+        long = "compare",
+        raw(conflicts_with_all = r#"&["threads", "components"]"#)
+    )]
+    compare: bool,
+    /// How to print the results: `text`, or a single `json` or `sarif` document of the errors
+    #[structopt(
+        long = "output-format",
+        default_value = "text",
+        raw(possible_values = "&OutputFormat::variants()", case_insensitive = "true")
     )]
-    algorithm: AlgorithmOpts,
+    output_format: OutputFormat,
     #[structopt(long = "skip-tuples")]
     skip_tuples: bool,
     #[structopt(long = "skip-timing")]
     skip_timing: bool,
+    /// Don't check the facts for inconsistencies before analyzing them
+    #[structopt(long = "skip-validation")]
+    skip_validation: bool,
     #[structopt(short = "v")]
     verbose: bool,
+    /// Split the loans of each function across this many threads (0 for one per core)
+    #[structopt(long = "threads")]
+    threads: Option<usize>,
+    /// Solve each connected component of the CFG on its own, on the threads of `--threads` if given
+    #[structopt(long = "components")]
+    components: bool,
     #[structopt(short = "o", long = "output")]
     output_directory: Option<String>,
+    /// Only print the errors and tuples at the point with this name, e.g. `Mid(bb7[2])`
+    #[structopt(long = "show-point")]
+    show_point: Option<String>,
+    /// Only print the errors and tuples at the points of this basic block, e.g. `bb7`
+    #[structopt(long = "block")]
+    block: Option<String>,
+    /// Only write these relations of the output, including intermediate ones like `subset`
+    #[structopt(
+        long = "dump",
+        raw(use_delimiter = "true", possible_values = "&dump::RELATION_NAMES")
+    )]
+    dump: Vec<String>,
+    /// Write an HTML report of the errors, facts and CFG of each function into this directory
+    #[structopt(long = "emit-html")]
+    emit_html: Option<String>,
+    /// Only analyze the functions whose directory name contains this
+    #[structopt(long = "function")]
+    function: Option<String>,
+    /// Only analyze the functions whose directory name this regex matches some part of
+    #[structopt(long = "function-regex", raw(conflicts_with = r#""function""#))]
+    function_regex: Option<String>,
+    /// Analyze the facts again whenever they change, until interrupted
+    #[structopt(long = "watch")]
+    watch: bool,
+    /// Don't keep a status line of the progress through the functions on stderr
+    #[structopt(long = "no-progress")]
+    no_progress: bool,
+    /// Print how long loading, preprocessing, and each phase of the analysis took
+    #[structopt(long = "timings")]
+    timings: bool,
+    /// Print the peak memory use, and the sizes of the largest relations the analysis derived
+    #[structopt(long = "memory")]
+    memory: bool,
+    /// Print nothing, and exit with 1 if there are borrow errors, 2 if some facts are invalid, or 3
+    /// if the budget ran out before finding any errors
+    #[structopt(long = "check", raw(conflicts_with = r#""watch""#))]
+    check: bool,
+    /// Write the errors of every function to this file, for `--compare-baseline` to compare with
+    #[structopt(long = "save-baseline")]
+    save_baseline: Option<String>,
+    /// Print the errors that appeared or disappeared since the baseline saved in this file, which
+    /// `--check` then only fails on
+    #[structopt(long = "compare-baseline")]
+    compare_baseline: Option<String>,
+    /// Analyze all the given fact directories together, as a single set of facts
+    #[structopt(long = "merge")]
+    merge: bool,
+    /// Print the derivation of every error from the input facts
+    #[structopt(
+        long = "why",
+        raw(conflicts_with_all = r#"&["compare", "threads", "components"]"#)
+    )]
+    why: bool,
+    /// Renumber the atoms of each set of facts densely before analyzing them
+    #[structopt(long = "renumber")]
+    renumber: bool,
+    /// The preprocessing passes to run before analyzing the facts, on top of the flags of each pass
+    #[structopt(
+        long = "preprocess",
+        default_value = "none",
+        raw(possible_values = "&PreprocessLevel::variants()", case_insensitive = "true")
+    )]
+    preprocess: PreprocessLevel,
+    /// Drop the facts about the points that no entry point reaches before analyzing them
+    #[structopt(long = "remove-unreachable")]
+    remove_unreachable: bool,
+    /// Remove the regions that can't affect the errors before analyzing the facts
+    #[structopt(long = "prune-dead-regions")]
+    prune_dead_regions: bool,
+    /// Remove the loans that nothing invalidates before analyzing the facts
+    #[structopt(long = "prune-dead-loans")]
+    prune_dead_loans: bool,
+    /// Drop the `outlives` facts that others imply by transitivity before analyzing the facts
+    #[structopt(long = "reduce-outlives")]
+    reduce_outlives: bool,
+    /// Remove the CFG points whose facts can be moved onto others before analyzing them
+    #[structopt(long = "simplify-cfg", raw(conflicts_with = r#""why""#))]
+    simplify_cfg: bool,
+    /// Check that simplifying the CFG doesn't change the errors, and fail if it does
+    #[structopt(long = "verify-simplification")]
+    verify_simplification: bool,
+    /// Give up on the facts directories whose analysis takes longer than this many seconds
+    #[structopt(
+        long = "time-limit",
+        raw(conflicts_with_all = r#"&["compare", "why", "threads", "components"]"#)
+    )]
+    time_limit: Option<u64>,
+    /// Give up on the facts directories whose analysis derives more than this many tuples
+    #[structopt(
+        long = "tuple-limit",
+        raw(conflicts_with_all = r#"&["compare", "why", "threads", "components"]"#)
+    )]
+    tuple_limit: Option<usize>,
     #[structopt(raw(required = "true"))]
     fact_dirs: Vec<String>,
 }
 
-pub fn main(opt: Opt) -> Result<(), Error> {
+/// The formats of `--output-format`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl OutputFormat {
+    fn variants() -> [&'static str; 3] {
+        ["text", "json", "sarif"]
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            _ => Err(format!(
+                "unknown output format `{}`, expected one of: {}",
+                s,
+                OutputFormat::variants().join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Run the borrow check on fact directories, and print or write its outputs
+    #[structopt(name = "analyze")]
+    Analyze(AnalyzeOpt),
+
+    /// Print the names of the available algorithms
+    #[structopt(name = "list-algorithms")]
+    ListAlgorithms,
+
+    /// Write a facts directory, or a JSON document, back out as a tab-delimited facts directory
+    #[structopt(name = "dump")]
+    Dump {
+        /// Renumber the atoms densely before writing them
+        #[structopt(long = "renumber")]
+        renumber: bool,
+        /// Write gzip-compressed `.facts.gz` files
+        #[structopt(long = "compress")]
+        compress: bool,
+        facts_dir: String,
+        output_dir: String,
+    },
+
+    /// Write the facts of a directory, and the relations derived from them, into another format
+    #[structopt(name = "export")]
+    Export(ExportCommand),
+
+    /// Print a graph of a facts directory in the Graphviz format
+    #[structopt(name = "graph")]
+    Graph(GraphCommand),
+
+    /// Analyze every function of `nll-facts` directories, printing a line for each and a summary
+    #[structopt(name = "batch")]
+    Batch {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        /// Also analyze the directories listed in this file, one per line
+        #[structopt(long = "list")]
+        list: Option<String>,
+        /// Only analyze the functions whose directory name contains this
+        #[structopt(long = "function")]
+        function: Option<String>,
+        /// Only analyze the functions whose directory name this regex matches some part of
+        #[structopt(long = "function-regex", raw(conflicts_with = r#""function""#))]
+        function_regex: Option<String>,
+        /// Don't keep a status line of the progress through the functions on stderr
+        #[structopt(long = "no-progress")]
+        no_progress: bool,
+        /// Analyze this many functions at once, each on its own thread
+        #[structopt(short = "j", long = "jobs", default_value = "1")]
+        jobs: usize,
+        /// The facts directories, or the directories of their functions
+        #[structopt(raw(required_unless = r#""list""#))]
+        roots: Vec<String>,
+    },
+
+    /// Time the algorithms on fact directories, over several runs
+    #[structopt(name = "bench")]
+    Bench {
+        /// The algorithms to time (all of them, except `compare`, if not given)
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithms: Vec<Algorithm>,
+        /// How many times to run each algorithm on each facts directory
+        #[structopt(long = "runs", default_value = "5")]
+        runs: usize,
+        /// How many untimed runs of each algorithm to do first
+        #[structopt(long = "warmup", default_value = "1")]
+        warmup: usize,
+        /// Print the results as CSV, with a header line
+        #[structopt(long = "csv")]
+        csv: bool,
+        #[structopt(raw(required = "true"))]
+        fact_dirs: Vec<String>,
+    },
+
+    /// Report, relation by relation, the tuples added and removed between two fact directories
+    #[structopt(name = "diff-facts")]
+    DiffFacts { before: String, after: String },
+
+    /// Print relation sizes, atom counts, and CFG shape statistics for a facts directory
+    #[structopt(name = "stats")]
+    Stats {
+        /// Also analyze the facts, and print the sizes of the relations this algorithm derives
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Option<Algorithm>,
+        facts_dir: String,
+    },
+
+    /// Write a copy of a facts directory with every atom renamed to an opaque identifier
+    #[structopt(name = "anonymize")]
+    Anonymize {
+        facts_dir: String,
+        output_dir: String,
+    },
+
+    /// Shrink a facts directory while it still reproduces an error or an algorithm disagreement
+    #[structopt(name = "minimize")]
+    Minimize {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        /// Preserve that the analysis reports an error at this point
+        #[structopt(long = "error-at")]
+        error_at: Option<String>,
+        /// Preserve that the naive and optimized algorithms report different errors
+        #[structopt(long = "disagree")]
+        disagree: bool,
+        facts_dir: String,
+        output_dir: String,
+    },
+
+    /// Explore the facts of a directory, and the results of the analysis, at a prompt
+    #[structopt(name = "repl")]
+    Repl {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        facts_dir: String,
+    },
+
+    /// Explain, step by step, why a loan is an error at a point
+    #[structopt(name = "explain")]
+    Explain {
+        /// The preprocessing passes to run before analyzing the facts, as with `analyze`
+        #[structopt(
+            long = "preprocess",
+            default_value = "none",
+            raw(possible_values = "&PreprocessLevel::variants()", case_insensitive = "true")
+        )]
+        preprocess: PreprocessLevel,
+        /// Drop the facts about the points that no entry point reaches before analyzing them
+        #[structopt(long = "remove-unreachable")]
+        remove_unreachable: bool,
+        /// Remove the regions that can't affect the errors before analyzing the facts
+        #[structopt(long = "prune-dead-regions")]
+        prune_dead_regions: bool,
+        /// Remove the loans that nothing invalidates before analyzing the facts
+        #[structopt(long = "prune-dead-loans")]
+        prune_dead_loans: bool,
+        /// Drop the `outlives` facts that others imply by transitivity before analyzing the facts
+        #[structopt(long = "reduce-outlives")]
+        reduce_outlives: bool,
+        facts_dir: String,
+        point: String,
+        loan: String,
+    },
+
+    /// Publish the errors of fact directories to editors, as a language server on stdin and stdout
+    #[structopt(name = "lsp")]
+    Lsp {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        /// The facts directories, or the directories of their functions, to watch
+        roots: Vec<String>,
+    },
+
+    /// Serve the analysis of the facts that clients upload, over HTTP, with JSON responses
+    #[structopt(name = "serve")]
+    Serve {
+        /// The algorithm of the analyses that don't ask for one
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        /// The address to listen on
+        #[structopt(long = "address", default_value = "127.0.0.1:8080")]
+        address: String,
+        /// Give up on the analyses that take longer than this many seconds
+        #[structopt(long = "time-limit", default_value = "10")]
+        time_limit: u64,
+        /// Give up on the analyses that derive more than this many tuples
+        #[structopt(long = "tuple-limit")]
+        tuple_limit: Option<usize>,
+    },
+
+    /// Diff the outputs of Soufflé, running the rules of the naive algorithm, with polonius'
+    #[structopt(name = "compare-souffle")]
+    CompareSouffle {
+        /// The Soufflé binary to run
+        #[structopt(long = "souffle", default_value = "souffle")]
+        souffle: String,
+        /// The Datalog program to run, instead of `souffle/naive.dl`
+        #[structopt(long = "program")]
+        program: Option<String>,
+        /// Where to write the facts and the outputs of Soufflé, a temporary directory if not given
+        #[structopt(long = "work-dir")]
+        work_dir: Option<String>,
+        facts_dir: String,
+    },
+}
+
+/// The formats that `export` writes.
+#[derive(StructOpt, Debug)]
+pub enum ExportCommand {
+    /// An SQLite database, with a table per relation and a column of names per atom
+    #[structopt(name = "sqlite")]
+    Sqlite {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        facts_dir: String,
+        database: String,
+    },
+}
+
+/// The graphs that `graph` prints.
+#[derive(StructOpt, Debug)]
+pub enum GraphCommand {
+    /// The CFG, with the facts of each point and, in red, the errors
+    #[structopt(name = "cfg")]
+    Cfg {
+        #[structopt(
+            short = "a",
+            long = "algorithm",
+            default_value = "naive",
+            raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
+        )]
+        algorithm: Algorithm,
+        /// Write the graph to this file, instead of printing it
+        #[structopt(short = "o", long = "output")]
+        output: Option<String>,
+        facts_dir: String,
+    },
+
+    /// The regions that each region is a subset of at a point, dashed where the analysis derives it
+    #[structopt(name = "subsets")]
+    Subsets {
+        /// The point to draw the subset relation at
+        #[structopt(long = "point")]
+        point: String,
+        /// Write the graph to this file, instead of printing it
+        #[structopt(short = "o", long = "output")]
+        output: Option<String>,
+        facts_dir: String,
+    },
+}
+
+/// What `analyze` found, most severe last. `--check` exits with its
+/// status.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    /// Every function was analyzed, without errors.
+    Clean,
+    /// The budget ran out before the analysis of some function was done,
+    /// and none of the functions has errors so far.
+    Incomplete,
+    /// The analysis reported borrow, subset or move errors in some
+    /// function.
+    BorrowErrors,
+    /// Some facts couldn't be loaded or analyzed.
+    InvalidInput,
+}
+
+impl Outcome {
+    /// The outcome of the analysis of a single function.
+    crate fn of_output(output: &Output) -> Outcome {
+        if !output.errors.is_empty()
+            || !output.subset_errors.is_empty()
+            || !output.move_errors.is_empty()
+        {
+            Outcome::BorrowErrors
+        } else if output.budget_exceeded.is_some() {
+            Outcome::Incomplete
+        } else {
+            Outcome::Clean
+        }
+    }
+
+    pub fn exit_status(self) -> i32 {
+        match self {
+            Outcome::Clean => 0,
+            Outcome::BorrowErrors => 1,
+            Outcome::InvalidInput => 2,
+            Outcome::Incomplete => 3,
+        }
+    }
+}
+
+/// How often, in milliseconds, `--watch` checks the facts for changes.
+const WATCH_INTERVAL_MILLIS: u64 = 500;
+
+/// How the CLI loads facts, shared by the analysis and by the commands.
+#[derive(Copy, Clone, Debug, Default)]
+crate struct LoadConfig {
+    cache: bool,
+    options: LoadOptions,
+}
+
+/// Runs the command of `opt`, returning the outcome that the process exits
+/// with.
+pub fn main(opt: Opt) -> Result<Outcome, Error> {
+    let load_config = LoadConfig {
+        cache: opt.cache,
+        options: LoadOptions {
+            strict: opt.strict,
+            parallel: opt.parallel_load,
+            mmap: opt.mmap,
+        },
+    };
+
+    run_command(opt.command, load_config)
+}
+
+/// Analyzes the facts directories of `opt` again whenever their facts
+/// change, until interrupted. With `--cache`, the directories that didn't
+/// change are reloaded from their cache.
+fn analyze_on_change(
+    opt: &AnalyzeOpt,
+    function: Option<&FunctionFilter>,
+    load_config: LoadConfig,
+) -> ! {
+    let interval = Duration::from_millis(WATCH_INTERVAL_MILLIS);
+    let mut snapshot = watch::Snapshot::take(&opt.fact_dirs, function);
+    loop {
+        if let Err(error) = analyze(opt, load_config) {
+            eprintln!("{}", error);
+        }
+        println!("Watching for changes...");
+        snapshot = watch::wait_for_change(&opt.fact_dirs, function, &snapshot, interval);
+    }
+}
+
+/// Analyzes each facts directory of `opt`, or all of them together with
+/// `--merge`. The directories that fail to load or to analyze are reported
+/// and skipped.
+fn analyze(opt: &AnalyzeOpt, load_config: LoadConfig) -> Result<Outcome, Error> {
+    let preprocessor = preprocessor_from_options(opt);
+
     do catch {
-        let output_directory = opt.output_directory.map(|x| Path::new(&x).to_owned());
-        for facts_dir in opt.fact_dirs {
+        let output_directory = opt.output_directory.as_ref().map(|x| Path::new(x).to_owned());
+        let function = function_filter(&opt.function, &opt.function_regex)?;
+
+        // The directories of `-Znll-facts` hold one directory per function.
+        let mut outcome = Outcome::Clean;
+        let roots = &opt.fact_dirs;
+        let under_roots = batch::facts_dirs_under(roots, function.as_ref(), |root, error| {
+            eprintln!("`{}`: {}", root, error);
+            outcome = Outcome::InvalidInput;
+        });
+        let fact_dirs: Vec<String> = under_roots
+            .iter()
+            .map(|facts_dir| facts_dir.display().to_string())
+            .collect();
+        let inputs: Vec<Vec<String>> = if opt.merge {
+            vec![fact_dirs]
+        } else {
+            fact_dirs.into_iter().map(|facts_dir| vec![facts_dir]).collect()
+        };
+        let mut reports = Vec::new();
+        let show_progress = !(opt.no_progress || opt.check || opt.verbose || !opt.dump.is_empty());
+        let mut progress = Progress::new(inputs.len(), show_progress);
+        let mut baseline = Baseline::new();
+        let mut html_entries = Vec::new();
+        let mut sarif_log = SarifLog::new();
+        if let Some(ref html_dir) = opt.emit_html {
+            fs::create_dir_all(html_dir)?;
+        }
+
+        for facts_dirs in inputs {
             let tables = &mut intern::InternerTables::new();
+            let facts_dir = facts_dirs.join(" + ");
+            progress.start(&facts_dir);
+            let peak_rss_per_input = opt.memory && memory::reset_peak_rss();
 
-            let result: Result<(Duration, Output<Region, Loan, Point>), Error> = do catch {
+            let mut relation_sizes = Vec::new();
+            let mut stage_timings = Vec::new();
+            let html_page = format!("function-{}.html", html_entries.len() + 1);
+            let result: Result<(Duration, Output), Error> = do catch {
                 let verbose = opt.verbose;
-                let all_facts =
-                    tab_delim::load_tab_delimited_facts(tables, &Path::new(&facts_dir))?;
-                let algorithm = opt.algorithm.into();
-                timed(|| Output::compute(&all_facts, algorithm, verbose))
+                let (load_time, all_facts) = timed(|| {
+                    if facts_dirs.len() == 1 {
+                        load_facts(tables, &Path::new(&facts_dirs[0]), load_config)
+                    } else {
+                        load_merged_facts(tables, &facts_dirs, load_config)
+                    }
+                });
+                let all_facts = all_facts?;
+                stage_timings.push(("load, with the interning", load_time));
+                if !opt.skip_validation {
+                    let (validation_time, ()) =
+                        timed(|| warn_invalid_facts(&facts_dir, &all_facts, tables));
+                    stage_timings.push(("validation", validation_time));
+                }
+                relation_sizes = stats::relation_sizes(&all_facts);
+                let all_facts = if opt.renumber {
+                    let (renumber_time, (renumbered, renumbered_tables)) =
+                        timed(|| renumber::renumber_densely(all_facts, tables));
+                    stage_timings.push(("renumbering", renumber_time));
+                    *tables = renumbered_tables;
+                    renumbered
+                } else {
+                    all_facts
+                };
+                progress.phase("preprocessing");
+                let (preprocess_time, preprocessed) = timed(|| preprocessor.run(&all_facts));
+                stage_timings.push(("preprocessing", preprocess_time));
+                if verbose && preprocessor != Preprocessor::default() {
+                    println!(
+                        "preprocessing removed {} atoms, and {} tuples",
+                        preprocessed.stats.atoms, preprocessed.stats.tuples
+                    );
+                }
+                if opt.verify_simplification {
+                    let unsimplified_facts = preprocessed.unsimplified_facts();
+                    verify_simplification(unsimplified_facts, tables, opt.algorithm)?;
+                }
+                let analyzed_facts = &preprocessed.facts;
+                let algorithm = opt.algorithm;
+                let compare = opt.compare;
+                let threads = opt.threads;
+                let components = opt.components;
+                let why = opt.why;
+                let dump_enabled = verbose || !opt.dump.is_empty();
+                let budget = budget_from_limits(opt.time_limit, opt.tuple_limit);
+                progress.phase("solving");
+                let (duration, output) = timed(|| {
+                    let on_tuples = &mut |derived| progress.tuples(derived);
+                    if compare {
+                        Ok(Output::compute(analyzed_facts, Algorithm::Compare, dump_enabled))
+                    } else if why {
+                        Ok(Output::compute_with_provenance(analyzed_facts, algorithm, dump_enabled))
+                    } else if threads.is_some() || components {
+                        compute_output(analyzed_facts, algorithm, dump_enabled, threads, components)
+                    } else {
+                        let budget = budget.unwrap_or_else(Budget::unlimited);
+                        Ok(Output::compute_with_progress(
+                            analyzed_facts,
+                            algorithm,
+                            dump_enabled,
+                            budget,
+                            on_tuples,
+                        ))
+                    }
+                });
+                let output = check_comparison(output?, tables)?;
+
+                // The results are reported against the points of the facts
+                // as they were loaded.
+                let output = preprocessed.translate_output(output);
+                if let Some(ref html_dir) = opt.emit_html {
+                    html::write_function_page(
+                        &Path::new(html_dir).join(&html_page),
+                        &facts_dir,
+                        &all_facts,
+                        &output,
+                        tables,
+                        &relation_sizes,
+                    )?;
+                }
+                (duration, output)
             };
+            progress.clear();
+            let peak_rss = memory::peak_rss();
+
+            outcome = cmp::max(
+                outcome,
+                match result {
+                    Ok((_, ref output)) => Outcome::of_output(output),
+                    Err(_) => Outcome::InvalidInput,
+                },
+            );
+
+            // The partial output of a budget that ran out can't be trusted to
+            // have all the errors, so the function is reported as skipped.
+            let result = result.and_then(|(duration, output)| match output.budget_exceeded {
+                Some(exceeded) => Err(failure::err_msg(format!("skipped, {}", exceeded))),
+                None => Ok((duration, output)),
+            });
+            if let Ok((_, ref output)) = result {
+                baseline.record(&baseline::function_name(&facts_dirs), output, tables);
+            }
+            if opt.emit_html.is_some() {
+                html_entries.push(html::IndexEntry {
+                    directory: facts_dir.clone(),
+                    result: match result {
+                        Ok((_, ref output)) => Ok((html_page, output.error_count())),
+                        Err(ref error) => Err(error.to_string()),
+                    },
+                });
+            }
+            if opt.check {
+                if let Err(error) = result {
+                    eprintln!("`{}`: {}", facts_dir, error);
+                }
+                continue;
+            }
+            let result = result.map(|(duration, mut output)| {
+                if opt.show_point.is_some() || opt.block.is_some() {
+                    let show_point = opt.show_point.as_ref().map(|name| name.as_str());
+                    let block = opt.block.as_ref().map(|block| block.as_str());
+                    let keep = |point: Point| {
+                        shows_point(show_point, block, tables.unquoted(point))
+                    };
+                    retain_points(&mut output, keep);
+                }
+                (duration, output)
+            });
+
+            if opt.output_format == OutputFormat::Json {
+                let report = JsonReport::new(&facts_dir).with_relation_sizes(&relation_sizes);
+                reports.push(match result {
+                    Ok((duration, output)) => {
+                        report.with_output(seconds(duration), &output, tables)
+                    }
+                    Err(error) => report.with_failure(error.to_string()),
+                });
+                continue;
+            }
+
+            if opt.output_format == OutputFormat::Sarif {
+                match result {
+                    Ok((_, output)) => match sarif::load_spans(tables, &facts_dirs) {
+                        Ok(spans) => sarif_log.add_errors(&facts_dir, &output, tables, &spans),
+                        Err(error) => eprintln!("`{}`: {}", facts_dir, error),
+                    },
+                    Err(error) => eprintln!("`{}`: {}", facts_dir, error),
+                }
+                continue;
+            }
 
             match result {
                 Ok((duration, output)) => {
                     println!("--------------------------------------------------");
                     println!("Directory: {}", facts_dir);
                     if !opt.skip_timing {
-                        let seconds: f64 = duration.as_secs() as f64;
-                        let millis: f64 = duration.subsec_nanos() as f64 * 0.000_000_001_f64;
-                        println!("Time: {:0.3}s", seconds + millis);
+                        println!("Time: {:0.3}s", seconds(duration));
+                    }
+                    if opt.timings {
+                        print_timings(&stage_timings, &output);
+                    }
+                    if opt.memory {
+                        let memory = output.derived_relation_memory();
+                        print_memory(peak_rss, peak_rss_per_input, &memory);
                     }
-                    if !opt.skip_tuples {
+                    if !opt.dump.is_empty() {
+                        let relations: Vec<&str> =
+                            opt.dump.iter().map(|relation| relation.as_str()).collect();
+                        dump::dump_relations(&output, &output_directory, tables, &relations)
+                            .expect("Failed to write output");
+                    } else if !opt.skip_tuples {
                         dump::dump_output(&output, &output_directory, tables)
                             .expect("Failed to write output");
                     }
+                    if opt.why {
+                        dump::dump_error_derivations(&output, tables);
+                    }
                 }
 
                 Err(error) => {
@@ -84,6 +815,785 @@ pub fn main(opt: Opt) -> Result<(), Error> {
                 }
             }
         }
+
+        if opt.output_format == OutputFormat::Json && !opt.check {
+            json::write_json_reports(&reports, io::stdout())?;
+            println!();
+        }
+        if opt.output_format == OutputFormat::Sarif && !opt.check {
+            sarif_log.write(io::stdout())?;
+            println!();
+        }
+
+        if let Some(ref path) = opt.compare_baseline {
+            let changes = Baseline::load(Path::new(path))?.compare(&baseline);
+            // The JSON or SARIF document is alone on stdout.
+            if opt.output_format != OutputFormat::Text {
+                eprint!("{}", changes);
+            } else if !opt.check {
+                print!("{}", changes);
+            }
+
+            // The errors of the baseline are expected: `--check` only fails
+            // on the errors that changed.
+            if opt.check && (outcome == Outcome::Clean || outcome == Outcome::BorrowErrors) {
+                outcome = if changes.is_unchanged() {
+                    Outcome::Clean
+                } else {
+                    Outcome::BorrowErrors
+                };
+            }
+        }
+        if let Some(ref path) = opt.save_baseline {
+            baseline.save(Path::new(path))?;
+        }
+        if let Some(ref html_dir) = opt.emit_html {
+            html::write_index(Path::new(html_dir), &html_entries)?;
+        }
+
+        outcome
+    }
+}
+
+/// Runs `command`, returning the outcome that the process exits with: only
+/// `analyze --check` reports the outcome of the analyses through it, and
+/// the other commands return `Clean` unless they fail.
+fn run_command(command: Command, load_config: LoadConfig) -> Result<Outcome, Error> {
+    match command {
+        Command::Analyze(ref options) if options.watch => {
+            let function = function_filter(&options.function, &options.function_regex)?;
+            analyze_on_change(options, function.as_ref(), load_config)
+        }
+        Command::Analyze(ref options) if options.check => {
+            let outcome = analyze(options, load_config).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                Outcome::InvalidInput
+            });
+            return Ok(outcome);
+        }
+        Command::Analyze(ref options) => {
+            analyze(options, load_config)?;
+        }
+
+        Command::ListAlgorithms => {
+            for algorithm in Algorithm::variants().iter() {
+                println!("{}", algorithm);
+            }
+        }
+
+        Command::Dump {
+            renumber,
+            compress,
+            facts_dir,
+            output_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let all_facts = if renumber {
+                let (renumbered, renumbered_tables) =
+                    renumber::renumber_densely(all_facts, tables);
+                *tables = renumbered_tables;
+                renumbered
+            } else {
+                all_facts
+            };
+            if compress {
+                tab_delim::write_compressed_tab_delimited_facts(
+                    &all_facts,
+                    tables,
+                    Path::new(&output_dir),
+                )?;
+            } else {
+                tab_delim::write_tab_delimited_facts(&all_facts, tables, Path::new(&output_dir))?;
+            }
+        }
+
+        Command::Export(ExportCommand::Sqlite {
+            algorithm,
+            facts_dir,
+            database,
+        }) => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            sqlite::export(&all_facts, tables, algorithm, Path::new(&database))?;
+        }
+
+        Command::Graph(GraphCommand::Cfg {
+            algorithm,
+            output,
+            facts_dir,
+        }) => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let errors = Output::compute(&all_facts, algorithm, false).errors;
+            write_graph(output, |writer| {
+                graph::write_cfg_dot(&all_facts, tables, &errors, writer)
+            })?;
+        }
+
+        Command::Graph(GraphCommand::Subsets {
+            point,
+            output,
+            facts_dir,
+        }) => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let point = tables
+                .points
+                .lookup(&point)
+                .ok_or_else(|| failure::err_msg(format!("unknown point `{}`", point)))?;
+            let subsets = QueryEngine::new(&all_facts, Algorithm::Naive).subsets_at(point);
+            write_graph(output, |writer| {
+                graph::write_subsets_dot(&all_facts, tables, point, &subsets, writer)
+            })?;
+        }
+
+        Command::Batch {
+            algorithm,
+            list,
+            function,
+            function_regex,
+            no_progress,
+            jobs,
+            mut roots,
+        } => {
+            if let Some(list) = list {
+                roots.extend(batch::read_roots(Path::new(&list))?);
+            }
+
+            let function = function_filter(&function, &function_regex)?;
+            let mut summary = BatchSummary::default();
+            let fact_dirs = batch::facts_dirs_under(&roots, function.as_ref(), |root, error| {
+                eprintln!("`{}`: {}", root, error);
+                summary.record_failure();
+            });
+
+            let mut progress = Progress::new(fact_dirs.len(), !no_progress);
+            if jobs > 1 {
+                // Each function is loaded into its own tables on its thread,
+                // and the results are printed in the order of the functions.
+                let results = batch::run_in_parallel(
+                    fact_dirs,
+                    jobs,
+                    move |facts_dir| {
+                        let result = analyze_in_batch(&facts_dir, algorithm, load_config, None);
+                        (facts_dir.display().to_string(), result)
+                    },
+                    |&(ref name, _)| progress.finish(name),
+                );
+                progress.clear();
+                for (name, result) in results {
+                    report_in_batch(&mut summary, &name, result);
+                }
+            } else {
+                for facts_dir in fact_dirs {
+                    let name = facts_dir.display().to_string();
+                    progress.start(&name);
+                    let result =
+                        analyze_in_batch(&facts_dir, algorithm, load_config, Some(&mut progress));
+                    progress.clear();
+                    report_in_batch(&mut summary, &name, result);
+                }
+            }
+            println!("--------------------------------------------------");
+            print!("{}", summary);
+        }
+
+        Command::Bench {
+            algorithms,
+            runs,
+            warmup,
+            csv,
+            fact_dirs,
+        } => {
+            let algorithms = if algorithms.is_empty() {
+                vec![
+                    Algorithm::Naive,
+                    Algorithm::DatafrogOpt,
+                    Algorithm::LocationInsensitive,
+                    Algorithm::Hybrid,
+                ]
+            } else {
+                algorithms
+            };
+            if csv {
+                println!("{}", bench::CSV_HEADER);
+            }
+            for facts_dir in &fact_dirs {
+                let tables = &mut InternerTables::new();
+                let all_facts = load_facts(tables, Path::new(facts_dir), load_config)?;
+                if !csv {
+                    println!("Directory: {}", facts_dir);
+                }
+                for &algorithm in &algorithms {
+                    for _ in 0..warmup {
+                        Output::compute(&all_facts, algorithm, false);
+                    }
+
+                    let mut durations = Vec::new();
+                    let mut derived_tuples = 0;
+                    for _ in 0..runs {
+                        let (duration, output) =
+                            timed(|| Output::compute(&all_facts, algorithm, false));
+                        durations.push(duration);
+                        derived_tuples = derived_tuples.max(output.derived_tuples);
+                    }
+
+                    let result = BenchResult::new(&durations, derived_tuples)
+                        .ok_or_else(|| failure::err_msg("`--runs` has to be at least 1"))?;
+                    if csv {
+                        println!("{}", bench::csv_row(facts_dir, algorithm, &result));
+                    } else {
+                        println!("{:>20}: {}", algorithm.to_string(), result);
+                    }
+                }
+            }
+        }
+
+        Command::DiffFacts { before, after } => {
+            let before_tables = &mut InternerTables::new();
+            let before_facts = load_facts(before_tables, Path::new(&before), load_config)?;
+            let after_tables = &mut InternerTables::new();
+            let after_facts = load_facts(after_tables, Path::new(&after), load_config)?;
+
+            let diffs =
+                diff::diff_facts(&before_facts, before_tables, &after_facts, after_tables);
+            diff::print_relation_diffs(&diffs);
+        }
+
+        Command::Stats {
+            algorithm,
+            facts_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let mut stats = stats::compute_stats(&all_facts);
+            if let Some(algorithm) = algorithm {
+                let output = Output::compute(&all_facts, algorithm, false);
+                stats.derived_relation_memory = output.derived_relation_memory();
+            }
+            print!("{}", stats);
+        }
+
+        Command::Anonymize {
+            facts_dir,
+            output_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let anonymized_tables = anonymize::anonymized_tables(tables);
+            tab_delim::write_tab_delimited_facts(
+                &all_facts,
+                &anonymized_tables,
+                Path::new(&output_dir),
+            )?;
+        }
+
+        Command::Minimize {
+            algorithm,
+            error_at,
+            disagree,
+            facts_dir,
+            output_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+
+            let minimized = match (error_at, disagree) {
+                (Some(point), false) => {
+                    let point = tables
+                        .points
+                        .lookup(&point)
+                        .ok_or_else(|| failure::err_msg(format!("unknown point `{}`", point)))?;
+                    polonius_engine::minimize(&all_facts, |facts| {
+                        Output::compute(facts, algorithm, false)
+                            .errors
+                            .contains_key(&point)
+                    })
+                }
+
+                (None, true) => polonius_engine::minimize(&all_facts, |facts| {
+                    let naive = Output::compute(facts, Algorithm::Naive, false);
+                    let opt = Output::compute(facts, Algorithm::DatafrogOpt, false);
+                    naive.errors != opt.errors
+                }),
+
+                _ => {
+                    return Err(failure::err_msg(
+                        "exactly one of `--error-at` and `--disagree` is required",
+                    ));
+                }
+            };
+
+            tab_delim::write_tab_delimited_facts(&minimized, tables, Path::new(&output_dir))?;
+        }
+
+        Command::Repl {
+            algorithm,
+            facts_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let repl = Repl::new(all_facts, tables, algorithm);
+            let stdin = io::stdin();
+            repl.run(&mut stdin.lock(), &mut io::stdout())?;
+        }
+
+        Command::Explain {
+            preprocess,
+            remove_unreachable,
+            prune_dead_regions,
+            prune_dead_loans,
+            reduce_outlives,
+            facts_dir,
+            point,
+            loan,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let point_name = point;
+            let point = tables
+                .points
+                .lookup(&point_name)
+                .ok_or_else(|| failure::err_msg(format!("unknown point `{}`", point_name)))?;
+            let loan_name = loan;
+            let loan = tables
+                .loans
+                .lookup(&loan_name)
+                .ok_or_else(|| failure::err_msg(format!("unknown loan `{}`", loan_name)))?;
+
+            // The explanation is about the given point, so the CFG stays as
+            // it is, even at the `aggressive` level.
+            let passes = Preprocessor {
+                remove_unreachable,
+                reduce_outlives,
+                prune_dead_regions,
+                prune_dead_loans,
+                simplify_cfg: false,
+            };
+            let preprocessor = with_passes(preprocess, passes).simplify_cfg(false);
+            let preprocessed = preprocessor.run(&all_facts);
+            let analyzed_facts = &preprocessed.facts;
+            let output = Output::compute_with_provenance(analyzed_facts, Algorithm::Naive, false);
+            match output.why(point, loan) {
+                Some(derivation) => {
+                    let lines = explain::explain_error(&derivation, tables).ok_or_else(|| {
+                        failure::err_msg(format!(
+                            "the derivation of `{}` at `{}` isn't that of an error",
+                            loan_name, point_name
+                        ))
+                    })?;
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                }
+                None if all_facts.invalidates.contains(&(point, loan)) => println!(
+                    "`{}` invalidates `{}`, but the loan isn't in scope there: no error.",
+                    point_name, loan_name
+                ),
+                None => println!("`{}` doesn't invalidate `{}`: no error.", point_name, loan_name),
+            }
+        }
+
+        Command::Lsp { algorithm, roots } => lsp::run(roots, algorithm, load_config)?,
+
+        Command::Serve {
+            algorithm,
+            address,
+            time_limit,
+            tuple_limit,
+        } => {
+            let budget = budget_from_limits(Some(time_limit), tuple_limit).unwrap_or_default();
+            serve::run(&address, algorithm, budget)?
+        }
+
+        Command::CompareSouffle {
+            souffle,
+            program,
+            work_dir,
+            facts_dir,
+        } => {
+            let tables = &mut InternerTables::new();
+            let all_facts = load_facts(tables, Path::new(&facts_dir), load_config)?;
+            let temporary = work_dir.is_none();
+            let work_dir = match work_dir {
+                Some(work_dir) => PathBuf::from(work_dir),
+                None => env::temp_dir().join(format!("polonius-souffle-{}", process::id())),
+            };
+
+            let program = program.as_ref().map(Path::new);
+            let result =
+                souffle::compare(Path::new(&souffle), program, &all_facts, tables, &work_dir);
+            if temporary {
+                let _ = fs::remove_dir_all(&work_dir);
+            }
+            let diffs = result?;
+            diff::print_relation_diffs(&diffs);
+            if diffs.iter().any(|diff| !diff.is_empty()) {
+                return Err(failure::err_msg("Soufflé and polonius derive different outputs"));
+            }
+            println!("Soufflé and polonius derive the same outputs.");
+        }
+    }
+
+    Ok(Outcome::Clean)
+}
+
+/// Analyzes the facts of `facts_dir` for `batch` and `cargo polonius`,
+/// returning the number of loaded tuples and of errors, and how long the
+/// analysis took.
+crate fn analyze_in_batch(
+    facts_dir: &Path,
+    algorithm: Algorithm,
+    load_config: LoadConfig,
+    mut progress: Option<&mut Progress>,
+) -> Result<(usize, usize, Duration), Error> {
+    let tables = &mut InternerTables::new();
+    let all_facts = load_facts(tables, facts_dir, load_config)?;
+    let tuples = all_facts.tuple_count();
+    if let Some(ref mut progress) = progress {
+        progress.phase("solving");
+    }
+    let (duration, output) = timed(|| {
+        let on_tuples = &mut |derived| {
+            if let Some(ref mut progress) = progress {
+                progress.tuples(derived);
+            }
+        };
+        Output::compute_with_progress(&all_facts, algorithm, false, Budget::unlimited(), on_tuples)
+    });
+    Ok((tuples, output.error_count(), duration))
+}
+
+/// Prints the line of the analysis of `name` in `batch`, and adds it to
+/// `summary`.
+crate fn report_in_batch(
+    summary: &mut BatchSummary,
+    name: &str,
+    result: Result<(usize, usize, Duration), Error>,
+) {
+    match result {
+        Ok((tuples, errors, duration)) => {
+            println!("{}\t{} errors\t{:0.3}s\t{} tuples", name, errors, seconds(duration), tuples);
+            summary.record_analysis(name, errors, duration);
+        }
+        Err(error) => {
+            eprintln!("`{}`: {}", name, error);
+            summary.record_failure();
+        }
+    }
+}
+
+/// Prints how long each stage of loading and preparing the facts took, and
+/// then each phase of the analysis, with those of the algorithm under the
+/// `loans` one.
+fn print_timings(stage_timings: &[(&str, Duration)], output: &Output) {
+    println!("Timings:");
+    for &(stage, duration) in stage_timings {
+        println!("    {}: {:0.3}s", stage, seconds(duration));
+    }
+    for &(phase, duration) in &output.phase_timings {
+        println!("    solving, {}: {:0.3}s", phase, seconds(duration));
+        if phase == "loans" {
+            for &(algorithm_phase, duration) in &output.algorithm_phase_timings {
+                println!("        {}: {:0.3}s", algorithm_phase, seconds(duration));
+            }
+        }
+    }
+}
+
+/// The number of the largest derived relations that `--memory` prints.
+const LARGEST_RELATIONS: usize = 5;
+
+/// Prints the peak resident memory, which is of the whole process unless
+/// it was `per_input`, and the largest relations of `relation_memory`.
+fn print_memory(peak_rss: Option<u64>, per_input: bool, relation_memory: &[RelationMemory]) {
+    match peak_rss {
+        Some(bytes) => {
+            let mebibytes = bytes as f64 / (1024.0 * 1024.0);
+            let scope = if per_input { "" } else { ", since the start" };
+            println!("Peak memory: {:0.1} MiB{}", mebibytes, scope);
+        }
+        None => println!("Peak memory: unknown"),
+    }
+    println!("Largest derived relations:");
+    for relation in relation_memory.iter().take(LARGEST_RELATIONS) {
+        println!(
+            "    {}: {} tuples, {} bytes",
+            relation.relation, relation.tuples, relation.bytes
+        );
+    }
+}
+
+/// Writes a graph with `write` into the file `output`, or to stdout.
+fn write_graph(
+    output: Option<String>,
+    write: impl FnOnce(&mut dyn io::Write) -> io::Result<()>,
+) -> Result<(), Error> {
+    match output {
+        Some(output) => write(&mut File::create(&output)?)?,
+        None => {
+            let stdout = io::stdout();
+            write(&mut stdout.lock())?;
+        }
+    }
+    Ok(())
+}
+
+/// Loads the facts at `facts_path`, which is either a directory of
+/// tab-delimited `.facts` files or a single `.json` document. Directories
+/// can optionally go through the binary fact cache.
+crate fn load_facts(
+    tables: &mut InternerTables,
+    facts_path: &Path,
+    load_config: LoadConfig,
+) -> Result<AllFacts, Error> {
+    if facts_path.extension().map_or(false, |extension| extension == "json") {
+        Ok(json::load_json_facts(tables, facts_path)?)
+    } else if load_config.cache {
+        Ok(cache::load_cached_facts(tables, facts_path, load_config.options)?)
+    } else {
+        Ok(tab_delim::load_tab_delimited_facts_with(tables, facts_path, load_config.options)?)
+    }
+}
+
+/// Loads several fact directories into one `AllFacts`. Each directory is
+/// interned separately and then re-interned into `tables` with its path as
+/// a prefix, so that atoms with the same name in different dumps (which
+/// is nearly all of them) stay distinct.
+fn load_merged_facts(
+    tables: &mut InternerTables,
+    facts_dirs: &[String],
+    load_config: LoadConfig,
+) -> Result<AllFacts, Error> {
+    let mut merged = AllFacts::default();
+    for facts_dir in facts_dirs {
+        let dir_tables = &mut InternerTables::new();
+        let all_facts = load_facts(dir_tables, Path::new(facts_dir), load_config)?;
+        let prefix = merge_prefix(facts_dir);
+        merged.merge(intern::reintern_facts(all_facts, dir_tables, tables, &prefix));
+    }
+    Ok(merged)
+}
+
+/// The prefix of the names of the atoms of `facts_dir` in merged facts.
+crate fn merge_prefix(facts_dir: &str) -> String {
+    format!("{}:", facts_dir)
+}
+
+/// Computes the output on `threads` threads if given, which needs the
+/// `parallel` feature, and one connected component of the CFG at a time if
+/// `components` is set.
+#[cfg(feature = "parallel")]
+fn compute_output(
+    all_facts: &AllFacts,
+    algorithm: Algorithm,
+    verbose: bool,
+    threads: Option<usize>,
+    components: bool,
+) -> Result<Output, Error> {
+    Ok(match (threads, components) {
+        (Some(threads), true) => {
+            Output::compute_by_component_parallel(all_facts, algorithm, verbose, threads)
+        }
+        (Some(threads), false) => Output::compute_parallel(all_facts, algorithm, verbose, threads),
+        (None, true) => Output::compute_by_component(all_facts, algorithm, verbose),
+        (None, false) => Output::compute(all_facts, algorithm, verbose),
+    })
+}
+
+#[cfg(not(feature = "parallel"))]
+fn compute_output(
+    all_facts: &AllFacts,
+    algorithm: Algorithm,
+    verbose: bool,
+    threads: Option<usize>,
+    components: bool,
+) -> Result<Output, Error> {
+    if threads.is_some() {
+        return Err(failure::err_msg(
+            "`--threads` needs polonius to be built with the `parallel` feature",
+        ));
+    }
+    Ok(if components {
+        Output::compute_by_component(all_facts, algorithm, verbose)
+    } else {
+        Output::compute(all_facts, algorithm, verbose)
+    })
+}
+
+/// Whether the point named `point`, unquoted, passes the `--show-point`
+/// and `--block` filters, which match whole names: the points of block
+/// `bb7` are named like `Mid(bb7[2])`, and those of `bb70` are not in it.
+crate fn shows_point(show_point: Option<&str>, block: Option<&str>, point: &str) -> bool {
+    show_point.map_or(true, |name| point == name)
+        && block.map_or(true, |block| block_of(point) == Some(block))
+}
+
+/// The basic block of the point named `point`, e.g. `bb7` for `Mid(bb7[2])`.
+fn block_of(point: &str) -> Option<&str> {
+    let start = point.find('(')? + 1;
+    let end = start + point[start..].find('[')?;
+    Some(&point[start..end])
+}
+
+/// Removes the results at the points that `keep` rejects, from every
+/// relation of `output` indexed by point.
+crate fn retain_points(output: &mut Output, keep: impl Fn(Point) -> bool) {
+    output.errors.retain(|&point, _| keep(point));
+    output.borrow_live_at_mut().retain(|&point, _| keep(point));
+    output.move_errors.retain(|&point, _| keep(point));
+    output.var_maybe_uninitialized_on_entry.retain(|&point, _| keep(point));
+    output.subset_errors.retain(|&point, _| keep(point));
+    output.restricts.retain(|&point, _| keep(point));
+    output.region_live_at.retain(|&point, _| keep(point));
+    output.invalidates.retain(|&point, _| keep(point));
+    output.subset.retain(|&point, _| keep(point));
+}
+
+/// The functions that `--function` or `--function-regex` keep, if either
+/// is given.
+fn function_filter(
+    function: &Option<String>,
+    function_regex: &Option<String>,
+) -> Result<Option<FunctionFilter>, Error> {
+    let function = function.as_ref().map(|function| function.as_str());
+    let function_regex = function_regex.as_ref().map(|regex| regex.as_str());
+    FunctionFilter::from_options(function, function_regex).map_err(|error| {
+        failure::err_msg(format!("invalid `--function-regex`: {}", error))
+    })
+}
+
+/// The passes of `--preprocess`, with the ones that the other flags
+/// enable or disable. `--why` explains the errors at the analyzed points,
+/// so it keeps the CFG as it is.
+fn preprocessor_from_options(opt: &AnalyzeOpt) -> Preprocessor {
+    let passes = Preprocessor {
+        remove_unreachable: opt.remove_unreachable,
+        reduce_outlives: opt.reduce_outlives,
+        prune_dead_regions: opt.prune_dead_regions,
+        prune_dead_loans: opt.prune_dead_loans,
+        simplify_cfg: opt.simplify_cfg,
+    };
+    let preprocessor = with_passes(opt.preprocess, passes);
+    preprocessor.simplify_cfg(preprocessor.simplify_cfg && !opt.why)
+}
+
+/// The passes of `level`, and those of `passes` on top of them.
+fn with_passes(level: PreprocessLevel, passes: Preprocessor) -> Preprocessor {
+    let preprocessor = Preprocessor::new(level);
+    Preprocessor {
+        remove_unreachable: preprocessor.remove_unreachable || passes.remove_unreachable,
+        reduce_outlives: preprocessor.reduce_outlives || passes.reduce_outlives,
+        prune_dead_regions: preprocessor.prune_dead_regions || passes.prune_dead_regions,
+        prune_dead_loans: preprocessor.prune_dead_loans || passes.prune_dead_loans,
+        simplify_cfg: preprocessor.simplify_cfg || passes.simplify_cfg,
+    }
+}
+
+/// The budget set by `--time-limit` and `--tuple-limit`, if any.
+fn budget_from_limits(time_limit: Option<u64>, tuple_limit: Option<usize>) -> Option<Budget> {
+    if time_limit.is_none() && tuple_limit.is_none() {
+        return None;
+    }
+
+    let mut budget = Budget::unlimited();
+    if let Some(seconds) = time_limit {
+        budget = budget.with_max_duration(Duration::from_secs(seconds));
+    }
+    if let Some(tuples) = tuple_limit {
+        budget = budget.with_max_tuples(tuples);
+    }
+    Some(budget)
+}
+
+/// Analyzes the facts with and without `simplify_cfg`, printing the errors
+/// that only one of them reports and failing if there are any.
+fn verify_simplification(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    algorithm: Algorithm,
+) -> Result<(), Error> {
+    let diff = polonius_engine::verify_simplification(all_facts, algorithm);
+    if diff.is_empty() {
+        return Ok(());
+    }
+
+    for &(point, loan) in &diff.missing {
+        eprintln!(
+            "only original: {}\t{}",
+            tables.points.untern(point),
+            tables.loans.untern(loan)
+        );
+    }
+    for &(point, loan) in &diff.unexpected {
+        eprintln!(
+            "only simplified: {}\t{}",
+            tables.points.untern(point),
+            tables.loans.untern(loan)
+        );
+    }
+    Err(failure::err_msg(format!(
+        "simplifying the CFG changes {} errors",
+        diff.missing.len() + diff.unexpected.len()
+    )))
+}
+
+/// Fails if the naive and optimized algorithms of `Algorithm::Compare`
+/// disagree on the errors of `output`, printing the errors reported by
+/// only one of them. The output of the other algorithms is returned as it
+/// is.
+fn check_comparison(output: Output, tables: &InternerTables) -> Result<Output, Error> {
+    let disagree = output.errors_diff.as_ref().map_or(false, |diff| !diff.is_empty());
+    if !disagree {
+        return Ok(output);
+    }
+
+    let diff = output.errors_diff.unwrap();
+    for &(point, loan) in &diff.missing {
+        eprintln!(
+            "only naive: {}\t{}",
+            tables.points.untern(point),
+            tables.loans.untern(loan)
+        );
+    }
+    for &(point, loan) in &diff.unexpected {
+        eprintln!(
+            "only opt: {}\t{}",
+            tables.points.untern(point),
+            tables.loans.untern(loan)
+        );
+    }
+    Err(failure::err_msg(format!(
+        "the naive and optimized algorithms disagree on {} errors",
+        diff.missing.len() + diff.unexpected.len()
+    )))
+}
+
+/// Prints a warning for each problem `AllFacts::validate` finds.
+fn warn_invalid_facts(facts_dir: &str, all_facts: &AllFacts, tables: &InternerTables) {
+    let report = all_facts.validate();
+    for &(relation, point) in &report.points_not_in_cfg {
+        eprintln!(
+            "warning: `{}`: point {} in `{}` is not in `cfg_edge`",
+            facts_dir,
+            tables.points.untern(point),
+            relation
+        );
+    }
+    for &(relation, loan) in &report.unissued_loans {
+        eprintln!(
+            "warning: `{}`: loan {} in `{}` is never issued by `borrow_region`",
+            facts_dir,
+            tables.loans.untern(loan),
+            relation
+        );
+    }
+    for &(relation, count) in &report.duplicate_tuples {
+        eprintln!(
+            "warning: `{}`: `{}` contains {} duplicate tuples",
+            facts_dir, relation, count
+        );
     }
 }
 
@@ -93,3 +1603,10 @@ fn timed<T>(op: impl FnOnce() -> T) -> (Duration, T) {
     let duration = start.elapsed();
     (duration, output)
 }
+
+/// `duration` in seconds, for printing.
+crate fn seconds(duration: Duration) -> f64 {
+    let seconds: f64 = duration.as_secs() as f64;
+    let millis: f64 = duration.subsec_nanos() as f64 * 0.000_000_001_f64;
+    seconds + millis
+}
@@ -0,0 +1,112 @@
+use crate::facts::AllFacts;
+use crate::intern::InternerTables;
+use crate::tab_delim::ToTabDelimited;
+use std::collections::BTreeSet;
+
+/// The tuples of one relation that differ between two sets of facts,
+/// rendered as tab-delimited rows of interned names.
+crate struct RelationDiff {
+    crate relation: &'static str,
+    crate added: Vec<String>,
+    crate removed: Vec<String>,
+}
+
+impl RelationDiff {
+    crate fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Prints the added (`+`) and removed (`-`) rows of each relation of
+/// `diffs` that differs.
+crate fn print_relation_diffs(diffs: &[RelationDiff]) {
+    for diff in diffs.iter().filter(|diff| !diff.is_empty()) {
+        println!(
+            "# {}: +{} -{}",
+            diff.relation,
+            diff.added.len(),
+            diff.removed.len()
+        );
+        for row in &diff.added {
+            println!("+ {}", row);
+        }
+        for row in &diff.removed {
+            println!("- {}", row);
+        }
+    }
+}
+
+/// Compares two sets of facts relation by relation. The facts are
+/// compared by name rather than by index, so `before` and `after` can
+/// (and usually do) come from different interner tables.
+crate fn diff_facts(
+    before: &AllFacts,
+    before_tables: &InternerTables,
+    after: &AllFacts,
+    after_tables: &InternerTables,
+) -> Vec<RelationDiff> {
+    macro_rules! diff_relations {
+        ($($t:ident,)*) => {
+            vec![
+                $(
+                    diff_relation(
+                        stringify!($t),
+                        &before.$t,
+                        before_tables,
+                        &after.$t,
+                        after_tables,
+                    ),
+                )*
+            ]
+        }
+    }
+
+    diff_relations! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_used_at,
+        var_defined_at,
+        var_drop_used_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        known_placeholder_subset,
+        loan_borrows_path,
+    }
+}
+
+fn diff_relation<Row: ToTabDelimited>(
+    relation: &'static str,
+    before: &[Row],
+    before_tables: &InternerTables,
+    after: &[Row],
+    after_tables: &InternerTables,
+) -> RelationDiff {
+    let before = named_rows(before, before_tables);
+    let after = named_rows(after, after_tables);
+    RelationDiff {
+        relation,
+        added: after.difference(&before).cloned().collect(),
+        removed: before.difference(&after).cloned().collect(),
+    }
+}
+
+fn named_rows<Row: ToTabDelimited>(rows: &[Row], tables: &InternerTables) -> BTreeSet<String> {
+    let mut columns = Vec::new();
+    rows.iter()
+        .map(|row| {
+            columns.clear();
+            row.push_columns(tables, &mut columns);
+            columns.join("\t")
+        })
+        .collect()
+}
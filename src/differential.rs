@@ -0,0 +1,279 @@
+//! `cargo polonius --compare-rustc`: compares the borrow errors that
+//! rustc's NLL borrow checker reports on the workspace with those that the
+//! analysis finds in the facts that rustc dumped.
+//!
+//! rustc's errors come from the JSON messages of `cargo check`, and only
+//! those about loans count, the errors of `BORROWCK_CODES`. They are on
+//! source lines, while the facts only name MIR points, so each function is
+//! matched with its lines through the `point_span.facts` file of its
+//! facts directory: the function of an error of rustc is the one whose
+//! points span the fewest lines around it, and the errors of the analysis
+//! are on the lines of their points.
+//!
+//! The analysis accepts more programs than NLL does, so the lines that only
+//! rustc reports an error on are expected. The functions with errors on
+//! lines that rustc accepts are the disagreements.
+
+use crate::cli::{self, LoadConfig};
+use crate::facts::{Output, Point};
+use crate::intern::InternerTables;
+use crate::sarif::{self, Span};
+use failure::Error;
+use polonius_engine::Algorithm;
+use rustc_hash::FxHashMap;
+use serde_json::{self, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The codes of rustc's errors about loans, which the analysis finds too:
+/// the conflicting borrows, the uses, moves and assignments of borrowed
+/// data, and the borrowed data that doesn't live long enough.
+crate const BORROWCK_CODES: &[&str] = &[
+    "E0499", "E0502", "E0503", "E0505", "E0506", "E0597", "E0713", "E0716",
+];
+
+/// An error of rustc about a loan.
+#[derive(Clone, Debug, PartialEq)]
+crate struct RustcError {
+    /// The directory of the facts of its crate, named like `cargo
+    /// polonius` names them, e.g. `polonius.bin`.
+    crate crate_dir: String,
+    crate code: String,
+    crate file: String,
+    crate line: u64,
+}
+
+/// The lines of a source file that the points of a function are on.
+#[derive(Clone, Debug, PartialEq)]
+crate struct Extent {
+    crate file: String,
+    crate first_line: u64,
+    crate last_line: u64,
+}
+
+/// A function, with the lines of the errors that the analysis finds in it.
+#[derive(Clone, Debug, PartialEq)]
+crate struct Function {
+    crate name: String,
+
+    /// `None` if the facts have no `point_span.facts`.
+    crate extent: Option<Extent>,
+    crate error_lines: BTreeSet<u64>,
+
+    /// The errors at points without a span in the file of the function.
+    crate unlocated_errors: usize,
+}
+
+/// How the errors of a function differ between rustc and the analysis.
+#[derive(Clone, Debug, PartialEq)]
+crate struct Disagreement {
+    crate function: String,
+
+    /// The lines that only the analysis reports an error on.
+    crate polonius_only: Vec<u64>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+crate struct DifferentialReport {
+    /// The functions whose errors were compared.
+    crate compared: usize,
+
+    /// The compared functions that rustc reports more errors in, as it is
+    /// expected to.
+    crate rustc_only: usize,
+    crate disagreements: Vec<Disagreement>,
+
+    /// The functions that have no spans, and can't be compared.
+    crate without_spans: Vec<String>,
+
+    /// The errors of rustc outside of every function with spans.
+    crate unattributed: Vec<RustcError>,
+
+    /// The errors of the compared functions at points without spans.
+    crate unlocated_errors: usize,
+}
+
+/// The errors about loans of the JSON messages of `cargo check
+/// --message-format=json`, one per line.
+crate fn parse_rustc_errors(messages: &str) -> Vec<RustcError> {
+    let mut errors = Vec::new();
+    for line in messages.lines() {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        if message["reason"] != "compiler-message" || message["message"]["level"] != "error" {
+            continue;
+        }
+        let code = match message["message"]["code"]["code"].as_str() {
+            Some(code) if BORROWCK_CODES.contains(&code) => code,
+            _ => continue,
+        };
+        let target = &message["target"];
+        let crate_dir = match (target["name"].as_str(), target["kind"][0].as_str()) {
+            (Some(name), Some(kind)) => format!("{}.{}", name.replace('-', "_"), kind),
+            _ => continue,
+        };
+        let primary = message["message"]["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|span| span["is_primary"] == true));
+        if let Some(span) = primary {
+            if let (Some(file), Some(line)) =
+                (span["file_name"].as_str(), span["line_start"].as_u64())
+            {
+                errors.push(RustcError {
+                    crate_dir,
+                    code: code.to_string(),
+                    file: file.to_string(),
+                    line,
+                });
+            }
+        }
+    }
+    errors
+}
+
+impl Extent {
+    /// The lines of the spans that are in the file of the first point; the
+    /// other files are those of the macros that the function expands.
+    crate fn of_spans(spans: &FxHashMap<Point, Span>) -> Option<Extent> {
+        let file = &spans.iter().min_by_key(|&(&point, _)| point)?.1.file;
+        let lines: Vec<u64> = spans
+            .values()
+            .filter(|span| span.file == *file)
+            .map(|span| span.line)
+            .collect();
+        Some(Extent {
+            file: file.clone(),
+            first_line: *lines.iter().min()?,
+            last_line: *lines.iter().max()?,
+        })
+    }
+
+    fn contains(&self, error: &RustcError) -> bool {
+        same_file(&self.file, &error.file)
+            && self.first_line <= error.line
+            && error.line <= self.last_line
+    }
+
+    fn lines(&self) -> u64 {
+        self.last_line - self.first_line + 1
+    }
+}
+
+/// Whether the paths name the same file, one relative to the other's
+/// directory; rustc and the facts don't always agree on where the paths
+/// start.
+fn same_file(a: &str, b: &str) -> bool {
+    a == b || Path::new(a).ends_with(b) || Path::new(b).ends_with(a)
+}
+
+impl Function {
+    /// Analyzes the facts of `facts_dir` with `algorithm`.
+    crate fn analyze(name: &str, facts_dir: &Path, algorithm: Algorithm) -> Result<Self, Error> {
+        let tables = &mut InternerTables::new();
+        let all_facts = cli::load_facts(tables, facts_dir, LoadConfig::default())?;
+        let output = Output::compute(&all_facts, algorithm, false);
+
+        let spans_file = facts_dir.join(sarif::SPANS_FILE_NAME);
+        let spans = if spans_file.exists() {
+            sarif::parse_spans(tables, "", &fs::read_to_string(&spans_file)?)?
+        } else {
+            FxHashMap::default()
+        };
+
+        let mut function = Function {
+            name: name.to_string(),
+            extent: Extent::of_spans(&spans),
+            error_lines: BTreeSet::new(),
+            unlocated_errors: 0,
+        };
+        for (point, _) in output.iter_errors() {
+            let line = match (spans.get(&point), &function.extent) {
+                (Some(span), &Some(ref extent)) if span.file == extent.file => Some(span.line),
+                _ => None,
+            };
+            match line {
+                Some(line) => {
+                    function.error_lines.insert(line);
+                }
+                None => function.unlocated_errors += 1,
+            }
+        }
+        Ok(function)
+    }
+}
+
+impl DifferentialReport {
+    /// Compares the errors of the functions of a crate with `rustc_errors`,
+    /// those of rustc in the crate.
+    crate fn record(&mut self, functions: &[Function], rustc_errors: &[RustcError]) {
+        let mut rustc_lines = vec![BTreeSet::new(); functions.len()];
+        for error in rustc_errors {
+            let innermost = functions
+                .iter()
+                .enumerate()
+                .filter_map(|(index, function)| function.extent.as_ref().map(|e| (index, e)))
+                .filter(|&(_, extent)| extent.contains(error))
+                .min_by_key(|&(_, extent)| extent.lines())
+                .map(|(index, _)| index);
+            match innermost {
+                Some(index) => {
+                    rustc_lines[index].insert(error.line);
+                }
+                None => self.unattributed.push(error.clone()),
+            }
+        }
+
+        for (function, rustc_lines) in functions.iter().zip(rustc_lines) {
+            if function.extent.is_none() {
+                self.without_spans.push(function.name.clone());
+                continue;
+            }
+            self.compared += 1;
+            self.unlocated_errors += function.unlocated_errors;
+            let polonius_only: Vec<u64> =
+                function.error_lines.difference(&rustc_lines).cloned().collect();
+            if !polonius_only.is_empty() {
+                self.disagreements.push(Disagreement {
+                    function: function.name.clone(),
+                    polonius_only,
+                });
+            } else if rustc_lines.len() > function.error_lines.len() {
+                self.rustc_only += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for DifferentialReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for disagreement in &self.disagreements {
+            let lines: Vec<String> =
+                disagreement.polonius_only.iter().map(|line| line.to_string()).collect();
+            writeln!(
+                f,
+                "{}\terrors that rustc doesn't report, on lines {}",
+                disagreement.function,
+                lines.join(", ")
+            )?;
+        }
+        for error in &self.unattributed {
+            writeln!(
+                f,
+                "{}:{}\t{} of rustc outside of the functions with spans",
+                error.file, error.line, error.code
+            )?;
+        }
+        writeln!(f, "--------------------------------------------------")?;
+        writeln!(f, "{:<20} {}", "compared", self.compared)?;
+        writeln!(f, "{:<20} {}", "disagree", self.disagreements.len())?;
+        writeln!(f, "{:<20} {}", "rustc reports more", self.rustc_only)?;
+        writeln!(f, "{:<20} {}", "without spans", self.without_spans.len())?;
+        writeln!(f, "{:<20} {}", "unattributed", self.unattributed.len())?;
+        writeln!(f, "{:<20} {}", "unlocated errors", self.unlocated_errors)?;
+        Ok(())
+    }
+}
@@ -1,106 +1,322 @@
 use crate::facts::*;
 use crate::intern::InternerTables;
 use crate::intern::*;
-use polonius_engine::Output;
-use rustc_hash::FxHashMap;
+use polonius_engine::{unquoted, Derivation, Tuple};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::ser::{Serialize, SerializeMap, Serializer};
 use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
 use std::hash::Hash;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// The relations of an output that `dump_relations` writes, in the order
+/// `dump_output` writes them. All but `borrow_live_at` and `errors` are
+/// only computed when the output has `dump_enabled`.
+crate const RELATIONS: [&str; 11] = [
+    "borrow_live_at",
+    "restricts",
+    "restricts_anywhere",
+    "region_live_at",
+    "invalidates",
+    "errors",
+    "subset",
+    "subset_anywhere",
+    "move_errors",
+    "var_maybe_uninitialized_on_entry",
+    "subset_errors",
+];
+
+/// The names `dump_relations` accepts: the `RELATIONS`, and `requires`,
+/// the name of `restricts` in the rules.
+crate const RELATION_NAMES: [&str; 12] = [
+    "borrow_live_at",
+    "restricts",
+    "requires",
+    "restricts_anywhere",
+    "region_live_at",
+    "invalidates",
+    "errors",
+    "subset",
+    "subset_anywhere",
+    "move_errors",
+    "var_maybe_uninitialized_on_entry",
+    "subset_errors",
+];
 
 crate fn dump_output(
-    output: &Output<Region, Loan, Point>,
+    output: &Output,
     output_dir: &Option<PathBuf>,
     intern: &InternerTables,
 ) -> io::Result<()> {
-    dump_rows(
-        &mut writer_for(output_dir, "borrow_live_at")?,
-        intern,
-        &output.borrow_live_at,
-    )?;
-
     if output.dump_enabled {
-        dump_rows(
-            &mut writer_for(output_dir, "restricts")?,
-            intern,
-            &output.restricts,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "restricts_anywhere")?,
-            intern,
-            &output.restricts_anywhere,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "region_live_at")?,
-            intern,
-            &output.region_live_at,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "invalidates")?,
-            intern,
-            &output.invalidates,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "errors")?,
-            intern,
-            &output.errors,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "subset")?,
-            intern,
-            &output.subset,
-        )?;
-        dump_rows(
-            &mut writer_for(output_dir, "subset_anywhere")?,
-            intern,
-            &output.subset_anywhere,
-        )?;
-    }
-    return Ok(());
-
-    fn writer_for(out_dir: &Option<PathBuf>, name: &str) -> io::Result<Box<Write>> {
-        // create a writer for the provided output.
-        // If we have an output directory use that, otherwise just dump to stdout
-        use std::fs;
-
-        Ok(match out_dir {
-            Some(dir) => {
-                fs::create_dir_all(&dir)?;
-                let mut of = dir.join(name);
-                of.set_extension("facts");
-                Box::new(fs::File::create(of)?)
+        dump_relations(output, output_dir, intern, &RELATIONS)
+    } else {
+        dump_relations(output, output_dir, intern, &["borrow_live_at"])
+    }
+}
+
+/// Writes the relations of `output` named in `relations`, each into its
+/// own file of `output_dir`, or to stdout.
+crate fn dump_relations(
+    output: &Output,
+    output_dir: &Option<PathBuf>,
+    intern: &InternerTables,
+    relations: &[&str],
+) -> io::Result<()> {
+    let unknown = relations.iter().find(|&&relation| !RELATION_NAMES.contains(&relation));
+    if let Some(relation) = unknown {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no relation `{}` to dump", relation),
+        ));
+    }
+
+    for &relation in relations {
+        let writer = &mut writer_for(output_dir, relation)?;
+        dump_rows(writer, &relation_rows(output, intern, relation))?;
+    }
+    Ok(())
+}
+
+/// Writing an output as a facts directory, in the format of the input
+/// facts: a `<relation>.facts` file per relation, with a row per tuple of
+/// quoted atoms separated by tabs. The rows start with the point of the
+/// tuple, for the relations that have one.
+crate trait WriteFacts {
+    fn write_facts(&self, intern: &InternerTables, dir: &Path) -> io::Result<()>;
+}
+
+impl WriteFacts for Output {
+    /// Writes the `computed_relations` of the output.
+    fn write_facts(&self, intern: &InternerTables, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for &relation in computed_relations(self) {
+            let file = fs::File::create(dir.join(format!("{}.facts", relation)))?;
+            let mut writer = io::BufWriter::new(file);
+            for row in relation_rows(self, intern, relation) {
+                writeln!(writer, "{}", row.join("\t"))?;
             }
-            None => {
-                let mut stdout = io::stdout();
-                write!(&mut stdout, "# {}\n\n", name)?;
-                Box::new(stdout)
+        }
+        Ok(())
+    }
+}
+
+/// An output that shows its atoms by the names they have in the facts,
+/// like `'_#3r` and `bb5[2]`, rather than by their indices: shown with
+/// `Display`, it has a `# <relation>` section per relation, with a row of
+/// names per tuple, and serialized, it is a map from each relation to its
+/// rows. Either way, it only has the relations that the output computed,
+/// as `write_facts` writes them.
+crate struct NamedOutput<'a> {
+    output: &'a Output,
+    intern: &'a InternerTables,
+}
+
+crate trait WithTables {
+    fn with_tables(&'a self, intern: &'a InternerTables) -> NamedOutput<'a>;
+}
+
+impl WithTables for Output {
+    fn with_tables(&'a self, intern: &'a InternerTables) -> NamedOutput<'a> {
+        NamedOutput {
+            output: self,
+            intern,
+        }
+    }
+}
+
+impl NamedOutput<'a> {
+    /// The names of the point and loan of each error, sorted by index.
+    crate fn errors(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        let intern = self.intern;
+        self.output
+            .iter_errors()
+            .map(move |(point, loan)| (intern.unquoted(point), intern.unquoted(loan)))
+    }
+
+    /// The rows of each relation that the output computed.
+    crate fn relations(&self) -> Vec<(&'static str, Vec<Vec<&'a str>>)> {
+        computed_relations(self.output)
+            .iter()
+            .map(|&relation| {
+                let rows = relation_rows(self.output, self.intern, relation)
+                    .into_iter()
+                    .map(|row| row.into_iter().map(unquoted).collect())
+                    .collect();
+                (relation, rows)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for NamedOutput<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (relation, rows) in self.relations() {
+            writeln!(f, "# {}\n", relation)?;
+            for row in rows {
+                writeln!(f, "{}", row.join("\t"))?;
             }
-        })
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for NamedOutput<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let relations = self.relations();
+        let mut map = serializer.serialize_map(Some(relations.len()))?;
+        for (relation, rows) in relations {
+            map.serialize_entry(relation, &rows)?;
+        }
+        map.end()
+    }
+}
+
+/// The relations that `output` computed: all of `RELATIONS` if it has
+/// `dump_enabled`, and otherwise only those that every analysis computes.
+fn computed_relations(output: &Output) -> &'static [&'static str] {
+    if output.dump_enabled {
+        &RELATIONS
+    } else {
+        &[
+            "borrow_live_at",
+            "errors",
+            "move_errors",
+            "var_maybe_uninitialized_on_entry",
+            "subset_errors",
+        ]
+    }
+}
+
+/// The rows of the relation of `output` named `relation`, as the names of
+/// their atoms.
+fn relation_rows(
+    output: &'a Output,
+    intern: &'a InternerTables,
+    relation: &str,
+) -> Vec<Vec<&'a str>> {
+    match relation {
+        "borrow_live_at" => rows(intern, &*output.borrow_live_at()),
+        "restricts" | "requires" => rows(intern, &output.restricts),
+        "restricts_anywhere" => rows(intern, &output.restricts_anywhere),
+        "region_live_at" => rows(intern, &output.region_live_at),
+        "invalidates" => rows(intern, &output.invalidates),
+        "errors" => rows(intern, &output.errors),
+        "subset" => rows(intern, &output.subset),
+        "subset_anywhere" => rows(intern, &output.subset_anywhere),
+        "move_errors" => rows(intern, &output.move_errors),
+        "var_maybe_uninitialized_on_entry" => {
+            rows(intern, &output.var_maybe_uninitialized_on_entry)
+        }
+        "subset_errors" => rows(intern, &output.subset_errors),
+        _ => unreachable!(),
+    }
+}
+
+fn rows(intern: &'a InternerTables, value: &impl OutputDump) -> Vec<Vec<&'a str>> {
+    let mut rows = Vec::new();
+    OutputDump::push_all(value, intern, &mut vec![], &mut rows);
+    rows
+}
+
+fn writer_for(out_dir: &Option<PathBuf>, name: &str) -> io::Result<Box<Write>> {
+    // create a writer for the provided output.
+    // If we have an output directory use that, otherwise just dump to stdout
+    Ok(match out_dir {
+        Some(dir) => {
+            fs::create_dir_all(&dir)?;
+            let mut of = dir.join(name);
+            of.set_extension("facts");
+            Box::new(fs::File::create(of)?)
+        }
+        None => {
+            let mut stdout = io::stdout();
+            write!(&mut stdout, "# {}\n\n", name)?;
+            Box::new(stdout)
+        }
+    })
+}
+
+crate fn dump_error_derivations(output: &Output, intern: &InternerTables) {
+    println!("# why\n");
+    for (point, loan) in output.iter_errors() {
+        match output.why(point, loan) {
+            Some(derivation) => dump_derivation(&derivation, intern),
+            None => println!(
+                "errors({}, {})  [not reported by the naive analysis]",
+                intern.loans.untern(loan),
+                intern.points.untern(point)
+            ),
+        }
+        println!();
+    }
+}
+
+/// Prints `derivation` as an indented tree: each tuple, followed by the
+/// rule that derived it and then its premises, one level deeper. A derived
+/// tuple whose derivation was already printed is marked `[as above]`
+/// instead, as the shared derivations would repeat exponentially.
+crate fn dump_derivation(derivation: &Derivation<Region, Loan, Point>, intern: &InternerTables) {
+    dump_derivation_at(derivation, intern, 0, &mut FxHashSet::default());
+}
+
+fn dump_derivation_at(
+    derivation: &Derivation<Region, Loan, Point>,
+    intern: &InternerTables,
+    depth: usize,
+    printed: &mut FxHashSet<Tuple<Region, Loan, Point>>,
+) {
+    let tuple = tuple_to_string(derivation.tuple, intern);
+    match derivation.rule {
+        Some(_) if !printed.insert(derivation.tuple) => {
+            println!("{:indent$}{}  [as above]", "", tuple, indent = depth * 2);
+            return;
+        }
+        Some(rule) => println!("{:indent$}{}  [{}]", "", tuple, rule, indent = depth * 2),
+        None => println!("{:indent$}{}", "", tuple, indent = depth * 2),
+    }
+    for premise in &derivation.premises {
+        dump_derivation_at(premise, intern, depth + 1, printed);
+    }
+}
+
+fn tuple_to_string(tuple: Tuple<Region, Loan, Point>, intern: &InternerTables) -> String {
+    let r = |region: Region| intern.regions.untern(region);
+    let l = |loan: Loan| intern.loans.untern(loan);
+    let p = |point: Point| intern.points.untern(point);
+    match tuple {
+        Tuple::BorrowRegion(r1, l1, p1) => format!("borrow_region({}, {}, {})", r(r1), l(l1), p(p1)),
+        Tuple::CfgEdge(p1, p2) => format!("cfg_edge({}, {})", p(p1), p(p2)),
+        Tuple::Outlives(r1, r2, p1) => format!("outlives({}, {}, {})", r(r1), r(r2), p(p1)),
+        Tuple::RegionLiveAt(r1, p1) => format!("region_live_at({}, {})", r(r1), p(p1)),
+        Tuple::Invalidates(p1, l1) => format!("invalidates({}, {})", p(p1), l(l1)),
+        Tuple::NotKilled(l1, p1) => format!("!killed({}, {})", l(l1), p(p1)),
+        Tuple::Subset(r1, r2, p1) => format!("subset({}, {}, {})", r(r1), r(r2), p(p1)),
+        Tuple::Requires(r1, l1, p1) => format!("requires({}, {}, {})", r(r1), l(l1), p(p1)),
+        Tuple::BorrowLiveAt(l1, p1) => format!("borrow_live_at({}, {})", l(l1), p(p1)),
+        Tuple::Error(l1, p1) => format!("errors({}, {})", l(l1), p(p1)),
     }
 }
 
 trait OutputDump {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
     );
 }
 
-fn dump_rows(
-    stream: &mut Write,
-    intern: &InternerTables,
-    value: &impl OutputDump,
-) -> io::Result<()> {
-    let mut rows = Vec::new();
-    OutputDump::push_all(value, intern, &mut vec![], &mut rows);
+fn dump_rows(stream: &mut Write, rows: &[Vec<&str>]) -> io::Result<()> {
     let col_width: usize = rows
         .iter()
         .map(|cols| cols.iter().map(|s| s.len()).max().unwrap_or(0))
         .max()
         .unwrap_or(0);
-    for row in &rows {
+    for row in rows {
         let mut string = String::new();
 
         let (last, not_last) = row.split_last().unwrap();
@@ -126,7 +342,7 @@ where
     V: OutputDump,
 {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
@@ -151,7 +367,7 @@ where
     V: OutputDump,
 {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
@@ -175,7 +391,7 @@ where
     K: OutputDump,
 {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
@@ -188,15 +404,17 @@ where
 
 impl<V> OutputDump for Vec<V>
 where
-    V: OutputDump,
+    V: OutputDump + Ord,
 {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
     ) {
-        for value in self {
+        let mut values: Vec<_> = self.iter().collect();
+        values.sort();
+        for value in values {
             value.push_all(intern, prefix, output);
         }
     }
@@ -204,7 +422,7 @@ where
 
 impl<T: Atom> OutputDump for T {
     fn push_all(
-        &'a self,
+        &self,
         intern: &'a InternerTables,
         prefix: &mut Vec<&'a str>,
         output: &mut Vec<Vec<&'a str>>,
@@ -218,6 +436,24 @@ impl<T: Atom> OutputDump for T {
     }
 }
 
+impl<A: Atom, B: Atom> OutputDump for (A, B) {
+    fn push_all(
+        &self,
+        intern: &'a InternerTables,
+        prefix: &mut Vec<&'a str>,
+        output: &mut Vec<Vec<&'a str>>,
+    ) {
+        let (a, b) = *self;
+        let a = A::table(intern).untern(a);
+        let b = B::table(intern).untern(b);
+        preserve(prefix, |prefix| {
+            prefix.push(a);
+            prefix.push(b);
+            output.push(prefix.clone());
+        });
+    }
+}
+
 fn preserve<'a>(s: &mut Vec<&'a str>, op: impl FnOnce(&mut Vec<&'a str>)) {
     let len = s.len();
     op(s);
@@ -245,3 +481,15 @@ impl Atom for Loan {
         &intern.loans
     }
 }
+
+impl Atom for Variable {
+    fn table(intern: &InternerTables) -> &Interner<Self> {
+        &intern.variables
+    }
+}
+
+impl Atom for MovePath {
+    fn table(intern: &InternerTables) -> &Interner<Self> {
+        &intern.move_paths
+    }
+}
@@ -0,0 +1,97 @@
+//! The `explain` command: the derivation of an error, retold as the steps
+//! that bring the loan from where it is issued to where it is invalidated.
+
+use crate::facts::{Loan, Point, Region};
+use crate::intern::InternerTables;
+use polonius_engine::{Derivation, OriginStep, Tuple};
+
+/// The explanation of `derivation`, the derivation of an error, one line
+/// per step. `None` if it isn't the derivation of an error.
+crate fn explain_error(
+    derivation: &Derivation<Region, Loan, Point>,
+    tables: &InternerTables,
+) -> Option<Vec<String>> {
+    let (loan, point) = match derivation.tuple {
+        Tuple::Error(loan, point) => (loan, point),
+        _ => return None,
+    };
+    let borrow_live_at = derivation
+        .premises
+        .iter()
+        .find(|premise| match premise.tuple {
+            Tuple::BorrowLiveAt(..) => true,
+            _ => false,
+        })?;
+    let requires = borrow_live_at.premises.first()?;
+    let live_region = match requires.tuple {
+        Tuple::Requires(region, _, _) => region,
+        _ => return None,
+    };
+
+    let region = |region: Region| tables.unquoted(region);
+    let point_name = |point: Point| tables.unquoted(point);
+    let loan_name = tables.unquoted(loan);
+
+    let mut lines = vec![format!(
+        "`{}` is invalidated at `{}` while it is in scope there:",
+        loan_name,
+        point_name(point)
+    )];
+    let mut descriptions = Vec::new();
+    for step in requires.origin_chain()? {
+        descriptions.push(match step {
+            OriginStep::Issued { region: r, point: p } => format!(
+                "`{}` is issued at `{}`, into `{}`.",
+                loan_name,
+                point_name(p),
+                region(r)
+            ),
+            OriginStep::Subset {
+                from,
+                to,
+                point: p,
+                because,
+            } => {
+                let because: Vec<String> = because
+                    .into_iter()
+                    .map(|(r1, r2, q)| {
+                        format!("`{}: {}` at `{}`", region(r1), region(r2), point_name(q))
+                    })
+                    .collect();
+                format!(
+                    "At `{}`, `{}` flows into `{}`, as the outlives facts {} require.",
+                    point_name(p),
+                    region(from),
+                    region(to),
+                    because.join(", ")
+                )
+            }
+            OriginStep::Flow {
+                region: r,
+                from,
+                to,
+                steps,
+            } => format!(
+                "It stays in `{}` along the CFG, from `{}` to `{}` in {} step{}: `{}` is live \
+                 there, and the loan isn't killed.",
+                region(r),
+                point_name(from),
+                point_name(to),
+                steps,
+                if steps == 1 { "" } else { "s" },
+                region(r)
+            ),
+        });
+    }
+    descriptions.push(format!(
+        "`{}` is live at `{}`, so the loan is in scope there.",
+        region(live_region),
+        point_name(point)
+    ));
+    descriptions.push(format!("`{}` invalidates `{}`.", point_name(point), loan_name));
+
+    for (index, description) in descriptions.into_iter().enumerate() {
+        lines.push(format!("{:4}. {}", index + 1, description));
+    }
+    Some(lines)
+}
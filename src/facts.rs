@@ -1,36 +1,9 @@
 use polonius_engine;
 
-crate type AllFacts = polonius_engine::AllFacts<Region, Loan, Point>;
+pub use polonius_engine::{Loan, MovePath, Point, Region, Variable};
 
-macro_rules! index_type {
-    ($t:ident) => {
-        #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Debug, Hash)]
-        pub(crate) struct $t {
-            index: u32,
-        }
+pub type AllFacts = polonius_engine::AllFacts<Region, Loan, Point, Variable, MovePath>;
 
-        impl From<usize> for $t {
-            fn from(index: usize) -> $t {
-                $t {
-                    index: index as u32,
-                }
-            }
-        }
+pub type Output = polonius_engine::Output<Region, Loan, Point, Variable, MovePath>;
 
-        impl Into<usize> for $t {
-            fn into(self) -> usize {
-                self.index as usize
-            }
-        }
-
-        impl polonius_engine::Atom for $t {
-            fn index(self) -> usize {
-                self.into()
-            }
-        }
-    };
-}
-
-index_type!(Region);
-index_type!(Loan);
-index_type!(Point);
+crate type QueryEngine = polonius_engine::QueryEngine<Region, Loan, Point, Variable, MovePath>;
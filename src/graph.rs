@@ -0,0 +1,122 @@
+use crate::facts::{AllFacts, Loan, Point, Region};
+use crate::intern::InternerTables;
+use rustc_hash::FxHashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+/// The number of facts of a few relations that mention a point.
+#[derive(Default)]
+struct PointCounts {
+    borrows: usize,
+    kills: usize,
+    live_regions: usize,
+}
+
+/// Writes the CFG of `all_facts` as a Graphviz `digraph`, with one node per
+/// point, labeled with the name of the point and the number of loans it
+/// issues and kills and of regions live there. The points in `errors` are
+/// drawn in red, and list the loans that are invalidated there while live.
+crate fn write_cfg_dot(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    errors: &FxHashMap<Point, Vec<Loan>>,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let points: BTreeSet<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .flat_map(|&(p, q)| vec![p, q])
+        .collect();
+
+    let mut counts: FxHashMap<Point, PointCounts> = FxHashMap::default();
+    for &(_, _, p) in &all_facts.borrow_region {
+        counts.entry(p).or_insert_with(PointCounts::default).borrows += 1;
+    }
+    for &(_, p) in &all_facts.killed {
+        counts.entry(p).or_insert_with(PointCounts::default).kills += 1;
+    }
+    for &(_, p) in &all_facts.region_live_at {
+        counts.entry(p).or_insert_with(PointCounts::default).live_regions += 1;
+    }
+
+    writeln!(writer, "digraph cfg {{")?;
+    writeln!(writer, "    node [shape = box];")?;
+    let no_counts = PointCounts::default();
+    for &point in &points {
+        let point_counts = counts.get(&point).unwrap_or(&no_counts);
+        let mut label = format!(
+            "{}\\nborrows: {}, kills: {}, live regions: {}",
+            escaped(tables.unquoted(point)),
+            point_counts.borrows,
+            point_counts.kills,
+            point_counts.live_regions
+        );
+        let mut attributes = String::new();
+        if let Some(loans) = errors.get(&point) {
+            let loans: Vec<&str> = loans
+                .iter()
+                .map(|&loan| tables.unquoted(loan))
+                .collect();
+            label.push_str(&format!("\\nerrors: {}", escaped(&loans.join(", "))));
+            attributes.push_str(", color = red");
+        }
+        writeln!(
+            writer,
+            "    {} [label = \"{}\"{}];",
+            node(tables, point),
+            label,
+            attributes
+        )?;
+    }
+    for &(p, q) in &all_facts.cfg_edge {
+        writeln!(writer, "    {} -> {};", node(tables, p), node(tables, q))?;
+    }
+    writeln!(writer, "}}")
+}
+
+/// Writes the subset relation at `point` as a Graphviz `digraph`, with an
+/// edge `R1 -> R2` for each region `R1` that is a subset of `R2` there.
+/// The edges of the `outlives` facts at `point` are solid, the others,
+/// which the analysis derives, are dashed.
+crate fn write_subsets_dot(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    point: Point,
+    subsets: &BTreeMap<Region, BTreeSet<Region>>,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let outlives: BTreeSet<(Region, Region)> = all_facts
+        .outlives
+        .iter()
+        .filter(|&&(_, _, p)| p == point)
+        .map(|&(r1, r2, _)| (r1, r2))
+        .collect();
+    let region = |r: Region| format!("\"{}\"", escaped(tables.unquoted(r)));
+
+    writeln!(writer, "digraph subsets {{")?;
+    writeln!(
+        writer,
+        "    label = \"{}\";",
+        escaped(tables.unquoted(point))
+    )?;
+    for (&r1, regions) in subsets {
+        for &r2 in regions {
+            if outlives.contains(&(r1, r2)) {
+                writeln!(writer, "    {} -> {};", region(r1), region(r2))?;
+            } else {
+                writeln!(writer, "    {} -> {} [style = dashed];", region(r1), region(r2))?;
+            }
+        }
+    }
+    writeln!(writer, "}}")
+}
+
+/// The Graphviz ID of the node of `point`.
+fn node(tables: &InternerTables, point: Point) -> String {
+    format!("\"{}\"", escaped(tables.unquoted(point)))
+}
+
+/// The names in fact files are quoted, which Graphviz IDs have to be too.
+fn escaped(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,231 @@
+//! The static report of `analyze --emit-html`: an index of the analyzed
+//! functions, and a page for each, with its errors, the sizes of its
+//! relations, its CFG and, for each point, the facts that mention it.
+//!
+//! The CFG is drawn by Graphviz's `dot`, if it is installed; otherwise the
+//! page shows the Graphviz source instead.
+
+use crate::facts::{AllFacts, Loan, Output, Point, Region};
+use crate::graph;
+use crate::intern::InternerTables;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+crate const INDEX_FILE_NAME: &str = "index.html";
+
+/// The line of the index about one facts directory.
+crate struct IndexEntry {
+    crate directory: String,
+
+    /// The file name of the page of the directory, and the number of its
+    /// errors, or else why it couldn't be analyzed.
+    crate result: Result<(String, usize), String>,
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+.error { color: #c00; }
+:target { background: #ffc; }";
+
+/// Writes the page of the analysis of `directory` to `path`.
+crate fn write_function_page(
+    path: &Path,
+    directory: &str,
+    all_facts: &AllFacts,
+    output: &Output,
+    tables: &InternerTables,
+    relation_sizes: &[(&'static str, usize)],
+) -> io::Result<()> {
+    let mut html = Vec::new();
+    write_header(&mut html, directory)?;
+    writeln!(html, "<p><a href=\"{}\">All functions</a></p>", INDEX_FILE_NAME)?;
+
+    writeln!(html, "<h2>Errors</h2>")?;
+    if output.error_count() == 0 {
+        writeln!(html, "<p>No errors.</p>")?;
+    } else {
+        writeln!(html, "<ul>")?;
+        let errors: BTreeMap<&str, Point> = output
+            .errors
+            .keys()
+            .map(|&point| (tables.unquoted(point), point))
+            .collect();
+        for (_, point) in errors {
+            for &loan in output.errors_at(point) {
+                writeln!(
+                    html,
+                    "<li class=\"error\"><code>{}</code> is invalidated at {} while live</li>",
+                    escaped(tables.unquoted(loan)),
+                    point_link(tables, point)
+                )?;
+            }
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "<h2>Relations</h2>")?;
+    writeln!(html, "<table>\n<tr><th>relation</th><th>tuples</th></tr>")?;
+    for &(relation, size) in relation_sizes {
+        writeln!(html, "<tr><td>{}</td><td>{}</td></tr>", relation, size)?;
+    }
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>CFG</h2>")?;
+    let mut dot = Vec::new();
+    graph::write_cfg_dot(all_facts, tables, &output.errors, &mut dot)?;
+    match svg_of_dot(&dot) {
+        Some(svg) => html.extend(svg),
+        None => {
+            let dot = String::from_utf8_lossy(&dot);
+            writeln!(html, "<p>Install Graphviz to draw the CFG, written here in its format:</p>")?;
+            writeln!(html, "<pre>{}</pre>", escaped(&dot))?;
+        }
+    }
+
+    writeln!(html, "<h2>Points</h2>")?;
+    let mut facts = point_facts(all_facts, tables);
+    let points: BTreeMap<&str, Point> = facts
+        .keys()
+        .map(|&point| (tables.unquoted(point), point))
+        .collect();
+    for (name, point) in points {
+        writeln!(
+            html,
+            "<h3 id=\"{}\"><code>{}</code></h3>",
+            anchor(tables, point),
+            escaped(name)
+        )?;
+        writeln!(html, "<ul>")?;
+        for fact in facts.remove(&point).unwrap_or_default() {
+            writeln!(html, "<li>{}</li>", fact)?;
+        }
+        writeln!(html, "</ul>")?;
+    }
+
+    writeln!(html, "</body>\n</html>")?;
+    fs::write(path, html)
+}
+
+/// Writes the index of the pages of `entries` into `dir`.
+crate fn write_index(dir: &Path, entries: &[IndexEntry]) -> io::Result<()> {
+    let mut html = Vec::new();
+    write_header(&mut html, "Polonius report")?;
+    writeln!(html, "<table>\n<tr><th>function</th><th>errors</th></tr>")?;
+    for entry in entries {
+        match entry.result {
+            Ok((ref page, errors)) => {
+                let class = if errors > 0 { " class=\"error\"" } else { "" };
+                writeln!(
+                    html,
+                    "<tr><td><a href=\"{}\">{}</a></td><td{}>{}</td></tr>",
+                    page,
+                    escaped(&entry.directory),
+                    class,
+                    errors
+                )?;
+            }
+            Err(ref failure) => {
+                writeln!(
+                    html,
+                    "<tr><td>{}</td><td class=\"error\">failed: {}</td></tr>",
+                    escaped(&entry.directory),
+                    escaped(failure)
+                )?;
+            }
+        }
+    }
+    writeln!(html, "</table>\n</body>\n</html>")?;
+    fs::write(dir.join(INDEX_FILE_NAME), html)
+}
+
+fn write_header(html: &mut Vec<u8>, title: &str) -> io::Result<()> {
+    writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>", escaped(title))?;
+    writeln!(html, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+    writeln!(html, "<h1>{}</h1>", escaped(title))
+}
+
+/// The facts that mention each point, as HTML, linking to the headings of the
+/// other points that they mention.
+fn point_facts(all_facts: &AllFacts, tables: &InternerTables) -> BTreeMap<Point, Vec<String>> {
+    let region = |r: Region| format!("<code>{}</code>", escaped(tables.unquoted(r)));
+    let loan = |l: Loan| format!("<code>{}</code>", escaped(tables.unquoted(l)));
+
+    let mut facts: BTreeMap<Point, Vec<String>> = BTreeMap::new();
+    {
+        let mut add = |point, fact| facts.entry(point).or_insert_with(Vec::new).push(fact);
+        for &(p, q) in &all_facts.cfg_edge {
+            add(p, format!("cfg_edge to {}", point_link(tables, q)));
+            add(q, format!("cfg_edge from {}", point_link(tables, p)));
+        }
+        for &(r, l, p) in &all_facts.borrow_region {
+            add(p, format!("borrow_region({}, {})", region(r), loan(l)));
+        }
+        for &(l, p) in &all_facts.killed {
+            add(p, format!("killed({})", loan(l)));
+        }
+        for &(r1, r2, p) in &all_facts.outlives {
+            add(p, format!("outlives({}, {})", region(r1), region(r2)));
+        }
+        for &(r, p) in &all_facts.region_live_at {
+            add(p, format!("region_live_at({})", region(r)));
+        }
+        for &(p, l) in &all_facts.invalidates {
+            add(p, format!("invalidates({})", loan(l)));
+        }
+    }
+    facts
+}
+
+/// The SVG that `dot` draws of the graph `dot`, if it is installed.
+fn svg_of_dot(dot: &[u8]) -> Option<Vec<u8>> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(dot).ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // The XML prologue can't be inside HTML.
+    let svg = String::from_utf8(output.stdout).ok()?;
+    let start = svg.find("<svg")?;
+    Some(svg[start..].as_bytes().to_vec())
+}
+
+fn point_link(tables: &InternerTables, point: Point) -> String {
+    format!(
+        "<a href=\"#{}\"><code>{}</code></a>",
+        anchor(tables, point),
+        escaped(tables.unquoted(point))
+    )
+}
+
+/// The ID of the heading of `point`, which only uses the characters that
+/// are safe in a URL fragment.
+fn anchor(tables: &InternerTables, point: Point) -> String {
+    let name: String = tables
+        .unquoted(point)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let index: usize = point.into();
+    format!("point-{}-{}", index, name)
+}
+
+fn escaped(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
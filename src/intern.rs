@@ -1,11 +1,32 @@
+use dump::Atom;
 use facts::*;
-use std::collections::HashMap;
+use polonius_engine;
+use rustc_hash::FxHashMap;
+use std::cmp;
+
+/// The average length, in bytes, of a row of `cfg_edge`, which has about
+/// one row per point, and of `borrow_region`, which has one per loan. They
+/// are used to guess the number of atoms from the sizes of the files.
+const CFG_EDGE_ROW_BYTES: u64 = 32;
+const BORROW_REGION_ROW_BYTES: u64 = 32;
+
+/// The size of the chunks of an `Arena`, in bytes.
+const ARENA_CHUNK_BYTES: usize = 64 * 1024;
 
 /// When we load facts out of the table, they are essentially random
 /// strings. We create an intern table to map those to small integers.
-crate struct Interner<TargetType: From<usize> + Copy> {
-    strings: HashMap<String, TargetType>,
-    rev_strings: Vec<String>,
+///
+/// Every column of every row of the facts is interned, which makes the
+/// hashing of the names most of the time of loading facts: they are hashed
+/// with `FxHash`, which is much faster than `SipHash` on short strings.
+/// The strings themselves are copied once into an arena, which both the
+/// map and the strings by index borrow.
+pub struct Interner<TargetType: From<usize> + Copy> {
+    // The strings borrow `arena`, which outlives them: they are only ever
+    // handed out with the lifetime of the interner.
+    strings: FxHashMap<&'static str, TargetType>,
+    rev_strings: Vec<&'static str>,
+    arena: Arena,
 }
 
 impl<TargetType> Interner<TargetType>
@@ -14,14 +35,43 @@ where
 {
     fn new() -> Self {
         Self {
-            strings: HashMap::new(),
+            strings: FxHashMap::default(),
             rev_strings: vec![],
+            arena: Arena { chunks: vec![] },
         }
     }
 
-    crate fn untern(&self, data: TargetType) -> &str {
+    pub fn len(&self) -> usize {
+        self.rev_strings.len()
+    }
+
+    /// Reserves room for at least `additional` more strings.
+    pub fn reserve(&mut self, additional: usize) {
+        self.strings.reserve(additional);
+        self.rev_strings.reserve(additional);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rev_strings.is_empty()
+    }
+
+    /// All interned strings, in the order of the indices they were given.
+    pub fn strings(&self) -> &[&str] {
+        &self.rev_strings
+    }
+
+    /// Looks up an already-interned string. Since the names in fact files
+    /// are quoted, `name` also matches if it is only missing the quotes.
+    pub fn lookup(&self, name: &str) -> Option<TargetType> {
+        self.strings
+            .get(name)
+            .or_else(|| self.strings.get(format!("\"{}\"", name).as_str()))
+            .cloned()
+    }
+
+    pub fn untern(&self, data: TargetType) -> &str {
         let data: usize = data.into();
-        &self.rev_strings[data]
+        self.rev_strings[data]
     }
 
     crate fn intern(&mut self, data: &str) -> TargetType {
@@ -30,25 +80,110 @@ where
         }
 
         let index = TargetType::from(self.strings.len());
-        self.rev_strings.push(data.to_string());
-        *self.strings.entry(data.to_string()).or_insert(index)
+        let data = self.arena.alloc(data);
+        self.rev_strings.push(data);
+        self.strings.insert(data, index);
+        index
+    }
+}
+
+/// The strings of an interner, copied into chunks that are never
+/// reallocated, so that they can be borrowed for as long as the arena
+/// lives. Unlike a `String` per string, this makes an allocation per
+/// `ARENA_CHUNK_BYTES`.
+struct Arena {
+    chunks: Vec<String>,
+}
+
+impl Arena {
+    /// Copies `data` into the arena. The string only lives as long as the
+    /// arena, despite its lifetime.
+    fn alloc(&mut self, data: &str) -> &'static str {
+        let fits = self
+            .chunks
+            .last()
+            .map_or(false, |chunk| chunk.capacity() - chunk.len() >= data.len());
+        if !fits {
+            let capacity = cmp::max(ARENA_CHUNK_BYTES, data.len());
+            self.chunks.push(String::with_capacity(capacity));
+        }
+
+        let chunk = self.chunks.last_mut().unwrap();
+        let start = chunk.len();
+        chunk.push_str(data);
+        // The chunk had room for `data`, so pushing it didn't move the
+        // bytes of the chunk, and they won't move until it is dropped.
+        unsafe { &*(&chunk[start..] as *const str) }
     }
 }
 
-crate struct InternerTables {
-    crate regions: Interner<Region>,
-    crate loans: Interner<Loan>,
-    crate points: Interner<Point>,
+pub struct InternerTables {
+    pub regions: Interner<Region>,
+    pub loans: Interner<Loan>,
+    pub points: Interner<Point>,
+    pub variables: Interner<Variable>,
+    pub move_paths: Interner<MovePath>,
 }
 
 impl InternerTables {
-    crate fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             regions: Interner::new(),
             loans: Interner::new(),
             points: Interner::new(),
+            variables: Interner::new(),
+            move_paths: Interner::new(),
         }
     }
+
+    /// Reserves room for the points and loans of a dump whose `cfg_edge`
+    /// and `borrow_region` files are that many bytes long, so that the
+    /// tables of the biggest functions aren't rehashed as they grow.
+    pub fn reserve_for_file_sizes(&mut self, cfg_edge_bytes: u64, borrow_region_bytes: u64) {
+        self.points.reserve((cfg_edge_bytes / CFG_EDGE_ROW_BYTES) as usize);
+        self.loans.reserve((borrow_region_bytes / BORROW_REGION_ROW_BYTES) as usize);
+    }
+
+    /// The name of `atom`, without the quotes of the `.facts` files.
+    crate fn unquoted<A: Atom>(&self, atom: A) -> &str {
+        polonius_engine::unquoted(A::table(self).untern(atom))
+    }
+
+    /// Interns the names of `other` into these tables, in the order `other`
+    /// interned them, and returns the atom here of each atom of `other`.
+    /// Each name is hashed once, rather than once per row it is in.
+    crate fn merge(&mut self, other: &InternerTables) -> Remapping {
+        fn merge_interner<T>(to: &mut Interner<T>, from: &Interner<T>) -> Vec<T>
+        where
+            T: From<usize> + Into<usize> + Copy,
+        {
+            from.strings().iter().map(|name| to.intern(name)).collect()
+        }
+
+        Remapping {
+            regions: merge_interner(&mut self.regions, &other.regions),
+            loans: merge_interner(&mut self.loans, &other.loans),
+            points: merge_interner(&mut self.points, &other.points),
+            variables: merge_interner(&mut self.variables, &other.variables),
+            move_paths: merge_interner(&mut self.move_paths, &other.move_paths),
+        }
+    }
+}
+
+/// Where the atoms of some tables are in the tables they were merged into,
+/// by their index in the former: see `InternerTables::merge`.
+crate struct Remapping {
+    regions: Vec<Region>,
+    loans: Vec<Loan>,
+    points: Vec<Point>,
+    variables: Vec<Variable>,
+    move_paths: Vec<MovePath>,
+}
+
+impl Default for InternerTables {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 crate trait InternTo<To> {
@@ -68,6 +203,147 @@ macro_rules! intern_impl {
 intern_impl!(Region, regions);
 intern_impl!(Loan, loans);
 intern_impl!(Point, points);
+intern_impl!(Variable, variables);
+intern_impl!(MovePath, move_paths);
+
+/// Moves an atom from one set of tables to another, by interning its
+/// name (with `prefix` prepended) in the target tables.
+crate trait Reintern: Sized {
+    fn reintern(self, from: &InternerTables, to: &mut InternerTables, prefix: &str) -> Self;
+}
+
+macro_rules! reintern_impl {
+    ($t:ident, $field:ident) => {
+        impl Reintern for $t {
+            fn reintern(self, from: &InternerTables, to: &mut InternerTables, prefix: &str) -> $t {
+                let name = from.$field.untern(self);
+                if prefix.is_empty() {
+                    to.$field.intern(name)
+                } else {
+                    to.$field.intern(&format!("{}{}", prefix, name))
+                }
+            }
+        }
+    };
+}
+
+reintern_impl!(Region, regions);
+reintern_impl!(Loan, loans);
+reintern_impl!(Point, points);
+reintern_impl!(Variable, variables);
+reintern_impl!(MovePath, move_paths);
+
+impl<A: Reintern, B: Reintern> Reintern for (A, B) {
+    fn reintern(self, from: &InternerTables, to: &mut InternerTables, prefix: &str) -> Self {
+        let (a, b) = self;
+        (a.reintern(from, to, prefix), b.reintern(from, to, prefix))
+    }
+}
+
+impl<A: Reintern, B: Reintern, C: Reintern> Reintern for (A, B, C) {
+    fn reintern(self, from: &InternerTables, to: &mut InternerTables, prefix: &str) -> Self {
+        let (a, b, c) = self;
+        (
+            a.reintern(from, to, prefix),
+            b.reintern(from, to, prefix),
+            c.reintern(from, to, prefix),
+        )
+    }
+}
+
+/// Re-interns every atom of `all_facts` from the `from` tables into the
+/// `to` tables.
+crate fn reintern_facts(
+    all_facts: AllFacts,
+    from: &InternerTables,
+    to: &mut InternerTables,
+    prefix: &str,
+) -> AllFacts {
+    macro_rules! reintern_facts {
+        (AllFacts($all_facts:expr) { $($t:ident,)* }) => {
+            AllFacts {
+                $(
+                    $t: reintern_rows($all_facts.$t.into_vec(), from, to, prefix).into(),
+                )*
+            }
+        }
+    }
+
+    reintern_facts! {
+        AllFacts(all_facts) {
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        }
+    }
+}
+
+/// Moves an atom from merged tables to the tables they were merged into.
+crate trait Remap: Sized {
+    fn remap(self, remapping: &Remapping) -> Self;
+}
+
+macro_rules! remap_impl {
+    ($t:ident, $field:ident) => {
+        impl Remap for $t {
+            fn remap(self, remapping: &Remapping) -> $t {
+                let index: usize = self.into();
+                remapping.$field[index]
+            }
+        }
+    };
+}
+
+remap_impl!(Region, regions);
+remap_impl!(Loan, loans);
+remap_impl!(Point, points);
+remap_impl!(Variable, variables);
+remap_impl!(MovePath, move_paths);
+
+impl<A: Remap, B: Remap> Remap for (A, B) {
+    fn remap(self, remapping: &Remapping) -> Self {
+        let (a, b) = self;
+        (a.remap(remapping), b.remap(remapping))
+    }
+}
+
+impl<A: Remap, B: Remap, C: Remap> Remap for (A, B, C) {
+    fn remap(self, remapping: &Remapping) -> Self {
+        let (a, b, c) = self;
+        (a.remap(remapping), b.remap(remapping), c.remap(remapping))
+    }
+}
+
+crate fn remap_rows<Row: Remap>(rows: Vec<Row>, remapping: &Remapping) -> Vec<Row> {
+    rows.into_iter().map(|row| row.remap(remapping)).collect()
+}
+
+crate fn reintern_rows<Row: Reintern>(
+    rows: Vec<Row>,
+    from: &InternerTables,
+    to: &mut InternerTables,
+    prefix: &str,
+) -> Vec<Row> {
+    rows.into_iter()
+        .map(|row| row.reintern(from, to, prefix))
+        .collect()
+}
 
 impl<A, FromA, B, FromB> InternTo<(A, B)> for (FromA, FromB)
 where
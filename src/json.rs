@@ -0,0 +1,218 @@
+use crate::dump::WithTables;
+use crate::facts::{AllFacts, Output};
+use crate::intern::InternerTables;
+use crate::tab_delim::FromTabDelimited;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, prelude::*};
+use std::path::Path;
+
+/// The un-interned contents of a JSON facts document: one array per
+/// relation, where each row is either an array of strings or, for unary
+/// relations like `universal_region`, a bare string. Relations missing
+/// from the document are empty.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonFacts {
+    #[serde(default)]
+    borrow_region: Vec<JsonRow>,
+    #[serde(default)]
+    universal_region: Vec<JsonRow>,
+    #[serde(default)]
+    cfg_edge: Vec<JsonRow>,
+    #[serde(default)]
+    killed: Vec<JsonRow>,
+    #[serde(default)]
+    outlives: Vec<JsonRow>,
+    #[serde(default)]
+    region_live_at: Vec<JsonRow>,
+    #[serde(default)]
+    invalidates: Vec<JsonRow>,
+    #[serde(default)]
+    var_used_at: Vec<JsonRow>,
+    #[serde(default)]
+    var_defined_at: Vec<JsonRow>,
+    #[serde(default)]
+    var_drop_used_at: Vec<JsonRow>,
+    #[serde(default)]
+    use_of_var_derefs_origin: Vec<JsonRow>,
+    #[serde(default)]
+    drop_of_var_derefs_origin: Vec<JsonRow>,
+    #[serde(default)]
+    child_path: Vec<JsonRow>,
+    #[serde(default)]
+    path_is_var: Vec<JsonRow>,
+    #[serde(default)]
+    path_assigned_at: Vec<JsonRow>,
+    #[serde(default)]
+    path_moved_at: Vec<JsonRow>,
+    #[serde(default)]
+    path_accessed_at: Vec<JsonRow>,
+    #[serde(default)]
+    known_placeholder_subset: Vec<JsonRow>,
+    #[serde(default)]
+    loan_borrows_path: Vec<JsonRow>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRow {
+    Single(String),
+    Tuple(Vec<String>),
+}
+
+crate fn load_json_facts(tables: &mut InternerTables, path: &Path) -> io::Result<AllFacts> {
+    let file = File::open(path)?;
+    read_json_facts(tables, io::BufReader::new(file))
+}
+
+crate fn read_json_facts(tables: &mut InternerTables, reader: impl Read) -> io::Result<AllFacts> {
+    let json_facts: JsonFacts = serde_json::from_reader(reader)?;
+
+    macro_rules! intern_facts {
+        (from ($tables:expr, $json_facts:expr) intern AllFacts { $($t:ident,)* }) => {
+            Ok(AllFacts {
+                $(
+                    $t: intern_rows($tables, stringify!($t), $json_facts.$t)?.into(),
+                )*
+            })
+        }
+    }
+
+    intern_facts! {
+        from (tables, json_facts) intern AllFacts {
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        }
+    }
+}
+
+fn intern_rows<Row>(
+    tables: &mut InternerTables,
+    relation: &str,
+    rows: Vec<JsonRow>,
+) -> io::Result<Vec<Row>>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    let mut result = Vec::with_capacity(rows.len());
+    for (index, row) in rows.iter().enumerate() {
+        let columns: Vec<&str> = match row {
+            JsonRow::Single(column) => vec![column.as_str()],
+            JsonRow::Tuple(columns) => columns.iter().map(|s| s.as_str()).collect(),
+        };
+
+        let mut columns = columns.into_iter();
+        let row = match FromTabDelimited::parse(tables, &mut columns) {
+            Some(row) => row,
+            None => {
+                return Err(invalid_row(relation, index, "too few columns"));
+            }
+        };
+
+        if columns.next().is_some() {
+            return Err(invalid_row(relation, index, "too many columns"));
+        }
+
+        result.push(row);
+    }
+    Ok(result)
+}
+
+fn invalid_row(relation: &str, index: usize, reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} in row {} of `{}`", reason, index, relation),
+    )
+}
+
+/// The results of analyzing a facts directory, as `--output-format json`
+/// writes them: one per directory, in a single array.
+#[derive(Serialize)]
+crate struct JsonReport {
+    directory: String,
+
+    /// The number of tuples of each relation of the loaded facts.
+    relation_sizes: BTreeMap<&'static str, usize>,
+
+    /// How long the analysis took, unless it failed.
+    time_seconds: Option<f64>,
+    errors: Vec<JsonError>,
+
+    /// Why the directory couldn't be analyzed, if it couldn't.
+    failure: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonError {
+    point: String,
+    loan: String,
+
+    /// What went wrong, as a stable name: `invalidated_while_live` for the
+    /// errors of `Output::errors`.
+    kind: &'static str,
+}
+
+impl JsonReport {
+    crate fn new(directory: &str) -> Self {
+        JsonReport {
+            directory: directory.to_string(),
+            relation_sizes: BTreeMap::new(),
+            time_seconds: None,
+            errors: Vec::new(),
+            failure: None,
+        }
+    }
+
+    crate fn with_relation_sizes(mut self, relation_sizes: &[(&'static str, usize)]) -> Self {
+        self.relation_sizes = relation_sizes.iter().cloned().collect();
+        self
+    }
+
+    crate fn with_output(
+        mut self,
+        time_seconds: f64,
+        output: &Output,
+        tables: &InternerTables,
+    ) -> Self {
+        self.time_seconds = Some(time_seconds);
+        self.errors = output
+            .with_tables(tables)
+            .errors()
+            .map(|(point, loan)| JsonError {
+                point: point.to_string(),
+                loan: loan.to_string(),
+                kind: "invalidated_while_live",
+            })
+            .collect();
+        self
+    }
+
+    crate fn with_failure(mut self, failure: String) -> Self {
+        self.failure = Some(failure);
+        self
+    }
+}
+
+crate fn write_json_reports(reports: &[JsonReport], writer: impl Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(writer, reports)?;
+    Ok(())
+}
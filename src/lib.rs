@@ -6,20 +6,79 @@
 #![feature(termination_trait_test)]
 #![allow(dead_code)]
 
+extern crate bincode;
+extern crate clap;
 extern crate datafrog;
 extern crate failure;
+extern crate flate2;
 extern crate histo;
+extern crate memchr;
+extern crate memmap;
 extern crate polonius_engine;
+#[cfg(test)]
+extern crate quickcheck;
+extern crate regex;
+extern crate rusqlite;
 extern crate rustc_hash;
+extern crate serde;
+extern crate serde_json;
 extern crate structopt;
+extern crate toml;
 
 #[macro_use]
-extern crate clap;
+extern crate serde_derive;
 
+mod agreement;
+mod anonymize;
+mod baseline;
+mod batch;
+mod bench;
+mod cache;
+mod diff;
+mod differential;
 mod dump;
+mod explain;
 mod facts;
+mod graph;
+mod html;
 mod intern;
+mod json;
+mod lsp;
+mod manifest;
+mod memory;
+mod progress;
+mod renumber;
+mod repl;
+mod sarif;
+mod serve;
+mod souffle;
+mod sqlite;
+mod stats;
 mod tab_delim;
 mod test;
+mod watch;
 
+pub mod cargo;
 pub mod cli;
+
+pub use crate::facts::{AllFacts, Loan, MovePath, Output, Point, Region, Variable};
+pub use crate::intern::{Interner, InternerTables};
+pub use polonius_engine::Algorithm;
+
+use failure::Error;
+use std::path::Path;
+
+/// Loads the facts at `path`, a directory of tab-delimited `.facts` files
+/// (some maybe gzip-compressed) or a `.json` document, like the `analyze`
+/// command does.
+/// The tables map the atoms of the facts back to their names.
+pub fn load_facts(path: impl AsRef<Path>) -> Result<(AllFacts, InternerTables), Error> {
+    let mut tables = InternerTables::new();
+    let all_facts = cli::load_facts(&mut tables, path.as_ref(), Default::default())?;
+    Ok((all_facts, tables))
+}
+
+/// Analyzes `all_facts` with `algorithm`.
+pub fn analyze(all_facts: &AllFacts, algorithm: Algorithm) -> Output {
+    Output::compute(all_facts, algorithm, false)
+}
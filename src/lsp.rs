@@ -0,0 +1,472 @@
+//! The `lsp` command: a language server, speaking JSON-RPC over stdin and
+//! stdout, that publishes the errors of fact dumps as diagnostics, so that
+//! editors show them at their source.
+//!
+//! The server watches the facts directories under its roots, and analyzes
+//! again the ones whose facts change. Clients can also send the facts
+//! themselves, in a `polonius/facts` notification whose `facts` are a JSON
+//! facts document, named by `name`, with the `spans` of their points:
+//!
+//! ```json
+//! { "name": "main", "facts": { "cfg_edge": [...] },
+//!   "spans": [{ "point": "Mid(bb0[0])", "file": "src/main.rs", "line": 3, "column": 5 }] }
+//! ```
+//!
+//! The spans of the points of a facts directory are those of its
+//! `point_span.facts`, as for SARIF. The errors at points without a span
+//! can't be shown in a document, and are logged instead.
+
+use crate::cli::{self, LoadConfig};
+use crate::facts::{Output, Point};
+use crate::intern::InternerTables;
+use crate::json;
+use crate::sarif::{self, Span};
+use crate::watch::Snapshot;
+use failure::Error;
+use polonius_engine::Algorithm;
+use rustc_hash::FxHashMap;
+use serde_json::{self, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// How often, in milliseconds, the server checks the facts for changes.
+const WATCH_INTERVAL_MILLIS: u64 = 500;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The diagnostics of some facts, by the URI of their document.
+type Diagnostics = BTreeMap<String, Vec<Diagnostic>>;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Diagnostic {
+    range: Range,
+    severity: u8,
+    source: &'static str,
+    message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Position {
+    line: u64,
+    character: u64,
+}
+
+#[derive(Serialize)]
+struct Notification<P> {
+    jsonrpc: &'static str,
+    method: &'static str,
+    params: P,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    result: Value,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    error: ResponseError,
+}
+
+#[derive(Serialize)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PublishDiagnosticsParams<'a> {
+    uri: &'a str,
+    diagnostics: Vec<&'a Diagnostic>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogMessageParams {
+    #[serde(rename = "type")]
+    message_type: u8,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct FactsParams {
+    name: String,
+    facts: Value,
+    #[serde(default)]
+    spans: Vec<SpanParams>,
+}
+
+#[derive(Deserialize)]
+struct SpanParams {
+    point: String,
+    file: String,
+    line: u64,
+    column: u64,
+}
+
+crate struct Server<W: Write> {
+    roots: Vec<String>,
+    algorithm: Algorithm,
+    load_config: LoadConfig,
+    writer: W,
+
+    /// Whether the client sent `initialized`, after which the server
+    /// watches the roots.
+    initialized: bool,
+    snapshot: Snapshot,
+
+    /// The diagnostics last published for each facts directory, or each
+    /// name of the facts of `polonius/facts`.
+    published: BTreeMap<String, Diagnostics>,
+}
+
+/// Serves the errors of the facts under `roots` over stdin and stdout,
+/// until the client sends `exit` or closes stdin.
+crate fn run(roots: Vec<String>, algorithm: Algorithm, load_config: LoadConfig) -> io::Result<()> {
+    // Messages are read on their own thread, so that the facts are
+    // checked for changes while the client is quiet. A malformed message
+    // is reported to the client, and the following ones are still read:
+    // only the end of the input, or failing to read it, stops the server.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+        loop {
+            let message = match read_message(&mut stdin) {
+                Ok(Some(message)) => Ok(message),
+                Ok(None) => break,
+                Err(ref error) if error.kind() == io::ErrorKind::InvalidData => {
+                    Err(error.to_string())
+                }
+                Err(_) => break,
+            };
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let stdout = io::stdout();
+    let mut server = Server::new(roots, algorithm, load_config, stdout.lock());
+    let interval = Duration::from_millis(WATCH_INTERVAL_MILLIS);
+    loop {
+        match receiver.recv_timeout(interval) {
+            Ok(Ok(message)) => {
+                if !server.handle(&message)? {
+                    return Ok(());
+                }
+            }
+            Ok(Err(error)) => server.handle_malformed(&error)?,
+            Err(RecvTimeoutError::Timeout) => server.refresh()?,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+impl<W: Write> Server<W> {
+    crate fn new(
+        roots: Vec<String>,
+        algorithm: Algorithm,
+        load_config: LoadConfig,
+        writer: W,
+    ) -> Self {
+        Server {
+            roots,
+            algorithm,
+            load_config,
+            writer,
+            initialized: false,
+            snapshot: Snapshot::default(),
+            published: BTreeMap::new(),
+        }
+    }
+
+    /// Handles a message from the client. Returns false once the client
+    /// asked the server to exit.
+    crate fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or("");
+        let id = message.get("id").cloned();
+        match method {
+            "initialize" => {
+                let result = serde_json::from_str(r#"{"capabilities":{}}"#)?;
+                self.respond(id, result)?;
+            }
+            "initialized" => {
+                self.initialized = true;
+                self.refresh()?;
+            }
+            "shutdown" => self.respond(id, Value::Null)?,
+            "exit" => return Ok(false),
+            "polonius/facts" => match serde_json::from_value(message["params"].clone()) {
+                Ok(params) => self.analyze_params(params)?,
+                Err(error) => self.log(&format!("invalid `polonius/facts`: {}", error))?,
+            },
+            _ => {
+                // Notifications the server doesn't know are ignored.
+                if let Some(id) = id {
+                    let message = format!("unknown method `{}`", method);
+                    self.respond_error(id, METHOD_NOT_FOUND, message)?;
+                }
+            }
+        }
+        self.writer.flush()?;
+        Ok(true)
+    }
+
+    /// Tells the client that a message it sent isn't JSON, or isn't framed
+    /// by a `Content-Length` header.
+    crate fn handle_malformed(&mut self, error: &str) -> io::Result<()> {
+        let message = format!("malformed message: {}", error);
+        self.respond_error(Value::Null, PARSE_ERROR, message)?;
+        self.writer.flush()
+    }
+
+    /// Analyzes the facts directories under the roots that changed since
+    /// the last refresh, and clears the diagnostics of those that are gone.
+    crate fn refresh(&mut self) -> io::Result<()> {
+        if !self.initialized {
+            return Ok(());
+        }
+
+        let snapshot = Snapshot::take(&self.roots, None);
+        let (changed, removed) = snapshot.changes_since(&self.snapshot);
+        self.snapshot = snapshot;
+        for facts_dir in removed {
+            self.publish(facts_dir.display().to_string(), Diagnostics::new())?;
+        }
+        for facts_dir in changed {
+            let name = facts_dir.display().to_string();
+            match self.analyze_dir(&facts_dir) {
+                Ok((output, tables, spans)) => {
+                    let diagnostics = self.diagnostics(&name, &output, &tables, &spans)?;
+                    self.publish(name, diagnostics)?;
+                }
+                Err(error) => self.log(&format!("can't analyze `{}`: {}", name, error))?,
+            }
+        }
+        self.writer.flush()
+    }
+
+    fn analyze_dir(
+        &self,
+        facts_dir: &Path,
+    ) -> Result<(Output, InternerTables, FxHashMap<Point, Span>), Error> {
+        let mut tables = InternerTables::new();
+        let all_facts = cli::load_facts(&mut tables, facts_dir, self.load_config)?;
+        let spans = sarif::load_spans(&tables, &[facts_dir.display().to_string()])?;
+        let output = Output::compute(&all_facts, self.algorithm, false);
+        Ok((output, tables, spans))
+    }
+
+    fn analyze_params(&mut self, params: FactsParams) -> io::Result<()> {
+        let mut tables = InternerTables::new();
+        let document = serde_json::to_vec(&params.facts)?;
+        let all_facts = match json::read_json_facts(&mut tables, &document[..]) {
+            Ok(all_facts) => all_facts,
+            Err(error) => return self.log(&format!("invalid facts `{}`: {}", params.name, error)),
+        };
+
+        let mut spans = FxHashMap::default();
+        for span in params.spans {
+            if let Some(point) = tables.points.lookup(&span.point) {
+                let span = Span {
+                    file: span.file,
+                    line: span.line,
+                    column: span.column,
+                };
+                spans.insert(point, span);
+            }
+        }
+
+        let output = Output::compute(&all_facts, self.algorithm, false);
+        let diagnostics = self.diagnostics(&params.name, &output, &tables, &spans)?;
+        self.publish(params.name, diagnostics)
+    }
+
+    /// The diagnostics of the errors of `output`, the analysis of the facts
+    /// named `name`. The errors at points without a span are logged.
+    fn diagnostics(
+        &mut self,
+        name: &str,
+        output: &Output,
+        tables: &InternerTables,
+        spans: &FxHashMap<Point, Span>,
+    ) -> io::Result<Diagnostics> {
+        let mut diagnostics = Diagnostics::new();
+        for (point, loan) in output.iter_errors() {
+            let message = format!(
+                "`{}` is invalidated at `{}` while it is live",
+                tables.unquoted(loan),
+                tables.unquoted(point)
+            );
+            match spans.get(&point) {
+                Some(span) => {
+                    // LSP positions are 0-based, spans are 1-based.
+                    let position = Position {
+                        line: span.line.saturating_sub(1),
+                        character: span.column.saturating_sub(1),
+                    };
+                    diagnostics
+                        .entry(file_uri(&span.file))
+                        .or_insert_with(Vec::new)
+                        .push(Diagnostic {
+                            range: Range {
+                                start: position.clone(),
+                                end: position,
+                            },
+                            severity: 1,
+                            source: "polonius",
+                            message,
+                        });
+                }
+                None => self.log(&format!("{}: {}", name, message))?,
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// Replaces the diagnostics of the facts named `name` with
+    /// `diagnostics`, publishing every document whose diagnostics change.
+    /// The documents can have diagnostics from several facts, e.g. from
+    /// the functions of the same source file.
+    fn publish(&mut self, name: String, diagnostics: Diagnostics) -> io::Result<()> {
+        let previous = self.published.remove(&name).unwrap_or_default();
+        if previous == diagnostics {
+            self.published.insert(name, diagnostics);
+            return Ok(());
+        }
+
+        let uris: BTreeSet<String> = previous.keys().chain(diagnostics.keys()).cloned().collect();
+        if !diagnostics.is_empty() {
+            self.published.insert(name, diagnostics);
+        }
+        for uri in uris {
+            let document_diagnostics = self
+                .published
+                .values()
+                .filter_map(|diagnostics| diagnostics.get(&uri))
+                .flat_map(|diagnostics| diagnostics.iter())
+                .collect();
+            let params = PublishDiagnosticsParams {
+                uri: &uri,
+                diagnostics: document_diagnostics,
+            };
+            let notification = Notification {
+                jsonrpc: "2.0",
+                method: "textDocument/publishDiagnostics",
+                params,
+            };
+            write_message(&mut self.writer, &serde_json::to_value(&notification)?)?;
+        }
+        Ok(())
+    }
+
+    fn log(&mut self, message: &str) -> io::Result<()> {
+        let notification = Notification {
+            jsonrpc: "2.0",
+            method: "window/logMessage",
+            params: LogMessageParams {
+                message_type: 1,
+                message: message.to_string(),
+            },
+        };
+        write_message(&mut self.writer, &serde_json::to_value(&notification)?)
+    }
+
+    fn respond(&mut self, id: Option<Value>, result: Value) -> io::Result<()> {
+        let id = match id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let response = Response {
+            jsonrpc: "2.0",
+            id,
+            result,
+        };
+        write_message(&mut self.writer, &serde_json::to_value(&response)?)
+    }
+
+    fn respond_error(&mut self, id: Value, code: i64, message: String) -> io::Result<()> {
+        let response = ErrorResponse {
+            jsonrpc: "2.0",
+            id,
+            error: ResponseError { code, message },
+        };
+        write_message(&mut self.writer, &serde_json::to_value(&response)?)
+    }
+}
+
+/// Reads a message, framed by its `Content-Length` header. Returns `None`
+/// at the end of the input, and an `InvalidData` error if the message is
+/// malformed, after which the next message can still be read if the
+/// header was there.
+crate fn read_message(reader: &mut dyn BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_right();
+        if header.is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        let (name, value) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing `Content-Length` header")
+    })?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    let message = serde_json::from_slice(&content)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    Ok(Some(message))
+}
+
+crate fn write_message(writer: &mut dyn Write, message: &Value) -> io::Result<()> {
+    let content = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content)
+}
+
+/// The URI of `file`, relative to the current directory unless it is
+/// absolute, like the files of rustc's spans. The bytes of the path that
+/// can't be in the path of a URI, like spaces, are percent-encoded.
+crate fn file_uri(file: &str) -> String {
+    let mut path = PathBuf::from(file);
+    if !path.is_absolute() {
+        path = env::current_dir().unwrap_or_default().join(path);
+    }
+
+    let mut uri = String::from("file://");
+    for &byte in path.to_string_lossy().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
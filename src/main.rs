@@ -2,9 +2,18 @@ extern crate failure;
 extern crate polonius;
 extern crate structopt;
 
+use polonius::cli::Outcome;
+use std::io::{self, Write};
+use std::process;
 use structopt::StructOpt;
 
 pub fn main() -> Result<(), failure::Error> {
     let opt = polonius::cli::Opt::from_args();
-    polonius::cli::main(opt)
+    let outcome = polonius::cli::main(opt)?;
+    if outcome != Outcome::Clean {
+        // `exit` doesn't flush what was printed.
+        io::stdout().flush()?;
+        process::exit(outcome.exit_status());
+    }
+    Ok(())
 }
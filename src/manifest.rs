@@ -0,0 +1,227 @@
+//! The `polonius-facts.toml` manifest written next to tab-delimited fact
+//! dumps. It records which version of the fact schema the dump follows,
+//! so that the loader can keep reading old dumps as rustc adds and
+//! renames relations.
+
+use crate::tab_delim::{LoadError, LoadErrorKind};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use toml;
+
+crate const MANIFEST_FILE_NAME: &str = "polonius-facts.toml";
+
+/// The schema version written by this version of polonius.
+///
+/// - 0: dumps without a manifest, as originally emitted by rustc.
+/// - 1: the same relations as version 0, with a manifest.
+/// - 2: adds the variable liveness relations, `var_used_at` and so on.
+/// - 3: adds the move path relations, `child_path` and so on.
+/// - 4: adds `known_placeholder_subset`.
+/// - 5: adds `loan_borrows_path`.
+crate const SCHEMA_VERSION: u32 = 5;
+
+/// The relations of the current schema, the number of columns of each, and
+/// whether a dump can go without it. The relations that the later schemas
+/// added are optional: the dumps of the versions of rustc before them
+/// don't have their files, which are then empty without a warning.
+const RELATIONS: &[(&str, usize, bool)] = &[
+    ("borrow_region", 3, false),
+    ("universal_region", 1, false),
+    ("cfg_edge", 2, false),
+    ("killed", 2, false),
+    ("outlives", 3, false),
+    ("region_live_at", 2, false),
+    ("invalidates", 2, false),
+    ("var_used_at", 2, true),
+    ("var_defined_at", 2, true),
+    ("var_drop_used_at", 2, true),
+    ("use_of_var_derefs_origin", 2, true),
+    ("drop_of_var_derefs_origin", 2, true),
+    ("child_path", 2, true),
+    ("path_is_var", 2, true),
+    ("path_assigned_at", 2, true),
+    ("path_moved_at", 2, true),
+    ("path_accessed_at", 2, true),
+    ("known_placeholder_subset", 2, true),
+    ("loan_borrows_path", 2, true),
+];
+
+/// Another name of a relation, under which some versions of rustc dump
+/// it, like the `origin` terminology of the newer ones.
+crate struct RelationAlias {
+    crate relation: &'static str,
+    crate name: &'static str,
+
+    /// The columns of each row of the alias that make up the row of the
+    /// relation, in order, when the alias has other columns.
+    crate columns: Option<&'static [usize]>,
+}
+
+/// The aliases of the relations, which the loader reads when a dump has
+/// no file by the relation's own name.
+crate const RELATION_ALIASES: &[RelationAlias] = &[
+    RelationAlias {
+        relation: "borrow_region",
+        name: "loan_issued_at",
+        columns: None,
+    },
+    // `placeholder(origin, loan)`, with the loan of each placeholder.
+    RelationAlias {
+        relation: "universal_region",
+        name: "placeholder",
+        columns: Some(&[0]),
+    },
+    RelationAlias {
+        relation: "killed",
+        name: "loan_killed_at",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "outlives",
+        name: "subset_base",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "region_live_at",
+        name: "origin_live_on_entry",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "invalidates",
+        name: "loan_invalidated_at",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "var_drop_used_at",
+        name: "var_dropped_at",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "use_of_var_derefs_origin",
+        name: "var_uses_region",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "drop_of_var_derefs_origin",
+        name: "var_drops_region",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "path_assigned_at",
+        name: "path_assigned_at_base",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "path_moved_at",
+        name: "path_moved_at_base",
+        columns: None,
+    },
+    RelationAlias {
+        relation: "path_accessed_at",
+        name: "path_accessed_at_base",
+        columns: None,
+    },
+];
+
+/// The relations that every dump has, whose missing files are worth a
+/// warning.
+crate fn required_relations() -> impl Iterator<Item = &'static str> {
+    RELATIONS
+        .iter()
+        .filter(|&&(_, _, optional)| !optional)
+        .map(|&(relation, _, _)| relation)
+}
+
+/// The aliases of `relation`, in the order the loader tries them.
+crate fn aliases_of(relation: &'a str) -> impl Iterator<Item = &'static RelationAlias> + 'a {
+    RELATION_ALIASES
+        .iter()
+        .filter(move |alias| alias.relation == relation)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+crate struct Manifest {
+    crate schema_version: u32,
+
+    /// The number of columns of each relation in the dump.
+    crate relations: BTreeMap<String, usize>,
+}
+
+impl Manifest {
+    /// The manifest describing dumps written by this version of polonius.
+    crate fn current() -> Self {
+        Self::for_version(SCHEMA_VERSION)
+    }
+
+    fn for_version(schema_version: u32) -> Self {
+        Manifest {
+            schema_version,
+            relations: RELATIONS
+                .iter()
+                .map(|&(relation, arity, _)| (relation.to_string(), arity))
+                .collect(),
+        }
+    }
+
+    /// The name of the file holding `relation` (as named by the current
+    /// schema) in a dump of this manifest's version. When a future schema
+    /// renames a relation, this is where older dumps get mapped onto it.
+    crate fn relation_file_stem(&self, relation: &'static str) -> &'static str {
+        match self.schema_version {
+            0..=SCHEMA_VERSION => relation,
+            version => unreachable!("unsupported schema version {}", version),
+        }
+    }
+}
+
+/// Reads the manifest in `facts_dir`. Dumps without one are assumed to be
+/// version 0 dumps, and are checked against the version 0 schema.
+crate fn read_manifest(facts_dir: &Path) -> Result<Manifest, LoadError> {
+    let manifest_file = facts_dir.join(MANIFEST_FILE_NAME);
+    if !manifest_file.exists() {
+        return Ok(Manifest::for_version(0));
+    }
+
+    let contents =
+        fs::read_to_string(&manifest_file).map_err(|error| LoadError::io(&manifest_file, error))?;
+    let manifest: Manifest = toml::from_str(&contents).map_err(|error| {
+        LoadError::new(
+            &manifest_file,
+            LoadErrorKind::InvalidManifest(error.to_string()),
+        )
+    })?;
+
+    if manifest.schema_version > SCHEMA_VERSION {
+        return Err(LoadError::new(
+            &manifest_file,
+            LoadErrorKind::UnsupportedSchema(manifest.schema_version),
+        ));
+    }
+
+    let expected = Manifest::for_version(manifest.schema_version);
+    for (relation, &arity) in &expected.relations {
+        match manifest.relations.get(relation) {
+            Some(&found) if found != arity => {
+                return Err(LoadError::new(
+                    &manifest_file,
+                    LoadErrorKind::ArityMismatch {
+                        relation: relation.clone(),
+                        expected: arity,
+                        found,
+                    },
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(manifest)
+}
+
+crate fn write_manifest(facts_dir: &Path) -> io::Result<()> {
+    let contents = toml::to_string(&Manifest::current())
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+    fs::write(facts_dir.join(MANIFEST_FILE_NAME), contents)
+}
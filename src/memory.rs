@@ -0,0 +1,29 @@
+//! The peak memory use of the process, as Linux reports it in `/proc`.
+//! Elsewhere, it is unknown.
+
+use std::fs;
+
+/// The most memory the process has had resident at once, in bytes, since
+/// it started or since the last successful `reset_peak_rss`.
+crate fn peak_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    parse_peak_rss(&status)
+}
+
+/// Starts measuring the peak resident memory anew from the current use,
+/// returning whether the system supports it.
+crate fn reset_peak_rss() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// The `VmHWM` line of `/proc/self/status`, which is in kilobytes.
+crate fn parse_peak_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes: u64 = line["VmHWM:".len()..]
+        .trim()
+        .trim_right_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,110 @@
+//! The status line that `analyze` and `batch` keep up to date on stderr,
+//! while they work through many functions or through a long analysis.
+
+use crate::cli::seconds;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How long, in milliseconds, to wait between two updates of the tuple
+/// count, so that printing it doesn't slow the analysis down.
+const UPDATE_INTERVAL_MILLIS: u64 = 100;
+
+crate struct Progress {
+    enabled: bool,
+    total: usize,
+    current: usize,
+    function: String,
+    phase: &'static str,
+    tuples: usize,
+    start: Instant,
+    last_update: Option<Instant>,
+    /// The length of the line on screen, to blank it out with.
+    width: usize,
+}
+
+impl Progress {
+    /// The progress through `total` functions, only printed if `enabled`.
+    crate fn new(total: usize, enabled: bool) -> Self {
+        Progress {
+            enabled,
+            total,
+            current: 0,
+            function: String::new(),
+            phase: "",
+            tuples: 0,
+            start: Instant::now(),
+            last_update: None,
+            width: 0,
+        }
+    }
+
+    /// Moves on to the next function, starting with loading its facts.
+    crate fn start(&mut self, function: &str) {
+        self.current += 1;
+        self.function = function.to_string();
+        self.phase = "loading";
+        self.tuples = 0;
+        self.update(true);
+    }
+
+    /// Records that `function` is analyzed, when the functions are analyzed
+    /// in parallel, and finish in any order.
+    crate fn finish(&mut self, function: &str) {
+        self.current += 1;
+        self.function = function.to_string();
+        self.phase = "done";
+        self.tuples = 0;
+        self.update(true);
+    }
+
+    crate fn phase(&mut self, phase: &'static str) {
+        self.phase = phase;
+        self.update(true);
+    }
+
+    /// Records that the analysis of the current function has derived
+    /// `tuples` tuples so far.
+    crate fn tuples(&mut self, tuples: usize) {
+        self.tuples = tuples;
+        self.update(false);
+    }
+
+    /// Blanks out the status line, before printing something else.
+    crate fn clear(&mut self) {
+        if self.enabled && self.width > 0 {
+            eprint!("\r{:width$}\r", "", width = self.width);
+            self.width = 0;
+        }
+    }
+
+    crate fn status_line(&self) -> String {
+        format!(
+            "[{}/{}] {}: {}, {:0.1}s elapsed, {} tuples derived",
+            self.current,
+            self.total,
+            self.function,
+            self.phase,
+            seconds(self.start.elapsed()),
+            self.tuples
+        )
+    }
+
+    fn update(&mut self, force: bool) {
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let interval = Duration::from_millis(UPDATE_INTERVAL_MILLIS);
+        if !force && self.last_update.map_or(false, |last| now.duration_since(last) < interval) {
+            return;
+        }
+        self.last_update = Some(now);
+
+        let line = self.status_line();
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = write!(stderr, "\r{:width$}\r{}", "", line, width = self.width);
+        let _ = stderr.flush();
+        self.width = line.len();
+    }
+}
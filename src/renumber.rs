@@ -0,0 +1,41 @@
+use crate::facts::AllFacts;
+use crate::intern::{self, InternerTables};
+
+/// Renumbers the atoms of `all_facts` densely: the atoms that the facts
+/// mention get the indices `0..n` of their kind, in their original order,
+/// and atoms that only `tables` knows about are dropped. The returned
+/// tables map the new indices back to the original names, so output
+/// written with them is unchanged.
+crate fn renumber_densely(
+    all_facts: AllFacts,
+    tables: &InternerTables,
+) -> (AllFacts, InternerTables) {
+    // Reinterning only keeps the atoms in use, but numbers them in order
+    // of first use. Interning those atoms again in their original order
+    // restores it.
+    let used_tables = &mut InternerTables::new();
+    let used_facts = intern::reintern_facts(all_facts, tables, used_tables, "");
+
+    let mut renumbered_tables = InternerTables::new();
+    macro_rules! intern_in_order {
+        ($($field:ident,)*) => {
+            $(
+                for name in tables.$field.strings() {
+                    if used_tables.$field.lookup(name).is_some() {
+                        renumbered_tables.$field.intern(name);
+                    }
+                }
+            )*
+        }
+    }
+    intern_in_order! {
+        regions,
+        loans,
+        points,
+        variables,
+        move_paths,
+    }
+
+    let renumbered = intern::reintern_facts(used_facts, used_tables, &mut renumbered_tables, "");
+    (renumbered, renumbered_tables)
+}
@@ -0,0 +1,194 @@
+//! The `repl` command: questions about the facts of one directory, and the
+//! results of the analysis on them, answered at a prompt.
+//!
+//! There is no line editor to complete names with Tab, so an atom can
+//! instead be named by any prefix that only one of its kind starts with,
+//! and `complete` lists the names that start with a prefix.
+
+use crate::facts::{AllFacts, Loan, Output, Point, QueryEngine, Region};
+use crate::intern::{Interner, InternerTables};
+use polonius_engine::{unquoted, Algorithm};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+live_regions <point>      the regions live at the point
+borrows_in_scope <point>  the loans in scope at the point
+subsets <point>           the regions that each region is a subset of at the point
+outgoing <point>          the successors of the point in the CFG
+errors                    the loans invalidated while in scope, at each point
+complete <prefix>         the names of the atoms that start with the prefix
+help                      this list
+quit                      leave";
+
+/// The facts of a directory, analyzed once when the REPL starts.
+crate struct Repl<'t> {
+    all_facts: AllFacts,
+    tables: &'t InternerTables,
+    output: Output,
+    query: QueryEngine,
+}
+
+impl<'t> Repl<'t> {
+    crate fn new(all_facts: AllFacts, tables: &'t InternerTables, algorithm: Algorithm) -> Self {
+        let output = Output::compute(&all_facts, algorithm, false);
+        let query = QueryEngine::new(&all_facts, algorithm);
+        Repl {
+            all_facts,
+            tables,
+            output,
+            query,
+        }
+    }
+
+    /// Answers the commands read from `input` until it ends, or until
+    /// `quit`, prompting for each one on `output`.
+    crate fn run(&self, input: &mut dyn BufRead, output: &mut dyn Write) -> io::Result<()> {
+        loop {
+            write!(output, "> ")?;
+            output.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            match self.execute(&line) {
+                Some(ref reply) if reply.is_empty() => {}
+                Some(reply) => writeln!(output, "{}", reply)?,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// The reply to the command `line`, or `None` if it is `quit`.
+    crate fn execute(&self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, arguments) = match words.split_first() {
+            Some((&command, arguments)) => (command, arguments),
+            None => return Some(String::new()),
+        };
+        let reply = match (command, arguments.len()) {
+            ("live_regions", 1) => self
+                .point(arguments[0])
+                .map(|point| self.regions(self.query.regions_live_at(point))),
+            ("borrows_in_scope", 1) => self
+                .point(arguments[0])
+                .map(|point| self.loans(&self.query.borrows_in_scope_at(point))),
+            ("subsets", 1) => self.point(arguments[0]).map(|point| self.subsets(point)),
+            ("outgoing", 1) => self.point(arguments[0]).map(|point| self.outgoing(point)),
+            ("errors", 0) => Ok(self.errors()),
+            ("complete", 1) => Ok(self.complete(arguments[0]).join("\n")),
+            ("help", 0) => Ok(HELP.to_string()),
+            ("quit", 0) | ("exit", 0) => return None,
+            _ => Err(format!("unknown command `{}`, see `help`", words.join(" "))),
+        };
+        Some(reply.unwrap_or_else(|error| error))
+    }
+
+    /// The names of the atoms of every kind that start with `prefix`.
+    crate fn complete(&self, prefix: &str) -> Vec<&str> {
+        let tables = self.tables;
+        let mut names: Vec<&str> = tables
+            .regions
+            .strings()
+            .iter()
+            .chain(tables.loans.strings())
+            .chain(tables.points.strings())
+            .chain(tables.variables.strings())
+            .chain(tables.move_paths.strings())
+            .map(|name| unquoted(name))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn point(&self, name: &str) -> Result<Point, String> {
+        resolve(&self.tables.points, "point", name)
+    }
+
+    fn regions(&self, regions: impl IntoIterator<Item = Region>) -> String {
+        let names: Vec<&str> = regions
+            .into_iter()
+            .map(|r| self.tables.unquoted(r))
+            .collect();
+        names.join(", ")
+    }
+
+    fn loans(&self, loans: &[Loan]) -> String {
+        let names: Vec<&str> = loans
+            .iter()
+            .map(|&l| self.tables.unquoted(l))
+            .collect();
+        names.join(", ")
+    }
+
+    fn subsets(&self, point: Point) -> String {
+        let lines: Vec<String> = self
+            .query
+            .subsets_at(point)
+            .into_iter()
+            .map(|(r, superset)| {
+                let r = self.tables.unquoted(r);
+                format!("{}: {}", r, self.regions(superset))
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    fn outgoing(&self, point: Point) -> String {
+        let mut successors: Vec<&str> = self
+            .all_facts
+            .cfg_edge
+            .iter()
+            .filter(|&&(p, _)| p == point)
+            .map(|&(_, q)| self.tables.unquoted(q))
+            .collect();
+        successors.sort();
+        successors.join(", ")
+    }
+
+    fn errors(&self) -> String {
+        let errors: BTreeMap<&str, &Vec<Loan>> = self
+            .output
+            .errors
+            .iter()
+            .map(|(&p, loans)| (self.tables.unquoted(p), loans))
+            .collect();
+        if errors.is_empty() {
+            return "no errors".to_string();
+        }
+        let lines: Vec<String> = errors
+            .into_iter()
+            .map(|(point, loans)| format!("{}: {}", point, self.loans(loans)))
+            .collect();
+        lines.join("\n")
+    }
+}
+
+/// The atom of `interner` named `name`, or else the only one whose name
+/// starts with `name`.
+fn resolve<T>(interner: &Interner<T>, kind: &str, name: &str) -> Result<T, String>
+where
+    T: From<usize> + Into<usize> + Copy,
+{
+    if let Some(atom) = interner.lookup(name) {
+        return Ok(atom);
+    }
+
+    let candidates: Vec<(usize, &str)> = interner
+        .strings()
+        .iter()
+        .map(|string| unquoted(string))
+        .enumerate()
+        .filter(|&(_, string)| string.starts_with(name))
+        .collect();
+    match candidates.len() {
+        0 => Err(format!("unknown {} `{}`", kind, name)),
+        1 => Ok(T::from(candidates[0].0)),
+        _ => {
+            let names: Vec<&str> = candidates.iter().map(|&(_, string)| string).collect();
+            Err(format!("ambiguous {} `{}`: {}", kind, name, names.join(", ")))
+        }
+    }
+}
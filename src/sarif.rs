@@ -0,0 +1,253 @@
+//! The errors of `analyze --output-format sarif`, as a SARIF 2.1.0 log
+//! that code review tools and GitHub's code scanning can show.
+//!
+//! The facts only name MIR points, so the source location of each point
+//! comes from the optional `point_span.facts` file of the facts directory,
+//! with a row `point file line column` per point. The errors at points
+//! that it doesn't list only have the facts directory and the point as
+//! their location.
+
+use crate::cli;
+use crate::facts::{Output, Point};
+use crate::intern::InternerTables;
+use polonius_engine::unquoted;
+use rustc_hash::FxHashMap;
+use serde_json;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+crate const SPANS_FILE_NAME: &str = "point_span.facts";
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const RULE_ID: &str = "invalidated-while-live";
+
+/// Where a point is in the source code.
+#[derive(Clone, Debug, PartialEq)]
+crate struct Span {
+    crate file: String,
+    crate line: u64,
+    crate column: u64,
+}
+
+#[derive(Serialize)]
+crate struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<PhysicalLocation>,
+    logical_locations: Vec<LogicalLocation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: u64,
+    start_column: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    name: String,
+    fully_qualified_name: String,
+}
+
+impl SarifLog {
+    crate fn new() -> Self {
+        let rule = Rule {
+            id: RULE_ID,
+            short_description: Message {
+                text: "A loan is invalidated while it is live".to_string(),
+            },
+        };
+        SarifLog {
+            schema: SCHEMA,
+            version: "2.1.0",
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: "polonius",
+                        information_uri: "https://github.com/rust-lang-nursery/polonius",
+                        rules: vec![rule],
+                    },
+                },
+                results: Vec::new(),
+            }],
+        }
+    }
+
+    /// Adds the errors of `output`, the analysis of the facts of
+    /// `directory`, located with `spans`.
+    crate fn add_errors(
+        &mut self,
+        directory: &str,
+        output: &Output,
+        tables: &InternerTables,
+        spans: &FxHashMap<Point, Span>,
+    ) {
+        for (point, loan) in output.iter_errors() {
+            let point_name = tables.unquoted(point);
+            let loan_name = tables.unquoted(loan);
+            let physical_location = spans.get(&point).map(|span| PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: span.file.clone(),
+                },
+                region: Region {
+                    start_line: span.line,
+                    start_column: span.column,
+                },
+            });
+            self.runs[0].results.push(SarifResult {
+                rule_id: RULE_ID,
+                level: "error",
+                message: Message {
+                    text: format!(
+                        "`{}` is invalidated at `{}` while it is live",
+                        loan_name, point_name
+                    ),
+                },
+                locations: vec![Location {
+                    physical_location,
+                    logical_locations: vec![LogicalLocation {
+                        name: point_name.to_string(),
+                        fully_qualified_name: format!("{}::{}", directory, point_name),
+                    }],
+                }],
+            });
+        }
+    }
+
+    crate fn write(&self, writer: impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+/// The spans of the points of `tables` that the `point_span.facts` files
+/// of `facts_dirs` list, for those of the directories that have one. The
+/// points of several directories are those of `--merge`, whose names start
+/// with the prefix of their directory.
+crate fn load_spans(
+    tables: &InternerTables,
+    facts_dirs: &[String],
+) -> io::Result<FxHashMap<Point, Span>> {
+    let mut spans = FxHashMap::default();
+    for facts_dir in facts_dirs {
+        let path = Path::new(facts_dir).join(SPANS_FILE_NAME);
+        if path.exists() {
+            let prefix = if facts_dirs.len() > 1 {
+                cli::merge_prefix(facts_dir)
+            } else {
+                String::new()
+            };
+            spans.extend(parse_spans(tables, &prefix, &fs::read_to_string(&path)?)?);
+        }
+    }
+    Ok(spans)
+}
+
+/// The spans of the points of `tables` that `contents` lists, the name of
+/// each point being prefixed with `prefix` first.
+crate fn parse_spans(
+    tables: &InternerTables,
+    prefix: &str,
+    contents: &str,
+) -> io::Result<FxHashMap<Point, Span>> {
+    let mut spans = FxHashMap::default();
+    for (index, line) in contents.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let columns: Vec<&str> = line.split('\t').collect();
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} in line {} of `{}`", reason, index + 1, SPANS_FILE_NAME),
+            )
+        };
+        if columns.len() != 4 {
+            return Err(invalid("expected 4 columns"));
+        }
+        let number = |column: &str| {
+            unquoted(column)
+                .parse::<u64>()
+                .map_err(|_| invalid("invalid line or column"))
+        };
+        let span = Span {
+            file: unquoted(columns[1]).to_string(),
+            line: number(columns[2])?,
+            column: number(columns[3])?,
+        };
+
+        // The spans of the points that no fact mentions don't matter.
+        let point = if prefix.is_empty() {
+            tables.points.lookup(columns[0])
+        } else {
+            tables.points.lookup(&format!("{}{}", prefix, columns[0]))
+        };
+        if let Some(point) = point {
+            spans.insert(point, span);
+        }
+    }
+    Ok(spans)
+}
@@ -0,0 +1,431 @@
+//! The `serve` command: an HTTP server that analyzes the facts its clients
+//! upload, as the backend of a playground or of a farm of CI jobs. Every
+//! response is JSON, and the errors are `{ "error": "<message>" }`:
+//!
+//! - `GET /algorithms`: the names of the algorithms.
+//! - `POST /facts`, with a JSON facts document as the body: stores the
+//!   facts, and responds `{ "id": <id> }`.
+//! - `POST /facts/<id>/analyze?algorithm=<name>`: analyzes the facts, with
+//!   the server's algorithm if none is given, and responds with the
+//!   `errors` and the number of rows of each derived relation.
+//! - `GET /facts/<id>/errors`: the errors of the last analysis, as
+//!   `{ "point": ..., "loan": ... }` objects.
+//! - `GET /facts/<id>/relations/<relation>`: the rows of a relation derived
+//!   by the last analysis, as arrays of names.
+//! - `DELETE /facts/<id>`: forgets the facts.
+//!
+//! The server only keeps the last `MAX_UPLOADS` uploads, and forgets the
+//! oldest ones first. Requests are read and handled on a thread per
+//! connection. The uploads are locked while a request reads or changes
+//! them, but not while the facts are analyzed: each analysis runs on a copy
+//! of its facts, within the budget of the server, and the response says
+//! so with `budget_exceeded` if the budget ran out.
+
+use crate::dump::WithTables;
+use crate::facts::{AllFacts, Output};
+use crate::intern::InternerTables;
+use crate::json;
+use polonius_engine::{Algorithm, Budget};
+use serde::Serialize;
+use serde_json;
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The largest body of a request, in bytes.
+const MAX_BODY_BYTES: usize = 64 << 20;
+
+/// How many uploads the server keeps.
+const MAX_UPLOADS: usize = 64;
+
+crate struct Request {
+    crate method: String,
+    /// The path of the request, with its query string.
+    crate path: String,
+    crate body: Vec<u8>,
+}
+
+#[derive(Debug)]
+crate struct Response {
+    crate status: u16,
+    crate body: String,
+}
+
+#[derive(Serialize)]
+struct UploadResponse {
+    id: u64,
+}
+
+#[derive(Serialize)]
+struct AnalysisResponse<'a> {
+    algorithm: String,
+    errors: Vec<ErrorRow<'a>>,
+    relations: BTreeMap<&'static str, usize>,
+
+    /// Why the analysis stopped before it was complete, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    budget_exceeded: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorRow<'a> {
+    point: &'a str,
+    loan: &'a str,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+struct Upload {
+    all_facts: AllFacts,
+    tables: Arc<InternerTables>,
+
+    /// The output of the last analysis, which computes all the relations.
+    output: Option<Output>,
+}
+
+crate struct Server {
+    algorithm: Algorithm,
+    budget: Budget,
+    uploads: BTreeMap<u64, Upload>,
+    next_id: u64,
+}
+
+/// What a request needs: a response, or the analysis of an upload first.
+enum Routed {
+    Respond(Response),
+    Analyze(Analysis),
+}
+
+/// The analysis of the facts of an upload, on a copy of its facts, which
+/// needs no access to the server until it is done.
+struct Analysis {
+    id: u64,
+    algorithm: Algorithm,
+    budget: Budget,
+    all_facts: AllFacts,
+    tables: Arc<InternerTables>,
+}
+
+/// Serves the analysis of uploaded facts on `address`, like
+/// `127.0.0.1:8080`, until the process is killed. Each analysis stops
+/// once it exceeds `budget`.
+crate fn run(address: &str, algorithm: Algorithm, budget: Budget) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("serving on http://{}", listener.local_addr()?);
+    let server = Arc::new(Mutex::new(Server::new(algorithm, budget)));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let server = server.clone();
+        thread::spawn(move || {
+            if let Err(error) = serve_connection(&server, stream) {
+                eprintln!("{}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_connection(server: &Mutex<Server>, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok(Some(request)) => handle(server, &request),
+        Ok(None) => return Ok(()),
+        Err(error) => Response::error(400, error.to_string()),
+    };
+    let mut writer = stream;
+    write_response(&mut writer, &response)?;
+    writer.flush()
+}
+
+/// Handles `request` with the server, which is only locked while the
+/// request reads or changes the uploads, and not while it analyzes them.
+crate fn handle(server: &Mutex<Server>, request: &Request) -> Response {
+    let lock = move || server.lock().unwrap_or_else(|error| error.into_inner());
+    let analysis = match lock().route(request) {
+        Routed::Respond(response) => return response,
+        Routed::Analyze(analysis) => analysis,
+    };
+    let id = analysis.id;
+    let (output, response) = analysis.run();
+    lock().finish(id, output);
+    response
+}
+
+impl Server {
+    crate fn new(algorithm: Algorithm, budget: Budget) -> Self {
+        Server {
+            algorithm,
+            budget,
+            uploads: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Handles `request`, analyzing the facts while the server is borrowed.
+    crate fn handle(&mut self, request: &Request) -> Response {
+        match self.route(request) {
+            Routed::Respond(response) => response,
+            Routed::Analyze(analysis) => {
+                let id = analysis.id;
+                let (output, response) = analysis.run();
+                self.finish(id, output);
+                response
+            }
+        }
+    }
+
+    fn route(&mut self, request: &Request) -> Routed {
+        let mut parts = request.path.splitn(2, '?');
+        let path = parts.next().unwrap_or("");
+        let query = parts.next().unwrap_or("");
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        let route = (
+            segments.next(),
+            segments.next(),
+            segments.next(),
+            segments.next(),
+        );
+        if segments.next().is_some() {
+            return Routed::Respond(Response::not_found());
+        }
+
+        let id = route.1.and_then(|id| u64::from_str(id).ok());
+        let response = match (request.method.as_str(), route) {
+            ("OPTIONS", _) => Response {
+                status: 204,
+                body: String::new(),
+            },
+            ("GET", (Some("algorithms"), None, None, None)) => {
+                Response::json(200, &Algorithm::variants())
+            }
+            ("POST", (Some("facts"), None, None, None)) => self.upload(&request.body),
+            ("DELETE", (Some("facts"), Some(_), None, None)) => {
+                match id.and_then(|id| self.uploads.remove(&id)) {
+                    Some(_) => Response {
+                        status: 204,
+                        body: String::new(),
+                    },
+                    None => Response::not_found(),
+                }
+            }
+            ("POST", (Some("facts"), Some(_), Some("analyze"), None)) => {
+                return self.analysis(id, query_value(query, "algorithm"));
+            }
+            ("GET", (Some("facts"), Some(_), Some("errors"), None)) => {
+                self.with_output(id, |output, tables| {
+                    Response::json(200, &error_rows(output, tables))
+                })
+            }
+            ("GET", (Some("facts"), Some(_), Some("relations"), Some(relation))) => {
+                self.with_output(id, |output, tables| {
+                    let relations = output.with_tables(tables).relations();
+                    match relations.iter().find(|&&(name, _)| name == relation) {
+                        Some(&(_, ref rows)) => Response::json(200, rows),
+                        None => Response::error(404, format!("no relation `{}`", relation)),
+                    }
+                })
+            }
+            _ => Response::not_found(),
+        };
+        Routed::Respond(response)
+    }
+
+    fn upload(&mut self, body: &[u8]) -> Response {
+        let mut tables = InternerTables::new();
+        let all_facts = match json::read_json_facts(&mut tables, body) {
+            Ok(all_facts) => all_facts,
+            Err(error) => return Response::error(400, format!("invalid facts: {}", error)),
+        };
+
+        while self.uploads.len() >= MAX_UPLOADS {
+            let oldest = *self.uploads.keys().next().unwrap();
+            self.uploads.remove(&oldest);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let upload = Upload {
+            all_facts,
+            tables: Arc::new(tables),
+            output: None,
+        };
+        self.uploads.insert(id, upload);
+        Response::json(201, &UploadResponse { id })
+    }
+
+    /// The analysis of the upload `id` with `algorithm`, or the server's.
+    fn analysis(&self, id: Option<u64>, algorithm: Option<&str>) -> Routed {
+        let algorithm = match algorithm {
+            Some(algorithm) => match Algorithm::from_str(algorithm) {
+                Ok(algorithm) => algorithm,
+                Err(error) => return Routed::Respond(Response::error(400, error)),
+            },
+            None => self.algorithm,
+        };
+        let id = match id {
+            Some(id) if self.uploads.contains_key(&id) => id,
+            _ => return Routed::Respond(Response::not_found()),
+        };
+
+        let upload = &self.uploads[&id];
+        Routed::Analyze(Analysis {
+            id,
+            algorithm,
+            budget: self.budget,
+            all_facts: upload.all_facts.clone(),
+            tables: upload.tables.clone(),
+        })
+    }
+
+    /// Keeps `output` as the last analysis of the upload `id`, unless the
+    /// upload was forgotten while it was analyzed.
+    fn finish(&mut self, id: u64, output: Output) {
+        if let Some(upload) = self.uploads.get_mut(&id) {
+            upload.output = Some(output);
+        }
+    }
+
+    /// Responds with `respond` and the output of the last analysis of the
+    /// upload `id`.
+    fn with_output(
+        &self,
+        id: Option<u64>,
+        respond: impl FnOnce(&Output, &InternerTables) -> Response,
+    ) -> Response {
+        match id.and_then(|id| self.uploads.get(&id)) {
+            Some(&Upload {
+                output: Some(ref output),
+                ref tables,
+                ..
+            }) => respond(output, tables),
+            Some(_) => Response::error(409, "the facts weren't analyzed yet".to_string()),
+            None => Response::not_found(),
+        }
+    }
+}
+
+impl Analysis {
+    fn run(self) -> (Output, Response) {
+        let output =
+            Output::compute_with_budget(&self.all_facts, self.algorithm, true, self.budget);
+        let response = {
+            let relations = output
+                .with_tables(&self.tables)
+                .relations()
+                .into_iter()
+                .map(|(relation, rows)| (relation, rows.len()))
+                .collect();
+            Response::json(
+                200,
+                &AnalysisResponse {
+                    algorithm: self.algorithm.to_string(),
+                    errors: error_rows(&output, &self.tables),
+                    relations,
+                    budget_exceeded: output.budget_exceeded.map(|limit| limit.to_string()),
+                },
+            )
+        };
+        (output, response)
+    }
+}
+
+impl Response {
+    fn json(status: u16, body: &impl Serialize) -> Self {
+        match serde_json::to_string(body) {
+            Ok(body) => Response { status, body },
+            Err(error) => Response::error(500, error.to_string()),
+        }
+    }
+
+    fn error(status: u16, error: String) -> Self {
+        let body = serde_json::to_string(&ErrorResponse { error }).unwrap_or_default();
+        Response { status, body }
+    }
+
+    fn not_found() -> Self {
+        Response::error(404, "not found".to_string())
+    }
+}
+
+fn error_rows(output: &'a Output, tables: &'a InternerTables) -> Vec<ErrorRow<'a>> {
+    output
+        .with_tables(tables)
+        .errors()
+        .map(|(point, loan)| ErrorRow { point, loan })
+        .collect()
+}
+
+/// The value of the parameter `name` of the query string `query`.
+fn query_value(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .map(|parameter| parameter.splitn(2, '=').collect::<Vec<_>>())
+        .find(|parts| parts[0] == name)
+        .map(|parts| parts.get(1).cloned().unwrap_or(""))
+}
+
+/// Reads a request, with the body of its `Content-Length`. Returns `None`
+/// if the connection closed before a request.
+crate fn read_request(reader: &mut dyn BufRead) -> io::Result<Option<Request>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("missing method"))?.to_string();
+    let path = parts.next().ok_or_else(|| invalid("missing path"))?.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(invalid("unterminated headers"));
+        }
+        let header = header.trim_right();
+        if header.is_empty() {
+            break;
+        }
+        let mut parts = header.splitn(2, ':');
+        let (name, value) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| invalid("invalid `Content-Length` header"))?;
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(invalid("the body is too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request { method, path, body }))
+}
+
+/// Writes `response`, allowing the pages of any origin to read it, and
+/// closes the connection.
+crate fn write_response(writer: &mut dyn Write, response: &Response) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    };
+    write!(writer, "HTTP/1.1 {} {}\r\n", response.status, reason)?;
+    write!(writer, "Content-Type: application/json\r\n")?;
+    write!(writer, "Content-Length: {}\r\n", response.body.len())?;
+    write!(writer, "Access-Control-Allow-Origin: *\r\n")?;
+    write!(writer, "Access-Control-Allow-Methods: GET, POST, DELETE\r\n")?;
+    write!(writer, "Access-Control-Allow-Headers: Content-Type\r\n")?;
+    write!(writer, "Connection: close\r\n\r\n{}", response.body)
+}
@@ -0,0 +1,124 @@
+//! `compare-souffle`: runs Soufflé on the facts, with a Datalog program of
+//! the naive analysis, and diffs its outputs with those of the engine. An
+//! engine that was written independently shows the mistakes of encoding
+//! the rules as joins, which the algorithms of polonius could all share.
+//!
+//! Soufflé reads the facts that `dump` writes, after the engine derived
+//! the liveness of the regions and the kills of the loans, and writes an
+//! `<relation>.csv` file of tab-separated names for each output.
+
+use crate::diff::RelationDiff;
+use crate::facts::{AllFacts, Loan, Output, Point};
+use crate::intern::InternerTables;
+use crate::tab_delim;
+use failure::{self, Error};
+use polonius_engine::Algorithm;
+use rustc_hash::FxHashMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// The Datalog program that `compare-souffle` runs by default.
+crate const NAIVE_PROGRAM: &str = include_str!("../souffle/naive.dl");
+
+/// The output relations that are compared, which the program must
+/// `.output`.
+crate const COMPARED_RELATIONS: &[&str] = &["errors", "borrow_live_at"];
+
+/// Runs `souffle` with `program`, or `NAIVE_PROGRAM`, on `all_facts`
+/// in `work_dir`, and diffs its outputs with those of the naive algorithm.
+/// The added rows are those that only Soufflé derives, and the removed
+/// rows those that only the engine does.
+crate fn compare(
+    souffle: &Path,
+    program: Option<&Path>,
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    work_dir: &Path,
+) -> Result<Vec<RelationDiff>, Error> {
+    let facts_dir = work_dir.join("facts");
+    let output_dir = work_dir.join("output");
+    fs::create_dir_all(&output_dir)?;
+    tab_delim::write_tab_delimited_facts(&Output::derived_facts(all_facts), tables, &facts_dir)?;
+    let program = match program {
+        Some(program) => program.to_owned(),
+        None => {
+            let program = work_dir.join("naive.dl");
+            fs::write(&program, NAIVE_PROGRAM)?;
+            program
+        }
+    };
+
+    let status = Command::new(souffle)
+        .arg("-F")
+        .arg(&facts_dir)
+        .arg("-D")
+        .arg(&output_dir)
+        .arg(&program)
+        .status()
+        .map_err(|error| {
+            failure::err_msg(format!("can't run `{}`: {}", souffle.display(), error))
+        })?;
+    if !status.success() {
+        return Err(failure::err_msg(format!("`{}` failed", souffle.display())));
+    }
+
+    let output = Output::compute(all_facts, Algorithm::Naive, false);
+    let expected = polonius_rows(&output, tables);
+    let actual = read_souffle_rows(&output_dir)?;
+    Ok(diff_rows(&expected, &actual))
+}
+
+/// The rows of the compared relations of `output`, tab-delimited, with
+/// the columns in the order of the program's relations.
+crate fn polonius_rows(output: &Output, tables: &InternerTables) -> Vec<BTreeSet<String>> {
+    vec![
+        loan_rows(&output.errors, tables),
+        loan_rows(&output.borrow_live_at(), tables),
+    ]
+}
+
+/// The `loan point` rows of a relation of loans by point.
+fn loan_rows(relation: &FxHashMap<Point, Vec<Loan>>, tables: &InternerTables) -> BTreeSet<String> {
+    relation
+        .iter()
+        .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (loan, point)))
+        .map(|(loan, point)| {
+            format!("{}\t{}", tables.loans.untern(loan), tables.points.untern(point))
+        })
+        .collect()
+}
+
+/// The rows of the compared relations that Soufflé wrote to `output_dir`.
+crate fn read_souffle_rows(output_dir: &Path) -> io::Result<Vec<BTreeSet<String>>> {
+    let mut relations = Vec::new();
+    for relation in COMPARED_RELATIONS {
+        let contents = fs::read_to_string(output_dir.join(format!("{}.csv", relation)))?;
+        let rows = contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect();
+        relations.push(rows);
+    }
+    Ok(relations)
+}
+
+/// The differences of each compared relation, from the rows of the
+/// engine, `expected`, to those of Soufflé, `actual`.
+crate fn diff_rows(
+    expected: &[BTreeSet<String>],
+    actual: &[BTreeSet<String>],
+) -> Vec<RelationDiff> {
+    COMPARED_RELATIONS
+        .iter()
+        .zip(expected.iter().zip(actual))
+        .map(|(&relation, (expected, actual))| RelationDiff {
+            relation,
+            added: actual.difference(expected).cloned().collect(),
+            removed: expected.difference(actual).cloned().collect(),
+        })
+        .collect()
+}
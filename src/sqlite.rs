@@ -0,0 +1,140 @@
+//! `export sqlite`: writes the input facts of a facts directory, and the
+//! relations that the analysis derives from them, into an SQLite database.
+//! Each relation is a table with a text column per atom, holding its name
+//! without the quotes, so that the relations can be joined and grouped
+//! in SQL, e.g. to count the points where each loan is live:
+//!
+//! ```sql
+//! SELECT loan, COUNT(*) FROM borrow_live_at GROUP BY loan;
+//! ```
+//!
+//! The derived relations named like an input relation, such as
+//! `region_live_at`, are written as `derived_<relation>`.
+
+use crate::dump::WithTables;
+use crate::facts::{AllFacts, Output};
+use crate::intern::InternerTables;
+use crate::tab_delim;
+use failure::{self, Error};
+use polonius_engine::{unquoted, Algorithm};
+use rusqlite::types::ToSql;
+use rusqlite::Connection;
+use std::path::Path;
+
+/// The columns of each input relation, in the order of its facts file.
+crate const INPUT_COLUMNS: &[(&str, &[&str])] = &[
+    ("borrow_region", &["region", "loan", "point"]),
+    ("universal_region", &["region"]),
+    ("cfg_edge", &["source", "target"]),
+    ("killed", &["loan", "point"]),
+    ("outlives", &["region1", "region2", "point"]),
+    ("region_live_at", &["region", "point"]),
+    ("invalidates", &["point", "loan"]),
+    ("var_used_at", &["var", "point"]),
+    ("var_defined_at", &["var", "point"]),
+    ("var_drop_used_at", &["var", "point"]),
+    ("use_of_var_derefs_origin", &["var", "region"]),
+    ("drop_of_var_derefs_origin", &["var", "region"]),
+    ("child_path", &["child", "parent"]),
+    ("path_is_var", &["path", "var"]),
+    ("path_assigned_at", &["path", "point"]),
+    ("path_moved_at", &["path", "point"]),
+    ("path_accessed_at", &["path", "point"]),
+    ("known_placeholder_subset", &["region1", "region2"]),
+    ("loan_borrows_path", &["loan", "path"]),
+];
+
+/// The columns of each derived relation, in the order `dump` writes its
+/// rows.
+crate const OUTPUT_COLUMNS: &[(&str, &[&str])] = &[
+    ("borrow_live_at", &["point", "loan"]),
+    ("restricts", &["point", "region", "loan"]),
+    ("restricts_anywhere", &["region", "loan"]),
+    ("region_live_at", &["point", "region"]),
+    ("invalidates", &["point", "loan"]),
+    ("errors", &["point", "loan"]),
+    ("subset", &["point", "region1", "region2"]),
+    ("subset_anywhere", &["region1", "region2"]),
+    ("move_errors", &["point", "path"]),
+    ("var_maybe_uninitialized_on_entry", &["point", "var"]),
+    ("subset_errors", &["point", "region1", "region2"]),
+];
+
+/// Writes the relations of `all_facts`, and all those that `algorithm`
+/// derives from them, into the database at `path`, replacing the tables
+/// of an earlier export.
+crate fn export(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    algorithm: Algorithm,
+    path: &Path,
+) -> Result<(), Error> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+
+    for (relation, rows) in tab_delim::named_relations(all_facts, tables) {
+        let rows: Vec<Vec<&str>> = rows
+            .into_iter()
+            .map(|row| row.into_iter().map(unquoted).collect())
+            .collect();
+        write_table(&transaction, relation, columns(INPUT_COLUMNS, relation)?, &rows)?;
+    }
+
+    let output = Output::compute(all_facts, algorithm, true);
+    for (relation, rows) in output.with_tables(tables).relations() {
+        let table = table_name(relation);
+        write_table(&transaction, &table, columns(OUTPUT_COLUMNS, relation)?, &rows)?;
+    }
+
+    transaction.commit()?;
+    Ok(())
+}
+
+/// The name of the table of the derived relation `relation`.
+crate fn table_name(relation: &str) -> String {
+    if INPUT_COLUMNS.iter().any(|&(input, _)| input == relation) {
+        format!("derived_{}", relation)
+    } else {
+        relation.to_string()
+    }
+}
+
+fn columns(
+    relations: &[(&str, &'static [&'static str])],
+    relation: &str,
+) -> Result<&'static [&'static str], Error> {
+    relations
+        .iter()
+        .find(|&&(name, _)| name == relation)
+        .map(|&(_, columns)| columns)
+        .ok_or_else(|| failure::err_msg(format!("no columns for the relation `{}`", relation)))
+}
+
+/// Creates the table `table`, dropping any earlier one, and inserts
+/// `rows` into it.
+fn write_table(
+    connection: &Connection,
+    table: &str,
+    columns: &[&str],
+    rows: &[Vec<&str>],
+) -> Result<(), Error> {
+    let definitions: Vec<String> =
+        columns.iter().map(|column| format!("{} TEXT NOT NULL", column)).collect();
+    connection.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {table}; CREATE TABLE {table} ({definitions});",
+        table = table,
+        definitions = definitions.join(", "),
+    ))?;
+
+    let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
+    let mut statement = connection.prepare(&format!(
+        "INSERT INTO {} VALUES ({})",
+        table,
+        placeholders.join(", "),
+    ))?;
+    for row in rows {
+        let parameters: Vec<&ToSql> = row.iter().map(|name| name as &ToSql).collect();
+        statement.execute(&parameters)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use crate::facts::AllFacts;
+use histo::Histogram;
+use polonius_engine::RelationMemory;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
+
+/// Summary statistics about a set of input facts, used to triage which
+/// inputs are interesting to benchmark and where fact generation blows up.
+crate struct FactStats {
+    crate relation_memory: Vec<RelationMemory>,
+
+    /// The sizes of the relations that an analysis of the facts derived,
+    /// largest first, if there was one.
+    crate derived_relation_memory: Vec<RelationMemory>,
+
+    /// The number of distinct atoms of each kind mentioned by any relation.
+    crate points: usize,
+    crate regions: usize,
+    crate loans: usize,
+    crate variables: usize,
+    crate move_paths: usize,
+
+    crate cfg_nodes: usize,
+    crate cfg_edges: usize,
+
+    /// The out-degree of each CFG node that has at least one successor.
+    crate successor_counts: Vec<usize>,
+}
+
+impl FactStats {
+    /// The average number of successors of the CFG nodes that have any.
+    crate fn average_branching_factor(&self) -> f64 {
+        if self.successor_counts.is_empty() {
+            0.0
+        } else {
+            self.cfg_edges as f64 / self.successor_counts.len() as f64
+        }
+    }
+}
+
+/// The number of tuples of each relation, in the order of `AllFacts`.
+crate fn relation_sizes(all_facts: &AllFacts) -> Vec<(&'static str, usize)> {
+    macro_rules! relation_sizes {
+        ($($t:ident,)*) => {
+            vec![$((stringify!($t), all_facts.$t.len()),)*]
+        }
+    }
+
+    relation_sizes! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_used_at,
+        var_defined_at,
+        var_drop_used_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        known_placeholder_subset,
+        loan_borrows_path,
+    }
+}
+
+crate fn compute_stats(all_facts: &AllFacts) -> FactStats {
+    let relation_memory = all_facts.relation_memory();
+
+    let mut points = FxHashSet::default();
+    let mut regions = FxHashSet::default();
+    let mut loans = FxHashSet::default();
+    let mut variables = FxHashSet::default();
+    let mut move_paths = FxHashSet::default();
+
+    for &(r, l, p) in &all_facts.borrow_region {
+        regions.insert(r);
+        loans.insert(l);
+        points.insert(p);
+    }
+    regions.extend(all_facts.universal_region.iter().cloned());
+    for &(p, q) in &all_facts.cfg_edge {
+        points.insert(p);
+        points.insert(q);
+    }
+    for &(l, p) in &all_facts.killed {
+        loans.insert(l);
+        points.insert(p);
+    }
+    for &(r1, r2, p) in &all_facts.outlives {
+        regions.insert(r1);
+        regions.insert(r2);
+        points.insert(p);
+    }
+    for &(r, p) in &all_facts.region_live_at {
+        regions.insert(r);
+        points.insert(p);
+    }
+    for &(p, l) in &all_facts.invalidates {
+        points.insert(p);
+        loans.insert(l);
+    }
+    for var_points in &[
+        &all_facts.var_used_at,
+        &all_facts.var_defined_at,
+        &all_facts.var_drop_used_at,
+    ] {
+        for &(v, p) in var_points.iter() {
+            variables.insert(v);
+            points.insert(p);
+        }
+    }
+    for var_regions in &[
+        &all_facts.use_of_var_derefs_origin,
+        &all_facts.drop_of_var_derefs_origin,
+    ] {
+        for &(v, r) in var_regions.iter() {
+            variables.insert(v);
+            regions.insert(r);
+        }
+    }
+    for &(child, parent) in &all_facts.child_path {
+        move_paths.insert(child);
+        move_paths.insert(parent);
+    }
+    for &(m, v) in &all_facts.path_is_var {
+        move_paths.insert(m);
+        variables.insert(v);
+    }
+    for path_points in &[
+        &all_facts.path_assigned_at,
+        &all_facts.path_moved_at,
+        &all_facts.path_accessed_at,
+    ] {
+        for &(m, p) in path_points.iter() {
+            move_paths.insert(m);
+            points.insert(p);
+        }
+    }
+    for &(r1, r2) in &all_facts.known_placeholder_subset {
+        regions.insert(r1);
+        regions.insert(r2);
+    }
+    for &(l, m) in &all_facts.loan_borrows_path {
+        loans.insert(l);
+        move_paths.insert(m);
+    }
+
+    let mut cfg_nodes = FxHashSet::default();
+    let mut successors = FxHashMap::default();
+    for &(p, q) in &all_facts.cfg_edge {
+        cfg_nodes.insert(p);
+        cfg_nodes.insert(q);
+        *successors.entry(p).or_insert(0) += 1;
+    }
+
+    FactStats {
+        relation_memory,
+        derived_relation_memory: Vec::new(),
+        points: points.len(),
+        regions: regions.len(),
+        loans: loans.len(),
+        variables: variables.len(),
+        move_paths: move_paths.len(),
+        cfg_nodes: cfg_nodes.len(),
+        cfg_edges: all_facts.cfg_edge.len(),
+        successor_counts: successors.values().cloned().collect(),
+    }
+}
+
+impl fmt::Display for FactStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# relations")?;
+        write_relation_memory(f, &self.relation_memory)?;
+        if !self.derived_relation_memory.is_empty() {
+            writeln!(f, "\n# derived relations")?;
+            write_relation_memory(f, &self.derived_relation_memory)?;
+        }
+
+        writeln!(f, "\n# atoms")?;
+        writeln!(f, "{:<20} {}", "points", self.points)?;
+        writeln!(f, "{:<20} {}", "regions", self.regions)?;
+        writeln!(f, "{:<20} {}", "loans", self.loans)?;
+        writeln!(f, "{:<20} {}", "variables", self.variables)?;
+        writeln!(f, "{:<20} {}", "move paths", self.move_paths)?;
+
+        writeln!(f, "\n# cfg")?;
+        writeln!(f, "{:<20} {}", "nodes", self.cfg_nodes)?;
+        writeln!(f, "{:<20} {}", "edges", self.cfg_edges)?;
+        writeln!(
+            f,
+            "{:<20} {:.3}",
+            "branching factor",
+            self.average_branching_factor()
+        )?;
+
+        if !self.successor_counts.is_empty() {
+            let mut histogram = Histogram::with_buckets(10);
+            for &count in &self.successor_counts {
+                histogram.add(count as u64);
+            }
+            writeln!(f, "\n# successors per node")?;
+            write!(f, "{}", histogram)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the tuples and the estimated bytes of each relation of `memory`.
+fn write_relation_memory(f: &mut fmt::Formatter, memory: &[RelationMemory]) -> fmt::Result {
+    for relation in memory {
+        writeln!(
+            f,
+            "{:<20} {} ({} bytes)",
+            relation.relation, relation.tuples, relation.bytes
+        )?;
+    }
+    Ok(())
+}
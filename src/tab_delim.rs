@@ -1,32 +1,219 @@
+use crate::dump::Atom;
 use crate::facts::AllFacts;
-use crate::intern::{InternTo, InternerTables};
-use std::fs::File;
+use crate::intern::{self, InternTo, InternerTables};
+use crate::manifest::{self, Manifest};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use memchr::{memchr, memchr_iter};
+use memmap::Mmap;
+use std::cell::RefCell;
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{self, prelude::*};
-use std::path::Path;
-use std::process;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::thread;
 
-trait FromTabDelimited<'input>: Sized {
+crate trait FromTabDelimited<'input>: Sized {
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
     ) -> Option<Self>;
 }
 
+/// An error encountered while loading a facts file, carrying enough
+/// context to point at the offending input.
+#[derive(Debug)]
+crate struct LoadError {
+    crate path: PathBuf,
+
+    /// The 1-based line and column where the error occurred, when it was
+    /// caused by the contents of the file rather than by reading it.
+    crate line: Option<usize>,
+    crate column: Option<usize>,
+
+    crate kind: LoadErrorKind,
+}
+
+#[derive(Debug)]
+crate enum LoadErrorKind {
+    Io(io::Error),
+    MissingColumn,
+    ExtraColumn,
+    InvalidManifest(String),
+    UnsupportedSchema(u32),
+    ArityMismatch {
+        relation: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl LoadError {
+    crate fn new(path: &Path, kind: LoadErrorKind) -> Self {
+        LoadError {
+            path: path.to_owned(),
+            line: None,
+            column: None,
+            kind,
+        }
+    }
+
+    crate fn io(path: &Path, error: io::Error) -> Self {
+        LoadError::new(path, LoadErrorKind::Io(error))
+    }
+
+    fn parse(path: &Path, line: usize, column: usize, kind: LoadErrorKind) -> Self {
+        LoadError {
+            path: path.to_owned(),
+            line: Some(line),
+            column: Some(column),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            LoadErrorKind::Io(ref error) => write!(f, "{}", error)?,
+            LoadErrorKind::MissingColumn => write!(f, "missing data")?,
+            LoadErrorKind::ExtraColumn => write!(f, "extra data")?,
+            LoadErrorKind::InvalidManifest(ref error) => write!(f, "invalid manifest: {}", error)?,
+            LoadErrorKind::UnsupportedSchema(version) => {
+                write!(f, "unsupported fact schema version {}", version)?
+            }
+            LoadErrorKind::ArityMismatch {
+                ref relation,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` has {} columns, but {} were expected",
+                relation, found, expected
+            )?,
+        }
+
+        write!(f, " in `{}`", self.path.display())?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at line {}, column {}", line, column)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for LoadError {
+    fn description(&self) -> &str {
+        match self.kind {
+            LoadErrorKind::Io(ref error) => error.description(),
+            LoadErrorKind::MissingColumn => "missing data",
+            LoadErrorKind::ExtraColumn => "extra data",
+            LoadErrorKind::InvalidManifest(_) => "invalid manifest",
+            LoadErrorKind::UnsupportedSchema(_) => "unsupported fact schema version",
+            LoadErrorKind::ArityMismatch { .. } => "relation arity mismatch",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self.kind {
+            LoadErrorKind::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Options controlling how a facts directory is loaded.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+crate struct LoadOptions {
+    /// Fail when a relation file is missing, instead of warning and
+    /// treating the relation as empty.
+    crate strict: bool,
+
+    /// Parse each relation file on its own thread.
+    crate parallel: bool,
+
+    /// Parse uncompressed relation files by memory-mapping them.
+    crate mmap: bool,
+}
+
 crate fn load_tab_delimited_facts(
     tables: &mut InternerTables,
     facts_dir: &Path,
-) -> io::Result<AllFacts> {
+) -> Result<AllFacts, LoadError> {
+    load_tab_delimited_facts_with(tables, facts_dir, LoadOptions::default())
+}
+
+crate fn load_tab_delimited_facts_with(
+    tables: &mut InternerTables,
+    facts_dir: &Path,
+    options: LoadOptions,
+) -> Result<AllFacts, LoadError> {
+    let manifest = manifest::read_manifest(facts_dir)?;
+    if !options.strict {
+        for (relation, facts_file) in missing_required_files(&manifest, facts_dir) {
+            eprintln!(
+                "warning: `{}` not found, treating `{}` as empty",
+                facts_file.display(),
+                relation
+            );
+        }
+    }
+    let file_size = |relation: &'static str| {
+        let (facts_file, _) = relation_file(&manifest, facts_dir, relation);
+        fs::metadata(resolve_facts_file(&facts_file)).map_or(0, |metadata| metadata.len())
+    };
+    tables.reserve_for_file_sizes(file_size("cfg_edge"), file_size("borrow_region"));
+
     macro_rules! load_facts {
         (from ($tables:expr, $facts_dir:expr) load AllFacts { $($t:ident,)* }) => {
-            Ok(AllFacts {
+            if options.parallel {
+                // Each thread interns into its own tables, which are then
+                // merged into `tables` in relation order, and the rows are
+                // remapped by index. Merging the tables interns their names
+                // in the order the sequential load does, so the resulting
+                // indices are identical, and only hashes each name once.
                 $(
-                    $t: {
-                        let filename = format!("{}.facts", stringify!($t));
-                        let facts_file = $facts_dir.join(&filename);
-                        load_tab_delimited_file($tables, &facts_file)?
-                    },
+                    let $t = {
+                        let (facts_file, columns) =
+                            relation_file(&manifest, $facts_dir, stringify!($t));
+                        thread::spawn(move || -> Result<_, LoadError> {
+                            let mut relation_tables = InternerTables::new();
+                            let rows = load_relation(
+                                &mut relation_tables,
+                                &facts_file,
+                                columns,
+                                options,
+                            )?;
+                            Ok((relation_tables, rows))
+                        })
+                    };
                 )*
-            })
+
+                Ok(AllFacts {
+                    $(
+                        $t: {
+                            let (relation_tables, rows) =
+                                $t.join().expect("fact loading thread panicked")?;
+                            let remapping = $tables.merge(&relation_tables);
+                            intern::remap_rows(rows, &remapping).into()
+                        },
+                    )*
+                })
+            } else {
+                Ok(AllFacts {
+                    $(
+                        $t: {
+                            let (facts_file, columns) =
+                                relation_file(&manifest, $facts_dir, stringify!($t));
+                            let rows = load_relation($tables, &facts_file, columns, options)?;
+                            rows.into()
+                        },
+                    )*
+                })
+            }
         }
     }
 
@@ -39,36 +226,551 @@ crate fn load_tab_delimited_facts(
             outlives,
             region_live_at,
             invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        }
+    }
+}
+
+/// The file that holds `relation` in `facts_dir`: the one `manifest` names,
+/// or else the file of one of its aliases, with the columns of the alias
+/// that make up its rows.
+fn relation_file(
+    manifest: &Manifest,
+    facts_dir: &Path,
+    relation: &'static str,
+) -> (PathBuf, Option<&'static [usize]>) {
+    let stem = manifest.relation_file_stem(relation);
+    let facts_file = facts_dir.join(format!("{}.facts", stem));
+    if !facts_file_exists(&facts_file) {
+        for alias in manifest::aliases_of(relation) {
+            let alias_file = facts_dir.join(format!("{}.facts", alias.name));
+            if facts_file_exists(&alias_file) {
+                return (alias_file, alias.columns);
+            }
+        }
+    }
+    (facts_file, None)
+}
+
+/// The required relations, see `manifest::required_relations`, whose files
+/// are missing from `facts_dir`, with the file each was looked for in.
+crate fn missing_required_files(
+    manifest: &Manifest,
+    facts_dir: &Path,
+) -> Vec<(&'static str, PathBuf)> {
+    manifest::required_relations()
+        .map(|relation| (relation, relation_file(manifest, facts_dir, relation).0))
+        .filter(|&(_, ref facts_file)| !facts_file_exists(facts_file))
+        .collect()
+}
+
+fn load_relation<Row>(
+    tables: &mut InternerTables,
+    facts_file: &Path,
+    columns: Option<&[usize]>,
+    options: LoadOptions,
+) -> Result<Vec<Row>, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    // The missing files of the required relations were warned about
+    // already, and the optional ones are often missing.
+    if !options.strict && !facts_file_exists(facts_file) {
+        return Ok(Vec::new());
+    }
+
+    if let Some(columns) = columns {
+        load_reshaped_file(tables, facts_file, columns)
+    } else if options.mmap && !is_gzipped(&resolve_facts_file(facts_file)) {
+        load_mapped_file(tables, facts_file)
+    } else {
+        load_tab_delimited_file(tables, facts_file)
+    }
+}
+
+crate trait ToTabDelimited {
+    fn push_columns(&self, tables: &'a InternerTables, columns: &mut Vec<&'a str>);
+}
+
+/// Writes `all_facts` into `facts_dir` as one `.facts` file per relation,
+/// in the same format that `load_tab_delimited_facts` reads.
+crate fn write_tab_delimited_facts(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    facts_dir: &Path,
+) -> io::Result<()> {
+    write_facts(all_facts, tables, facts_dir, false)
+}
+
+/// Like `write_tab_delimited_facts`, but writes gzip-compressed
+/// `.facts.gz` files, which the loader reads transparently.
+crate fn write_compressed_tab_delimited_facts(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    facts_dir: &Path,
+) -> io::Result<()> {
+    write_facts(all_facts, tables, facts_dir, true)
+}
+
+fn write_facts(
+    all_facts: &AllFacts,
+    tables: &InternerTables,
+    facts_dir: &Path,
+    compressed: bool,
+) -> io::Result<()> {
+    fs::create_dir_all(facts_dir)?;
+
+    macro_rules! write_facts {
+        (to ($tables:expr, $facts_dir:expr) write AllFacts($all_facts:expr) { $($t:ident,)* }) => {
+            $(
+                {
+                    let filename = if compressed {
+                        format!("{}.facts.gz", stringify!($t))
+                    } else {
+                        format!("{}.facts", stringify!($t))
+                    };
+                    let facts_file = $facts_dir.join(&filename);
+                    write_tab_delimited_file($tables, &$all_facts.$t, &facts_file, compressed)?;
+                }
+            )*
+        }
+    }
+
+    write_facts! {
+        to (tables, facts_dir) write AllFacts(all_facts) {
+            borrow_region,
+            universal_region,
+            cfg_edge,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_used_at,
+            var_defined_at,
+            var_drop_used_at,
+            use_of_var_derefs_origin,
+            drop_of_var_derefs_origin,
+            child_path,
+            path_is_var,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            known_placeholder_subset,
+            loan_borrows_path,
+        }
+    }
+
+    manifest::write_manifest(facts_dir)
+}
+
+/// The relations of `all_facts`, named like their facts files, with the
+/// rows of each as the quoted names of their atoms.
+crate fn named_relations(
+    all_facts: &'a AllFacts,
+    tables: &'a InternerTables,
+) -> Vec<(&'static str, Vec<Vec<&'a str>>)> {
+    macro_rules! named_relations {
+        ($($t:ident,)*) => {
+            vec![$((stringify!($t), named_rows(tables, &all_facts.$t)),)*]
+        }
+    }
+
+    named_relations! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_used_at,
+        var_defined_at,
+        var_drop_used_at,
+        use_of_var_derefs_origin,
+        drop_of_var_derefs_origin,
+        child_path,
+        path_is_var,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        known_placeholder_subset,
+        loan_borrows_path,
+    }
+}
+
+fn named_rows<Row>(tables: &'a InternerTables, rows: &[Row]) -> Vec<Vec<&'a str>>
+where
+    Row: ToTabDelimited,
+{
+    rows.iter()
+        .map(|row| {
+            let mut columns = Vec::new();
+            row.push_columns(tables, &mut columns);
+            columns
+        })
+        .collect()
+}
+
+fn write_tab_delimited_file<Row>(
+    tables: &InternerTables,
+    rows: &[Row],
+    path: &Path,
+    compressed: bool,
+) -> io::Result<()>
+where
+    Row: ToTabDelimited,
+{
+    let writer = io::BufWriter::new(File::create(path)?);
+    if compressed {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        write_rows(tables, rows, &mut encoder)?;
+        encoder.finish()?.flush()
+    } else {
+        let mut writer = writer;
+        write_rows(tables, rows, &mut writer)?;
+        writer.flush()
+    }
+}
+
+fn write_rows<Row>(
+    tables: &InternerTables,
+    rows: &[Row],
+    writer: &mut dyn Write,
+) -> io::Result<()>
+where
+    Row: ToTabDelimited,
+{
+    let mut columns = Vec::new();
+    for row in rows {
+        columns.clear();
+        row.push_columns(tables, &mut columns);
+        writeln!(writer, "{}", columns.join("\t"))?;
+    }
+    Ok(())
+}
+
+fn is_gzipped(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension == "gz")
+}
+
+/// The file that actually holds the facts for `path`: `path` itself if
+/// it exists, or else its `.gz` sibling (e.g. `cfg_edge.facts.gz` for
+/// `cfg_edge.facts`) if that exists.
+fn resolve_facts_file(path: &Path) -> PathBuf {
+    if !path.exists() && !is_gzipped(path) {
+        let mut gz_path = path.as_os_str().to_owned();
+        gz_path.push(".gz");
+        let gz_path = PathBuf::from(gz_path);
+        if gz_path.exists() {
+            return gz_path;
+        }
+    }
+    path.to_owned()
+}
+
+fn facts_file_exists(path: &Path) -> bool {
+    resolve_facts_file(path).exists()
+}
+
+/// Opens a facts file for reading, decompressing it if it is gzipped.
+fn open_facts_file(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let path = resolve_facts_file(path);
+    let file = File::open(&path)?;
+    if is_gzipped(&path) {
+        Ok(Box::new(io::BufReader::new(GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(io::BufReader::new(file)))
+    }
+}
+
+/// The largest capacity that the scratch buffer of a thread keeps between
+/// two files: the buffer of a bigger file is freed once it is loaded, so
+/// that the biggest file of a dump doesn't keep its memory for the rest of
+/// the life of the thread.
+crate const MAX_SCRATCH_BYTES: usize = 4 << 20;
+
+thread_local! {
+    /// The contents of the last file loaded by `load_tab_delimited_file` on
+    /// this thread, whose capacity is reused for the next one.
+    static SCRATCH: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+/// The capacity of the scratch buffer of this thread.
+#[cfg(test)]
+crate fn scratch_capacity() -> usize {
+    SCRATCH.with(|scratch| scratch.borrow().capacity())
+}
+
+/// Loads a facts file by reading all its bytes into a scratch buffer, and
+/// scanning them for the newlines and tabs with `memchr`: no `String` is
+/// allocated per line, and the rows are allocated once, for the number of
+/// lines of the file.
+fn load_tab_delimited_file<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+) -> Result<Vec<Row>, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        scratch.clear();
+        let rows = parse_tab_delimited_file(tables, path, &mut scratch);
+        if scratch.capacity() > MAX_SCRATCH_BYTES {
+            *scratch = Vec::new();
         }
+        rows
+    })
+}
+
+fn parse_tab_delimited_file<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+    scratch: &mut Vec<u8>,
+) -> Result<Vec<Row>, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    let io_error = |error| LoadError::io(path, error);
+    open_facts_file(path)
+        .and_then(|mut reader| reader.read_to_end(scratch))
+        .map_err(io_error)?;
+    let contents = str::from_utf8(scratch)
+        .map_err(|error| io_error(io::Error::new(io::ErrorKind::InvalidData, error)))?;
+
+    let mut rows = Vec::with_capacity(memchr_iter(b'\n', contents.as_bytes()).count() + 1);
+    for (index, line) in Lines::new(contents).enumerate() {
+        rows.push(parse_row(tables, path, index + 1, line)?);
     }
+    Ok(rows)
 }
 
-fn load_tab_delimited_file<Row>(tables: &mut InternerTables, path: &Path) -> io::Result<Vec<Row>>
+/// Opens a single tab-delimited file for streaming: rows are parsed and
+/// interned one line at a time as the iterator is advanced, so callers
+/// can filter, deduplicate, or build relations on the fly instead of
+/// materializing every row first.
+crate fn stream_tab_delimited_file<Row>(
+    tables: &'t mut InternerTables,
+    path: &Path,
+) -> Result<TabDelimitedRows<'t, Row>, LoadError>
 where
     Row: for<'input> FromTabDelimited<'input>,
 {
-    let file = File::open(path)?;
-    let mut result = Vec::new();
-    for (index, line) in io::BufReader::new(file).lines().enumerate() {
-        let line = line?;
-        let mut columns = line.split("\t");
-        let row = match FromTabDelimited::parse(tables, &mut columns) {
+    let reader = open_facts_file(path).map_err(|error| LoadError::io(path, error))?;
+    Ok(TabDelimitedRows {
+        tables,
+        path: path.to_owned(),
+        reader,
+        line: String::new(),
+        line_number: 0,
+        row: PhantomData,
+    })
+}
+
+crate struct TabDelimitedRows<'t, Row> {
+    tables: &'t mut InternerTables,
+    path: PathBuf,
+    reader: Box<dyn BufRead>,
+
+    /// Scratch buffer reused for every line of the file.
+    line: String,
+    line_number: usize,
+    row: PhantomData<Row>,
+}
+
+impl<Row> Iterator for TabDelimitedRows<'t, Row>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    type Item = Result<Row, LoadError>;
+
+    fn next(&mut self) -> Option<Result<Row, LoadError>> {
+        self.line.clear();
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(error) => return Some(Err(LoadError::io(&self.path, error))),
+        }
+        self.line_number += 1;
+
+        let line = self.line.trim_right_matches(&['\n', '\r'][..]);
+        Some(parse_row(self.tables, &self.path, self.line_number, line))
+    }
+}
+
+/// Parses and interns a single line of a facts file.
+fn parse_row<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+    line_number: usize,
+    line: &str,
+) -> Result<Row, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    let column_count = || Fields::new(line).count();
+    let mut columns = Fields::new(line);
+    let row = match FromTabDelimited::parse(tables, &mut columns) {
+        None => {
+            return Err(LoadError::parse(
+                path,
+                line_number,
+                column_count() + 1,
+                LoadErrorKind::MissingColumn,
+            ));
+        }
+
+        Some(v) => v,
+    };
+
+    if columns.next().is_some() {
+        let extra_columns = 1 + columns.count();
+        return Err(LoadError::parse(
+            path,
+            line_number,
+            column_count() - extra_columns + 1,
+            LoadErrorKind::ExtraColumn,
+        ));
+    }
+
+    Ok(row)
+}
+
+/// The lines of the contents of a facts file, without their `\n` or
+/// `\r\n`, like `str::lines`, but found with `memchr`.
+struct Lines<'input> {
+    rest: &'input str,
+}
+
+impl Lines<'input> {
+    fn new(contents: &'input str) -> Self {
+        Lines { rest: contents }
+    }
+}
+
+impl Iterator for Lines<'input> {
+    type Item = &'input str;
+
+    fn next(&mut self) -> Option<&'input str> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let line = match memchr(b'\n', self.rest.as_bytes()) {
+            Some(end) => {
+                let line = &self.rest[..end];
+                self.rest = &self.rest[end + 1..];
+                line
+            }
             None => {
-                eprintln!("error parsing line {} of `{}`", index + 1, path.display());
-                process::exit(1);
+                let line = self.rest;
+                self.rest = "";
+                line
             }
-
-            Some(v) => v,
         };
+        Some(line.trim_right_matches('\r'))
+    }
+}
+
+/// The tab-separated fields of a line, like `line.split('\t')`, but found
+/// with `memchr`.
+struct Fields<'input> {
+    /// The fields that weren't returned yet, or `None` after the last one.
+    rest: Option<&'input str>,
+}
+
+impl Fields<'input> {
+    fn new(line: &'input str) -> Self {
+        Fields { rest: Some(line) }
+    }
+}
+
+impl Iterator for Fields<'input> {
+    type Item = &'input str;
 
-        if columns.next().is_some() {
-            eprintln!("extra data on line {} of `{}`", index + 1, path.display());
-            process::exit(1);
+    fn next(&mut self) -> Option<&'input str> {
+        let rest = self.rest?;
+        match memchr(b'\t', rest.as_bytes()) {
+            Some(end) => {
+                self.rest = Some(&rest[end + 1..]);
+                Some(&rest[..end])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
         }
+    }
+}
+
+/// Loads the facts file of an alias whose rows have other columns than
+/// those of the relation, keeping the `columns` of each row, in order.
+fn load_reshaped_file<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+    columns: &[usize],
+) -> Result<Vec<Row>, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    let reader = open_facts_file(path).map_err(|error| LoadError::io(path, error))?;
+    let mut rows = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| LoadError::io(path, error))?;
+        let fields: Vec<&str> = line.split('\t').collect();
+        let mut kept = Vec::with_capacity(columns.len());
+        for &column in columns {
+            match fields.get(column) {
+                Some(&field) => kept.push(field),
+                None => {
+                    let kind = LoadErrorKind::MissingColumn;
+                    return Err(LoadError::parse(path, index + 1, fields.len() + 1, kind));
+                }
+            }
+        }
+        rows.push(parse_row(tables, path, index + 1, &kept.join("\t"))?);
+    }
+    Ok(rows)
+}
+
+/// Loads a facts file by memory-mapping it and splitting the mapped text
+/// in place. Unlike `stream_tab_delimited_file`, no `String` is allocated
+/// per line: the only allocations are made by the interner, when it sees
+/// an atom for the first time.
+fn load_mapped_file<Row>(
+    tables: &mut InternerTables,
+    path: &Path,
+) -> Result<Vec<Row>, LoadError>
+where
+    Row: for<'input> FromTabDelimited<'input>,
+{
+    let io_error = |error| LoadError::io(path, error);
+
+    let file = File::open(path).map_err(io_error)?;
 
-        result.push(row);
+    // Mapping an empty file is an error on some platforms.
+    if file.metadata().map_err(io_error)?.len() == 0 {
+        return Ok(Vec::new());
     }
-    Ok(result)
+
+    let mmap = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+    let contents = str::from_utf8(&mmap)
+        .map_err(|error| io_error(io::Error::new(io::ErrorKind::InvalidData, error)))?;
+
+    Lines::new(contents)
+        .enumerate()
+        .map(|(index, line)| parse_row(tables, path, index + 1, line))
+        .collect()
 }
 
 impl<T> FromTabDelimited<'input> for T
@@ -134,3 +836,48 @@ where
         Some((a, b, c, d))
     }
 }
+
+impl<T: Atom> ToTabDelimited for T {
+    fn push_columns(&self, tables: &'a InternerTables, columns: &mut Vec<&'a str>) {
+        columns.push(T::table(tables).untern(*self));
+    }
+}
+
+impl<A, B> ToTabDelimited for (A, B)
+where
+    A: ToTabDelimited,
+    B: ToTabDelimited,
+{
+    fn push_columns(&self, tables: &'a InternerTables, columns: &mut Vec<&'a str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+    }
+}
+
+impl<A, B, C> ToTabDelimited for (A, B, C)
+where
+    A: ToTabDelimited,
+    B: ToTabDelimited,
+    C: ToTabDelimited,
+{
+    fn push_columns(&self, tables: &'a InternerTables, columns: &mut Vec<&'a str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+        self.2.push_columns(tables, columns);
+    }
+}
+
+impl<A, B, C, D> ToTabDelimited for (A, B, C, D)
+where
+    A: ToTabDelimited,
+    B: ToTabDelimited,
+    C: ToTabDelimited,
+    D: ToTabDelimited,
+{
+    fn push_columns(&self, tables: &'a InternerTables, columns: &mut Vec<&'a str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+        self.2.push_columns(tables, columns);
+        self.3.push_columns(tables, columns);
+    }
+}
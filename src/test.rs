@@ -1,12 +1,57 @@
 #![cfg(test)]
 
-use crate::facts::{Loan, Point};
+use crate::anonymize;
+use crate::baseline::{self, Baseline, BaselineChanges, BaselineError};
+use crate::batch::{self, FunctionFilter};
+use crate::bench;
+use crate::cache;
+use crate::cargo;
+use crate::cli::{self, Outcome};
+use crate::diff;
+use crate::differential::{self, DifferentialReport, Disagreement, Extent, Function};
+use crate::dump::{self, WithTables, WriteFacts};
+use crate::explain;
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region, Variable};
+use crate::graph;
+use crate::html;
 use crate::intern;
+use crate::json;
+use crate::lsp;
+use crate::manifest;
+use crate::memory;
+use crate::progress::Progress;
+use crate::renumber;
+use crate::repl::Repl;
+use crate::sarif;
+use crate::serve;
+use crate::souffle;
+use crate::sqlite;
+use crate::stats;
 use crate::tab_delim;
+use crate::watch;
 use failure::Error;
-use polonius_engine::{Algorithm, Output};
+use polonius_engine::{
+    generator, AllFactsBuilder, Algorithm, Atom, AtomBitsets, Budget, BudgetExceeded, ErrorsDiff,
+    FactsDelta, IncrementalAnalysis, LoanBitsets, OriginStep, Output, PreprocessLevel,
+    Preprocessor, QueryEngine, SolverObserver, SortedIndex, SortedRelation, Tuple,
+};
+use rusqlite::Connection;
 use rustc_hash::FxHashMap;
-use std::path::Path;
+use serde_json;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use structopt::StructOpt;
 
 fn test_fn(dir_name: &str, fn_name: &str) -> Result<(), Error> {
     do catch {
@@ -20,10 +65,40 @@ fn test_fn(dir_name: &str, fn_name: &str) -> Result<(), Error> {
         let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
         let naive = Output::compute(&all_facts, Algorithm::Naive, false);
         let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, true);
-        assert_eq!(naive.borrow_live_at, opt.borrow_live_at);
+        assert_eq!(naive.borrow_live_at(), opt.borrow_live_at());
+        assert_eq!(naive.errors, opt.errors);
     }
 }
 
+/// The facts generated from `params` with each of the seeds `0..seeds`,
+/// with their seed.
+fn generated_facts(
+    seeds: u64,
+    params: generator::GeneratorParams,
+) -> impl Iterator<Item = (u64, AllFacts)> {
+    (0..seeds).map(move |seed| {
+        let params = generator::GeneratorParams {
+            seed,
+            ..params.clone()
+        };
+        (seed, generator::generate(&params))
+    })
+}
+
+/// A new empty directory named after `name`, in the temporary directory,
+/// that no other test, or run of the tests, writes to.
+fn temp_dir(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = env::temp_dir().join(format!(
+        "polonius-test-{}-{}-{}",
+        name,
+        process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ));
+    fs::create_dir_all(&dir).expect("a temporary directory");
+    dir
+}
+
 macro_rules! tests {
     ($($name:ident($dir:expr, $fn:expr),)*) => {
         $(
@@ -60,6 +135,29 @@ fn test_insensitive_errors() -> Result<(), Error> {
     }
 }
 
+#[test]
+fn test_naive_errors() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // `l0` is still live at `p2`, where it is invalidated.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let naive = Output::compute(&all_facts, Algorithm::Naive, false);
+    let mut expected = FxHashMap::default();
+    expected.insert(p2, vec![l0]);
+    assert_eq!(naive.errors, expected);
+}
+
 #[test]
 fn test_sensitive_passes_issue_47680() -> Result<(), Error> {
     do catch {
@@ -75,3 +173,3159 @@ fn test_sensitive_passes_issue_47680() -> Result<(), Error> {
         assert!(sensitive.errors.is_empty());
     }
 }
+
+#[test]
+fn test_json_facts() -> Result<(), Error> {
+    do catch {
+        let document = r#"{
+            "borrow_region": [["'a", "bw0", "Mid(bb0[0])"]],
+            "universal_region": ["'static"],
+            "cfg_edge": [["Start(bb0[0])", "Mid(bb0[0])"], ["Mid(bb0[0])", "Start(bb0[1])"]],
+            "killed": [],
+            "outlives": [["'a", "'b", "Mid(bb0[0])"]],
+            "region_live_at": [["'b", "Start(bb0[1])"]],
+            "invalidates": [["Start(bb0[1])", "bw0"]]
+        }"#;
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = json::read_json_facts(tables, document.as_bytes())?;
+
+        assert_eq!(all_facts.borrow_region.len(), 1);
+        assert_eq!(all_facts.universal_region.len(), 1);
+        assert_eq!(all_facts.cfg_edge.len(), 2);
+        assert!(all_facts.killed.is_empty());
+        assert_eq!(all_facts.invalidates[0].0, all_facts.cfg_edge[1].1);
+        assert_eq!(tables.points.untern(all_facts.cfg_edge[1].1), "Start(bb0[1])");
+    }
+}
+
+#[test]
+fn test_write_facts_round_trip() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let output_dir = temp_dir("write-facts");
+        tab_delim::write_tab_delimited_facts(&all_facts, tables, &output_dir)?;
+
+        let reloaded_tables = &mut intern::InternerTables::new();
+        let reloaded = tab_delim::load_tab_delimited_facts(reloaded_tables, &output_dir)?;
+        assert_eq!(all_facts.borrow_region, reloaded.borrow_region);
+        assert_eq!(all_facts.universal_region, reloaded.universal_region);
+        assert_eq!(all_facts.cfg_edge, reloaded.cfg_edge);
+        assert_eq!(all_facts.killed, reloaded.killed);
+        assert_eq!(all_facts.outlives, reloaded.outlives);
+        assert_eq!(all_facts.region_live_at, reloaded.region_live_at);
+        assert_eq!(all_facts.invalidates, reloaded.invalidates);
+    }
+}
+
+#[test]
+fn test_stream_facts_matches_load() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let region_live_at_file = facts_dir.join("region_live_at.facts");
+        let streamed: Vec<(Region, Point)> =
+            tab_delim::stream_tab_delimited_file(tables, &region_live_at_file)?
+                .collect::<Result<_, _>>()?;
+        assert_eq!(streamed, all_facts.region_live_at);
+    }
+}
+
+#[test]
+fn test_load_error_location() -> Result<(), Error> {
+    do catch {
+        let facts_dir = temp_dir("load-error");
+        let cfg_edge_file = facts_dir.join("cfg_edge.facts");
+        fs::write(&cfg_edge_file, "\"a\"\t\"b\"\n\"b\"\n")?;
+
+        let tables = &mut intern::InternerTables::new();
+        let result: Result<Vec<(Point, Point)>, _> =
+            tab_delim::stream_tab_delimited_file(tables, &cfg_edge_file)?.collect();
+        let error = result.unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(2));
+    }
+}
+
+#[test]
+fn test_load_frees_big_scratch_buffers() -> Result<(), Error> {
+    do catch {
+        let facts_dir = temp_dir("big-scratch");
+        let row = "\"'a\"\n";
+        let rows = tab_delim::MAX_SCRATCH_BYTES / row.len() + 1;
+        fs::write(facts_dir.join("universal_region.facts"), row.repeat(rows))?;
+
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        assert_eq!(all_facts.universal_region.len(), rows);
+        assert!(tab_delim::scratch_capacity() <= tab_delim::MAX_SCRATCH_BYTES);
+    }
+}
+
+#[test]
+fn test_byte_parser_matches_streaming() -> Result<(), Error> {
+    do catch {
+        let facts_dir = temp_dir("byte-parser");
+        let cfg_edge_file = facts_dir.join("cfg_edge.facts");
+
+        // `\r\n` line endings, and a last line without any.
+        fs::write(&cfg_edge_file, "\"a\"\t\"b\"\r\n\"b\"\t\"c\"\r\n\"c\"\t\"a\"")?;
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let streamed: Vec<(Point, Point)> =
+            tab_delim::stream_tab_delimited_file(tables, &cfg_edge_file)?
+                .collect::<Result<_, _>>()?;
+        assert_eq!(all_facts.cfg_edge.len(), 3);
+        assert_eq!(streamed, all_facts.cfg_edge);
+
+        fs::write(&cfg_edge_file, "\"a\"\t\"b\"\n\"b\"\t\"c\"\t\"d\"\n")?;
+        let tables = &mut intern::InternerTables::new();
+        let error = tab_delim::load_tab_delimited_facts(tables, &facts_dir).unwrap_err();
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(3));
+        fs::remove_dir_all(&facts_dir)?;
+    }
+}
+
+#[test]
+fn test_parallel_load_matches_sequential() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let sequential = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let options = tab_delim::LoadOptions {
+            parallel: true,
+            ..tab_delim::LoadOptions::default()
+        };
+        let parallel_tables = &mut intern::InternerTables::new();
+        let parallel =
+            tab_delim::load_tab_delimited_facts_with(parallel_tables, &facts_dir, options)?;
+
+        assert_eq!(sequential.borrow_region, parallel.borrow_region);
+        assert_eq!(sequential.cfg_edge, parallel.cfg_edge);
+        assert_eq!(sequential.outlives, parallel.outlives);
+        assert_eq!(sequential.region_live_at, parallel.region_live_at);
+        assert_eq!(sequential.invalidates, parallel.invalidates);
+
+        // The atoms have the same names and indices, in every relation.
+        assert_eq!(tables.regions.strings(), parallel_tables.regions.strings());
+        assert_eq!(tables.loans.strings(), parallel_tables.loans.strings());
+        assert_eq!(tables.points.strings(), parallel_tables.points.strings());
+        assert_eq!(tables.variables.strings(), parallel_tables.variables.strings());
+        assert_eq!(tables.move_paths.strings(), parallel_tables.move_paths.strings());
+        let diffs = diff::diff_facts(&sequential, tables, &parallel, parallel_tables);
+        assert!(diffs.iter().all(|diff| diff.is_empty()));
+    }
+}
+
+#[test]
+fn test_merge_keeps_dumps_apart() {
+    fn facts(tables: &mut intern::InternerTables) -> AllFacts {
+        let r0 = tables.regions.intern("'a");
+        let l0 = tables.loans.intern("bw0");
+        let (p0, p1) = (tables.points.intern("Start"), tables.points.intern("Mid"));
+        let mut builder = AllFactsBuilder::new();
+        builder.edge(p0, p1).borrow(r0, l0, p0).live(r0, p1).invalidate(p1, l0);
+        builder.build()
+    }
+
+    let first_tables = &mut intern::InternerTables::new();
+    let first = facts(first_tables);
+    let second_tables = &mut intern::InternerTables::new();
+    let second = facts(second_tables);
+
+    // The atoms of both dumps have the same names, and the prefixes keep
+    // them distinct.
+    let tables = &mut intern::InternerTables::new();
+    let prefix = cli::merge_prefix("first");
+    let mut merged = intern::reintern_facts(first, first_tables, tables, &prefix);
+    let prefix = cli::merge_prefix("second");
+    merged.merge(intern::reintern_facts(second, second_tables, tables, &prefix));
+    assert_eq!(merged.borrow_region.len(), 2);
+    assert_eq!(merged.cfg_edge.len(), 2);
+    assert_eq!((tables.points.len(), tables.loans.len()), (4, 2));
+
+    let output = Output::compute(&merged, Algorithm::Naive, false);
+    let mut errors: Vec<(&str, &str)> = output.with_tables(tables).errors().collect();
+    errors.sort();
+    assert_eq!(
+        errors,
+        vec![("first:Mid", "first:bw0"), ("second:Mid", "second:bw0")]
+    );
+}
+
+#[test]
+fn test_diff_facts_compares_names() {
+    // The same names have other indices in each set of facts.
+    let before_tables = &mut intern::InternerTables::new();
+    let start = before_tables.points.intern("Start");
+    let mid = before_tables.points.intern("Mid");
+    let mut builder = AllFactsBuilder::new();
+    builder.edge(start, mid);
+    let before: AllFacts = builder.build();
+
+    let after_tables = &mut intern::InternerTables::new();
+    let end = after_tables.points.intern("End");
+    let mid = after_tables.points.intern("Mid");
+    let start = after_tables.points.intern("Start");
+    let mut builder = AllFactsBuilder::new();
+    builder.edge(start, mid).edge(mid, end);
+    let after: AllFacts = builder.build();
+
+    let diffs = diff::diff_facts(&before, before_tables, &after, after_tables);
+    let changed: Vec<_> = diffs.iter().filter(|diff| !diff.is_empty()).collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].relation, "cfg_edge");
+    assert_eq!(changed[0].added, vec!["Mid\tEnd"]);
+    assert!(changed[0].removed.is_empty());
+
+    let diffs = diff::diff_facts(&after, after_tables, &before, before_tables);
+    let removed: Vec<&String> = diffs.iter().flat_map(|diff| &diff.removed).collect();
+    assert_eq!(removed, vec!["Mid\tEnd"]);
+    let diffs = diff::diff_facts(&after, after_tables, &after, after_tables);
+    assert!(diffs.iter().all(|diff| diff.is_empty()));
+}
+
+#[test]
+fn test_strict_load_rejects_missing_files() -> Result<(), Error> {
+    do catch {
+        let facts_dir = temp_dir("strict");
+        fs::write(facts_dir.join("cfg_edge.facts"), "\"a\"\t\"b\"\n")?;
+
+        // Without `--strict`, the missing relations are empty.
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        assert_eq!(all_facts.cfg_edge.len(), 1);
+        assert!(all_facts.borrow_region.is_empty());
+
+        let options = tab_delim::LoadOptions {
+            strict: true,
+            ..tab_delim::LoadOptions::default()
+        };
+        let tables = &mut intern::InternerTables::new();
+        match tab_delim::load_tab_delimited_facts_with(tables, &facts_dir, options) {
+            Err(tab_delim::LoadError {
+                kind: tab_delim::LoadErrorKind::Io(ref error),
+                ..
+            }) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("a strict load accepted missing files"),
+        }
+    }
+}
+
+#[test]
+fn test_optional_relations_are_not_warned_about() -> Result<(), Error> {
+    do catch {
+        // The dumps of older versions of rustc only have 7 relations.
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let manifest = manifest::read_manifest(&facts_dir)?;
+        assert!(tab_delim::missing_required_files(&manifest, &facts_dir).is_empty());
+
+        let facts_dir = temp_dir("required");
+        fs::write(facts_dir.join("cfg_edge.facts"), "\"a\"\t\"b\"\n")?;
+        let manifest = manifest::read_manifest(&facts_dir)?;
+        let missing: Vec<&str> = tab_delim::missing_required_files(&manifest, &facts_dir)
+            .into_iter()
+            .map(|(relation, _)| relation)
+            .collect();
+        assert_eq!(
+            missing,
+            vec![
+                "borrow_region",
+                "universal_region",
+                "killed",
+                "outlives",
+                "region_live_at",
+                "invalidates",
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_cache_is_invalidated() -> Result<(), Error> {
+    do catch {
+        // The cache is written next to the facts directory.
+        let facts_dir = temp_dir("cache").join("main");
+        fs::create_dir(&facts_dir)?;
+        fs::write(facts_dir.join("cfg_edge.facts"), "\"a\"\t\"b\"\n")?;
+        fs::write(facts_dir.join("killed.facts"), "\"bw0\"\t\"a\"\n")?;
+        let options = tab_delim::LoadOptions::default();
+        let load = |options| {
+            let tables = &mut intern::InternerTables::new();
+            cache::load_cached_facts(tables, &facts_dir, options)
+        };
+        assert_eq!(load(options)?.killed.len(), 1);
+        assert!(cache::cache_path(&facts_dir).exists());
+
+        // A cache of a lenient load doesn't skip the checks of a strict one.
+        let strict = tab_delim::LoadOptions {
+            strict: true,
+            ..options
+        };
+        assert!(load(strict).is_err());
+
+        // Removing a file doesn't change the newest modification time.
+        fs::remove_file(facts_dir.join("killed.facts"))?;
+        let all_facts = load(options)?;
+        assert!(all_facts.killed.is_empty());
+        assert_eq!(all_facts.cfg_edge.len(), 1);
+    }
+}
+
+#[test]
+fn test_mapped_load_matches_read() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let read = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let options = tab_delim::LoadOptions {
+            mmap: true,
+            ..tab_delim::LoadOptions::default()
+        };
+        let mapped_tables = &mut intern::InternerTables::new();
+        let mapped =
+            tab_delim::load_tab_delimited_facts_with(mapped_tables, &facts_dir, options)?;
+        assert_eq!(read.cfg_edge, mapped.cfg_edge);
+        assert_eq!(read.borrow_region, mapped.borrow_region);
+        let diffs = diff::diff_facts(&read, tables, &mapped, mapped_tables);
+        assert!(diffs.iter().all(|diff| diff.is_empty()));
+    }
+}
+
+#[test]
+fn test_fact_stats() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let l0 = Loan::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p0, p2)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p1);
+    let all_facts: AllFacts = builder.build();
+
+    let stats = stats::compute_stats(&all_facts);
+    assert_eq!((stats.points, stats.regions, stats.loans), (3, 2, 1));
+    assert_eq!((stats.variables, stats.move_paths), (0, 0));
+    assert_eq!((stats.cfg_nodes, stats.cfg_edges), (3, 3));
+    let mut successor_counts = stats.successor_counts.clone();
+    successor_counts.sort();
+    assert_eq!(successor_counts, vec![1, 2]);
+    assert_eq!(stats.average_branching_factor(), 1.5);
+
+    let printed = stats.to_string();
+    assert!(printed.lines().any(|line| line == format!("{:<20} {}", "points", 3)));
+    assert!(printed.contains("\n# successors per node\n"));
+}
+
+#[test]
+fn test_anonymize_keeps_structure() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let anonymized_tables = anonymize::anonymized_tables(tables);
+        assert_eq!(anonymized_tables.points.len(), tables.points.len());
+        assert_eq!(anonymized_tables.loans.len(), tables.loans.len());
+
+        let output_dir = temp_dir("anonymize");
+        tab_delim::write_tab_delimited_facts(&all_facts, &anonymized_tables, &output_dir)?;
+        let reloaded_tables = &mut intern::InternerTables::new();
+        let reloaded = tab_delim::load_tab_delimited_facts(reloaded_tables, &output_dir)?;
+        assert!(reloaded_tables.points.strings().iter().all(|name| name.starts_with("\"p")));
+        assert!(reloaded_tables.regions.strings().iter().all(|name| name.starts_with("\"r")));
+
+        // The errors are those of the original facts, at the points and of
+        // the loans named after the indices of the originals, unquoted.
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let expected: BTreeSet<(String, String)> = output
+            .iter_errors()
+            .map(|(point, loan)| {
+                let (point, loan): (usize, usize) = (point.into(), loan.into());
+                (format!("p{}", point), format!("l{}", loan))
+            })
+            .collect();
+        let output = Output::compute(&reloaded, Algorithm::Naive, false);
+        let errors: BTreeSet<(String, String)> = output
+            .with_tables(reloaded_tables)
+            .errors()
+            .map(|(point, loan)| (point.to_string(), loan.to_string()))
+            .collect();
+        assert!(!errors.is_empty());
+        assert_eq!(errors, expected);
+    }
+}
+
+#[test]
+fn test_minimize_keeps_the_error() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // Only the borrow of `l0`, its flow to `p1`, and its invalidation there
+    // are needed for the error at `p1`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .borrow(r1, l1, p1)
+        .outlives(r1, r0, p1)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let minimized = polonius_engine::minimize(&all_facts, |facts| {
+        Output::compute(facts, Algorithm::Naive, false).errors.contains_key(&p1)
+    });
+    assert_eq!(vec![(p0, p1)], minimized.cfg_edge);
+    assert_eq!(vec![(r0, l0, p0)], minimized.borrow_region);
+    assert_eq!(vec![(r0, p1)], minimized.region_live_at);
+    assert_eq!(vec![(p1, l0)], minimized.invalidates);
+    assert!(minimized.outlives.is_empty());
+}
+
+#[test]
+fn test_generated_facts_naive_matches_opt() {
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let naive = Output::compute(&all_facts, Algorithm::Naive, false);
+        let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        assert_eq!(naive.borrow_live_at(), opt.borrow_live_at(), "seed {}", seed);
+        assert_eq!(naive.errors, opt.errors, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_generator_more_loops_than_blocks() {
+    // The loops that don't fit in the CFG are left out.
+    let params = generator::GeneratorParams {
+        blocks: 1,
+        loop_nesting: 2,
+        ..generator::GeneratorParams::default()
+    };
+    let all_facts: AllFacts = generator::generate(&params);
+    let last = Point::from(2 * params.statements_per_block - 1);
+    let back_edges = all_facts.cfg_edge.iter().filter(|&&(p, q)| p == last && q == Point::from(0));
+    assert_eq!(back_edges.count(), 1);
+}
+
+#[test]
+fn test_builder_dedups_and_sorts() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let l0 = Loan::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p1, p2)
+        .edge(p0, p1)
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .live(r1, p1)
+        .live(r1, p2)
+        .invalidate(p2, l0);
+    let all_facts: AllFacts = builder.build();
+
+    assert_eq!(all_facts.cfg_edge, vec![(p0, p1), (p1, p2)]);
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_eq!(output.borrow_live_at()[&p2], vec![l0]);
+}
+
+#[test]
+#[should_panic]
+fn test_builder_rejects_unissued_loan() {
+    let mut builder: AllFactsBuilder<Region, Loan, Point, Variable, MovePath> = AllFactsBuilder::new();
+    builder.invalidate(Point::from(0), Loan::from(0));
+}
+
+#[test]
+fn test_validate_reports_inconsistencies() {
+    let r0 = Region::from(0);
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    let mut all_facts = AllFacts::default();
+    all_facts.cfg_edge = vec![(p0, p1), (p0, p1)].into();
+    all_facts.borrow_region = vec![(r0, l0, p0)].into();
+    all_facts.region_live_at = vec![(r0, p1), (r0, p2)].into();
+    all_facts.invalidates = vec![(p1, l1)].into();
+
+    let report = all_facts.validate();
+    assert_eq!(report.points_not_in_cfg, vec![("region_live_at", p2)]);
+    assert_eq!(report.unissued_loans, vec![("invalidates", l1)]);
+    assert_eq!(report.duplicate_tuples, vec![("cfg_edge", 1)]);
+
+    let generated: AllFacts = generator::generate(&generator::GeneratorParams::default());
+    assert!(generated.validate().is_empty());
+}
+
+#[test]
+fn test_shared_facts_clone() {
+    let all_facts: AllFacts = generator::generate(&generator::GeneratorParams::default());
+    let mut clone = all_facts.clone();
+    assert!(clone.cfg_edge.shares_storage_with(&all_facts.cfg_edge));
+    assert!(clone.borrow_region.shares_storage_with(&all_facts.borrow_region));
+
+    // Changing a relation of the clone only copies that relation.
+    let edges = all_facts.cfg_edge.len();
+    clone.cfg_edge.push((Point::from(0), Point::from(0)));
+    assert!(!clone.cfg_edge.shares_storage_with(&all_facts.cfg_edge));
+    assert!(clone.borrow_region.shares_storage_with(&all_facts.borrow_region));
+    assert_eq!(all_facts.cfg_edge.len(), edges);
+    assert_eq!(clone.cfg_edge.len(), edges + 1);
+
+    assert_eq!(clone.borrow_region.clone().into_vec(), all_facts.borrow_region);
+}
+
+#[test]
+fn test_manifest_schema_version() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let output_dir = temp_dir("manifest");
+        tab_delim::write_tab_delimited_facts(&all_facts, tables, &output_dir)?;
+        let manifest_file = output_dir.join(manifest::MANIFEST_FILE_NAME);
+        assert!(manifest_file.exists());
+
+        let future_manifest = fs::read_to_string(&manifest_file)?
+            .replace(
+                &format!("schema_version = {}", manifest::SCHEMA_VERSION),
+                "schema_version = 1000",
+            );
+        fs::write(&manifest_file, future_manifest)?;
+
+        let future_tables = &mut intern::InternerTables::new();
+        let error = tab_delim::load_tab_delimited_facts(future_tables, &output_dir).unwrap_err();
+        match error.kind {
+            tab_delim::LoadErrorKind::UnsupportedSchema(1000) => {}
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+}
+
+#[test]
+fn test_reserved_interner() {
+    // Reserving room doesn't change the indices the atoms are given.
+    let tables = &mut intern::InternerTables::new();
+    tables.reserve_for_file_sizes(1 << 20, 1 << 10);
+    assert!(tables.points.is_empty());
+    let points: Vec<Point> = ["\"a\"", "\"b\"", "\"a\""]
+        .iter()
+        .map(|name| tables.points.intern(name))
+        .collect();
+    assert_eq!(points, vec![Point::from(0), Point::from(1), Point::from(0)]);
+    assert_eq!(tables.points.lookup("b"), Some(Point::from(1)));
+}
+
+#[test]
+fn test_sorted_relation() {
+    let edges = SortedRelation::from_vec(vec![(2, 3), (1, 2), (2, 4), (1, 2), (4, 1)]);
+    assert_eq!(&edges[..], &[(1, 2), (2, 3), (2, 4), (4, 1)]);
+    assert!(edges.contains(&(2, 4)));
+    assert!(!edges.contains(&(3, 2)));
+    assert_eq!(edges.with_key(&2), &[(2, 3), (2, 4)]);
+    assert!(edges.with_key(&3).is_empty());
+
+    // The paths of two edges, joined on the point between them.
+    let reversed: SortedRelation<(u32, u32)> = edges.iter().map(|&(p, q)| (q, p)).collect();
+    let mut paths = Vec::new();
+    reversed.merge_join(&edges, |&q, &p, &r| paths.push((p, q, r)));
+    assert_eq!(paths, vec![(4, 1, 2), (1, 2, 3), (1, 2, 4), (2, 4, 1)]);
+
+    let successors = SortedIndex::from(edges);
+    assert_eq!(successors.keys(), &[1, 2, 4]);
+    assert_eq!(successors.get(&2), &[3, 4]);
+    assert!(successors.get(&3).is_empty());
+}
+
+#[test]
+fn test_engine_atoms() {
+    // The atoms of the CLI are those of the engine, which are `u32`s.
+    let point = Point(7);
+    assert_eq!(point.index(), 7);
+    assert_eq!(Point::from(7), point);
+    assert_eq!(mem::size_of::<(Region, Loan, Point)>(), 12);
+}
+
+#[test]
+fn test_bitset_liveness() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let (v0, v1) = (Variable::from(0), Variable::from(1));
+    let points: Vec<Point> = (0..4).map(Point::from).collect();
+
+    // The loop `p1 -> p2 -> p1` keeps `v0` live until its use at `p3`,
+    // while `v1`, dropped at `p1` and redefined at `p2`, is only live
+    // before the loop.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(points[0], points[1])
+        .edge(points[1], points[2])
+        .edge(points[2], points[1])
+        .edge(points[2], points[3])
+        .var_defined(v0, points[0])
+        .var_used(v0, points[3])
+        .var_uses_region(v0, r0)
+        .var_defined(v1, points[2])
+        .var_drop_used(v1, points[1])
+        .var_drops_region(v1, r1);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute(&all_facts, Algorithm::Naive, true);
+    let live: Vec<Vec<Region>> = points
+        .iter()
+        .map(|&point| {
+            let mut regions = output.regions_live_at(point).to_vec();
+            regions.sort();
+            regions
+        })
+        .collect();
+    assert_eq!(live, vec![vec![r1], vec![r0, r1], vec![r0], vec![r0]]);
+
+    // The word-parallel operations, across a word boundary.
+    let (l1, l70) = (Loan::from(1), Loan::from(70));
+    let mut live: LoanBitsets<Loan, Point> = AtomBitsets::new(2, 71);
+    let mut killed: LoanBitsets<Loan, Point> = AtomBitsets::new(2, 71);
+    assert!(live.insert(points[1], l1));
+    assert!(live.insert(points[1], l70));
+    assert!(!live.insert(points[1], l70));
+    killed.insert(points[0], l1);
+    assert!(live.propagate(points[1], points[0], &killed));
+    assert!(!live.propagate(points[1], points[0], &killed));
+    assert_eq!(live.atoms_at(points[0]), vec![l70]);
+    assert_eq!(live.intersection_at(points[1], &killed), Vec::<Loan>::new());
+    assert!(live.union_from(points[0], &killed, points[0]));
+    assert_eq!(live.intersection_at(points[0], &killed), vec![l1]);
+    assert_eq!(
+        live.to_pairs(),
+        vec![(l1, points[0]), (l1, points[1]), (l70, points[0]), (l70, points[1])]
+    );
+}
+
+#[test]
+fn test_interner_arena() {
+    // Enough names to fill several chunks of the arena, which must all
+    // stay where they are as the next ones are allocated.
+    let tables = &mut intern::InternerTables::new();
+    let names: Vec<String> = (0..10_000).map(|index| format!("\"Mid(bb{}[0])\"", index)).collect();
+    let loans: Vec<Loan> = names.iter().map(|name| tables.loans.intern(name)).collect();
+    for (name, &loan) in names.iter().zip(&loans) {
+        assert_eq!(tables.loans.untern(loan), name.as_str());
+        assert_eq!(tables.loans.lookup(name), Some(loan));
+    }
+    assert_eq!(tables.loans.strings().len(), names.len());
+    let huge = "x".repeat(1 << 17);
+    let loan = tables.loans.intern(&huge);
+    assert_eq!(tables.loans.untern(loan), huge);
+}
+
+#[test]
+fn test_load_relation_aliases() -> Result<(), Error> {
+    do catch {
+        // A dump with the newer names of rustc, and a `placeholder` with
+        // the loan of each universal region.
+        let facts_dir = temp_dir("relation-aliases");
+        let point = "\"Mid(bb0[0])\"";
+        fs::write(
+            facts_dir.join("loan_issued_at.facts"),
+            format!("\"'a\"\t\"bw0\"\t{}\n", point),
+        )?;
+        fs::write(facts_dir.join("placeholder.facts"), "\"'b\"\t\"bw1\"\n")?;
+        // The current name wins over an alias.
+        fs::write(facts_dir.join("killed.facts"), format!("\"bw0\"\t{}\n", point))?;
+        fs::write(facts_dir.join("loan_killed_at.facts"), "")?;
+
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        assert_eq!(all_facts.borrow_region.len(), 1);
+        assert_eq!(all_facts.killed.len(), 1);
+        let universal: Vec<_> = all_facts
+            .universal_region
+            .iter()
+            .map(|&region| tables.regions.untern(region))
+            .collect();
+        assert_eq!(universal, vec!["\"'b\""]);
+        fs::remove_dir_all(&facts_dir)?;
+
+        let relations = stats::relation_sizes(&all_facts);
+        for alias in manifest::RELATION_ALIASES {
+            assert!(relations.iter().any(|&(name, _)| name == alias.relation));
+        }
+    }
+}
+
+#[test]
+fn test_insensitive_errors_include_sensitive_errors() {
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let sensitive = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let insensitive = Output::compute(&all_facts, Algorithm::LocationInsensitive, false);
+        for (point, loans) in &sensitive.errors {
+            let insensitive_loans = &insensitive.errors[point];
+            for loan in loans {
+                assert!(insensitive_loans.contains(loan), "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_hybrid_matches_opt() {
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let hybrid = Output::compute(&all_facts, Algorithm::Hybrid, false);
+        assert_eq!(opt.errors, hybrid.errors, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_budget_stops_analysis() {
+    let all_facts: AllFacts = generator::generate(&generator::GeneratorParams::default());
+    let unlimited = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+    assert_eq!(unlimited.budget_exceeded, None);
+
+    let ample = Budget::unlimited().with_max_tuples(usize::max_value());
+    let output = Output::compute_with_budget(&all_facts, Algorithm::DatafrogOpt, false, ample);
+    assert_eq!(output.budget_exceeded, None);
+    assert_eq!(output.errors, unlimited.errors);
+    assert!(unlimited.derived_tuples > 0);
+    assert_eq!(output.derived_tuples, unlimited.derived_tuples);
+
+    let tiny = Budget::unlimited().with_max_tuples(1);
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+        let output = Output::compute_with_budget(&all_facts, algorithm, false, tiny);
+        assert_eq!(output.budget_exceeded, Some(BudgetExceeded::Tuples));
+    }
+}
+
+#[test]
+fn test_observer_sees_every_error_once() {
+    for (seed, all_facts) in generated_facts(4, generator::GeneratorParams::default()) {
+        for &algorithm in &[
+            Algorithm::Naive,
+            Algorithm::DatafrogOpt,
+            Algorithm::LocationInsensitive,
+            Algorithm::Hybrid,
+            Algorithm::Compare,
+        ] {
+            let mut observed: Vec<(Point, Loan)> = Vec::new();
+            let output = Output::compute_with_observer(
+                &all_facts,
+                algorithm,
+                false,
+                Budget::unlimited(),
+                &mut |point, loan| observed.push((point, loan)),
+            );
+
+            let mut expected: Vec<(Point, Loan)> = output
+                .errors
+                .iter()
+                .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+                .collect();
+            expected.sort();
+            observed.sort();
+            assert_eq!(observed, expected, "seed {}, {}", seed, algorithm);
+        }
+    }
+}
+
+#[test]
+fn test_compute_with_progress() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let mut reported = Vec::new();
+        let output = Output::compute_with_progress(
+            &all_facts,
+            Algorithm::DatafrogOpt,
+            false,
+            Budget::unlimited(),
+            &mut |tuples| reported.push(tuples),
+        );
+        assert!(!reported.is_empty());
+        assert!(reported.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(reported.last(), Some(&output.derived_tuples));
+
+        let mut progress = Progress::new(2, false);
+        progress.start("main");
+        progress.tuples(42);
+        let status = progress.status_line();
+        assert!(status.starts_with("[1/2] main: loading, "), "{}", status);
+        assert!(status.ends_with(", 42 tuples derived"), "{}", status);
+    }
+}
+
+/// Records the events of a solve, as lines.
+#[derive(Default)]
+struct RecordingObserver {
+    events: Vec<String>,
+    total: usize,
+}
+
+impl SolverObserver for RecordingObserver {
+    fn phase_start(&mut self, phase: &'static str) {
+        self.events.push(format!("start {}", phase));
+    }
+
+    fn phase_end(&mut self, phase: &'static str, _duration: Duration) {
+        self.events.push(format!("end {}", phase));
+    }
+
+    fn iteration(&mut self, delta: usize, total: usize) {
+        assert_eq!(self.total + delta, total);
+        self.total = total;
+    }
+
+    fn relation_finalized(&mut self, relation: &'static str, size: usize) {
+        self.events.push(format!("{}: {}", relation, size));
+    }
+}
+
+#[test]
+fn test_solver_observer() {
+    let all_facts: AllFacts = generator::generate(&generator::GeneratorParams::default());
+    let mut observer = RecordingObserver::default();
+    let output = Output::compute_with_solver_observer(
+        &all_facts,
+        Algorithm::Naive,
+        false,
+        Budget::unlimited(),
+        &mut observer,
+    );
+    assert_eq!(observer.total, output.derived_tuples);
+
+    let phases: Vec<&str> = observer
+        .events
+        .iter()
+        .map(|event| event.as_str())
+        .filter(|event| event.starts_with("start ") || event.starts_with("end "))
+        .collect();
+    let mut expected = Vec::new();
+    for &(phase, _) in &output.phase_timings {
+        expected.push(format!("start {}", phase));
+        expected.push(format!("end {}", phase));
+    }
+    assert_eq!(phases, expected);
+
+    // The loan analysis finalizes its relations within its phase.
+    let start = observer.events.iter().position(|event| event == "start loans");
+    let requires = observer.events.iter().position(|event| event.starts_with("requires: "));
+    let end = observer.events.iter().position(|event| event == "end loans");
+    assert!(start < requires && requires < end);
+}
+
+/// Stops the analyses after `rounds` rounds.
+struct StopAfter {
+    rounds: usize,
+}
+
+impl SolverObserver for StopAfter {
+    fn iteration(&mut self, _delta: usize, _total: usize) {
+        self.rounds = self.rounds.saturating_sub(1);
+    }
+
+    fn exceeded(&self) -> Option<BudgetExceeded> {
+        if self.rounds == 0 {
+            Some(BudgetExceeded::Duration)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_solver_observer_stops_analysis() {
+    let all_facts: AllFacts = generator::generate(&generator::GeneratorParams::default());
+    let complete = Output::compute(&all_facts, Algorithm::Naive, false);
+    let output = Output::compute_with_solver_observer(
+        &all_facts,
+        Algorithm::Naive,
+        false,
+        Budget::unlimited(),
+        &mut StopAfter { rounds: 1 },
+    );
+    assert_eq!(output.budget_exceeded, Some(BudgetExceeded::Duration));
+    assert!(output.derived_tuples < complete.derived_tuples);
+}
+
+#[test]
+fn test_timings() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let phases = |timings: &[(&'static str, Duration)]| -> Vec<&'static str> {
+            timings.iter().map(|&(phase, _)| phase).collect()
+        };
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        assert_eq!(
+            phases(&output.phase_timings),
+            ["liveness", "initialization", "subset errors", "loans"]
+        );
+        assert_eq!(
+            phases(&output.algorithm_phase_timings),
+            ["setup", "fixpoint", "results"]
+        );
+
+        // The precise pass of `Hybrid` only runs if the first one finds
+        // potential errors.
+        let output = Output::compute(&all_facts, Algorithm::Hybrid, false);
+        assert_eq!(phases(&output.algorithm_phase_timings)[0], "location-insensitive pass");
+    }
+}
+
+#[test]
+fn test_memory() -> Result<(), Error> {
+    do catch {
+        let status = "Name:\tpolonius\nVmPeak:\t  20000 kB\nVmHWM:\t    1500 kB\n";
+        assert_eq!(memory::parse_peak_rss(status), Some(1500 * 1024));
+        assert_eq!(memory::parse_peak_rss("Name:\tpolonius\n"), None);
+
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let sizes = &output.derived_relation_sizes;
+        assert!(sizes.iter().any(|&(relation, _)| relation == "requires"));
+        assert!(sizes.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+}
+
+#[test]
+fn test_relation_memory() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let inputs = all_facts.relation_memory();
+        assert_eq!(inputs.len(), stats::relation_sizes(&all_facts).len());
+        let cfg_edge = inputs.iter().find(|memory| memory.relation == "cfg_edge").unwrap();
+        assert_eq!(cfg_edge.tuples, all_facts.cfg_edge.len());
+        assert!(cfg_edge.bytes >= cfg_edge.tuples * mem::size_of::<(Point, Point)>());
+
+        let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let derived = output.derived_relation_memory();
+        assert_eq!(derived.len(), output.derived_relation_sizes.len());
+        assert!(derived.windows(2).all(|pair| pair[0].bytes >= pair[1].bytes));
+        let requires = derived.iter().find(|memory| memory.relation == "requires").unwrap();
+        assert_eq!(requires.bytes, requires.tuples * mem::size_of::<(Region, Loan, Point)>());
+
+        let mut stats = stats::compute_stats(&all_facts);
+        stats.derived_relation_memory = derived;
+        let printed = stats.to_string();
+        let derived_section = printed.split("\n# derived relations\n").nth(1).unwrap();
+        assert!(derived_section.lines().any(|line| line.starts_with("requires ")));
+    }
+}
+
+#[test]
+fn test_dump_relations() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, true);
+
+        let output_dir = temp_dir("dump-relations");
+        let output_dir = Some(output_dir);
+        dump::dump_relations(&output, &output_dir, tables, &["subset", "requires"])?;
+        let output_dir = output_dir.unwrap();
+        let subset = fs::read_to_string(output_dir.join("subset.facts"))?;
+        let subset_tuples: usize = output
+            .subset
+            .values()
+            .flat_map(|subsets| subsets.values())
+            .map(|regions| regions.len())
+            .sum();
+        assert_eq!(subset.lines().count(), subset_tuples);
+        assert!(output_dir.join("requires.facts").exists());
+        assert!(!output_dir.join("borrow_live_at.facts").exists());
+
+        let output_dir = Some(output_dir);
+        assert!(dump::dump_relations(&output, &output_dir, tables, &["nothing"]).is_err());
+    }
+}
+
+#[test]
+fn test_write_output_facts() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let output_dir = temp_dir("write-output-facts");
+
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        output.write_facts(tables, &output_dir)?;
+        assert!(output_dir.join("errors.facts").exists());
+        assert!(!output_dir.join("subset.facts").exists());
+
+        // The rows are tab-delimited, like those of the input facts.
+        let borrow_live_at = fs::read_to_string(output_dir.join("borrow_live_at.facts"))?;
+        let tuples: usize = output.borrow_live_at().values().map(|loans| loans.len()).sum();
+        assert_eq!(borrow_live_at.lines().count(), tuples);
+        for line in borrow_live_at.lines() {
+            let columns: Vec<&str> = line.split('\t').collect();
+            assert!(tables.points.lookup(columns[0]).is_some());
+            assert!(tables.loans.lookup(columns[1]).is_some());
+        }
+
+        let output = Output::compute(&all_facts, Algorithm::Naive, true);
+        output.write_facts(tables, &output_dir)?;
+        for relation in &dump::RELATIONS {
+            assert!(output_dir.join(format!("{}.facts", relation)).exists());
+        }
+    }
+}
+
+#[test]
+fn test_output_ordering() {
+    fn is_sorted<K, V: Ord>(relation: &FxHashMap<K, Vec<V>>) -> bool {
+        relation
+            .values()
+            .all(|values| values.windows(2).all(|pair| pair[0] <= pair[1]))
+    }
+
+    let params = generator::GeneratorParams::default();
+    for (seed, all_facts) in generated_facts(4, params.clone()) {
+        // There are two points per statement, and at most one loan.
+        let points = 2 * params.blocks * params.statements_per_block;
+        let tables = &mut intern::InternerTables::new();
+        for index in 0..points {
+            tables.points.intern(&format!("\"p{}\"", index));
+            tables.loans.intern(&format!("\"l{}\"", index));
+        }
+        for index in 0..params.regions {
+            tables.regions.intern(&format!("\"r{}\"", index));
+        }
+
+        for &algorithm in &[
+            Algorithm::Naive,
+            Algorithm::DatafrogOpt,
+            Algorithm::LocationInsensitive,
+            Algorithm::Hybrid,
+        ] {
+            let output = Output::compute(&all_facts, algorithm, true);
+            assert!(is_sorted(&output.errors), "seed {}, {}", seed, algorithm);
+            assert!(is_sorted(&output.borrow_live_at()), "seed {}, {}", seed, algorithm);
+            assert!(is_sorted(&output.region_live_at), "seed {}, {}", seed, algorithm);
+            assert!(is_sorted(&output.invalidates), "seed {}, {}", seed, algorithm);
+
+            // The dumps don't depend on the order of the tuples either.
+            let mut reversed = output.clone();
+            for loans in reversed.borrow_live_at_mut().values_mut() {
+                loans.reverse();
+            }
+            assert_eq!(
+                reversed.with_tables(tables).to_string(),
+                output.with_tables(tables).to_string()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_named_output() {
+    let tables = &mut intern::InternerTables::new();
+    let r0 = tables.regions.intern("\"'_#0r\"");
+    let l0 = tables.loans.intern("\"bw0\"");
+    let p0 = tables.points.intern("\"Start(bb0[0])\"");
+    let p1 = tables.points.intern("\"Start(bb0[1])\"");
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .live(r0, p0)
+        .live(r0, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    let named = output.with_tables(tables);
+
+    assert_eq!(named.errors().collect::<Vec<_>>(), vec![("Start(bb0[1])", "bw0")]);
+    let text = named.to_string();
+    assert!(text.contains("# errors\n\nStart(bb0[1])\tbw0\n"));
+    assert!(!text.contains("# subset\n"));
+
+    let json = serde_json::to_value(&named).unwrap();
+    assert_eq!(json["errors"][0][0], "Start(bb0[1])");
+    assert_eq!(json["errors"][0][1], "bw0");
+    assert_eq!(json["borrow_live_at"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_simplify_cfg_merges_points() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1, p2, p3, p4) = (
+        Point::from(0),
+        Point::from(1),
+        Point::from(2),
+        Point::from(3),
+        Point::from(4),
+    );
+
+    // `p2` is merged into `p1`, and `p4` into `p3`, but `p3` can't join
+    // `p1`: the loan killed at `p1` would reach its invalidation.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .edge(p2, p3)
+        .edge(p3, p4)
+        .borrow(r0, l0, p0)
+        .kill(l0, p1)
+        .invalidate(p3, l0);
+    for &p in &[p1, p2, p3, p4] {
+        builder.live(r0, p);
+    }
+    let all_facts: AllFacts = builder.build();
+
+    let (simplified, mapping) = polonius_engine::simplify_cfg(&all_facts);
+    assert_eq!(simplified.cfg_edge, vec![(p0, p1), (p1, p3)]);
+    assert_eq!(mapping.removed_points(), 2);
+    assert_eq!(mapping.representative(p2), p1);
+    assert_eq!(mapping.representative(p4), p3);
+    assert_eq!(simplified.region_live_at, vec![(r0, p1), (r0, p3)]);
+    assert_eq!(simplified.killed, vec![(l0, p1)]);
+    assert_eq!(simplified.invalidates, vec![(p3, l0)]);
+
+    let output = Output::compute(&simplified, Algorithm::Naive, false);
+    assert!(output.errors.is_empty());
+
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+            let diff = polonius_engine::verify_simplification(&all_facts, algorithm);
+            assert!(diff.is_empty(), "seed {}, {}:\n{}", seed, algorithm, diff);
+        }
+    }
+}
+
+#[test]
+fn test_simplify_cfg_merges_branch_arms() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1, p2, p3) = (Point::from(0), Point::from(1), Point::from(2), Point::from(3));
+
+    // The arms `p1` and `p2` of the branch at `p0` are the same, so `p2`
+    // is merged into `p1`, which is then a chain with the join point `p3`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p0, p2)
+        .edge(p1, p3)
+        .edge(p2, p3)
+        .borrow(r0, l0, p0)
+        .live(r0, p1)
+        .live(r0, p2)
+        .live(r0, p3)
+        .invalidate(p3, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let (simplified, mapping) = polonius_engine::simplify_cfg(&all_facts);
+    assert_eq!(simplified.cfg_edge, vec![(p0, p1)]);
+    assert_eq!(simplified.invalidates, vec![(p1, l0)]);
+
+    let output = Output::compute(&simplified, Algorithm::Naive, false);
+    assert_eq!(output.errors[&p1], vec![l0]);
+
+    let translated = mapping.translate_output(&output, &all_facts);
+    assert_eq!(translated.errors.len(), 1);
+    assert_eq!(translated.errors[&p3], vec![l0]);
+    assert_eq!(translated.borrow_live_at()[&p2], vec![l0]);
+}
+
+#[test]
+fn test_simplify_cfg_keeps_arms_with_outlives() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let l0 = Loan::from(0);
+    let (p0, p1, p2, p3) = (Point::from(0), Point::from(1), Point::from(2), Point::from(3));
+
+    // `p1` and `p2` have the same live regions, but only the subset of
+    // `p1` carries the loan to the invalidation at `p3`: neither arm is
+    // merged into the other.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p0, p2)
+        .edge(p1, p3)
+        .edge(p2, p3)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p1)
+        .live(r1, p3)
+        .invalidate(p3, l0);
+    for &p in &[p1, p2] {
+        builder.live(r0, p).live(r1, p);
+    }
+    let all_facts: AllFacts = builder.build();
+
+    let (simplified, mapping) = polonius_engine::simplify_cfg(&all_facts);
+    assert_eq!(mapping.removed_points(), 0);
+    assert_eq!(simplified.cfg_edge, all_facts.cfg_edge);
+    assert_eq!(simplified.outlives, vec![(r0, r1, p1)]);
+
+    let output = Output::compute(&simplified, Algorithm::Naive, false);
+    assert_eq!(output.errors[&p3], vec![l0]);
+    let diff = polonius_engine::verify_simplification(&all_facts, Algorithm::Naive);
+    assert!(diff.is_empty(), "{}", diff);
+}
+
+#[test]
+fn test_remove_unreachable_points() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1, p2, p3) = (Point::from(0), Point::from(1), Point::from(2), Point::from(3));
+
+    // `p2` and `p3` only reach each other, and the error at `p3` goes away
+    // with them.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p2, p3)
+        .edge(p3, p2)
+        .borrow(r0, l0, p2)
+        .live(r0, p3)
+        .invalidate(p3, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let reachable = polonius_engine::remove_unreachable_points(&all_facts);
+    assert_eq!(reachable.cfg_edge, vec![(p0, p1)]);
+    assert!(reachable.borrow_region.is_empty());
+    assert!(reachable.region_live_at.is_empty());
+    assert!(reachable.invalidates.is_empty());
+    assert!(Output::compute(&reachable, Algorithm::Naive, false).errors.is_empty());
+}
+
+#[test]
+fn test_remove_unreachable_points_without_entry() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1, p2, p3, p4) = (
+        Point::from(0),
+        Point::from(1),
+        Point::from(2),
+        Point::from(3),
+        Point::from(4),
+    );
+
+    // Every point has a predecessor: the function starts with the loop
+    // of `p0` and `p1`, and `p3` and `p4` are only reached from each other.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p0)
+        .edge(p1, p2)
+        .edge(p3, p4)
+        .edge(p4, p3)
+        .borrow(r0, l0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l0)
+        .invalidate(p4, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let reachable = polonius_engine::remove_unreachable_points(&all_facts);
+    assert_eq!(reachable.cfg_edge, vec![(p0, p1), (p1, p0), (p1, p2)]);
+    assert_eq!(reachable.invalidates, vec![(p2, l0)]);
+    let output = Output::compute(&reachable, Algorithm::Naive, false);
+    assert_eq!(output.errors[&p2], vec![l0]);
+}
+
+#[test]
+fn test_prune_dead_regions() {
+    let (r0, r1, r2, r3) = (Region::from(0), Region::from(1), Region::from(2), Region::from(3));
+    let l0 = Loan::from(0);
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    // `r1` is never live, but links `r0` to `r2` at `p0`; `r3` is live but
+    // never constrained.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .outlives(r1, r2, p0)
+        .live(r0, p0)
+        .live(r2, p1)
+        .live(r3, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let (pruned, stats) = polonius_engine::prune_dead_regions(&all_facts);
+    assert_eq!(stats.atoms, 2);
+    assert_eq!(pruned.outlives, vec![(r0, r2, p0)]);
+    assert_eq!(pruned.region_live_at, vec![(r0, p0), (r2, p1)]);
+
+    let expected = Output::compute(&all_facts, Algorithm::Naive, false);
+    let actual = Output::compute(&pruned, Algorithm::Naive, false);
+    assert_eq!(actual.errors, expected.errors);
+    assert_eq!(actual.errors[&p1], vec![l0]);
+}
+
+#[test]
+fn test_prune_dead_loans() {
+    let r0 = Region::from(0);
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    // Nothing invalidates `l1`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .borrow(r0, l1, p0)
+        .kill(l1, p1)
+        .live(r0, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let (pruned, stats) = polonius_engine::prune_dead_loans(&all_facts);
+    assert_eq!(stats, polonius_engine::PruningStats { atoms: 1, tuples: 2 });
+    assert_eq!(pruned.borrow_region, vec![(r0, l0, p0)]);
+    assert!(pruned.killed.is_empty());
+
+    let expected = Output::compute(&all_facts, Algorithm::Naive, false);
+    let actual = Output::compute(&pruned, Algorithm::Naive, false);
+    assert_eq!(actual.errors, expected.errors);
+    assert_eq!(actual.errors[&p1], vec![l0]);
+
+    // `l1` is still live at `p1`, but no longer in `borrow_live_at`.
+    let preprocessed = Preprocessor::default().prune_dead_loans(true).run(&all_facts);
+    let output = Output::compute(&preprocessed.facts, Algorithm::Naive, false);
+    let output = preprocessed.translate_output(output);
+    assert!(!output.has_full_borrow_live_at());
+    assert_eq!(output.borrows_in_scope_at(p1), &[l0][..]);
+
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let (pruned, _) = polonius_engine::prune_dead_loans(&all_facts);
+        let expected = Output::compute(&all_facts, Algorithm::Naive, false);
+        let actual = Output::compute(&pruned, Algorithm::Naive, false);
+        assert_eq!(actual.errors, expected.errors, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_reduce_outlives() {
+    let (r0, r1, r2) = (Region::from(0), Region::from(1), Region::from(2));
+    let l0 = Loan::from(0);
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    // `r0: r2` is implied at `p0`, but not at `p1`; `r1` and `r2` form a
+    // cycle at `p1`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .outlives(r1, r2, p0)
+        .outlives(r0, r2, p0)
+        .outlives(r0, r2, p1)
+        .outlives(r1, r2, p1)
+        .outlives(r2, r1, p1)
+        .live(r2, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let (reduced, stats) = polonius_engine::reduce_outlives(&all_facts);
+    assert_eq!(stats.tuples, 1);
+    assert_eq!(
+        reduced.outlives,
+        vec![
+            (r0, r1, p0),
+            (r0, r2, p1),
+            (r1, r2, p0),
+            (r1, r2, p1),
+            (r2, r1, p1),
+        ]
+    );
+
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let (reduced, _) = polonius_engine::reduce_outlives(&all_facts);
+        let expected = Output::compute(&all_facts, Algorithm::Naive, false);
+        let actual = Output::compute(&reduced, Algorithm::Naive, false);
+        assert_eq!(actual.errors, expected.errors, "seed {}", seed);
+        assert_eq!(actual.subset_errors, expected.subset_errors, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_preprocessor_levels() {
+    assert_eq!(
+        Preprocessor::new(PreprocessLevel::None),
+        Preprocessor::default()
+    );
+    assert_eq!(
+        Preprocessor::new(PreprocessLevel::Basic).simplify_cfg(true),
+        Preprocessor::new(PreprocessLevel::Aggressive).prune_dead_loans(false)
+    );
+    for name in PreprocessLevel::variants().iter() {
+        let level: PreprocessLevel = name.parse().unwrap();
+        assert_eq!(level.to_string(), *name);
+    }
+
+    for (seed, all_facts) in generated_facts(8, generator::GeneratorParams::default()) {
+        let expected = Output::compute(&all_facts, Algorithm::Naive, false);
+        for level in &[PreprocessLevel::Basic, PreprocessLevel::Aggressive] {
+            let preprocessed = Preprocessor::new(*level).run(&all_facts);
+            let output = Output::compute(&preprocessed.facts, Algorithm::Naive, false);
+            let output = preprocessed.translate_output(output);
+            assert_eq!(output.errors, expected.errors, "seed {}, {}", seed, level);
+        }
+    }
+}
+
+#[test]
+fn test_cfg_dot() {
+    let tables = &mut intern::InternerTables::new();
+    let p0 = tables.points.intern("\"Start(bb0[0])\"");
+    let p1 = tables.points.intern("\"Mid(bb0[0])\"");
+    let l0 = tables.loans.intern("\"bw0\"");
+    let r0 = tables.regions.intern("\"'_#0r\"");
+
+    let mut all_facts = AllFacts::default();
+    all_facts.cfg_edge.push((p0, p1));
+    all_facts.borrow_region.push((r0, l0, p0));
+    all_facts.region_live_at.push((r0, p0));
+    all_facts.region_live_at.push((r0, p1));
+    let mut errors = FxHashMap::default();
+    errors.insert(p1, vec![l0]);
+
+    let mut dot = Vec::new();
+    graph::write_cfg_dot(&all_facts, tables, &errors, &mut dot).unwrap();
+    let expected = r#"digraph cfg {
+    node [shape = box];
+    "Start(bb0[0])" [label = "Start(bb0[0])\nborrows: 1, kills: 0, live regions: 1"];
+    "Mid(bb0[0])" [label = "Mid(bb0[0])\nborrows: 0, kills: 0, live regions: 1\nerrors: bw0", color = red];
+    "Start(bb0[0])" -> "Mid(bb0[0])";
+}
+"#;
+    assert_eq!(String::from_utf8(dot).unwrap(), expected);
+}
+
+#[test]
+fn test_subsets_dot() {
+    let tables = &mut intern::InternerTables::new();
+    let p0 = tables.points.intern("\"Mid(bb0[0])\"");
+    let r0 = tables.regions.intern("\"'_#0r\"");
+    let r1 = tables.regions.intern("\"'_#1r\"");
+    let r2 = tables.regions.intern("\"'_#2r\"");
+
+    let mut all_facts = AllFacts::default();
+    all_facts.outlives.push((r0, r1, p0));
+    all_facts.outlives.push((r1, r2, p0));
+    let mut subsets = BTreeMap::new();
+    subsets.insert(r0, vec![r1, r2].into_iter().collect());
+    subsets.insert(r1, vec![r2].into_iter().collect());
+
+    let mut dot = Vec::new();
+    graph::write_subsets_dot(&all_facts, tables, p0, &subsets, &mut dot).unwrap();
+    let expected = r#"digraph subsets {
+    label = "Mid(bb0[0])";
+    "'_#0r" -> "'_#1r";
+    "'_#0r" -> "'_#2r" [style = dashed];
+    "'_#1r" -> "'_#2r";
+}
+"#;
+    assert_eq!(String::from_utf8(dot).unwrap(), expected);
+}
+
+#[test]
+fn test_json_report() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+
+        let report = json::JsonReport::new("main")
+            .with_relation_sizes(&stats::relation_sizes(&all_facts))
+            .with_output(1.5, &output, tables);
+        let mut document = Vec::new();
+        json::write_json_reports(&[report], &mut document)?;
+
+        let document: serde_json::Value = serde_json::from_slice(&document)?;
+        let report = &document[0];
+        assert_eq!(report["directory"], "main");
+        assert_eq!(report["time_seconds"], 1.5);
+        assert!(report["failure"].is_null());
+        assert_eq!(report["relation_sizes"]["cfg_edge"], all_facts.cfg_edge.len());
+
+        let errors = report["errors"].as_array().unwrap();
+        let error_count: usize = output.errors.values().map(|loans| loans.len()).sum();
+        assert_eq!(errors.len(), error_count);
+        for error in errors {
+            assert_eq!(error["kind"], "invalidated_while_live");
+            let point = tables.points.lookup(error["point"].as_str().unwrap()).unwrap();
+            let loan = tables.loans.lookup(error["loan"].as_str().unwrap()).unwrap();
+            assert!(output.errors[&point].contains(&loan));
+        }
+    }
+}
+
+#[test]
+fn test_sarif_log() -> Result<(), Error> {
+    do catch {
+        let tables = &mut intern::InternerTables::new();
+        let r0 = tables.regions.intern("\"'a\"");
+        let l0 = tables.loans.intern("\"bw0\"");
+        let (p0, p1) = (tables.points.intern("\"Start\""), tables.points.intern("\"Mid\""));
+
+        let mut builder = AllFactsBuilder::new();
+        builder.edge(p0, p1).borrow(r0, l0, p0).live(r0, p1).invalidate(p1, l0);
+        let all_facts: AllFacts = builder.build();
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+
+        let spans_file = "\"Mid\"\t\"src/main.rs\"\t12\t5\nUnknown\tx.rs\t1\t1\n";
+        let spans = sarif::parse_spans(tables, "", spans_file)?;
+        assert_eq!(spans.len(), 1);
+        let invalid = "\"Mid\"\t\"src/main.rs\"\ttwelve\t5";
+        assert!(sarif::parse_spans(tables, "", invalid).is_err());
+
+        // The points of merged facts are named after their directory.
+        let merged = tables.points.intern("main:\"Mid\"");
+        let merged_spans = sarif::parse_spans(tables, "main:", spans_file)?;
+        assert_eq!(merged_spans.keys().collect::<Vec<_>>(), vec![&merged]);
+
+        let mut log = sarif::SarifLog::new();
+        log.add_errors("main", &output, tables, &spans);
+        log.add_errors("helper", &output, tables, &Default::default());
+        let mut document = Vec::new();
+        log.write(&mut document)?;
+
+        let document: serde_json::Value = serde_json::from_slice(&document)?;
+        assert_eq!(document["version"], "2.1.0");
+        let results = document["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "invalidated-while-live");
+        let location = &results[0]["locations"][0];
+        assert_eq!(location["physicalLocation"]["artifactLocation"]["uri"], "src/main.rs");
+        assert_eq!(location["physicalLocation"]["region"]["startLine"], 12);
+        assert_eq!(location["logicalLocations"][0]["fullyQualifiedName"], "main::Mid");
+        // Without a span, only the point locates the error.
+        assert!(results[1]["locations"][0]["physicalLocation"].is_null());
+    }
+}
+
+#[test]
+fn test_html_report() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+
+        let html_dir = temp_dir("html-report");
+        let relation_sizes = stats::relation_sizes(&all_facts);
+        let page = html_dir.join("function-1.html");
+        html::write_function_page(&page, "main", &all_facts, &output, tables, &relation_sizes)?;
+        let entries = vec![
+            html::IndexEntry {
+                directory: "main".to_string(),
+                result: Ok(("function-1.html".to_string(), 0)),
+            },
+            html::IndexEntry {
+                directory: "broken <fn>".to_string(),
+                result: Err("missing facts".to_string()),
+            },
+        ];
+        html::write_index(&html_dir, &entries)?;
+
+        let page = fs::read_to_string(&page)?;
+        assert!(page.contains("<h2>Errors</h2>"));
+        assert!(page.contains("<td>cfg_edge</td>"));
+        // Each point has a heading, which the facts of its neighbors link to.
+        let start = tables.points.lookup("\"Start(bb0[0])\"").unwrap();
+        let start_index: usize = start.into();
+        let anchor = format!("point-{}-Start-bb0-0--", start_index);
+        assert!(page.contains(&format!("<h3 id=\"{}\">", anchor)));
+        assert!(page.contains(&format!("<a href=\"#{}\">", anchor)));
+
+        let index = fs::read_to_string(html_dir.join(html::INDEX_FILE_NAME))?;
+        assert!(index.contains("<a href=\"function-1.html\">main</a>"));
+        assert!(index.contains("broken &lt;fn&gt;"));
+    }
+}
+
+#[test]
+fn test_batch_facts_dirs() -> Result<(), Error> {
+    do catch {
+        let nll_facts = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts");
+        let main = nll_facts.join("main");
+        assert_eq!(
+            batch::facts_dirs(&nll_facts, None)?,
+            vec![main.clone(), nll_facts.join("{{impl}}-maybe_next")]
+        );
+        assert_eq!(batch::facts_dirs(&main, None)?, vec![main.clone()]);
+        let containing = |function: &str| FunctionFilter::Containing(function.to_string());
+        assert_eq!(batch::facts_dirs(&nll_facts, Some(&containing("ai")))?, vec![main.clone()]);
+        assert!(batch::facts_dirs(&main, Some(&containing("next")))?.is_empty());
+
+        // `--function-regex` matches some part of the name, like `--function`.
+        let matching = |regex| FunctionFilter::from_options(None, Some(regex)).unwrap();
+        let impls = matching(r"^\{\{impl\}\}-");
+        assert_eq!(
+            batch::facts_dirs(&nll_facts, impls.as_ref())?,
+            vec![nll_facts.join("{{impl}}-maybe_next")]
+        );
+        assert_eq!(batch::facts_dirs(&nll_facts, matching("ai|next").as_ref())?.len(), 2);
+        assert!(batch::facts_dirs(&nll_facts, matching("^ai").as_ref())?.is_empty());
+        assert!(FunctionFilter::from_options(None, Some("main(")).is_err());
+
+        // A root that doesn't exist is reported, and the others are still
+        // listed.
+        let roots = vec!["/polonius-test/missing".to_string(), main.display().to_string()];
+        let mut failed_roots = Vec::new();
+        let under_roots = batch::facts_dirs_under(&roots, None, |root, error| {
+            failed_roots.push((root.to_string(), error.kind()));
+        });
+        assert_eq!(under_roots, vec![main.clone()]);
+        assert_eq!(failed_roots, vec![(roots[0].clone(), io::ErrorKind::NotFound)]);
+
+        let list = temp_dir("batch-list").join("roots");
+        fs::write(&list, format!("# roots\n{}\n\n", main.display()))?;
+        assert_eq!(batch::read_roots(&list)?, vec![main.display().to_string()]);
+
+        let mut summary = batch::BatchSummary::default();
+        summary.record_analysis("a", 2, Duration::from_millis(10));
+        summary.record_analysis("b", 1, Duration::from_millis(30));
+        summary.record_failure();
+        assert_eq!(summary.analyzed, 2);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.errors, 3);
+        assert_eq!(summary.time, Duration::from_millis(40));
+        assert_eq!(summary.slowest, Some(("b".to_string(), Duration::from_millis(30))));
+    }
+}
+
+#[test]
+fn test_run_in_parallel() {
+    // The first items take the longest, so they finish last.
+    let items: Vec<u64> = (0..8).collect();
+    let mut finished = Vec::new();
+    let results = batch::run_in_parallel(
+        items,
+        4,
+        |item| {
+            thread::sleep(Duration::from_millis((8 - item) * 5));
+            item * 10
+        },
+        |&result| finished.push(result),
+    );
+    assert_eq!(results, vec![0, 10, 20, 30, 40, 50, 60, 70]);
+    finished.sort();
+    assert_eq!(finished, results);
+    assert_eq!(batch::run_in_parallel(vec![1, 2], 0, |item| item, |_| {}), vec![1, 2]);
+}
+
+#[test]
+fn test_bench_result() {
+    let durations: Vec<Duration> = [300, 100, 200]
+        .iter()
+        .map(|&millis| Duration::from_millis(millis))
+        .collect();
+    let result = bench::BenchResult::new(&durations, 42).unwrap();
+    assert_eq!(result.runs, 3);
+    assert_eq!(result.min, Duration::from_millis(100));
+    assert_eq!(result.median, Duration::from_millis(200));
+    assert_eq!(result.max, Duration::from_millis(300));
+    assert!((result.stddev - 0.0816).abs() < 0.0001);
+    assert_eq!(
+        bench::csv_row("nll-facts/main", Algorithm::Naive, &result),
+        "nll-facts/main,Naive,3,0.100000,0.200000,0.300000,0.081650,42"
+    );
+    assert!(bench::csv_row("a,b", Algorithm::Naive, &result).starts_with("\"a,b\",Naive,"));
+    assert!(bench::BenchResult::new(&[], 0).is_none());
+}
+
+#[test]
+fn test_watch_snapshot() -> Result<(), Error> {
+    do catch {
+        let root = temp_dir("watch");
+        fs::create_dir_all(root.join("main"))?;
+        fs::write(root.join("main").join("cfg_edge.facts"), "")?;
+        let roots = vec![root.display().to_string()];
+
+        let snapshot = watch::Snapshot::take(&roots, None);
+        assert_eq!(watch::Snapshot::take(&roots, None), snapshot);
+
+        fs::create_dir_all(root.join("other"))?;
+        fs::write(root.join("other").join("cfg_edge.facts"), "")?;
+        assert_ne!(watch::Snapshot::take(&roots, None), snapshot);
+        let main = FunctionFilter::Containing("main".to_string());
+        assert_eq!(watch::Snapshot::take(&roots, Some(&main)), snapshot);
+    }
+}
+
+#[test]
+fn test_repl() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let repl = Repl::new(all_facts, tables, Algorithm::Naive);
+
+        let outgoing = Some("Mid(bb0[0])".to_string());
+        assert_eq!(repl.execute("outgoing Start(bb0[0])"), outgoing);
+        // A prefix names the only point that starts with it.
+        assert_eq!(repl.execute("outgoing Start(bb0[0]"), outgoing);
+        assert!(repl.execute("outgoing Start").unwrap().starts_with("ambiguous point `Start`"));
+        assert!(repl.execute("outgoing nowhere").unwrap().starts_with("unknown point"));
+        assert!(repl.execute("frobnicate").unwrap().starts_with("unknown command"));
+        assert!(repl.complete("bw").contains(&"bw0"));
+        assert_eq!(repl.execute("quit"), None);
+
+        let mut printed = Vec::new();
+        repl.run(&mut "errors\nquit\nerrors\n".as_bytes(), &mut printed)?;
+        assert_eq!(String::from_utf8(printed)?.matches("> ").count(), 2);
+    }
+}
+
+#[test]
+fn test_outcome_exit_status() {
+    let outcomes = [Outcome::Clean, Outcome::InvalidInput, Outcome::BorrowErrors];
+    assert_eq!(outcomes.iter().max(), Some(&Outcome::InvalidInput));
+    assert!(Outcome::Incomplete < Outcome::BorrowErrors);
+    assert_eq!(Outcome::Clean.exit_status(), 0);
+    assert_ne!(Outcome::BorrowErrors.exit_status(), Outcome::InvalidInput.exit_status());
+    assert_ne!(Outcome::Incomplete.exit_status(), Outcome::Clean.exit_status());
+}
+
+#[test]
+fn test_outcome_of_output() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1) = (Point::from(0), Point::from(1));
+    let mut builder = AllFactsBuilder::new();
+    builder.edge(p0, p1).borrow(r0, l0, p0).live(r0, p1);
+    let all_facts: AllFacts = builder.build();
+    let mut output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_eq!(Outcome::of_output(&output), Outcome::Clean);
+
+    // Errors of every kind count, and the budget running out doesn't
+    // hide them.
+    output.budget_exceeded = Some(BudgetExceeded::Tuples);
+    assert_eq!(Outcome::of_output(&output), Outcome::Incomplete);
+    output.move_errors.insert(p1, vec![MovePath::from(0)]);
+    assert_eq!(Outcome::of_output(&output), Outcome::BorrowErrors);
+    output.move_errors.clear();
+    output.subset_errors.entry(p1).or_insert_with(BTreeSet::new).insert((r0, Region::from(1)));
+    assert_eq!(Outcome::of_output(&output), Outcome::BorrowErrors);
+}
+
+#[test]
+fn test_check_incomplete_analysis() -> Result<(), Error> {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs/issue-47680/nll-facts/main");
+    let analyze = |tuple_limit: &str| {
+        let args = ["polonius", "analyze", "--check", "--tuple-limit", tuple_limit];
+        let facts_dir = facts_dir.to_str().unwrap();
+        cli::main(cli::Opt::from_iter(args.iter().cloned().chain(Some(facts_dir))))
+    };
+
+    // `main` has no errors, but the budget runs out before the analysis
+    // can tell.
+    assert_eq!(analyze("1")?, Outcome::Incomplete);
+    assert_eq!(analyze("1000000000")?, Outcome::Clean);
+    Ok(())
+}
+
+#[test]
+fn test_single_threaded_options_conflict() {
+    let parses = |options: &[&str]| {
+        let args = ["polonius", "analyze"].iter().chain(options).chain(&["facts"]);
+        cli::Opt::clap().get_matches_from_safe(args).is_ok()
+    };
+    assert!(parses(&["--compare"]));
+    for single_threaded in &["--compare", "--why", "--tuple-limit=1"] {
+        assert!(!parses(&[*single_threaded, "--threads", "4"]));
+        assert!(!parses(&[*single_threaded, "--components"]));
+    }
+}
+
+#[test]
+fn test_algorithm_from_str() {
+    for name in Algorithm::variants().iter() {
+        let algorithm: Algorithm = name.parse().unwrap();
+        assert_eq!(algorithm.to_string(), *name);
+    }
+    assert_eq!("datafrogopt".parse(), Ok(Algorithm::DatafrogOpt));
+    assert!("magic".parse::<Algorithm>().is_err());
+}
+
+#[test]
+fn test_compare_algorithm() {
+    let params = generator::GeneratorParams::default();
+    let all_facts: AllFacts = generator::generate(&params);
+    let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+    let compare = Output::compute(&all_facts, Algorithm::Compare, false);
+    assert_eq!(opt.errors, compare.errors);
+    assert!(compare.errors_diff.as_ref().unwrap().is_empty());
+    assert!(opt.errors_diff.is_none());
+
+    let mut missing = opt.clone();
+    missing.errors.clear();
+    let diff = ErrorsDiff::between(&opt, &missing);
+    let error_count: usize = opt.errors.values().map(|loans| loans.len()).sum();
+    assert_eq!(diff.missing.len(), error_count);
+    assert!(diff.unexpected.is_empty());
+}
+
+#[test]
+fn test_output_diff() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .live(r0, p0)
+        .live(r0, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert!(output.diff(&output.clone()).is_empty());
+
+    let mut changed = output.clone();
+    changed.errors.clear();
+    changed.borrow_live_at_mut().entry(p0).or_insert_with(Vec::new).push(Loan::from(1));
+    let diff = output.diff(&changed);
+    assert_eq!(diff.errors.removed, vec![(p1, l0)]);
+    assert!(diff.errors.added.is_empty());
+    assert_eq!(diff.borrow_live_at.added, vec![(p0, Loan::from(1))]);
+    assert!(diff.borrow_live_at.removed.is_empty());
+    assert!(diff.move_errors.is_empty());
+    assert_eq!(diff.to_string(), "- errors\t1\t0\n+ borrow_live_at\t0\t1\n");
+}
+
+#[test]
+fn test_liveness_from_variables() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let v0 = Variable::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // `v0` is assigned at `p0` and used at `p2`, so its region `r0` is
+    // live at `p1` and `p2` without any `region_live_at` facts.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .var_defined(v0, p0)
+        .var_used(v0, p2)
+        .var_uses_region(v0, r0)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let output = Output::compute(&all_facts, algorithm, true);
+        assert_eq!(output.regions_live_at(p0), &[] as &[Region]);
+        assert_eq!(output.regions_live_at(p1), &[r0]);
+        assert_eq!(output.errors_at(p1), &[l0]);
+    }
+}
+
+#[test]
+fn test_move_errors() {
+    let v0 = Variable::from(0);
+    let (m0, m1) = (MovePath::from(0), MovePath::from(1));
+    let (p0, p1, p2, p3) = (Point::from(0), Point::from(1), Point::from(2), Point::from(3));
+
+    // `m1` is a field of the variable `m0`: moving it out at `p1` leaves
+    // `v0` partially uninitialized, and the access at `p2` is an error
+    // until the assignment at `p2` reinitializes the whole variable.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .edge(p2, p3)
+        .child_path(m1, m0)
+        .path_is_var(m0, v0)
+        .path_assigned(m0, p0)
+        .path_moved(m1, p1)
+        .path_accessed(m1, p2)
+        .path_assigned(m0, p2)
+        .path_accessed(m1, p3);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_eq!(output.move_errors.len(), 1);
+    assert_eq!(output.move_errors[&p2], vec![m1]);
+    assert_eq!(output.var_maybe_uninitialized_on_entry[&p2], vec![v0]);
+    assert!(!output.var_maybe_uninitialized_on_entry.contains_key(&p3));
+}
+
+#[test]
+fn test_subset_errors() {
+    let (r0, r1, r2) = (Region::from(0), Region::from(1), Region::from(2));
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    // The body requires `r0: r1` (through the local region `r2`) and
+    // `r1: r0`, but only the latter is declared.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .universal(r0)
+        .universal(r1)
+        .outlives(r0, r2, p0)
+        .outlives(r2, r1, p0)
+        .outlives(r1, r0, p1)
+        .known_subset(r1, r0);
+    let all_facts: AllFacts = builder.build();
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::LocationInsensitive] {
+        let output = Output::compute(&all_facts, algorithm, false);
+        assert!(output.errors.is_empty());
+        assert_eq!(output.subset_errors.len(), 2);
+        for &point in &[p0, p1] {
+            let errors: Vec<_> = output.subset_errors[&point].iter().cloned().collect();
+            assert_eq!(errors, vec![(r0, r1)]);
+        }
+    }
+}
+
+#[test]
+fn test_kills_through_borrowed_paths() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (m0, m1) = (MovePath::from(0), MovePath::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // `l0` borrows `m1`, a projection of `m0`: assigning `m0` at `p1` kills
+    // it, even though `killed` doesn't say so, and the invalidation at `p2`
+    // is not an error.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .child_path(m1, m0)
+        .borrow_path(l0, m1)
+        .path_assigned(m0, p1)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l0);
+    let all_facts: AllFacts = builder.build();
+    assert!(all_facts.killed.is_empty());
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt] {
+        let output = Output::compute(&all_facts, algorithm, false);
+        assert!(output.errors.is_empty());
+        assert_eq!(output.borrow_live_at()[&p1], vec![l0]);
+        assert!(!output.borrow_live_at().contains_key(&p2));
+    }
+}
+
+#[test]
+fn test_incremental_matches_recomputation() {
+    let r0 = Region::from(0);
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .borrow(r0, l1, p0)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l0)
+        .invalidate(p2, l1);
+    let all_facts: AllFacts = builder.build();
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+        let mut analysis = IncrementalAnalysis::new(all_facts.clone(), algorithm);
+        assert_eq!(analysis.output().errors[&p2], vec![l0, l1]);
+
+        // Killing `l0` only affects `l0`.
+        let mut kill = FactsDelta::new();
+        kill.added.killed.push((l0, p1));
+        analysis.apply(&kill);
+        let expected = Output::compute(analysis.facts(), algorithm, false);
+        assert_eq!(analysis.output().errors, expected.errors);
+        assert_eq!(analysis.output().borrow_live_at(), expected.borrow_live_at());
+        assert_eq!(analysis.output().errors[&p2], vec![l1]);
+
+        // `r0` no longer being live at `p2` affects every loan.
+        let mut dead = FactsDelta::new();
+        dead.removed.region_live_at.push((r0, p2));
+        analysis.apply(&dead);
+        assert!(analysis.output().errors.is_empty());
+
+        // Undoing both changes gets back to the original output.
+        let undo = FactsDelta {
+            added: dead.removed,
+            removed: kill.added,
+        };
+        analysis.apply(&undo);
+        let expected = Output::compute(&all_facts, algorithm, false);
+        assert_eq!(analysis.output().errors, expected.errors);
+        assert_eq!(analysis.output().borrow_live_at(), expected.borrow_live_at());
+    }
+}
+
+#[test]
+fn test_incremental_with_path_kills() {
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let (m0, m1) = (MovePath::from(0), MovePath::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // Assigning `m0` at `p1` kills `l0`, whatever the `killed` facts say.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .child_path(m1, m0)
+        .borrow_path(l0, m1)
+        .path_assigned(m0, p1)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l0);
+    let all_facts: AllFacts = builder.build();
+
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+        let mut analysis = IncrementalAnalysis::new(all_facts.clone(), algorithm);
+        let mut kill = FactsDelta::new();
+        kill.added.killed.push((l0, p1));
+        let undo = FactsDelta {
+            added: AllFacts::default(),
+            removed: kill.added.clone(),
+        };
+        for _ in 0..3 {
+            for delta in &[&kill, &undo] {
+                analysis.apply(delta);
+                let expected = Output::compute(analysis.facts(), algorithm, false);
+                assert!(analysis.output().errors.is_empty());
+                assert_eq!(analysis.output().borrow_live_at(), expected.borrow_live_at());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_matches_sequential() {
+    let params = generator::GeneratorParams {
+        seed: 7,
+        ..generator::GeneratorParams::default()
+    };
+    let all_facts: AllFacts = generator::generate(&params);
+
+    for &threads in &[1, 3] {
+        for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+            let sequential = Output::compute(&all_facts, algorithm, false);
+            let parallel = Output::compute_parallel(&all_facts, algorithm, false, threads);
+            assert_eq!(sequential.errors, parallel.errors);
+            assert_eq!(sequential.borrow_live_at(), parallel.borrow_live_at());
+            assert_eq!(sequential.subset_errors, parallel.subset_errors);
+        }
+    }
+}
+
+#[test]
+fn test_compute_by_component() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let points: Vec<Point> = (0..5).map(Point::from).collect();
+
+    // Two components, each with an error, and the point `p4`, without
+    // any CFG edge, where `l0` isn't live.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(points[0], points[1])
+        .borrow(r0, l0, points[0])
+        .live(r0, points[1])
+        .invalidate(points[1], l0)
+        .edge(points[2], points[3])
+        .borrow(r1, l1, points[2])
+        .live(r1, points[3])
+        .invalidate(points[3], l1)
+        .invalidate(points[4], l0);
+    let mut facts: Vec<AllFacts> = vec![builder.build()];
+    let generated = generated_facts(4, generator::GeneratorParams::default());
+    facts.extend(generated.map(|(_, all_facts)| all_facts));
+
+    for all_facts in &facts {
+        for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+            let whole = Output::compute(all_facts, algorithm, true);
+            let by_component = Output::compute_by_component(all_facts, algorithm, true);
+            assert_eq!(whole.errors, by_component.errors);
+            assert_eq!(whole.region_live_at, by_component.region_live_at);
+            // `Hybrid` only keeps the loans that may cause errors in their
+            // own component.
+            if algorithm != Algorithm::Hybrid {
+                assert_eq!(whole.borrow_live_at(), by_component.borrow_live_at());
+            }
+            assert_eq!(whole.subset_errors, by_component.subset_errors);
+        }
+    }
+    let output = Output::compute_by_component(&facts[0], Algorithm::Naive, false);
+    assert_eq!(output.errors_at(points[1]), &[l0]);
+    assert_eq!(output.errors_at(points[3]), &[l1]);
+    assert!(output.errors_at(points[4]).is_empty());
+}
+
+#[test]
+fn test_location_insensitive_collapses_cycles() {
+    let (r0, r1, r2) = (Region::from(0), Region::from(1), Region::from(2));
+    let l0 = Loan::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // `r0` and `r1` outlive each other, and both outlive `r2`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r1, l0, p0)
+        .outlives(r0, r1, p0)
+        .outlives(r1, r0, p1)
+        .outlives(r0, r2, p1)
+        .live(r2, p2)
+        .invalidate(p2, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute(&all_facts, Algorithm::LocationInsensitive, true);
+    assert_eq!(output.errors_at(p2), &[l0]);
+
+    let (subset_anywhere, restricts_anywhere) = uncollapsed_anywhere(&all_facts);
+    assert_eq!(output.subset_anywhere, subset_anywhere);
+    assert_eq!(output.subset_anywhere[&r1].iter().collect::<Vec<_>>(), vec![&r0]);
+    assert_eq!(output.restricts_anywhere, restricts_anywhere);
+    for &region in &[r0, r1, r2] {
+        let loans: Vec<_> = output.restricts_anywhere[&region].iter().cloned().collect();
+        assert_eq!(loans, vec![l0]);
+    }
+    assert_eq!(output.regions_live_at(p2), &[r2]);
+}
+
+#[test]
+fn test_location_insensitive_matches_uncollapsed() {
+    let params = generator::GeneratorParams {
+        outlives_density: 0.5,
+        ..generator::GeneratorParams::default()
+    };
+    for (seed, all_facts) in generated_facts(8, params) {
+        let output = Output::compute(&all_facts, Algorithm::LocationInsensitive, true);
+        let (subset_anywhere, restricts_anywhere) = uncollapsed_anywhere(&all_facts);
+        assert_eq!(output.subset_anywhere, subset_anywhere, "seed {}", seed);
+        assert_eq!(output.restricts_anywhere, restricts_anywhere, "seed {}", seed);
+    }
+}
+
+/// The `subset_anywhere` and `restricts_anywhere` of the location-insensitive
+/// rules, solved naively without collapsing the cycles of `outlives`.
+fn uncollapsed_anywhere(
+    all_facts: &AllFacts,
+) -> (FxHashMap<Region, BTreeSet<Region>>, FxHashMap<Region, BTreeSet<Loan>>) {
+    let mut subset: FxHashMap<Region, BTreeSet<Region>> = FxHashMap::default();
+    for &(r1, r2, _) in all_facts.outlives.iter() {
+        subset.entry(r1).or_insert_with(BTreeSet::new).insert(r2);
+    }
+
+    let mut requires: FxHashMap<Region, BTreeSet<Loan>> = FxHashMap::default();
+    for &(r, l, _) in all_facts.borrow_region.iter() {
+        requires.entry(r).or_insert_with(BTreeSet::new).insert(l);
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (&r1, successors) in &subset {
+            let loans = match requires.get(&r1) {
+                Some(loans) => loans.clone(),
+                None => continue,
+            };
+            for &r2 in successors {
+                let required = requires.entry(r2).or_insert_with(BTreeSet::new);
+                for &l in &loans {
+                    changed |= required.insert(l);
+                }
+            }
+        }
+    }
+    (subset, requires)
+}
+
+#[test]
+fn test_renumber_densely() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+
+        // Atoms that no fact mentions leave holes in the numbering.
+        tables.points.intern("\"unused point\"");
+        tables.regions.intern("\"unused region\"");
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let (renumbered, renumbered_tables) =
+            renumber::renumber_densely(all_facts.clone(), tables);
+        assert_eq!(renumbered_tables.points.len(), tables.points.len() - 1);
+        assert_eq!(renumbered_tables.regions.len(), tables.regions.len() - 1);
+        assert!(renumbered_tables.points.lookup("unused point").is_none());
+
+        // The facts are the same, up to the names of their atoms.
+        for (&(p, q), &(renumbered_p, renumbered_q)) in
+            all_facts.cfg_edge.iter().zip(&renumbered.cfg_edge)
+        {
+            assert_eq!(tables.points.untern(p), renumbered_tables.points.untern(renumbered_p));
+            assert_eq!(tables.points.untern(q), renumbered_tables.points.untern(renumbered_q));
+        }
+
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let renumbered_output = Output::compute(&renumbered, Algorithm::Naive, false);
+        let error_count: usize = output.errors.values().map(|loans| loans.len()).sum();
+        let renumbered_error_count: usize =
+            renumbered_output.errors.values().map(|loans| loans.len()).sum();
+        assert_eq!(error_count, renumbered_error_count);
+    }
+}
+
+#[test]
+fn test_error_iteration() {
+    let r0 = Region::from(0);
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l1, p0)
+        .borrow(r0, l0, p0)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p2, l1)
+        .invalidate(p2, l0)
+        .invalidate(p1, l1);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_eq!(output.error_count(), 3);
+    assert_eq!(output.errors_at(p1), &[l1]);
+    assert!(output.errors_at(p0).is_empty());
+    let errors: Vec<(Point, Loan)> = output.iter_errors().collect();
+    assert_eq!(errors, vec![(p1, l1), (p2, l0), (p2, l1)]);
+}
+
+#[test]
+fn test_why_error() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let l0 = Loan::from(0);
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .live(r1, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute_with_provenance(&all_facts, Algorithm::DatafrogOpt, false);
+    assert!(output.why(p0, l0).is_none());
+
+    // errors(l0, p1) :- invalidates(p1, l0), borrow_live_at(l0, p1).
+    let derivation = output.why(p1, l0).unwrap();
+    assert_eq!(derivation.tuple, Tuple::Error(l0, p1));
+    assert_eq!(derivation.rule, Some("errors"));
+    assert_eq!(derivation.premises[0].tuple, Tuple::Invalidates(p1, l0));
+    assert_eq!(derivation.premises[0].rule, None);
+
+    // borrow_live_at(l0, p1) :- requires(r1, l0, p1), region_live_at(r1, p1).
+    let borrow_live_at = &derivation.premises[1];
+    assert_eq!(borrow_live_at.tuple, Tuple::BorrowLiveAt(l0, p1));
+    assert_eq!(borrow_live_at.premises[0].tuple, Tuple::Requires(r1, l0, p1));
+
+    // requires(r1, l0, p1) flows from `p0`, where `r0: r1` relates it to
+    // the borrow.
+    let requires = &borrow_live_at.premises[0];
+    assert_eq!(requires.rule, Some("requires_cfg"));
+    assert_eq!(requires.premises[0].tuple, Tuple::Requires(r1, l0, p0));
+    assert_eq!(requires.premises[0].rule, Some("requires_subset"));
+
+    // Without provenance, there are no derivations.
+    let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+    assert!(output.why(p1, l0).is_none());
+}
+
+#[test]
+fn test_why_shares_derivations() {
+    for (_, all_facts) in generated_facts(4, generator::GeneratorParams::default()) {
+        let output = Output::compute_with_provenance(&all_facts, Algorithm::Naive, false);
+        for (point, loan) in output.iter_errors() {
+            // Each tuple is derived once, however many premises it is.
+            let mut shared = FxHashMap::default();
+            let mut stack = vec![Rc::new(output.why(point, loan).unwrap())];
+            while let Some(premise) = stack.pop() {
+                for premise in &premise.premises {
+                    match shared.entry(premise.tuple) {
+                        Entry::Occupied(entry) => assert!(Rc::ptr_eq(entry.get(), premise)),
+                        Entry::Vacant(entry) => {
+                            entry.insert(premise.clone());
+                            stack.push(premise.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_origin_chain() {
+    let (r0, r1) = (Region::from(0), Region::from(1));
+    let l0 = Loan::from(0);
+    let (p0, p1, p2) = (Point::from(0), Point::from(1), Point::from(2));
+
+    // `l0` is issued into `r0`, which flows into `r1`, where it stays
+    // until `p2`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .live(r1, p1)
+        .live(r1, p2);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute_with_provenance(&all_facts, Algorithm::Naive, false);
+    assert_eq!(
+        output.origin_chain(r1, l0, p2),
+        Some(vec![
+            OriginStep::Issued {
+                region: r0,
+                point: p0,
+            },
+            OriginStep::Subset {
+                from: r0,
+                to: r1,
+                point: p0,
+                because: vec![(r0, r1, p0)],
+            },
+            OriginStep::Flow {
+                region: r1,
+                from: p0,
+                to: p2,
+                steps: 2,
+            },
+        ])
+    );
+    assert_eq!(output.origin_chain(r0, l0, p2), None);
+
+    // Without provenance, there are no justifications to follow.
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_eq!(output.origin_chain(r1, l0, p2), None);
+}
+
+#[test]
+fn test_explain_error() {
+    let tables = &mut intern::InternerTables::new();
+    let (r0, r1) = (tables.regions.intern("'a"), tables.regions.intern("'b"));
+    let l0 = tables.loans.intern("bw0");
+    let (p0, p1) = (tables.points.intern("Start"), tables.points.intern("Mid"));
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .outlives(r0, r1, p0)
+        .live(r1, p1)
+        .invalidate(p1, l0);
+    let all_facts: AllFacts = builder.build();
+
+    let output = Output::compute_with_provenance(&all_facts, Algorithm::Naive, false);
+    let lines = explain::explain_error(&output.why(p1, l0).unwrap(), tables).unwrap();
+    assert_eq!(
+        lines,
+        vec![
+            "`bw0` is invalidated at `Mid` while it is in scope there:",
+            "   1. `bw0` is issued at `Start`, into `'a`.",
+            "   2. At `Start`, `'a` flows into `'b`, as the outlives facts `'a: 'b` at `Start` \
+             require.",
+            "   3. It stays in `'b` along the CFG, from `Start` to `Mid` in 1 step: `'b` is live \
+             there, and the loan isn't killed.",
+            "   4. `'b` is live at `Mid`, so the loan is in scope there.",
+            "   5. `Mid` invalidates `bw0`.",
+        ]
+    );
+
+    let derivation = output.why(p1, l0).unwrap();
+    assert!(explain::explain_error(&derivation.premises[1], tables).is_none());
+}
+
+#[test]
+fn test_shows_point_matches_whole_names() {
+    assert!(cli::shows_point(None, None, "Mid(bb7[2])"));
+    assert!(cli::shows_point(Some("Mid(bb7[2])"), None, "Mid(bb7[2])"));
+    assert!(!cli::shows_point(Some("Mid(bb7[2])"), None, "Mid(bb7[20])"));
+    assert!(!cli::shows_point(Some("bb7"), None, "Mid(bb7[2])"));
+
+    assert!(cli::shows_point(None, Some("bb7"), "Start(bb7[0])"));
+    assert!(!cli::shows_point(None, Some("bb7"), "Start(bb70[0])"));
+    assert!(!cli::shows_point(None, Some("b7"), "Start(bb7[0])"));
+    assert!(!cli::shows_point(None, Some("bb7"), "bb7"));
+    assert!(!cli::shows_point(Some("Mid(bb7[2])"), Some("bb3"), "Mid(bb7[2])"));
+}
+
+#[test]
+fn test_retain_points_of_block() {
+    let tables = &mut intern::InternerTables::new();
+    let r0 = tables.regions.intern("\"'a\"");
+    let (l0, l1) = (tables.loans.intern("\"bw0\""), tables.loans.intern("\"bw1\""));
+    let p0 = tables.points.intern("\"Mid(bb7[0])\"");
+    let p1 = tables.points.intern("\"Mid(bb7[1])\"");
+    let p2 = tables.points.intern("\"Mid(bb70[0])\"");
+
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .edge(p1, p2)
+        .borrow(r0, l0, p0)
+        .borrow(r0, l1, p0)
+        .live(r0, p1)
+        .live(r0, p2)
+        .invalidate(p1, l0)
+        .invalidate(p2, l1);
+    let all_facts: AllFacts = builder.build();
+    let mut output = Output::compute(&all_facts, Algorithm::Naive, true);
+    assert_eq!(output.errors.len(), 2);
+
+    cli::retain_points(&mut output, |point| {
+        cli::shows_point(None, Some("bb7"), tables.unquoted(point))
+    });
+    assert_eq!(output.errors.keys().collect::<Vec<_>>(), vec![&p1]);
+    assert!(output.region_live_at.keys().all(|&point| point != p2));
+}
+
+#[test]
+fn test_baseline_changes() -> Result<(), Error> {
+    do catch {
+        let tables = &mut intern::InternerTables::new();
+        let r0 = tables.regions.intern("'a");
+        let l0 = tables.loans.intern("bw0");
+        let (p0, p1) = (tables.points.intern("Start"), tables.points.intern("Mid"));
+
+        let mut builder = AllFactsBuilder::new();
+        builder.edge(p0, p1).borrow(r0, l0, p0).live(r0, p1).invalidate(p1, l0);
+        let all_facts: AllFacts = builder.build();
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let clean = Output::compute(&AllFacts::default(), Algorithm::Naive, false);
+
+        let mut saved = Baseline::new();
+        saved.record("main", &clean, tables);
+        saved.record("helper", &output, tables);
+        let path = temp_dir("baseline").join("baseline.json");
+        saved.save(&path)?;
+        let saved = Baseline::load(&path)?;
+
+        let mut current = Baseline::new();
+        current.record("main", &output, tables);
+        let changes = saved.compare(&current);
+        let error = BaselineError {
+            point: "Mid".to_string(),
+            loan: "bw0".to_string(),
+        };
+        assert_eq!(changes.appeared, vec![("main".to_string(), error.clone())]);
+        assert!(changes.disappeared.is_empty());
+        assert_eq!(changes.missing, vec!["helper".to_string()]);
+
+        current.record("helper", &clean, tables);
+        let changes = saved.compare(&current);
+        assert_eq!(changes.disappeared, vec![("helper".to_string(), error)]);
+        assert!(changes.missing.is_empty());
+        assert!(!changes.is_unchanged());
+        assert_eq!(saved.compare(&saved), BaselineChanges::default());
+        assert!(saved.compare(&saved).is_unchanged());
+    }
+}
+
+#[test]
+fn test_baseline_function_names() {
+    let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("inputs")
+        .join("issue-47680")
+        .join("nll-facts")
+        .join("main");
+    let other_path = facts_dir.join("..").join("main");
+    assert_eq!(
+        baseline::function_name(&[facts_dir.display().to_string()]),
+        baseline::function_name(&[other_path.display().to_string()])
+    );
+    assert_eq!(
+        baseline::function_name(&["missing".to_string(), "other".to_string()]),
+        "missing + other"
+    );
+}
+
+#[test]
+fn test_queries_match_full_analysis() {
+    for (seed, all_facts) in generated_facts(4, generator::GeneratorParams::default()) {
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let queries = QueryEngine::new(&all_facts, Algorithm::Naive);
+
+        let points: BTreeSet<Point> = all_facts.cfg_edge.iter().map(|&(p, _)| p).collect();
+        let loans: BTreeSet<Loan> = all_facts.borrow_region.iter().map(|&(_, l, _)| l).collect();
+        let sampled_points = points.iter().enumerate().filter(|&(index, _)| index % 5 == 0);
+        for (_, &point) in sampled_points {
+            for &loan in &loans {
+                assert_eq!(
+                    queries.loan_in_scope(loan, point),
+                    output.borrows_in_scope_at(point).contains(&loan),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+
+        let block: Vec<Point> = points.iter().cloned().skip(3).take(4).collect();
+        let mut expected = output.errors.clone();
+        expected.retain(|point, _| block.contains(point));
+        assert_eq!(queries.errors_at(&block), expected, "seed {}", seed);
+    }
+}
+
+#[test]
+fn test_borrows_in_scope_at() {
+    let r0 = Region::from(0);
+    let (l0, l1) = (Loan::from(0), Loan::from(1));
+    let (p0, p1) = (Point::from(0), Point::from(1));
+
+    // Only `l1` is invalidated, so `Hybrid` doesn't compute the scope of
+    // `l0`.
+    let mut builder = AllFactsBuilder::new();
+    builder
+        .edge(p0, p1)
+        .borrow(r0, l0, p0)
+        .borrow(r0, l1, p0)
+        .live(r0, p1)
+        .invalidate(p1, l1);
+    let all_facts: AllFacts = builder.build();
+
+    let naive = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert!(naive.has_full_borrow_live_at());
+    let mut in_scope = naive.borrows_in_scope_at(p1).to_vec();
+    in_scope.sort();
+    assert_eq!(in_scope, vec![l0, l1]);
+
+    let hybrid = Output::compute(&all_facts, Algorithm::Hybrid, false);
+    assert!(!hybrid.has_full_borrow_live_at());
+    assert_eq!(hybrid.borrows_in_scope_at(p1), &[l1][..]);
+    let queries = QueryEngine::new(&all_facts, Algorithm::Hybrid);
+    assert_eq!(queries.borrows_in_scope_at(p1), vec![l0, l1]);
+    assert!(queries.loan_in_scope(l0, p1));
+}
+
+#[test]
+fn test_compact_borrow_live_at() {
+    for (seed, all_facts) in generated_facts(4, generator::GeneratorParams::default()) {
+        let output = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let mut compact = output.clone();
+        compact.compact_borrow_live_at();
+        assert_eq!(compact.borrow_live_at(), output.borrow_live_at(), "seed {}", seed);
+        assert!(output.diff(&compact).is_empty(), "seed {}", seed);
+
+        let expected = output.borrow_live_at().into_owned();
+        let bitsets = LoanBitsets::from_map(&expected);
+        assert_eq!(bitsets.to_map(), expected);
+        assert_eq!(bitsets.len(), expected.values().map(|loans| loans.len()).sum());
+
+        for &(point, _) in &all_facts.cfg_edge {
+            assert_eq!(compact.borrows_in_scope_at(point).into_owned(), bitsets.atoms_at(point));
+            for &(_, loan, _) in &all_facts.borrow_region {
+                assert_eq!(
+                    compact.is_borrow_in_scope_at(point, loan),
+                    output.is_borrow_in_scope_at(point, loan),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_compact_borrow_live_at_only_when_smaller() {
+    // One loan live at one point of many: the bitsets of every point take
+    // more memory than the map.
+    let r0 = Region::from(0);
+    let l0 = Loan::from(0);
+    let mut builder = AllFactsBuilder::new();
+    for index in 0..64 {
+        builder.edge(Point::from(index), Point::from(index + 1));
+    }
+    builder.borrow(r0, l0, Point::from(63)).live(r0, Point::from(64));
+    let all_facts: AllFacts = builder.build();
+    let mut output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert!(!output.compact_borrow_live_at());
+    assert_eq!(output.borrows_in_scope_at(Point::from(64)), &[l0][..]);
+
+    // Every loan live at every point: one word per point is smaller.
+    let mut builder = AllFactsBuilder::new();
+    builder.edge(Point::from(0), Point::from(1));
+    for index in 0..32 {
+        builder.borrow(r0, Loan::from(index), Point::from(0));
+    }
+    builder.live(r0, Point::from(0)).live(r0, Point::from(1));
+    let all_facts: AllFacts = builder.build();
+    let mut output = Output::compute(&all_facts, Algorithm::Naive, false);
+    let expected = output.borrow_live_at().into_owned();
+    assert!(output.compact_borrow_live_at());
+    assert_eq!(*output.borrow_live_at(), expected);
+    output.borrow_live_at_mut().remove(&Point::from(0));
+    assert_eq!(output.borrows_in_scope_at(Point::from(1)).len(), 32);
+    assert!(output.borrows_in_scope_at(Point::from(0)).is_empty());
+}
+
+#[test]
+fn test_query_subsets_at() {
+    for (seed, all_facts) in generated_facts(4, generator::GeneratorParams::default()) {
+        let output = Output::compute(&all_facts, Algorithm::Naive, true);
+        let queries = QueryEngine::new(&all_facts, Algorithm::DatafrogOpt);
+
+        let points: BTreeSet<Point> = all_facts.cfg_edge.iter().map(|&(p, _)| p).collect();
+        let sampled_points = points.iter().enumerate().filter(|&(index, _)| index % 5 == 0);
+        for (_, &point) in sampled_points {
+            let subsets: Vec<(Region, Region)> = queries
+                .subsets_at(point)
+                .into_iter()
+                .flat_map(|(r1, regions)| regions.into_iter().map(move |r2| (r1, r2)))
+                .collect();
+            let expected: Vec<(Region, Region)> = output.subsets_at(point).collect();
+            assert_eq!(subsets, expected, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_library_facade() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let (all_facts, tables) = crate::load_facts(&facts_dir)?;
+        let output = crate::analyze(&all_facts, crate::Algorithm::Naive);
+
+        let expected_tables = &mut intern::InternerTables::new();
+        let expected = tab_delim::load_tab_delimited_facts(expected_tables, &facts_dir)?;
+        assert_eq!(all_facts.cfg_edge, expected.cfg_edge);
+        assert_eq!(tables.points.strings(), expected_tables.points.strings());
+        assert_eq!(output.errors, Output::compute(&expected, Algorithm::Naive, false).errors);
+
+        let loan = tables.loans.lookup("bw0").expect("`bw0` isn't interned");
+        assert_eq!(tables.loans.untern(loan), "\"bw0\"");
+    }
+}
+
+#[test]
+fn test_lsp_server() -> Result<(), Error> {
+    do catch {
+        let facts = |invalidates: &str| {
+            format!(
+                r#"{{
+                    "method": "polonius/facts",
+                    "params": {{
+                        "name": "main",
+                        "facts": {{
+                            "borrow_region": [["'a", "bw0", "Mid(bb0[0])"]],
+                            "cfg_edge": [["Mid(bb0[0])", "Mid(bb0[1])"]],
+                            "region_live_at": [["'a", "Mid(bb0[1])"]],
+                            "invalidates": {}
+                        }},
+                        "spans": [{{
+                            "point": "Mid(bb0[1])",
+                            "file": "/src/main.rs",
+                            "line": 3,
+                            "column": 5
+                        }}]
+                    }}
+                }}"#,
+                invalidates
+            )
+        };
+        let requests = vec![
+            r#"{ "id": 1, "method": "initialize", "params": {} }"#.to_string(),
+            r#"{ "method": "initialized", "params": {} }"#.to_string(),
+            facts(r#"[["Mid(bb0[1])", "bw0"]]"#),
+            facts("[]"),
+            r#"{ "id": 2, "method": "textDocument/hover", "params": {} }"#.to_string(),
+            r#"{ "id": 3, "method": "shutdown" }"#.to_string(),
+        ];
+
+        let mut written = Vec::new();
+        {
+            let load_config = Default::default();
+            let mut server =
+                lsp::Server::new(Vec::new(), Algorithm::Naive, load_config, &mut written);
+            for request in &requests {
+                assert!(server.handle(&serde_json::from_str(request)?)?);
+            }
+            assert!(!server.handle(&serde_json::from_str(r#"{ "method": "exit" }"#)?)?);
+        }
+
+        let mut reader = &written[..];
+        let mut messages = Vec::new();
+        while let Some(message) = lsp::read_message(&mut reader)? {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 5);
+
+        assert_eq!(messages[0]["id"], 1);
+        assert!(messages[0]["result"]["capabilities"].is_object());
+
+        assert_eq!(messages[1]["method"], "textDocument/publishDiagnostics");
+        assert_eq!(messages[1]["params"]["uri"], "file:///src/main.rs");
+        let diagnostic = &messages[1]["params"]["diagnostics"][0];
+        assert_eq!(diagnostic["range"]["start"]["line"], 2);
+        assert_eq!(diagnostic["range"]["start"]["character"], 4);
+        assert_eq!(
+            diagnostic["message"],
+            "`bw0` is invalidated at `Mid(bb0[1])` while it is live"
+        );
+
+        // Once the error is fixed, its diagnostic is cleared.
+        assert_eq!(messages[2]["params"]["uri"], "file:///src/main.rs");
+        assert_eq!(messages[2]["params"]["diagnostics"].as_array().map(Vec::len), Some(0));
+
+        assert_eq!(messages[3]["error"]["code"], -32601);
+        assert_eq!(messages[4]["id"], 3);
+        assert!(messages[4]["result"].is_null());
+    }
+}
+
+#[test]
+fn test_lsp_malformed_messages() -> Result<(), Error> {
+    do catch {
+        // The second message isn't JSON, but the third one is still read.
+        let mut input = Vec::new();
+        lsp::write_message(&mut input, &serde_json::from_str(r#"{ "id": 1 }"#)?)?;
+        input.extend_from_slice(b"Content-Length: 5\r\n\r\n{ id:");
+        lsp::write_message(&mut input, &serde_json::from_str(r#"{ "id": 2 }"#)?)?;
+        let mut reader = &input[..];
+        assert_eq!(lsp::read_message(&mut reader)?.unwrap()["id"], 1);
+        let error = lsp::read_message(&mut reader).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(lsp::read_message(&mut reader)?.unwrap()["id"], 2);
+        assert!(lsp::read_message(&mut reader)?.is_none());
+
+        let mut written = Vec::new();
+        {
+            let load_config = Default::default();
+            let mut server =
+                lsp::Server::new(Vec::new(), Algorithm::Naive, load_config, &mut written);
+            server.handle_malformed(&error.to_string())?;
+        }
+        let response = lsp::read_message(&mut &written[..])?.unwrap();
+        assert!(response["id"].is_null());
+        assert_eq!(response["error"]["code"], -32700);
+
+        assert_eq!(lsp::file_uri("/src/my crate/ü.rs"), "file:///src/my%20crate/%C3%BC.rs");
+    }
+}
+
+#[test]
+fn test_cargo_facts_flags() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let facts_root = Path::new("/tmp/facts");
+
+    let rustc_args = args(&[
+        "--crate-name",
+        "polonius",
+        "--edition=2015",
+        "src/main.rs",
+        "--crate-type",
+        "bin",
+    ]);
+    let flags = cargo::facts_flags(&rustc_args, facts_root);
+    assert_eq!(
+        flags,
+        Some(args(&[
+            "-Zborrowck=mir",
+            "-Znll-facts",
+            "-Znll-facts-dir=/tmp/facts/polonius.bin",
+        ]))
+    );
+
+    let flags = cargo::facts_flags(&args(&["--crate-name", "engine", "src/lib.rs"]), facts_root);
+    assert_eq!(flags.unwrap()[2], "-Znll-facts-dir=/tmp/facts/engine.lib");
+
+    // Cargo queries rustc without a crate to compile.
+    assert_eq!(cargo::facts_flags(&args(&["-vV"]), facts_root), None);
+}
+
+#[test]
+fn test_cargo_workspace_layout() -> Result<(), Error> {
+    do catch {
+        let metadata = r#"{
+            "packages": [
+                { "name": "polonius", "manifest_path": "/work/polonius/Cargo.toml" },
+                { "name": "polonius-engine", "manifest_path": "/work/polonius/engine/Cargo.toml" }
+            ],
+            "target_directory": "/work/polonius/target",
+            "version": 1
+        }"#;
+        let (members, target_dir) = cargo::workspace_layout(metadata)?;
+        assert_eq!(
+            members,
+            vec![Path::new("/work/polonius"), Path::new("/work/polonius/engine")]
+        );
+        assert_eq!(target_dir, Path::new("/work/polonius/target"));
+
+        assert!(cargo::workspace_layout(r#"{ "packages": 1 }"#).is_err());
+    }
+}
+
+#[test]
+fn test_parse_rustc_errors() {
+    let messages = [
+        r#"{"reason":"compiler-artifact","target":{"name":"dep","kind":["lib"]}}"#,
+        r#"{"reason":"compiler-message","target":{"name":"my-crate","kind":["lib"]},"message":{
+            "level":"error","code":{"code":"E0502"},"spans":[
+            {"file_name":"src/lib.rs","line_start":3,"is_primary":false},
+            {"file_name":"src/lib.rs","line_start":4,"is_primary":true}]}}"#,
+        r#"{"reason":"compiler-message","target":{"name":"my-crate","kind":["lib"]},"message":{
+            "level":"error","code":{"code":"E0308"},"spans":[
+            {"file_name":"src/lib.rs","line_start":9,"is_primary":true}]}}"#,
+        "not JSON",
+    ];
+    let messages: Vec<String> = messages.iter().map(|message| message.replace('\n', "")).collect();
+    let errors = differential::parse_rustc_errors(&messages.join("\n"));
+
+    // Only the errors about loans count.
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].crate_dir, "my_crate.lib");
+    assert_eq!(errors[0].code, "E0502");
+    assert_eq!((errors[0].file.as_str(), errors[0].line), ("src/lib.rs", 4));
+}
+
+#[test]
+fn test_differential_report() {
+    let function = |name: &str, lines: (u64, u64), error_lines: &[u64]| Function {
+        name: name.to_string(),
+        extent: Some(Extent {
+            file: "src/lib.rs".to_string(),
+            first_line: lines.0,
+            last_line: lines.1,
+        }),
+        error_lines: error_lines.iter().cloned().collect(),
+        unlocated_errors: 0,
+    };
+    let functions = vec![
+        function("agrees", (1, 10), &[4]),
+        function("accepts_more", (11, 20), &[]),
+        function("disagrees", (21, 30), &[25, 27]),
+        // A closure in `disagrees`: the errors of rustc on its lines are its own.
+        function("disagrees-{{closure}}", (26, 28), &[]),
+        Function {
+            extent: None,
+            ..function("without_spans", (0, 0), &[])
+        },
+    ];
+    let rustc_error = |line| differential::RustcError {
+        crate_dir: "my_crate.lib".to_string(),
+        code: "E0502".to_string(),
+        file: "/work/my-crate/src/lib.rs".to_string(),
+        line,
+    };
+    let rustc_errors: Vec<_> = [4, 15, 25, 27, 40].iter().map(|&line| rustc_error(line)).collect();
+
+    let mut report = DifferentialReport::default();
+    report.record(&functions, &rustc_errors);
+    assert_eq!(report.compared, 4);
+    assert_eq!(report.rustc_only, 2);
+    assert_eq!(
+        report.disagreements,
+        vec![Disagreement {
+            function: "disagrees".to_string(),
+            polonius_only: vec![27],
+        }]
+    );
+    assert_eq!(report.without_spans, vec!["without_spans".to_string()]);
+    assert_eq!(report.unattributed, vec![rustc_error(40)]);
+}
+
+#[test]
+fn test_souffle_diff() -> Result<(), Error> {
+    do catch {
+        let document = r#"{
+            "borrow_region": [["'a", "bw0", "Mid(bb0[0])"]],
+            "cfg_edge": [["Mid(bb0[0])", "Mid(bb0[1])"]],
+            "region_live_at": [["'a", "Mid(bb0[0])"], ["'a", "Mid(bb0[1])"]],
+            "invalidates": [["Mid(bb0[1])", "bw0"]]
+        }"#;
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = json::read_json_facts(tables, document.as_bytes())?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let expected = souffle::polonius_rows(&output, tables);
+        assert_eq!(expected[0].iter().collect::<Vec<_>>(), vec!["bw0\tMid(bb0[1])"]);
+        assert_eq!(expected[1].len(), 2);
+
+        // Soufflé derives the same errors, but only one `borrow_live_at`,
+        // and another that polonius doesn't.
+        let output_dir = temp_dir("souffle-diff");
+        fs::write(output_dir.join("errors.csv"), "bw0\tMid(bb0[1])\n")?;
+        fs::write(
+            output_dir.join("borrow_live_at.csv"),
+            "bw0\tMid(bb0[0])\nbw1\tMid(bb0[1])\n",
+        )?;
+        let actual = souffle::read_souffle_rows(&output_dir)?;
+        fs::remove_dir_all(&output_dir)?;
+
+        let diffs = souffle::diff_rows(&expected, &actual);
+        assert_eq!(diffs[0].relation, "errors");
+        assert!(diffs[0].is_empty());
+        assert_eq!(diffs[1].relation, "borrow_live_at");
+        assert_eq!(diffs[1].added, vec!["bw1\tMid(bb0[1])"]);
+        assert_eq!(diffs[1].removed, vec!["bw0\tMid(bb0[1])"]);
+
+        for relation in souffle::COMPARED_RELATIONS {
+            let output = format!(".output {}\n", relation);
+            assert!(souffle::NAIVE_PROGRAM.contains(&output), "`{}` isn't an output", relation);
+        }
+    }
+}
+
+#[test]
+fn test_souffle_facts_with_universal_regions() -> Result<(), Error> {
+    do catch {
+        // `'a` is only live at `Mid(bb0[1])` as a universal region, which
+        // the program derives from the `universal_region` input.
+        let document = r#"{
+            "borrow_region": [["'a", "bw0", "Mid(bb0[0])"]],
+            "universal_region": ["'a"],
+            "cfg_edge": [["Mid(bb0[0])", "Mid(bb0[1])"]],
+            "invalidates": [["Mid(bb0[1])", "bw0"]]
+        }"#;
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = json::read_json_facts(tables, document.as_bytes())?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, false);
+        let expected = souffle::polonius_rows(&output, tables);
+        assert_eq!(expected[0].iter().collect::<Vec<_>>(), vec!["bw0\tMid(bb0[1])"]);
+
+        let facts_dir = temp_dir("souffle-universal");
+        let derived_facts = Output::derived_facts(&all_facts);
+        tab_delim::write_tab_delimited_facts(&derived_facts, tables, &facts_dir)?;
+        let universal_regions = fs::read_to_string(facts_dir.join("universal_region.facts"))?;
+        fs::remove_dir_all(&facts_dir)?;
+        assert_eq!(universal_regions, "'a\n");
+        assert!(souffle::NAIVE_PROGRAM.contains(".input universal_region\n"));
+    }
+}
+
+#[test]
+fn test_sqlite_export() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let database = temp_dir("sqlite-export").join("facts.db");
+
+        // Exporting twice replaces the tables, rather than adding rows.
+        sqlite::export(&all_facts, tables, Algorithm::Naive, &database)?;
+        sqlite::export(&all_facts, tables, Algorithm::Naive, &database)?;
+        let output = Output::compute(&all_facts, Algorithm::Naive, true);
+        {
+            let connection = Connection::open(&database)?;
+            let count = |table: &str| -> Result<usize, Error> {
+                let sql = format!("SELECT COUNT(*) FROM {}", table);
+                let count: i64 = connection.query_row(&sql, &[], |row| row.get(0))?;
+                Ok(count as usize)
+            };
+            assert_eq!(count("cfg_edge")?, all_facts.cfg_edge.len());
+            let live: usize = output.borrow_live_at().values().map(|loans| loans.len()).sum();
+            assert_eq!(count("borrow_live_at")?, live);
+            let regions: usize = output.region_live_at.values().map(|regions| regions.len()).sum();
+            assert_eq!(count("derived_region_live_at")?, regions);
+
+            // The names are stored without their quotes.
+            let point: String = connection.query_row(
+                "SELECT source FROM cfg_edge ORDER BY source LIMIT 1",
+                &[],
+                |row| row.get(0),
+            )?;
+            assert!(!point.starts_with('"'), "`{}` is quoted", point);
+        }
+        fs::remove_file(&database)?;
+
+        let inputs: Vec<_> = tab_delim::named_relations(&all_facts, tables)
+            .into_iter()
+            .map(|(relation, _)| relation)
+            .collect();
+        let columns: Vec<_> = sqlite::INPUT_COLUMNS.iter().map(|&(name, _)| name).collect();
+        assert_eq!(inputs, columns);
+        let outputs: Vec<_> = sqlite::OUTPUT_COLUMNS.iter().map(|&(name, _)| name).collect();
+        assert_eq!(outputs, dump::RELATIONS.to_vec());
+    }
+}
+
+#[test]
+fn test_serve() -> Result<(), Error> {
+    do catch {
+        let request = |method: &str, path: &str, body: &str| serve::Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+        };
+        let mut server = serve::Server::new(Algorithm::Naive, Budget::unlimited());
+        let response = server.handle(&request("POST", "/facts", "not json"));
+        assert_eq!(response.status, 400);
+
+        let document = r#"{
+            "borrow_region": [["'a", "bw0", "Mid(bb0[0])"]],
+            "cfg_edge": [["Mid(bb0[0])", "Mid(bb0[1])"]],
+            "region_live_at": [["'a", "Mid(bb0[0])"], ["'a", "Mid(bb0[1])"]],
+            "invalidates": [["Mid(bb0[1])", "bw0"]]
+        }"#;
+        let response = server.handle(&request("POST", "/facts", document));
+        assert_eq!(response.status, 201);
+        let id = serde_json::from_str::<serde_json::Value>(&response.body)?["id"].clone();
+        let path = format!("/facts/{}", id);
+
+        // The relations can only be fetched once the facts were analyzed.
+        let response = server.handle(&request("GET", &format!("{}/errors", path), ""));
+        assert_eq!(response.status, 409);
+        let analyze = format!("{}/analyze?algorithm=nothing", path);
+        assert_eq!(server.handle(&request("POST", &analyze, "")).status, 400);
+        let analyze = format!("{}/analyze?algorithm=datafrogopt", path);
+        let response = server.handle(&request("POST", &analyze, ""));
+        assert_eq!(response.status, 200);
+        let analysis: serde_json::Value = serde_json::from_str(&response.body)?;
+        assert_eq!(analysis["algorithm"], "DatafrogOpt");
+        assert_eq!(analysis["relations"]["borrow_live_at"], 2);
+        assert!(analysis.get("budget_exceeded").is_none());
+
+        let response = server.handle(&request("GET", &format!("{}/errors", path), ""));
+        assert_eq!(response.body, r#"[{"point":"Mid(bb0[1])","loan":"bw0"}]"#);
+        let relation = format!("{}/relations/borrow_live_at", path);
+        let response = server.handle(&request("GET", &relation, ""));
+        let rows: Vec<Vec<String>> = serde_json::from_str(&response.body)?;
+        assert_eq!(rows.len(), 2);
+        let relation = format!("{}/relations/nothing", path);
+        assert_eq!(server.handle(&request("GET", &relation, "")).status, 404);
+
+        assert_eq!(server.handle(&request("DELETE", &path, "")).status, 204);
+        assert_eq!(server.handle(&request("DELETE", &path, "")).status, 404);
+
+        // The analyses of a shared server run without its lock, within its
+        // budget.
+        let shared = Mutex::new(serve::Server::new(
+            Algorithm::Naive,
+            Budget::unlimited().with_max_tuples(0),
+        ));
+        let response = serve::handle(&shared, &request("POST", "/facts", document));
+        let id = serde_json::from_str::<serde_json::Value>(&response.body)?["id"].clone();
+        let analyze = format!("/facts/{}/analyze", id);
+        let response = serve::handle(&shared, &request("POST", &analyze, ""));
+        assert_eq!(response.status, 200);
+        let analysis: serde_json::Value = serde_json::from_str(&response.body)?;
+        assert_eq!(analysis["budget_exceeded"], "the analysis derived too many tuples");
+        let errors = format!("/facts/{}/errors", id);
+        assert_eq!(serve::handle(&shared, &request("GET", &errors, "")).status, 200);
+
+        // Over the wire, requests are framed by their `Content-Length`.
+        let message = "POST /facts HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+        let request = serve::read_request(&mut message.as_bytes())?.unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/facts"));
+        assert_eq!(request.body, b"{}");
+        let mut written = Vec::new();
+        serve::write_response(&mut written, &server.handle(&request))?;
+        let written = String::from_utf8(written)?;
+        assert!(written.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(written.ends_with("\r\n\r\n{\"id\":2}"));
+    }
+}
@@ -0,0 +1,84 @@
+//! Polls fact directories for changes, for `analyze --watch`.
+
+use crate::batch::{self, FunctionFilter};
+use crate::cache;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// The facts directories under some roots, with the newest modification
+/// time of the facts in each. Two snapshots differ once a directory or
+/// its facts are added, removed, or modified.
+#[derive(Debug, Default, PartialEq, Eq)]
+crate struct Snapshot {
+    facts_dirs: Vec<(PathBuf, Option<(u64, u32)>)>,
+}
+
+impl Snapshot {
+    /// The facts under `roots`, found as `batch::facts_dirs` finds them.
+    /// The roots that can't be read are left out, so that they show up
+    /// as changed once they can be.
+    crate fn take(roots: &[String], function: Option<&FunctionFilter>) -> Self {
+        let mut facts_dirs = Vec::new();
+        for root in roots {
+            for facts_dir in batch::facts_dirs(Path::new(root), function).unwrap_or_default() {
+                let mtime = modified(&facts_dir).ok();
+                facts_dirs.push((facts_dir, mtime));
+            }
+        }
+        Snapshot { facts_dirs }
+    }
+
+    /// The facts directories that were added or modified since `previous`,
+    /// and those that were removed.
+    crate fn changes_since(&self, previous: &Snapshot) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let before: BTreeMap<&PathBuf, Option<(u64, u32)>> = previous
+            .facts_dirs
+            .iter()
+            .map(|&(ref facts_dir, mtime)| (facts_dir, mtime))
+            .collect();
+        let changed = self
+            .facts_dirs
+            .iter()
+            .filter(|&&(ref facts_dir, mtime)| before.get(facts_dir) != Some(&mtime))
+            .map(|&(ref facts_dir, _)| facts_dir.clone())
+            .collect();
+
+        let after: BTreeSet<&PathBuf> = self.facts_dirs.iter().map(|&(ref dir, _)| dir).collect();
+        let removed = previous
+            .facts_dirs
+            .iter()
+            .filter(|&&(ref facts_dir, _)| !after.contains(facts_dir))
+            .map(|&(ref facts_dir, _)| facts_dir.clone())
+            .collect();
+        (changed, removed)
+    }
+}
+
+/// Blocks until the snapshot of `roots` differs from `previous`, checking
+/// every `interval`, and returns the new snapshot.
+crate fn wait_for_change(
+    roots: &[String],
+    function: Option<&FunctionFilter>,
+    previous: &Snapshot,
+    interval: Duration,
+) -> Snapshot {
+    loop {
+        thread::sleep(interval);
+        let snapshot = Snapshot::take(roots, function);
+        if snapshot != *previous {
+            return snapshot;
+        }
+    }
+}
+
+/// When the facts at `path`, a directory or a JSON document, last changed.
+fn modified(path: &Path) -> io::Result<(u64, u32)> {
+    if path.is_file() {
+        cache::mtime(path)
+    } else {
+        cache::newest_mtime(path)
+    }
+}
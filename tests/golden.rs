@@ -0,0 +1,153 @@
+//! The golden tests: analyzes each facts directory of `tests/inputs`, and
+//! each function of the dumps of `inputs` with an `expected.errors` file,
+//! and compares its errors with those of the `expected.errors` file next
+//! to its facts, one `<point>\t<loan>` line per error.
+//!
+//! Run them with `cargo test --test golden`. After a change that is meant
+//! to change the errors, `cargo test --test golden -- --bless` rewrites
+//! the `expected.errors` files with the errors of the naive analysis, so
+//! that the new expectations are reviewed with the change.
+
+extern crate polonius;
+extern crate polonius_engine;
+
+use polonius::{Algorithm, InternerTables, Output};
+use polonius_engine::unquoted;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// The algorithms that must find exactly the expected errors, the first
+/// of which records them with `--bless`. The location-insensitive
+/// analysis finds more errors, and `Compare` only runs two of the others.
+const ALGORITHMS: &[Algorithm] = &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid];
+
+const EXPECTED_ERRORS: &str = "expected.errors";
+
+fn main() {
+    let bless = env::args().skip(1).any(|arg| arg == "--bless");
+    let inputs = inputs();
+    println!("\nrunning {} golden tests", inputs.len());
+
+    let mut failures = 0;
+    for facts_dir in &inputs {
+        let name = facts_dir
+            .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+            .unwrap()
+            .display();
+        match check(facts_dir, bless) {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(message) => {
+                println!("test {} ... FAILED\n{}", name, message);
+                failures += 1;
+            }
+        }
+    }
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed\n",
+        if failures == 0 { "ok" } else { "FAILED" },
+        inputs.len() - failures,
+        failures
+    );
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+/// The facts directories of `tests/inputs`, and those of the functions of
+/// `inputs` with expected errors, sorted. The functions of `inputs`
+/// without them, like those of the big dumps, aren't golden tests.
+fn inputs() -> Vec<PathBuf> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut inputs = subdirectories(&manifest_dir.join("tests").join("inputs"));
+    for dump in subdirectories(&manifest_dir.join("inputs")) {
+        let functions = subdirectories(&dump.join("nll-facts"));
+        inputs.extend(
+            functions
+                .into_iter()
+                .filter(|facts_dir| facts_dir.join(EXPECTED_ERRORS).exists()),
+        );
+    }
+    inputs.sort();
+    inputs
+}
+
+/// The directories in `dir`, or none if there is no `dir`.
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    if !dir.exists() {
+        return Vec::new();
+    }
+    fs::read_dir(dir)
+        .unwrap_or_else(|error| panic!("failed to read `{}`: {}", dir.display(), error))
+        .map(|entry| entry.expect("failed to read a directory entry").path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// Analyzes the facts of `facts_dir` with each algorithm, and compares
+/// their errors with the expected ones. With `bless`, the errors of the
+/// first algorithm are recorded as the expected ones first.
+fn check(facts_dir: &Path, bless: bool) -> Result<(), String> {
+    let (all_facts, tables) = polonius::load_facts(facts_dir)
+        .map_err(|error| format!("  failed to load the facts: {}", error))?;
+
+    let expected_file = facts_dir.join(EXPECTED_ERRORS);
+    if bless {
+        let output = polonius::analyze(&all_facts, ALGORITHMS[0]);
+        let contents: String = errors(&output, &tables)
+            .iter()
+            .map(|error| format!("{}\n", error))
+            .collect();
+        fs::write(&expected_file, contents).map_err(|error| {
+            format!("  failed to write `{}`: {}", expected_file.display(), error)
+        })?;
+    }
+    let expected: BTreeSet<String> = match fs::read_to_string(&expected_file) {
+        Ok(contents) => contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect(),
+        Err(error) => {
+            return Err(format!(
+                "  failed to read `{}`: {}\n  run with `--bless` to record the errors",
+                expected_file.display(),
+                error
+            ))
+        }
+    };
+
+    let mut message = String::new();
+    for &algorithm in ALGORITHMS {
+        let errors = errors(&polonius::analyze(&all_facts, algorithm), &tables);
+        for error in errors.difference(&expected) {
+            message += &format!("  {}: unexpected error `{}`\n", algorithm, error);
+        }
+        for error in expected.difference(&errors) {
+            message += &format!("  {}: missing error `{}`\n", algorithm, error);
+        }
+    }
+    if message.is_empty() {
+        Ok(())
+    } else {
+        Err(message)
+    }
+}
+
+/// The errors of `output`, as `<point>\t<loan>` lines of the names of the
+/// atoms, without their quotes.
+fn errors(output: &Output, tables: &InternerTables) -> BTreeSet<String> {
+    output
+        .iter_errors()
+        .map(|(point, loan)| {
+            format!(
+                "{}\t{}",
+                unquoted(tables.points.untern(point)),
+                unquoted(tables.loans.untern(loan))
+            )
+        })
+        .collect()
+}